@@ -1,16 +1,76 @@
-use num_traits;
 use crate::error::StreamError;
 use crate::state::*;
 use crate::constants::*;
 use crate::utils::*;
+use crate::instruction::BatchStreamTerms;
 use solana_program::{
     // msg,
     pubkey::Pubkey,
     account_info::AccountInfo,
     entrypoint::ProgramResult,
+    program_error::ProgramError,
     program_pack::{ Pack },
 };
 
+/// Passes if the signer is the treasurer or holds `role` in the treasury roles account
+pub fn check_treasury_authority<'info>(
+    program_id: &Pubkey,
+    signer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: Option<&AccountInfo<'info>>,
+    role: TreasuryRole
+
+) -> ProgramResult {
+
+    if get_treasurer_address(treasury_account_info)?.eq(signer_account_info.key) {
+        return Ok(());
+    }
+
+    let treasury_roles_account_info = match treasury_roles_account_info {
+        Some(account_info) => account_info,
+        None => return Err(StreamError::InstructionNotAuthorized.into())
+    };
+
+    let treasury_roles = get_treasury_roles(program_id, treasury_account_info, treasury_roles_account_info)?;
+
+    if !treasury_roles.has_role(signer_account_info.key, role) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    Ok(())
+}
+
+fn get_treasury_roles<'info>(
+    program_id: &Pubkey,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: &AccountInfo<'info>
+
+) -> Result<TreasuryRoles, ProgramError> {
+
+    if treasury_roles_account_info.owner != program_id ||
+       AccountKind::detect(&treasury_roles_account_info) != AccountKind::TreasuryRoles
+    {
+        return Err(StreamError::InvalidTreasuryRoles.into());
+    }
+
+    let treasury_roles = TreasuryRoles::unpack_from_slice(&treasury_roles_account_info.data.borrow())?;
+
+    if !treasury_roles.initialized || treasury_roles.treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InvalidTreasuryRoles.into());
+    }
+
+    let treasury_roles_address = Pubkey::create_program_address(
+        &[TREASURY_ROLES_SEED, treasury_account_info.key.as_ref(), &[treasury_roles.bump]],
+        program_id
+    )?;
+
+    if treasury_roles_address.ne(treasury_roles_account_info.key) {
+        return Err(StreamError::InvalidPdaAccount.into());
+    }
+
+    Ok(treasury_roles)
+}
+
 pub fn check_can_create_stream<'info>(
     program_id: &Pubkey,
     treasurer_account_info: &AccountInfo<'info>,
@@ -20,6 +80,7 @@ pub fn check_can_create_stream<'info>(
     msp_account_info: &AccountInfo<'info>,
     system_account_info: &AccountInfo<'info>,
     rent_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: Option<&AccountInfo<'info>>,
     allocation_assigned: f64,
     allocation_reserved: f64
 
@@ -37,6 +98,11 @@ pub fn check_can_create_stream<'info>(
     if treasury_account_info.owner != program_id {
         return Err(StreamError::InstructionNotAuthorized.into());
     }
+    // Check the signer is the treasurer or a stream creator of the treasury
+    check_treasury_authority(
+        program_id, treasurer_account_info, treasury_account_info,
+        treasury_roles_account_info, TreasuryRole::StreamCreator
+    )?;
     // Check the treasury associated token account info
     let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
 
@@ -57,10 +123,10 @@ pub fn check_can_create_stream<'info>(
     }
 
     let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-    let requested_allocation = (allocation_assigned * pow) as u64;
-    let unallocated_balance = ((treasury.balance * pow) as u64)
-        .checked_sub((treasury.allocation_left * pow) as u64)
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    let requested_allocation = scale.to_base_units(allocation_assigned);
+    let unallocated_balance = scale.to_base_units(treasury.balance)
+        .checked_sub(scale.to_base_units(treasury.allocation_left))
         .ok_or(StreamError::Overflow)?;
 
     if requested_allocation <= 0 {
@@ -74,6 +140,170 @@ pub fn check_can_create_stream<'info>(
     Ok(())
 }
 
+pub fn check_can_create_stream_v2<'info>(
+    program_id: &Pubkey,
+    treasurer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: Option<&AccountInfo<'info>>,
+    allocation_assigned: u64,
+    allocation_reserved: u64
+
+) -> ProgramResult {
+
+    // Check system accounts
+    check_system_accounts(
+        Option::None, Option::None, Option::Some(rent_account_info), Option::Some(system_account_info)
+    )?;
+    // Check the tresurer is the signer
+    if !treasurer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check the MSP is the owner of the treasury 
+    if treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the treasury account has a valid size
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+    // Check the treasury associated token account info
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    // Check the signer is the treasurer or a stream creator of the treasury
+    check_treasury_authority(
+        program_id, treasurer_account_info, treasury_account_info,
+        treasury_roles_account_info, TreasuryRole::StreamCreator
+    )?;
+
+    if treasury.associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check if the stream is already initialized
+    if stream_account_info.data_len() > 0 {
+        return Err(StreamError::StreamAlreadyInitialized.into())
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check if the requested allocations are valid
+    if allocation_reserved > allocation_assigned {
+        return Err(StreamError::StreamAllocationExceeded.into());
+    }
+
+    if allocation_assigned == 0 {
+        return Err(StreamError::InvalidAssignedAllocation.into());
+    }
+
+    let unallocated_balance = treasury.balance
+        .checked_sub(treasury.allocation_left)
+        .ok_or(StreamError::Overflow)?;
+
+    if treasury.auto_close == false && allocation_assigned > unallocated_balance {
+        return Err(StreamError::InvalidAssignedAllocation.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_create_streams_batch<'info>(
+    program_id: &Pubkey,
+    treasurer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: &AccountInfo<'info>,
+    streams: &[BatchStreamTerms],
+    stream_account_infos: &[&AccountInfo<'info>]
+
+) -> ProgramResult {
+
+    // Check system accounts
+    check_system_accounts(
+        Option::None, Option::None, Option::Some(rent_account_info), Option::Some(system_account_info)
+    )?;
+    // Check the tresurer is the signer
+    if !treasurer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check the MSP is the owner of the treasury 
+    if treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Batches are only supported by V2 treasuries
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    // The treasury roles account is always passed, it is only read when it has been created
+    let treasury_roles_account_info = Some(treasury_roles_account_info)
+        .filter(|account_info| account_info.data_len() > 0);
+
+    check_treasury_authority(
+        program_id, treasurer_account_info, treasury_account_info,
+        treasury_roles_account_info, TreasuryRole::StreamCreator
+    )?;
+
+    if treasury.associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Every stream comes with its stream and beneficiary accounts
+    if streams.is_empty() || stream_account_infos.len() != streams.len() * 2 {
+        return Err(StreamError::InvalidArgument.into());
+    }
+
+    let mut allocation_assigned = 0u64;
+
+    for (terms, account_infos) in streams.iter().zip(stream_account_infos.chunks(2)) {
+        // Check if the stream is already initialized
+        if account_infos[0].data_len() > 0 {
+            return Err(StreamError::StreamAlreadyInitialized.into());
+        }
+
+        // A zero rate never vests, so the allocation of the stream could never be released
+        if terms.rate_amount == 0 || terms.rate_interval_in_seconds == 0 || terms.cliff_vest_percent > BPS_DENOMINATOR {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        if terms.vesting_mode != VestingMode::Continuous as u8 && terms.vesting_mode != VestingMode::Stepped as u8 {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        if terms.allocation_reserved > terms.allocation_assigned {
+            return Err(StreamError::StreamAllocationExceeded.into());
+        }
+
+        if terms.allocation_assigned == 0 {
+            return Err(StreamError::InvalidAssignedAllocation.into());
+        }
+
+        allocation_assigned = allocation_assigned
+            .checked_add(terms.allocation_assigned)
+            .ok_or(StreamError::Overflow)?;
+    }
+    // The allocations of all the streams have to fit in the unallocated balance
+    let unallocated_balance = treasury.balance
+        .checked_sub(treasury.allocation_left)
+        .ok_or(StreamError::Overflow)?;
+
+    if !treasury.auto_close && allocation_assigned > unallocated_balance {
+        return Err(StreamError::InvalidAssignedAllocation.into());
+    }
+
+    Ok(())
+}
+
 pub fn check_can_add_funds_v0<'info>(
     program_id: &Pubkey,
     msp_account_info: &AccountInfo<'info>,
@@ -201,7 +431,7 @@ pub fn check_can_add_funds_v0<'info>(
         return Err(StreamError::InvalidTreasuryPoolMint.into());
     }
 
-    if stream_account_info.data_len() == Stream::LEN {
+    if AccountKind::detect(&stream_account_info) == AccountKind::Stream {
         let stream = Stream::unpack_from_slice(&stream_account_info.data.borrow())?;
         if stream.treasury_address.ne(&treasury_pool_address) {
             return Err(StreamError::InvalidStreamAccount.into());
@@ -275,17 +505,137 @@ pub fn check_can_add_funds<'info>(
     }
     // Check treasury address the valid PDA
     let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
-    let (treasury_pool_address, _) = Pubkey::find_program_address(
+    let (treasury_pool_address, _) = treasury.find_address(msp_account_info.key);
+
+    if treasury_pool_address != *treasury_account_info.key {
+        return Err(StreamError::InvalidTreasuryPool.into());
+    }
+    // Check the treasury token account is valid for the associated token Mint
+    let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        treasury_account_info.key,
+        associated_token_mint_info.key
+    );
+
+    if treasury_token_address != *treasury_token_account_info.key {
+        return Err(StreamError::InvalidTreasuryAccount.into());
+    }
+
+    if treasury_token_account_info.data_len() == 0 { // Create treasury associated token account if doesn't exist
+        let _ = create_ata_account(
+            &system_account_info, &rent_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &contributor_account_info, &treasury_account_info,
+            &treasury_token_account_info, &associated_token_mint_info
+        )?;
+    }
+    // Check treasury pool mint address
+    let (treasury_pool_mint_address, _) = Pubkey::find_program_address(
         &[
-            treasury.treasurer_address.as_ref(),
+            treasury.seed_address.as_ref(),
+            treasury_pool_address.as_ref(),
             &treasury.slot.to_le_bytes()
         ], 
         msp_account_info.key
     );
 
+    if treasury_pool_mint_address.ne(treasury_pool_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryPoolMint.into());
+    }
+
+    if AccountKind::detect(&stream_account_info) == AccountKind::StreamV1 {
+        let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+        if stream.treasury_address.ne(&treasury_pool_address) {
+            return Err(StreamError::InvalidStreamAccount.into());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn check_can_add_funds_v2<'info>(
+    program_id: &Pubkey,
+    msp_account_info: &AccountInfo<'info>,
+    contributor_account_info: &AccountInfo<'info>,
+    contributor_token_account_info: &AccountInfo<'info>, 
+    contributor_treasury_pool_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    treasury_pool_mint_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    associated_token_program_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    allocation_type: u8
+
+) -> ProgramResult {
+
+    // Check the allocation type (0: assigned, 1: reserved, 2: unallocated)
+    if allocation_type > 2 {
+        return Err(StreamError::InvalidArgument.into());
+    }
+    // Check system accounts
+    check_system_accounts(
+        Option::Some(associated_token_program_account_info),
+        Option::Some(token_program_account_info),
+        Option::Some(rent_account_info),
+        Option::Some(system_account_info)
+    )?;
+    // Check the Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the contributor is the signer
+    if !contributor_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check the contributor token account (native SOL can be contributed from the contributor account)
+    let contributor_token_address = spl_associated_token_account::get_associated_token_address(
+        contributor_account_info.key, associated_token_mint_info.key
+    );
+
+    if contributor_token_address.ne(contributor_token_account_info.key) &&
+       !is_native_contribution(&contributor_account_info, &contributor_token_account_info, &associated_token_mint_info)
+    {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+    // Check the contributor treasury pool token account
+    let contributor_treasury_pool_token_address = spl_associated_token_account::get_associated_token_address(
+        contributor_account_info.key, treasury_pool_mint_info.key
+    );
+
+    if contributor_treasury_pool_token_address.ne(contributor_treasury_pool_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+
+    if contributor_treasury_pool_token_account_info.data_len() == 0 { // Create the contributor treasury token account if the account does not exists
+        create_ata_account(
+            &system_account_info, &rent_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &contributor_account_info, &contributor_account_info,
+            &contributor_treasury_pool_token_account_info, &treasury_pool_mint_info
+        )?;
+    }
+    // Check the treasury account is owned by the Money Streaming Program
+    if treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the treasury account has a valid size
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+    // Check treasury address the valid PDA
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let (treasury_pool_address, _) = treasury.find_address(msp_account_info.key);
+
     if treasury_pool_address != *treasury_account_info.key {
         return Err(StreamError::InvalidTreasuryPool.into());
     }
+    // Check the treasury can only hold one associated token
+    if treasury.associated_token_address.ne(&Pubkey::default()) &&
+       treasury.associated_token_address.ne(associated_token_mint_info.key)
+    {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
     // Check the treasury token account is valid for the associated token Mint
     let treasury_token_address = spl_associated_token_account::get_associated_token_address(
         treasury_account_info.key,
@@ -297,7 +647,7 @@ pub fn check_can_add_funds<'info>(
     }
 
     if treasury_token_account_info.data_len() == 0 { // Create treasury associated token account if doesn't exist
-        let _ = create_ata_account(
+        create_ata_account(
             &system_account_info, &rent_account_info, &associated_token_program_account_info,
             &token_program_account_info, &contributor_account_info, &treasury_account_info,
             &treasury_token_account_info, &associated_token_mint_info
@@ -306,7 +656,7 @@ pub fn check_can_add_funds<'info>(
     // Check treasury pool mint address
     let (treasury_pool_mint_address, _) = Pubkey::find_program_address(
         &[
-            treasury.treasurer_address.as_ref(),
+            treasury.seed_address.as_ref(),
             treasury_pool_address.as_ref(),
             &treasury.slot.to_le_bytes()
         ], 
@@ -316,9 +666,15 @@ pub fn check_can_add_funds<'info>(
     if treasury_pool_mint_address.ne(treasury_pool_mint_info.key) {
         return Err(StreamError::InvalidTreasuryPoolMint.into());
     }
+    // Check the stream to allocate the funds to
+    if allocation_type == 0 || allocation_type == 1 {
+
+        if stream_account_info.owner != program_id || AccountKind::detect(&stream_account_info) != AccountKind::StreamV2 {
+            return Err(StreamError::InvalidStreamAccount.into());
+        }
+
+        let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
 
-    if stream_account_info.data_len() == StreamV1::LEN {
-        let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
         if stream.treasury_address.ne(&treasury_pool_address) {
             return Err(StreamError::InvalidStreamAccount.into());
         }
@@ -336,6 +692,7 @@ pub fn check_can_withdraw_funds_v0<'info>(
     treasury_token_account_info: &AccountInfo<'info>,
     stream_account_info: &AccountInfo<'info>,
     fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_treasury_address: &Pubkey,
     msp_account_info: &AccountInfo<'info>,
     associated_token_program_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>,
@@ -365,7 +722,7 @@ pub fn check_can_withdraw_funds_v0<'info>(
         return Err(StreamError::MissingInstructionSignature.into());
     }
     // Check if the stream data has a valid size
-    if stream_account_info.data_len() != Stream::LEN {
+    if AccountKind::detect(&stream_account_info) != AccountKind::Stream {
         return Err(StreamError::InvalidStreamData.into());
     }
 
@@ -400,7 +757,7 @@ pub fn check_can_withdraw_funds_v0<'info>(
     }
     // Check the fee treasury token account info
     let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
-        &FEE_TREASURY_ACCOUNT.parse().unwrap(), associated_token_mint_info.key
+        fee_treasury_address, associated_token_mint_info.key
     );
 
     if fee_treasury_token_address.ne(fee_treasury_token_account_info.key) {
@@ -419,6 +776,7 @@ pub fn check_can_withdraw_funds<'info>(
     treasury_token_account_info: &AccountInfo<'info>,
     stream_account_info: &AccountInfo<'info>,
     fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_treasury_address: &Pubkey,
     msp_account_info: &AccountInfo<'info>,
     associated_token_program_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>,
@@ -447,7 +805,7 @@ pub fn check_can_withdraw_funds<'info>(
         return Err(StreamError::MissingInstructionSignature.into());
     }
     // Check if the stream data has a valid size
-    if stream_account_info.data_len() != StreamV1::LEN {
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV1 {
         return Err(StreamError::InvalidStreamData.into());
     }
 
@@ -488,7 +846,7 @@ pub fn check_can_withdraw_funds<'info>(
     }
     // Check the fee treasury token account info
     let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
-        &FEE_TREASURY_ACCOUNT.parse().unwrap(), associated_token_mint_info.key
+        fee_treasury_address, associated_token_mint_info.key
     );
 
     if fee_treasury_token_address.ne(fee_treasury_token_account_info.key) {
@@ -498,14 +856,108 @@ pub fn check_can_withdraw_funds<'info>(
     Ok(())
 }
 
-pub fn check_can_pause_or_resume_stream<'info>(
+pub fn check_can_withdraw_funds_v2<'info>(
     program_id: &Pubkey,
-    initializer_account_info: &AccountInfo<'info>,
-    treasury_account_info: &AccountInfo<'info>,
+    beneficiary_account_info: &AccountInfo<'info>,
+    beneficiary_token_account_info: &AccountInfo<'info>,
     associated_token_mint_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
     stream_account_info: &AccountInfo<'info>,
-    msp_account_info: &AccountInfo<'info>
-
+    fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_treasury_address: &Pubkey,
+    msp_account_info: &AccountInfo<'info>,
+    associated_token_program_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    // Check system accounts
+    check_system_accounts(
+        Option::Some(associated_token_program_account_info),
+        Option::Some(token_program_account_info),
+        Option::Some(rent_account_info),
+        Option::Some(system_account_info)
+    )?;
+    // Check the Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury and the stream are owned by the MSP
+    if treasury_account_info.owner != program_id || stream_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the beneficiary is the signer
+    if !beneficiary_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check if the stream and treasury data have a valid size
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV2 {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+    // Check the beneficiary account info
+    if stream.beneficiary_address.ne(beneficiary_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the beneficiary token account info
+    let beneficiary_token_address = spl_associated_token_account::get_associated_token_address(
+        &stream.beneficiary_address, associated_token_mint_info.key
+    );
+
+    if beneficiary_token_address.ne(beneficiary_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+    // Check the associated token mint account
+    if stream.beneficiary_associated_token.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check treasury account info
+    if stream.treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check treasury token account info
+    let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        &stream.treasury_address, associated_token_mint_info.key
+    );
+
+    if treasury_token_address.ne(treasury_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    // Check the treasury token mint account
+    if treasury.associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check the fee treasury token account info
+    let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        fee_treasury_address, associated_token_mint_info.key
+    );
+
+    if fee_treasury_token_address.ne(fee_treasury_token_account_info.key) {
+        return Err(StreamError::InvalidMspOpsToken.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_pause_or_resume_stream<'info>(
+    program_id: &Pubkey,
+    initializer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: Option<&AccountInfo<'info>>
+
 ) -> ProgramResult {
 
     // Check the initializer is the signer
@@ -513,26 +965,37 @@ pub fn check_can_pause_or_resume_stream<'info>(
         return Err(StreamError::MissingInstructionSignature.into());
     }
 
-    let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
-
-    // Check that only the treasurer or the beneficiary can pause the stream
-    if stream_account_info.owner != program_id || (
-        stream.treasurer_address.ne(initializer_account_info.key) && 
-        stream.beneficiary_address.ne(initializer_account_info.key)
-    ) {
+    if stream_account_info.owner != program_id {
         return Err(StreamError::InstructionNotAuthorized.into());
     }
 
+    // Check the size of the Stream is correct, legacy streams have to be migrated first
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV1 {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+
     // Check the treasury account info
     if stream.treasury_address.ne(treasury_account_info.key) {
         return Err(StreamError::InstructionNotAuthorized.into());
     }
 
     // Check the size of the Treasury in the correct
-    if treasury_account_info.data_len() != TreasuryV1::LEN {
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV1 {
         return Err(StreamError::InvalidTreasuryData.into());
     }
 
+    // Check that only the treasurer, the beneficiary or a pauser can pause the stream
+    if get_treasurer_address(&treasury_account_info)?.ne(initializer_account_info.key) && 
+       stream.beneficiary_address.ne(initializer_account_info.key)
+    {
+        check_treasury_authority(
+            program_id, initializer_account_info, treasury_account_info,
+            treasury_roles_account_info, TreasuryRole::Pauser
+        )?;
+    }
+
     let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
 
     // Check the associated token mint info
@@ -549,6 +1012,63 @@ pub fn check_can_pause_or_resume_stream<'info>(
     Ok(())
 }
 
+pub fn check_can_pause_or_resume_stream_v2<'info>(
+    program_id: &Pubkey,
+    initializer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: Option<&AccountInfo<'info>>
+
+) -> ProgramResult {
+
+    // Check the initializer is the signer
+    if !initializer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check the stream and the treasury are owned by the MSP
+    if stream_account_info.owner != program_id || treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the size of the Stream and the Treasury are correct
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV2 {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+    // Check the treasury account info
+    if stream.treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check that only the treasurer, the beneficiary or a pauser can pause the stream
+    if get_treasurer_address(&treasury_account_info)?.ne(initializer_account_info.key) && 
+       stream.beneficiary_address.ne(initializer_account_info.key)
+    {
+        check_treasury_authority(
+            program_id, initializer_account_info, treasury_account_info,
+            treasury_roles_account_info, TreasuryRole::Pauser
+        )?;
+    }
+
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    // Check the associated token mint info
+    if stream.beneficiary_associated_token.ne(associated_token_mint_info.key) || 
+       treasury.associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    Ok(())
+}
+
 pub fn check_can_close_stream_v0<'info>(
     program_id: &Pubkey,
     initializer_account_info: &AccountInfo<'info>,
@@ -562,6 +1082,7 @@ pub fn check_can_close_stream_v0<'info>(
     treasury_pool_mint_info: &AccountInfo<'info>,
     stream_account_info: &AccountInfo<'info>,
     fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_treasury_address: &Pubkey,
     msp_account_info: &AccountInfo<'info>,
     associated_token_program_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>,
@@ -587,7 +1108,7 @@ pub fn check_can_close_stream_v0<'info>(
         return Err(StreamError::InstructionNotAuthorized.into());
     }
     // Check the stream account has a valid size
-    if stream_account_info.data_len() != Stream::LEN {
+    if AccountKind::detect(&stream_account_info) != AccountKind::Stream {
         return Err(StreamError::InvalidStreamData.into());
     }
 
@@ -605,7 +1126,7 @@ pub fn check_can_close_stream_v0<'info>(
         return Err(StreamError::InstructionNotAuthorized.into());
     }
     // Check the treasury account has a valid size
-    if treasury_account_info.data_len() != Treasury::LEN {
+    if AccountKind::detect(&treasury_account_info) != AccountKind::Treasury {
         return Err(StreamError::InvalidTreasuryData.into());
     }
     // Check that the treasury address is the valid PDA
@@ -646,7 +1167,7 @@ pub fn check_can_close_stream_v0<'info>(
     );
 
     let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
-        &FEE_TREASURY_ACCOUNT.parse().unwrap(),
+        fee_treasury_address,
         associated_token_mint_info.key
     );
 
@@ -696,6 +1217,7 @@ pub fn check_can_close_stream<'info>(
     treasury_pool_mint_info: &AccountInfo<'info>,
     stream_account_info: &AccountInfo<'info>,
     fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_treasury_address: &Pubkey,
     msp_account_info: &AccountInfo<'info>,
     associated_token_program_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>,
@@ -720,17 +1242,19 @@ pub fn check_can_close_stream<'info>(
         return Err(StreamError::InstructionNotAuthorized.into());
     }
     // Check the stream account has a valid size
-    if stream_account_info.data_len() != StreamV1::LEN {
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV1 {
         return Err(StreamError::InvalidStreamData.into());
     }
 
     let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
-    // Validate that only the treasurer or the beneficiary can close the stream
-    if stream.treasurer_address.ne(initializer_account_info.key) && stream.beneficiary_address.ne(initializer_account_info.key) {
+    // Check the treasury account info in the stream
+    if stream.treasury_address.ne(treasury_account_info.key) {
         return Err(StreamError::InstructionNotAuthorized.into());
     }
-    // Check the treasurer account info in the stream
-    if stream.treasurer_address.ne(treasurer_account_info.key) {
+    // Validate that only the treasurer or the beneficiary can close the stream
+    let treasurer_address = get_treasurer_address(&treasury_account_info)?;
+
+    if treasurer_address.ne(initializer_account_info.key) && stream.beneficiary_address.ne(initializer_account_info.key) {
         return Err(StreamError::InstructionNotAuthorized.into());
     }
     // Check the beneficiary account info in the stream
@@ -738,19 +1262,13 @@ pub fn check_can_close_stream<'info>(
         return Err(StreamError::InstructionNotAuthorized.into());
     }
     // Check the treasury account has a valid size
-    if treasury_account_info.data_len() != TreasuryV1::LEN {
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV1 {
         return Err(StreamError::InvalidTreasuryData.into());
     }
 
     // Check that the treasury address is the valid PDA
     let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
-    let (treasury_pool_address, _) = Pubkey::find_program_address(
-        &[
-            treasury.treasurer_address.as_ref(),
-            &treasury.slot.to_le_bytes()
-        ], 
-        msp_account_info.key
-    );
+    let (treasury_pool_address, _) = treasury.find_address(msp_account_info.key);
 
     if treasury_pool_address != *treasury_account_info.key {
         return Err(StreamError::InvalidTreasuryPool.into());
@@ -770,7 +1288,7 @@ pub fn check_can_close_stream<'info>(
 
     // Check all associated token accounts info
     let treasurer_token_address = spl_associated_token_account::get_associated_token_address(
-        &stream.treasurer_address, associated_token_mint_info.key
+        &treasury.treasurer_address, associated_token_mint_info.key
     );
 
     let beneficiary_token_address = spl_associated_token_account::get_associated_token_address(
@@ -784,7 +1302,7 @@ pub fn check_can_close_stream<'info>(
     );
 
     let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
-        &FEE_TREASURY_ACCOUNT.parse().unwrap(),
+        fee_treasury_address,
         associated_token_mint_info.key
     );
 
@@ -804,7 +1322,7 @@ pub fn check_can_close_stream<'info>(
     // Check that the treasury pool mint address is the valid PDA
     let (treasury_pool_mint_address, _) = Pubkey::find_program_address(
         &[
-            treasury.treasurer_address.as_ref(),
+            treasury.seed_address.as_ref(),
             treasury_pool_address.as_ref(),
             &treasury.slot.to_le_bytes()
         ], 
@@ -823,80 +1341,116 @@ pub fn check_can_close_stream<'info>(
     Ok(())
 }
 
-pub fn check_can_close_treasury_v0<'info>(
+pub fn check_can_close_stream_v2<'info>(
     program_id: &Pubkey,
+    initializer_account_info: &AccountInfo<'info>,
     treasurer_account_info: &AccountInfo<'info>,
     treasurer_token_account_info: &AccountInfo<'info>,
-    treasurer_treasury_pool_token_account_info: &AccountInfo<'info>,
+    beneficiary_account_info: &AccountInfo<'info>,
+    beneficiary_token_account_info: &AccountInfo<'info>,
     associated_token_mint_info: &AccountInfo<'info>,
-    treasury_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>, 
     treasury_token_account_info: &AccountInfo<'info>,
     treasury_pool_mint_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
     fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_treasury_address: &Pubkey,
     msp_account_info: &AccountInfo<'info>,
-    token_program_account_info: &AccountInfo<'info>
+    associated_token_program_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>
 
 ) -> ProgramResult {
 
     // Check system accounts
-    let _ = check_system_accounts(
-        Option::None, Option::Some(token_program_account_info),  Option::None, Option::None
-    );
-    // Check the tresurer is the signer
-    if !treasurer_account_info.is_signer {
+    check_system_accounts(
+        Option::Some(associated_token_program_account_info),
+        Option::Some(token_program_account_info),
+        Option::Some(rent_account_info),
+        Option::Some(system_account_info)
+    )?;
+    // Check the initializer is the signer
+    if !initializer_account_info.is_signer {
         return Err(StreamError::MissingInstructionSignature.into());
     }
-    // Check the treasury account size is valid
-    if treasury_account_info.data_len() != Treasury::LEN {
+    // Check that the stream and treasury accounts owner is the MSP 
+    if stream_account_info.owner != program_id || treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the stream account has a valid size
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV2 {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+    // Check the treasury account info in the stream
+    if stream.treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Validate that only the treasurer or the beneficiary can close the stream
+    let treasurer_address = get_treasurer_address(&treasury_account_info)?;
+
+    if treasurer_address.ne(initializer_account_info.key) && stream.beneficiary_address.ne(initializer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the beneficiary account info in the stream
+    if stream.beneficiary_address.ne(beneficiary_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the treasury account has a valid size
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
         return Err(StreamError::InvalidTreasuryData.into());
     }
     // Check that the treasury address is the valid PDA
-    let treasury = Treasury::unpack_from_slice(&treasury_account_info.data.borrow())?;
-    let (treasury_pool_address, _) = Pubkey::find_program_address(
-        &[
-            treasury.treasury_base_address.as_ref(),
-            &treasury.treasury_block_height.to_le_bytes()
-        ], 
-        msp_account_info.key
-    );
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let (treasury_pool_address, _) = treasury.find_address(msp_account_info.key);
 
     if treasury_pool_address != *treasury_account_info.key {
         return Err(StreamError::InvalidTreasuryPool.into());
     }
-    // Check the treasurer account info
-    if treasury.treasury_base_address.ne(treasurer_account_info.key) {
+    // Check the treasurer account info in the treasury
+    if treasury.treasurer_address.ne(treasurer_account_info.key) {
         return Err(StreamError::InstructionNotAuthorized.into());
     }
+    // Check the associated token mint account info
+    if stream.beneficiary_associated_token.ne(associated_token_mint_info.key) ||
+       treasury.associated_token_address.ne(associated_token_mint_info.key)
+    {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
     // Check all associated token accounts info
     let treasurer_token_address = spl_associated_token_account::get_associated_token_address(
-        &treasury.treasury_base_address,
-        associated_token_mint_info.key
+        &treasury.treasurer_address, associated_token_mint_info.key
     );
 
-    let treasurer_treasury_pool_token_address = spl_associated_token_account::get_associated_token_address(
-        &treasury.treasury_base_address,
-        treasury_pool_mint_info.key
+    let beneficiary_token_address = spl_associated_token_account::get_associated_token_address(
+        &stream.beneficiary_address,
+        associated_token_mint_info.key
     );
 
     let treasury_token_address = spl_associated_token_account::get_associated_token_address(
-        &treasury_pool_address,
+        &stream.treasury_address,
         associated_token_mint_info.key
     );
 
     let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
-        &FEE_TREASURY_ACCOUNT.parse().unwrap(),
+        fee_treasury_address,
         associated_token_mint_info.key
     );
 
     if treasurer_token_address.ne(treasurer_token_account_info.key) || 
-       treasurer_treasury_pool_token_address.ne(treasurer_treasury_pool_token_account_info.key) ||
+       beneficiary_token_address.ne(beneficiary_token_account_info.key) ||
        treasury_token_address.ne(treasury_token_account_info.key) ||
-       fee_treasury_token_address.ne(fee_treasury_token_account_info.key) 
+       fee_treasury_token_address.ne(fee_treasury_token_account_info.key)
     {
         return Err(StreamError::InstructionNotAuthorized.into());
     }
-
-    // Check Money Streaming Program account info
+    // Check the treasury pool mint account info
+    if treasury.mint_address.ne(treasury_pool_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryPoolMint.into());
+    }
+    // Check the Money Streaming Program account info
     if msp_account_info.key.ne(program_id) {
         return Err(StreamError::IncorrectProgramId.into());
     }
@@ -904,10 +1458,16 @@ pub fn check_can_close_treasury_v0<'info>(
     Ok(())
 }
 
-pub fn check_can_close_treasury<'info>(
+pub fn check_can_close_treasury_v0<'info>(
     program_id: &Pubkey,
     treasurer_account_info: &AccountInfo<'info>,
+    treasurer_token_account_info: &AccountInfo<'info>,
+    treasurer_treasury_pool_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
     treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    treasury_pool_mint_info: &AccountInfo<'info>,
+    _fee_treasury_token_account_info: &AccountInfo<'info>,
     msp_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>
 
@@ -915,26 +1475,1301 @@ pub fn check_can_close_treasury<'info>(
 
     // Check system accounts
     let _ = check_system_accounts(
-        Option::None, Option::Some(token_program_account_info), Option::None, Option::None
+        Option::None, Option::Some(token_program_account_info),  Option::None, Option::None
     );
-
-    if msp_account_info.key.ne(program_id) {
-        return Err(StreamError::IncorrectProgramId.into());
-    }
-
+    // Check the tresurer is the signer
     if !treasurer_account_info.is_signer {
         return Err(StreamError::MissingInstructionSignature.into());
     }
-    
-    let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
-
-    if treasury.treasurer_address.ne(treasurer_account_info.key) {
-        return Err(StreamError::InstructionNotAuthorized.into());
-    }
+    // Check the treasury account size is valid
+    if AccountKind::detect(&treasury_account_info) != AccountKind::Treasury {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+    // Check that the treasury address is the valid PDA
+    let treasury = Treasury::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let (treasury_pool_address, _) = Pubkey::find_program_address(
+        &[
+            treasury.treasury_base_address.as_ref(),
+            &treasury.treasury_block_height.to_le_bytes()
+        ], 
+        msp_account_info.key
+    );
+
+    if treasury_pool_address != *treasury_account_info.key {
+        return Err(StreamError::InvalidTreasuryPool.into());
+    }
+    // Check the treasurer account info
+    if treasury.treasury_base_address.ne(treasurer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check all associated token accounts info
+    let treasurer_token_address = spl_associated_token_account::get_associated_token_address(
+        &treasury.treasury_base_address,
+        associated_token_mint_info.key
+    );
+
+    let treasurer_treasury_pool_token_address = spl_associated_token_account::get_associated_token_address(
+        &treasury.treasury_base_address,
+        treasury_pool_mint_info.key
+    );
+
+    let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        &treasury_pool_address,
+        associated_token_mint_info.key
+    );
+
+    if treasurer_token_address.ne(treasurer_token_account_info.key) || 
+       treasurer_treasury_pool_token_address.ne(treasurer_treasury_pool_token_account_info.key) ||
+       treasury_token_address.ne(treasury_token_account_info.key)
+    {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_close_treasury<'info>(
+    program_id: &Pubkey,
+    treasurer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    // Check system accounts
+    let _ = check_system_accounts(
+        Option::None, Option::Some(token_program_account_info), Option::None, Option::None
+    );
+
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    if !treasurer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    
+    let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+    if treasury.treasurer_address.ne(treasurer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if treasury.streams_amount > 0 {
+        return Err(StreamError::CloseTreasuryWithStreams.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_close_treasury_v2<'info>(
+    program_id: &Pubkey,
+    treasurer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_pool_mint_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    // Check system accounts
+    check_system_accounts(
+        Option::None, Option::Some(token_program_account_info), Option::None, Option::None
+    )?;
+
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    if !treasurer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+
+    if treasury_account_info.owner != program_id || AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+    
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+    if treasury.treasurer_address.ne(treasurer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if treasury.mint_address.ne(treasury_pool_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryPoolMint.into());
+    }
 
     if treasury.streams_amount > 0 {
         return Err(StreamError::CloseTreasuryWithStreams.into());
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn check_can_migrate_stream<'info>(
+    program_id: &Pubkey,
+    initializer_account_info: &AccountInfo<'info>,
+    treasurer_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    check_system_accounts(
+        Option::None, Option::None, 
+        Option::Some(rent_account_info), Option::Some(system_account_info)
+    )?;
+    // Check the initializer is the signer
+    if !initializer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the stream is a legacy stream owned by the MSP
+    if stream_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if AccountKind::detect(&stream_account_info) != AccountKind::Stream {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    let stream = Stream::unpack_from_slice(&stream_account_info.data.borrow())?;
+    // Check that only the treasurer or the beneficiary can migrate the stream
+    if stream.treasurer_address.ne(initializer_account_info.key) && 
+       stream.beneficiary_address.ne(initializer_account_info.key)
+    {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if stream.treasurer_address.ne(treasurer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the associated token mint info
+    if stream.beneficiary_associated_token.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_migrate_treasury<'info>(
+    program_id: &Pubkey,
+    initializer_account_info: &AccountInfo<'info>,
+    treasurer_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    stream_account_infos: &[AccountInfo<'info>]
+
+) -> ProgramResult {
+
+    check_system_accounts(
+        Option::None, Option::None, 
+        Option::Some(rent_account_info), Option::Some(system_account_info)
+    )?;
+    // Check the initializer is the signer
+    if !initializer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury is a legacy treasury owned by the MSP
+    if treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if AccountKind::detect(&treasury_account_info) != AccountKind::Treasury {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let treasury = Treasury::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+    if treasury.treasury_base_address.ne(treasurer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check that only the treasurer or the MSP operations account can migrate the treasury
+    if treasury.treasury_base_address.ne(initializer_account_info.key) && 
+       initializer_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap())
+    {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the treasury token account info
+    let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        treasury_account_info.key,
+        associated_token_mint_info.key
+    );
+
+    if treasury_token_address.ne(treasury_token_account_info.key) {
+        return Err(StreamError::InvalidTreasuryAccount.into());
+    }
+    // Check the streams belong to the treasury and none of them is repeated
+    for (index, stream_account_info) in stream_account_infos.iter().enumerate() {
+
+        if stream_account_info.owner != program_id {
+            return Err(StreamError::InvalidStreamAccount.into());
+        }
+
+        if stream_account_infos[..index].iter().any(|s| s.key.eq(stream_account_info.key)) {
+            return Err(StreamError::InvalidStreamAccount.into());
+        }
+
+        let (treasury_address, associated_token) = match AccountKind::detect(&stream_account_info) {
+            AccountKind::Stream => {
+                let stream = Stream::unpack_from_slice(&stream_account_info.data.borrow())?;
+                (stream.treasury_address, stream.beneficiary_associated_token)
+            },
+            AccountKind::StreamV1 => {
+                let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+                (stream.treasury_address, stream.beneficiary_associated_token)
+            },
+            _ => return Err(StreamError::InvalidStreamData.into())
+        };
+
+        if treasury_address.ne(treasury_account_info.key) {
+            return Err(StreamError::InvalidStreamAccount.into());
+        }
+
+        if associated_token.ne(associated_token_mint_info.key) {
+            return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn check_can_propose_update<'info>(
+    program_id: &Pubkey,
+    proposer_account_info: &AccountInfo<'info>,
+    stream_terms_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    check_system_accounts(
+        Option::None, Option::None, 
+        Option::Some(rent_account_info), Option::Some(system_account_info)
+    )?;
+    // Check the proposer is the signer
+    if !proposer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the stream and the treasury are owned by the MSP
+    if stream_account_info.owner != program_id || treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV2 {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+    // Check the treasury account info
+    if stream.treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check that only the treasurer or the beneficiary can propose new terms
+    if get_treasurer_address(&treasury_account_info)?.ne(proposer_account_info.key) && 
+       stream.beneficiary_address.ne(proposer_account_info.key)
+    {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the stream terms account info
+    let (stream_terms_address, _) = Pubkey::find_program_address(
+        &[stream_account_info.key.as_ref(), STREAM_TERMS_SEED],
+        program_id
+    );
+
+    if stream_terms_address.ne(stream_terms_account_info.key) {
+        return Err(StreamError::InvalidPdaAccount.into());
+    }
+    // Only one proposal at a time
+    if stream_terms_account_info.data_len() > 0 {
+        return Err(StreamError::StreamTermsAlreadyInitialized.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_answer_update<'info>(
+    program_id: &Pubkey,
+    answerer_account_info: &AccountInfo<'info>,
+    proposer_account_info: &AccountInfo<'info>,
+    stream_terms_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    // Check the answerer is the signer
+    if !answerer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the stream terms, the stream and the treasury are owned by the MSP
+    if stream_terms_account_info.owner != program_id || 
+       stream_account_info.owner != program_id || 
+       treasury_account_info.owner != program_id
+    {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if AccountKind::detect(&stream_terms_account_info) != AccountKind::StreamTerms ||
+       AccountKind::detect(&stream_account_info) != AccountKind::StreamV2 
+    {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let stream_terms = StreamTerms::unpack_from_slice(&stream_terms_account_info.data.borrow())?;
+    let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+
+    if stream_terms.stream_id.ne(stream_account_info.key) || 
+       stream_terms.proposed_by.ne(proposer_account_info.key) 
+    {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+    // Check the treasury account info
+    if stream.treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check that only the counterparty of the proposer can answer
+    if get_treasurer_address(&treasury_account_info)?.ne(answerer_account_info.key) && 
+       stream.beneficiary_address.ne(answerer_account_info.key)
+    {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if stream_terms.proposed_by.eq(answerer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_transfer_stream<'info>(
+    program_id: &Pubkey,
+    beneficiary_account_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    new_beneficiary_address: &Pubkey
+
+) -> ProgramResult {
+
+    // Check the beneficiary is the signer
+    if !beneficiary_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the stream account info
+    if stream_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    let beneficiary_address = match AccountKind::detect(&stream_account_info) {
+        AccountKind::StreamV1 => StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?.beneficiary_address,
+        AccountKind::StreamV2 => StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?.beneficiary_address,
+        _ => return Err(StreamError::InvalidStreamData.into())
+    };
+    // Check that only the current beneficiary can transfer the stream
+    if beneficiary_address.ne(beneficiary_account_info.key) {
+        return Err(StreamError::NotAuthorizedToWithdraw.into());
+    }
+    // Check the new beneficiary
+    if new_beneficiary_address.eq(&Pubkey::default()) || new_beneficiary_address.eq(&beneficiary_address) {
+        return Err(StreamError::InvalidArgument.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_transfer_treasury<'info>(
+    program_id: &Pubkey,
+    treasurer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    stream_account_infos: &[AccountInfo<'info>],
+    new_treasurer_address: &Pubkey
+
+) -> ProgramResult {
+
+    // Check the treasurer is the signer
+    if !treasurer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury account info
+    if treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    let stream_kind = match AccountKind::detect(&treasury_account_info) {
+        AccountKind::TreasuryV1 => AccountKind::StreamV1,
+        AccountKind::TreasuryV2 => AccountKind::StreamV2,
+        _ => return Err(StreamError::InvalidTreasuryData.into())
+    };
+    let treasurer_address = get_treasurer_address(&treasury_account_info)?;
+    // Check that only the current treasurer can transfer the treasury
+    if treasurer_address.ne(treasurer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the new treasurer
+    if new_treasurer_address.eq(&Pubkey::default()) || new_treasurer_address.eq(&treasurer_address) {
+        return Err(StreamError::InvalidArgument.into());
+    }
+    // Check the treasury roles account info, it only exists once a role was granted
+    let (treasury_roles_address, _) = find_treasury_roles_address(program_id, treasury_account_info.key);
+
+    if treasury_roles_address.ne(treasury_roles_account_info.key) {
+        return Err(StreamError::InvalidTreasuryRoles.into());
+    }
+
+    if treasury_roles_account_info.data_len() > 0 {
+        get_treasury_roles(program_id, treasury_account_info, treasury_roles_account_info)?;
+    }
+    // The streams are optional, any subset of the treasury streams gets its treasurer copy refreshed
+    for (index, stream_account_info) in stream_account_infos.iter().enumerate() {
+
+        if stream_account_info.owner != program_id || AccountKind::detect(&stream_account_info) != stream_kind {
+            return Err(StreamError::InvalidStreamAccount.into());
+        }
+
+        if stream_account_infos[..index].iter().any(|s| s.key.eq(stream_account_info.key)) {
+            return Err(StreamError::InvalidStreamAccount.into());
+        }
+
+        let treasury_address = match stream_kind {
+            AccountKind::StreamV1 => StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?.treasury_address,
+            _ => StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?.treasury_address
+        };
+
+        if treasury_address.ne(treasury_account_info.key) {
+            return Err(StreamError::InvalidStreamAccount.into());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn check_can_treasury_withdraw<'info>(
+    program_id: &Pubkey,
+    treasurer_account_info: &AccountInfo<'info>,
+    destination_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    amount: u64
+
+) -> ProgramResult {
+
+    // Check system accounts
+    check_system_accounts(
+        Option::None, Option::Some(token_program_account_info), Option::None, Option::None
+    )?;
+    // Check the treasurer is the signer
+    if !treasurer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury account info
+    if treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+    let (treasurer_address, associated_token_address, unallocated_balance) = match AccountKind::detect(&treasury_account_info) {
+        AccountKind::TreasuryV1 => {
+            let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            let scale = TokenScale::new(associated_token_mint.decimals.into());
+            let unallocated_balance = scale.to_base_units(treasury.balance)
+                .checked_sub(scale.to_base_units(treasury.allocation_left))
+                .ok_or(StreamError::Overflow)?;
+
+            (treasury.treasurer_address, treasury.associated_token_address, unallocated_balance)
+        },
+        AccountKind::TreasuryV2 => {
+            let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            let unallocated_balance = treasury.balance
+                .checked_sub(treasury.allocation_left)
+                .ok_or(StreamError::Overflow)?;
+
+            (treasury.treasurer_address, treasury.associated_token_address, unallocated_balance)
+        },
+        _ => return Err(StreamError::InvalidTreasuryData.into())
+    };
+    // Check that only the treasurer can withdraw from the treasury
+    if treasurer_address.ne(treasurer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the treasury associated token
+    if associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check the treasury token account info
+    let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        treasury_account_info.key, associated_token_mint_info.key
+    );
+
+    if treasury_token_address.ne(treasury_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+    // Check the destination token account holds the treasury token
+    let destination_token = spl_token::state::Account::unpack_from_slice(&destination_token_account_info.data.borrow())?;
+
+    if destination_token.mint.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+    // Only the funds that are not allocated to any stream can be withdrawn
+    let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
+
+    if amount == 0 {
+        return Err(StreamError::InvalidArgument.into());
+    }
+
+    if amount > unallocated_balance || amount > treasury_token.amount {
+        return Err(StreamError::AvailableTreasuryAmountExceeded.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_allocate<'info>(
+    program_id: &Pubkey,
+    treasurer_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    amount: u64,
+    allocation_type: u8
+
+) -> ProgramResult {
+
+    // Check the treasurer is the signer
+    if !treasurer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury and the stream are owned by the MSP
+    if treasury_account_info.owner != program_id || stream_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the allocation type (0: assigned, 1: reserved)
+    if amount == 0 || allocation_type > 1 {
+        return Err(StreamError::InvalidArgument.into());
+    }
+
+    let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+    let (treasurer_address, associated_token_address, unallocated_balance, stream_treasury_address) = match (
+        AccountKind::detect(&treasury_account_info), AccountKind::detect(&stream_account_info)
+    ) {
+        (AccountKind::TreasuryV1, AccountKind::StreamV1) => {
+            let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+            let scale = TokenScale::new(associated_token_mint.decimals.into());
+            let unallocated_balance = scale.to_base_units(treasury.balance)
+                .checked_sub(scale.to_base_units(treasury.allocation_left))
+                .ok_or(StreamError::Overflow)?;
+
+            (treasury.treasurer_address, treasury.associated_token_address, unallocated_balance, stream.treasury_address)
+        },
+        (AccountKind::TreasuryV2, AccountKind::StreamV2) => {
+            let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            let unallocated_balance = treasury.balance
+                .checked_sub(treasury.allocation_left)
+                .ok_or(StreamError::Overflow)?;
+
+            (treasury.treasurer_address, treasury.associated_token_address, unallocated_balance, stream.treasury_address)
+        },
+        (AccountKind::TreasuryV1, _) | (AccountKind::TreasuryV2, _) => return Err(StreamError::InvalidStreamData.into()),
+        _ => return Err(StreamError::InvalidTreasuryData.into())
+    };
+    // Check that only the treasurer can allocate the treasury funds
+    if treasurer_address.ne(treasurer_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the stream belongs to the treasury
+    if stream_treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InvalidStreamAccount.into());
+    }
+    // Check the treasury associated token
+    if associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Only the funds that are not allocated to any stream can be allocated
+    if amount > unallocated_balance {
+        return Err(StreamError::AvailableTreasuryAmountExceeded.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_redeem<'info>(
+    program_id: &Pubkey,
+    contributor_account_info: &AccountInfo<'info>,
+    contributor_token_account_info: &AccountInfo<'info>,
+    contributor_treasury_pool_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    treasury_pool_mint_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    amount: u64
+
+) -> ProgramResult {
+
+    // Check system accounts
+    check_system_accounts(
+        Option::None, Option::Some(token_program_account_info), Option::None, Option::None
+    )?;
+    // Check the contributor is the signer
+    if !contributor_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury account info
+    if treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    let (associated_token_address, treasury_pool_mint_address) = match AccountKind::detect(&treasury_account_info) {
+        AccountKind::TreasuryV1 => {
+            let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            (treasury.associated_token_address, treasury.mint_address)
+        },
+        AccountKind::TreasuryV2 => {
+            let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            (treasury.associated_token_address, treasury.mint_address)
+        },
+        _ => return Err(StreamError::InvalidTreasuryData.into())
+    };
+    // Check the treasury associated token
+    if associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check the treasury pool mint
+    if treasury_pool_mint_address.ne(treasury_pool_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryPoolMint.into());
+    }
+    // Check the treasury token account info
+    let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        treasury_account_info.key, associated_token_mint_info.key
+    );
+
+    if treasury_token_address.ne(treasury_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+    // Check the contributor token account holds the treasury token
+    let contributor_token = spl_token::state::Account::unpack_from_slice(&contributor_token_account_info.data.borrow())?;
+
+    if contributor_token.mint.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+    // Check the contributor owns enough treasury pool tokens
+    let contributor_treasury_pool_token = spl_token::state::Account::unpack_from_slice(
+        &contributor_treasury_pool_token_account_info.data.borrow()
+    )?;
+
+    if contributor_treasury_pool_token.mint.ne(treasury_pool_mint_info.key) ||
+       contributor_treasury_pool_token.owner.ne(contributor_account_info.key)
+    {
+        return Err(StreamError::InvalidTreasuryPoolAddress.into());
+    }
+
+    if amount == 0 || amount > contributor_treasury_pool_token.amount {
+        return Err(StreamError::InvalidArgument.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_init_fee_config<'info>(
+    program_id: &Pubkey,
+    initializer_account_info: &AccountInfo<'info>,
+    fee_config_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    check_system_accounts(
+        Option::None, Option::None,
+        Option::Some(rent_account_info), Option::Some(system_account_info)
+    )?;
+    // Only the MSP operations account can initialize the fee config
+    if !initializer_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+
+    if initializer_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap()) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the fee config account info
+    let (fee_config_address, _) = Pubkey::find_program_address(&[FEE_CONFIG_SEED], program_id);
+
+    if fee_config_address.ne(fee_config_account_info.key) {
+        return Err(StreamError::InvalidPdaAccount.into());
+    }
+
+    if fee_config_account_info.data_len() > 0 {
+        return Err(StreamError::InvalidFeeConfig.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_update_fee_config<'info>(
+    program_id: &Pubkey,
+    authority_account_info: &AccountInfo<'info>,
+    fee_config_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    // Check the authority is the signer
+    if !authority_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the fee config account info
+    let fee_config = get_fee_config(program_id, &fee_config_account_info)?;
+
+    if !fee_config.initialized {
+        return Err(StreamError::InvalidFeeConfig.into());
+    }
+
+    if fee_config.authority.ne(authority_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_set_fee_tier<'info>(
+    program_id: &Pubkey,
+    authority_account_info: &AccountInfo<'info>,
+    fee_config_account_info: &AccountInfo<'info>,
+    fee_tier_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    subject_address: &Pubkey
+
+) -> ProgramResult {
+
+    check_system_accounts(
+        Option::None, Option::None,
+        Option::Some(rent_account_info), Option::Some(system_account_info)
+    )?;
+    // Check the authority is the signer
+    if !authority_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Only the fee config authority can manage fee tiers
+    let fee_config = get_fee_config(program_id, &fee_config_account_info)?;
+
+    if fee_config.authority.ne(authority_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the fee tier account info
+    let (fee_tier_address, _) = Pubkey::find_program_address(
+        &[FEE_TIER_SEED, subject_address.as_ref()],
+        program_id
+    );
+
+    if fee_tier_address.ne(fee_tier_account_info.key) {
+        return Err(StreamError::InvalidPdaAccount.into());
+    }
+
+    if fee_tier_account_info.data_len() > 0 &&
+       (fee_tier_account_info.owner != program_id ||
+        AccountKind::detect(&fee_tier_account_info) != AccountKind::FeeTier)
+    {
+        return Err(StreamError::InvalidFeeTier.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_close_fee_tier<'info>(
+    program_id: &Pubkey,
+    authority_account_info: &AccountInfo<'info>,
+    fee_config_account_info: &AccountInfo<'info>,
+    fee_tier_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    // Check the authority is the signer
+    if !authority_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Only the fee config authority can manage fee tiers
+    let fee_config = get_fee_config(program_id, &fee_config_account_info)?;
+
+    if fee_config.authority.ne(authority_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the fee tier account info
+    if fee_tier_account_info.owner != program_id ||
+       AccountKind::detect(&fee_tier_account_info) != AccountKind::FeeTier
+    {
+        return Err(StreamError::InvalidFeeTier.into());
+    }
+
+    let fee_tier = FeeTier::unpack_from_slice(&fee_tier_account_info.data.borrow())?;
+    let (fee_tier_address, _) = Pubkey::find_program_address(
+        &[FEE_TIER_SEED, fee_tier.subject_address.as_ref()],
+        program_id
+    );
+
+    if fee_tier_address.ne(fee_tier_account_info.key) {
+        return Err(StreamError::InvalidPdaAccount.into());
+    }
+
+    Ok(())
+}
+
+fn check_can_manage_treasury_roles<'info>(
+    program_id: &Pubkey,
+    authority_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    roles: u8
+
+) -> ProgramResult {
+
+    // Check the authority is the signer
+    if !authority_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the MSP is the owner of the treasury 
+    if treasury_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    if roles == 0 || roles & !TreasuryRole::ALL > 0 {
+        return Err(StreamError::InvalidArgument.into());
+    }
+    // Check the treasury roles account info
+    let (treasury_roles_address, _) = find_treasury_roles_address(program_id, treasury_account_info.key);
+
+    if treasury_roles_address.ne(treasury_roles_account_info.key) {
+        return Err(StreamError::InvalidPdaAccount.into());
+    }
+
+    if treasury_roles_account_info.data_len() > 0 {
+        get_treasury_roles(program_id, treasury_account_info, treasury_roles_account_info)?;
+    }
+    // The treasurer manages every role, admins manage every role but their own
+    if get_treasurer_address(treasury_account_info)?.eq(authority_account_info.key) {
+        return Ok(());
+    }
+
+    if roles & TreasuryRole::Admin as u8 > 0 || treasury_roles_account_info.data_len() == 0 {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    check_treasury_authority(
+        program_id, authority_account_info, treasury_account_info,
+        Some(treasury_roles_account_info), TreasuryRole::Admin
+    )
+}
+
+pub fn check_can_grant_treasury_roles<'info>(
+    program_id: &Pubkey,
+    authority_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    roles: u8
+
+) -> ProgramResult {
+
+    check_system_accounts(
+        Option::None, Option::None,
+        Option::Some(rent_account_info), Option::Some(system_account_info)
+    )?;
+
+    check_can_manage_treasury_roles(
+        program_id, authority_account_info, treasury_account_info,
+        treasury_roles_account_info, msp_account_info, roles
+    )
+}
+
+pub fn check_can_revoke_treasury_roles<'info>(
+    program_id: &Pubkey,
+    authority_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    roles: u8
+
+) -> ProgramResult {
+
+    if treasury_roles_account_info.data_len() == 0 {
+        return Err(StreamError::InvalidTreasuryRoles.into());
+    }
+
+    check_can_manage_treasury_roles(
+        program_id, authority_account_info, treasury_account_info,
+        treasury_roles_account_info, msp_account_info, roles
+    )
+}
+
+fn check_keeper_token_account<'info>(
+    keeper_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    if keeper_token_account_info.owner.ne(&spl_token::id()) ||
+       keeper_token_account_info.data_len() != spl_token::state::Account::LEN
+    {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+
+    let keeper_token = spl_token::state::Account::unpack_from_slice(&keeper_token_account_info.data.borrow())?;
+
+    if keeper_token.mint.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_set_push_vested<'info>(
+    program_id: &Pubkey,
+    authority_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    enabled: bool
+
+) -> ProgramResult {
+
+    // Check the authority is the signer
+    if !authority_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury and the stream are owned by the MSP
+    if treasury_account_info.owner != program_id || stream_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    let (beneficiary_address, treasury_address) = match (
+        AccountKind::detect(&stream_account_info), AccountKind::detect(&treasury_account_info)
+    ) {
+        (AccountKind::StreamV1, AccountKind::TreasuryV1) => {
+            let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+            (stream.beneficiary_address, stream.treasury_address)
+        },
+        (AccountKind::StreamV2, AccountKind::TreasuryV2) => {
+            let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            (stream.beneficiary_address, stream.treasury_address)
+        },
+        _ => return Err(StreamError::InvalidStreamData.into())
+    };
+    // Check the treasury account info
+    if treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    let treasurer_address = get_treasurer_address(&treasury_account_info)?;
+    // The treasurer pays the keeper tips, the beneficiary can only opt out
+    if treasurer_address.ne(authority_account_info.key) &&
+       (beneficiary_address.ne(authority_account_info.key) || enabled)
+    {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+
+    Ok(())
+}
+
+pub fn check_can_push_vested<'info>(
+    program_id: &Pubkey,
+    keeper_account_info: &AccountInfo<'info>,
+    keeper_token_account_info: &AccountInfo<'info>,
+    beneficiary_account_info: &AccountInfo<'info>,
+    beneficiary_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_treasury_address: &Pubkey,
+    msp_account_info: &AccountInfo<'info>,
+    associated_token_program_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    // Check system accounts
+    check_system_accounts(
+        Option::Some(associated_token_program_account_info),
+        Option::Some(token_program_account_info),
+        Option::Some(rent_account_info),
+        Option::Some(system_account_info)
+    )?;
+    // Check the Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury and the stream are owned by the MSP
+    if treasury_account_info.owner != program_id || stream_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the keeper is the signer
+    if !keeper_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check if the stream and treasury data have a valid size
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV1 {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV1 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+    // Only the streams that opted in can be pushed by anyone
+    if !stream.push_vested_enabled {
+        return Err(StreamError::PushVestedNotEnabled.into());
+    }
+    // Check the beneficiary account info
+    if stream.beneficiary_address.ne(beneficiary_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the beneficiary token account info
+    let beneficiary_token_address = spl_associated_token_account::get_associated_token_address(
+        &stream.beneficiary_address, associated_token_mint_info.key
+    );
+
+    if beneficiary_token_address.ne(beneficiary_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+    // Check the associated token mint account
+    if stream.beneficiary_associated_token.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check treasury account info
+    if stream.treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check treasury token account info
+    let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        &stream.treasury_address, associated_token_mint_info.key
+    );
+
+    if treasury_token_address.ne(treasury_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+
+    let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    // Check the treasury token mint account
+    if treasury.associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check the fee treasury token account info
+    let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        fee_treasury_address, associated_token_mint_info.key
+    );
+
+    if fee_treasury_token_address.ne(fee_treasury_token_account_info.key) {
+        return Err(StreamError::InvalidMspOpsToken.into());
+    }
+    // The keeper token account receives the tip, it has to hold the stream mint
+    if stream.push_vested_tip > 0 {
+        check_keeper_token_account(keeper_token_account_info, associated_token_mint_info)?;
+    }
+
+    Ok(())
+}
+
+pub fn check_can_push_vested_v2<'info>(
+    program_id: &Pubkey,
+    keeper_account_info: &AccountInfo<'info>,
+    keeper_token_account_info: &AccountInfo<'info>,
+    beneficiary_account_info: &AccountInfo<'info>,
+    beneficiary_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    stream_account_info: &AccountInfo<'info>,
+    fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_treasury_address: &Pubkey,
+    msp_account_info: &AccountInfo<'info>,
+    associated_token_program_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    // Check system accounts
+    check_system_accounts(
+        Option::Some(associated_token_program_account_info),
+        Option::Some(token_program_account_info),
+        Option::Some(rent_account_info),
+        Option::Some(system_account_info)
+    )?;
+    // Check the Money Streaming Program account info
+    if msp_account_info.key.ne(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+    // Check the treasury and the stream are owned by the MSP
+    if treasury_account_info.owner != program_id || stream_account_info.owner != program_id {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the keeper is the signer
+    if !keeper_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+    // Check if the stream and treasury data have a valid size
+    if AccountKind::detect(&stream_account_info) != AccountKind::StreamV2 {
+        return Err(StreamError::InvalidStreamData.into());
+    }
+
+    if AccountKind::detect(&treasury_account_info) != AccountKind::TreasuryV2 {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+    // Only the streams that opted in can be pushed by anyone
+    if !stream.push_vested_enabled {
+        return Err(StreamError::PushVestedNotEnabled.into());
+    }
+    // Check the beneficiary account info
+    if stream.beneficiary_address.ne(beneficiary_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check the beneficiary token account info
+    let beneficiary_token_address = spl_associated_token_account::get_associated_token_address(
+        &stream.beneficiary_address, associated_token_mint_info.key
+    );
+
+    if beneficiary_token_address.ne(beneficiary_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+    // Check the associated token mint account
+    if stream.beneficiary_associated_token.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check treasury account info
+    if stream.treasury_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InstructionNotAuthorized.into());
+    }
+    // Check treasury token account info
+    let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        &stream.treasury_address, associated_token_mint_info.key
+    );
+
+    if treasury_token_address.ne(treasury_token_account_info.key) {
+        return Err(StreamError::InvalidAssociatedTokenAccount.into());
+    }
+
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    // Check the treasury token mint account
+    if treasury.associated_token_address.ne(associated_token_mint_info.key) {
+        return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+    }
+    // Check the fee treasury token account info
+    let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
+        fee_treasury_address, associated_token_mint_info.key
+    );
+
+    if fee_treasury_token_address.ne(fee_treasury_token_account_info.key) {
+        return Err(StreamError::InvalidMspOpsToken.into());
+    }
+    // The keeper token account receives the tip, it has to hold the stream mint
+    if stream.push_vested_tip > 0 {
+        check_keeper_token_account(keeper_token_account_info, associated_token_mint_info)?;
+    }
+
+    Ok(())
+}
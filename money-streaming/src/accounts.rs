@@ -0,0 +1,400 @@
+// Instruction accounts, shared by the instruction builders and the processor
+use solana_program::{
+    pubkey::Pubkey,
+    account_info::{ next_account_info, AccountInfo },
+    instruction::AccountMeta,
+    program_error::ProgramError
+};
+
+macro_rules! account_meta {
+    ($key:expr, []) => { AccountMeta::new_readonly($key, false) };
+    ($key:expr, [writable]) => { AccountMeta::new($key, false) };
+    ($key:expr, [signer]) => { AccountMeta::new_readonly($key, true) };
+    ($key:expr, [signer, writable]) => { AccountMeta::new($key, true) };
+}
+
+// Declares the accounts of an instruction once, in the order the processor reads them. The builders
+// use the `Pubkey` flavour to produce the account metas and the processor the `AccountInfo` one
+macro_rules! instruction_accounts {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $( $field:ident: [$($flag:ident),*] ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $name<T> {
+            $( pub $field: T ),*
+        }
+
+        impl<'a, 'info> $name<&'a AccountInfo<'info>> {
+            /// Takes the accounts from the iterator in the order the instruction expects them
+            pub fn next<I: Iterator<Item = &'a AccountInfo<'info>>>(iter: &mut I) -> Result<Self, ProgramError> {
+                Ok(Self { $( $field: next_account_info(iter)? ),* })
+            }
+        }
+
+        impl $name<Pubkey> {
+            /// The account metas of the instruction, in order
+            pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+                vec![ $( account_meta!(self.$field, [$($flag),*]) ),* ]
+            }
+        }
+    };
+}
+
+instruction_accounts! {
+    /// Accounts of `CreateStream`, `CreateStreamV2`, `CreateStreamWithEndDate` and `CreateStreamWithSchedule`
+    pub struct CreateStreamAccounts {
+        treasurer: [signer, writable],
+        treasury: [writable],
+        associated_token_mint: [],
+        beneficiary: [],
+        stream: [signer, writable],
+        fee_treasury: [writable],
+        msp: [],
+        system_program: [],
+        rent: [],
+        fee_config: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `CreateStreamsBatch`, followed by the stream and beneficiary accounts of every stream
+    pub struct CreateStreamsBatchAccounts {
+        treasurer: [signer, writable],
+        treasury: [writable],
+        associated_token_mint: [],
+        fee_treasury: [writable],
+        msp: [],
+        system_program: [],
+        rent: [],
+        fee_config: [],
+        fee_tier: [],
+        treasury_roles: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `AddFunds` and `AddFundsV2`
+    pub struct AddFundsAccounts {
+        contributor: [signer, writable],
+        contributor_token: [writable],
+        contributor_treasury_pool_token: [writable],
+        treasury: [writable],
+        treasury_token: [writable],
+        associated_token_mint: [],
+        treasury_pool_mint: [writable],
+        stream: [writable],
+        fee_treasury: [writable],
+        msp: [],
+        associated_token_program: [],
+        token_program: [],
+        system_program: [],
+        rent: [],
+        fee_config: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `Withdraw`, `WithdrawV2` and `TransferStream`
+    pub struct WithdrawAccounts {
+        beneficiary: [signer, writable],
+        beneficiary_token: [writable],
+        associated_token_mint: [],
+        treasury: [writable],
+        treasury_token: [writable],
+        stream: [writable],
+        fee_treasury: [],
+        fee_treasury_token: [writable],
+        msp: [],
+        associated_token_program: [],
+        token_program: [],
+        rent: [],
+        system_program: [],
+        fee_config: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `PauseStream` and `ResumeStream`
+    pub struct PauseOrResumeStreamAccounts {
+        initializer: [signer],
+        treasury: [writable],
+        associated_token_mint: [],
+        stream: [writable],
+        fee_treasury: [],
+        msp: [],
+        fee_config: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `CloseStream`
+    pub struct CloseStreamAccounts {
+        initializer: [signer, writable],
+        treasurer: [writable],
+        treasurer_token: [writable],
+        treasurer_treasury_pool_token: [writable],
+        beneficiary: [writable],
+        beneficiary_token: [writable],
+        associated_token_mint: [],
+        treasury: [writable],
+        treasury_token: [writable],
+        treasury_pool_mint: [writable],
+        stream: [writable],
+        fee_treasury: [writable],
+        fee_treasury_token: [writable],
+        msp: [],
+        associated_token_program: [],
+        token_program: [],
+        rent: [],
+        system_program: [],
+        fee_config: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `CreateTreasury` and `CreateTreasuryV2`
+    pub struct CreateTreasuryAccounts {
+        treasurer: [signer, writable],
+        treasury: [writable],
+        treasury_pool_mint: [writable],
+        fee_treasury: [writable],
+        msp: [],
+        token_program: [],
+        system_program: [],
+        rent: [],
+        fee_config: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `CloseTreasury`
+    pub struct CloseTreasuryAccounts {
+        treasurer: [signer, writable],
+        treasurer_token: [writable],
+        treasurer_treasury_pool_token: [writable],
+        associated_token_mint: [],
+        treasury: [writable],
+        treasury_token: [writable],
+        treasury_pool_mint: [writable],
+        fee_treasury: [writable],
+        fee_treasury_token: [writable],
+        msp: [],
+        token_program: [],
+        associated_token_program: [],
+        system_program: [],
+        rent: [],
+        fee_config: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `RefreshTreasuryBalance`
+    pub struct RefreshTreasuryBalanceAccounts {
+        treasurer: [signer],
+        associated_token_mint: [],
+        treasury: [writable],
+        treasury_token: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `MigrateStream`
+    pub struct MigrateStreamAccounts {
+        initializer: [signer, writable],
+        treasurer: [],
+        associated_token_mint: [],
+        stream: [writable],
+        msp: [],
+        system_program: [],
+        rent: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `MigrateTreasury`, followed by the stream accounts of the treasury
+    pub struct MigrateTreasuryAccounts {
+        initializer: [signer, writable],
+        treasurer: [],
+        associated_token_mint: [],
+        treasury: [writable],
+        treasury_token: [],
+        msp: [],
+        system_program: [],
+        rent: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `ProposeUpdate`
+    pub struct ProposeUpdateAccounts {
+        proposer: [signer, writable],
+        stream_terms: [writable],
+        treasury: [],
+        stream: [],
+        fee_treasury: [writable],
+        msp: [],
+        system_program: [],
+        rent: [],
+        fee_config: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `AnswerUpdate`
+    pub struct AnswerUpdateAccounts {
+        answerer: [signer],
+        proposer: [writable],
+        stream_terms: [writable],
+        treasury: [writable],
+        stream: [writable],
+        msp: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `TransferTreasury`, followed by the stream accounts of the treasury
+    pub struct TransferTreasuryAccounts {
+        treasurer: [signer, writable],
+        treasury: [writable],
+        treasury_roles: [writable],
+        msp: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `TreasuryWithdraw`
+    pub struct TreasuryWithdrawAccounts {
+        treasurer: [signer],
+        destination_token: [writable],
+        associated_token_mint: [],
+        treasury: [writable],
+        treasury_token: [writable],
+        msp: [],
+        token_program: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `Allocate`
+    pub struct AllocateAccounts {
+        treasurer: [signer],
+        associated_token_mint: [],
+        treasury: [writable],
+        stream: [writable],
+        msp: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `Redeem`
+    pub struct RedeemAccounts {
+        contributor: [signer],
+        contributor_token: [writable],
+        contributor_treasury_pool_token: [writable],
+        associated_token_mint: [],
+        treasury: [writable],
+        treasury_token: [writable],
+        treasury_pool_mint: [writable],
+        msp: [],
+        token_program: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `InitFeeConfig`
+    pub struct InitFeeConfigAccounts {
+        initializer: [signer, writable],
+        fee_config: [writable],
+        msp: [],
+        system_program: [],
+        rent: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `UpdateFeeConfig`
+    pub struct UpdateFeeConfigAccounts {
+        authority: [signer],
+        fee_config: [writable],
+        msp: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `SetFeeTier`
+    pub struct SetFeeTierAccounts {
+        authority: [signer, writable],
+        fee_config: [],
+        fee_tier: [writable],
+        msp: [],
+        system_program: [],
+        rent: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `CloseFeeTier`
+    pub struct CloseFeeTierAccounts {
+        authority: [signer, writable],
+        fee_config: [],
+        fee_tier: [writable],
+        msp: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `GrantTreasuryRoles`
+    pub struct GrantTreasuryRolesAccounts {
+        authority: [signer, writable],
+        treasury: [],
+        treasury_roles: [writable],
+        msp: [],
+        system_program: [],
+        rent: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `RevokeTreasuryRoles`
+    pub struct RevokeTreasuryRolesAccounts {
+        authority: [signer],
+        treasury: [],
+        treasury_roles: [writable],
+        msp: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `SetPushVested`
+    pub struct SetPushVestedAccounts {
+        authority: [signer],
+        treasury: [],
+        stream: [writable],
+        msp: []
+    }
+}
+
+instruction_accounts! {
+    /// Accounts of `PushVested`
+    pub struct PushVestedAccounts {
+        keeper: [signer, writable],
+        keeper_token: [writable],
+        beneficiary: [],
+        beneficiary_token: [writable],
+        associated_token_mint: [],
+        treasury: [writable],
+        treasury_token: [writable],
+        stream: [writable],
+        fee_treasury: [],
+        fee_treasury_token: [writable],
+        msp: [],
+        associated_token_program: [],
+        token_program: [],
+        rent: [],
+        system_program: [],
+        fee_config: []
+    }
+}
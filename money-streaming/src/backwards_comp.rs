@@ -1,18 +1,20 @@
 use std::cmp;
-use num_traits;
 use std::{ convert::TryInto };
 use crate::error::StreamError;
 use crate::state::*;
-use crate::constants::*;
+use crate::constants::RATE_PRECISION;
+use crate::math::get_depletion_time;
 use crate::utils::*;
 use crate::extensions::*;
 use crate::account_validations::*;
+use crate::events::MspEvent;
 use solana_program::{
     // msg,
     program::{ invoke, invoke_signed },
     pubkey::Pubkey,
     account_info::AccountInfo,
     entrypoint::ProgramResult,
+    program_error::ProgramError,
     program_pack::{ Pack },
     sysvar::{ clock::Clock, Sysvar } 
 };
@@ -103,15 +105,15 @@ pub fn get_beneficiary_withdrawable_amount_v0<'info>(
     }
 
     //Check if PAUSED
-    let pow = num_traits::pow(10f64, decimals.try_into().unwrap());
-    let allocation_left = ((stream.total_deposits * pow) as u64)
-        .checked_sub((stream.total_withdrawals * pow) as u64)
+    let scale = TokenScale::new(decimals);
+    let allocation_left = scale.to_base_units(stream.total_deposits)
+        .checked_sub(scale.to_base_units(stream.total_withdrawals))
         .ok_or(StreamError::Overflow)?;
 
     if status == StreamStatus::Paused {
         let is_manual_pause = stream.escrow_vested_amount_snap_block_time > stream.stream_resumed_block_time;
         let paused_withdrawable = match is_manual_pause {
-            true => (stream.escrow_vested_amount_snap * pow) as u64,
+            true => scale.to_base_units(stream.escrow_vested_amount_snap),
             _ => allocation_left
         };
         return Ok(paused_withdrawable);
@@ -127,11 +129,11 @@ pub fn get_beneficiary_withdrawable_amount_v0<'info>(
                                                 .checked_sub(block_time_at_last_snap_or_resume)
                                                 .ok_or(StreamError::Overflow)?;
     let vested_amount_since_last_snap_or_resume = rate_amount_per_second * elapsed_time_since_last_snap_or_resume as f64; 
-    let allocation_left_vested_amount = ((stream.escrow_vested_amount_snap * pow) as u64)
-                                        .checked_add((vested_amount_since_last_snap_or_resume * pow) as u64)
+    let allocation_left_vested_amount = scale.to_base_units(stream.escrow_vested_amount_snap)
+                                        .checked_add(scale.to_base_units(vested_amount_since_last_snap_or_resume))
                                         .ok_or(StreamError::Overflow)?;
-    let stream_allocation_left = ((stream.total_deposits * pow) as u64)
-        .checked_sub((stream.total_withdrawals * pow) as u64)
+    let stream_allocation_left = scale.to_base_units(stream.total_deposits)
+        .checked_sub(scale.to_base_units(stream.total_withdrawals))
         .ok_or(StreamError::Overflow)?;
     let withdrawable = cmp::min(stream_allocation_left, allocation_left_vested_amount);
     return Ok(withdrawable);
@@ -141,6 +143,7 @@ pub fn add_funds_v0<'info>(
     program_id: &Pubkey,
     msp_account_info: &AccountInfo<'info>,
     fee_treasury_account_info: &AccountInfo<'info>,
+    fee_config: &FeeConfig,
     associated_token_program_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>,
     system_account_info: &AccountInfo<'info>,
@@ -178,19 +181,37 @@ pub fn add_funds_v0<'info>(
         &token_program_account_info, amount
     )?;
 
-    if stream_account_info.data_len() == Stream::LEN {
+    let stream = if AccountKind::detect(&stream_account_info) == AccountKind::Stream {
         let clock = Clock::get()?;
         let _ = add_funds_update_stream_v0(
             &stream_account_info, &associated_token_mint_info, &clock, amount
         )?;
-    }
+
+        *stream_account_info.key
+    } else {
+        Pubkey::default()
+    };
     // Pay fees
     transfer_sol_fee(
         &system_account_info,
         &contributor_account_info,
         &fee_treasury_account_info, 
-        ADD_FUNDS_FLAT_FEE
-    )
+        fee_config.add_funds_flat_fee
+    )?;
+
+    let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    // Legacy treasuries have no allocation types, deposits count as assigned
+    MspEvent::FundsAdded {
+        treasury: *treasury_account_info.key,
+        stream,
+        contributor: *contributor_account_info.key,
+        mint: *associated_token_mint_info.key,
+        amount: scale.to_base_units(amount),
+        allocation_type: 0
+    }.emit();
+
+    Ok(())
 }
 
 pub fn withdraw_v0<'info>(
@@ -203,6 +224,7 @@ pub fn withdraw_v0<'info>(
     stream_account_info: &AccountInfo<'info>,
     fee_treasury_account_info: &AccountInfo<'info>,
     fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_config: &FeeConfig,
     msp_account_info: &AccountInfo<'info>,
     associated_token_program_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>,
@@ -217,7 +239,7 @@ pub fn withdraw_v0<'info>(
         program_id, &beneficiary_account_info, &beneficiary_token_account_info,
         &associated_token_mint_info, &treasury_account_info,
         &treasury_token_account_info, &stream_account_info,
-        &fee_treasury_token_account_info, &msp_account_info,
+        &fee_treasury_token_account_info, &fee_config.fee_treasury_address, &msp_account_info,
         &associated_token_program_account_info, &token_program_account_info,
         &rent_account_info, &system_account_info
     )?;
@@ -228,10 +250,10 @@ pub fn withdraw_v0<'info>(
         &stream, &clock, associated_token_mint.decimals.into()
     )?;
 
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
     let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
-    let stream_allocation = ((stream.total_deposits * pow) as u64)
-        .checked_sub((stream.total_withdrawals * pow) as u64)
+    let stream_allocation = scale.to_base_units(stream.total_deposits)
+        .checked_sub(scale.to_base_units(stream.total_withdrawals))
         .ok_or(StreamError::Overflow)?;
 
     if stream_allocation > 0 && escrow_vested_amount > stream_allocation {
@@ -240,7 +262,7 @@ pub fn withdraw_v0<'info>(
         escrow_vested_amount = treasury_token.amount;
     }
 
-    let transfer_amount = (amount * pow) as u64;
+    let transfer_amount = scale.to_base_units(amount);
 
     if transfer_amount > escrow_vested_amount {
         return Err(StreamError::NotAllowedWithdrawalAmount.into());
@@ -272,15 +294,26 @@ pub fn withdraw_v0<'info>(
         )?;
     }
     
-    let fee = WITHDRAW_PERCENT_FEE * transfer_amount as f64 / 100f64;
+    let fee = get_percent_fee(transfer_amount, fee_config.withdraw_percent_fee)?;
     // Pay fees
     transfer_token_fee(
         &token_program_account_info,
         &beneficiary_token_account_info,
         &fee_treasury_token_account_info,
         &beneficiary_account_info,
-        fee as u64
-    )
+        fee
+    )?;
+
+    MspEvent::Withdrawn {
+        stream: *stream_account_info.key,
+        treasury: *treasury_account_info.key,
+        beneficiary: *beneficiary_account_info.key,
+        mint: *associated_token_mint_info.key,
+        amount: transfer_amount,
+        fee
+    }.emit();
+
+    Ok(())
 }
 
 pub fn close_treasury_v0<'info>(
@@ -406,6 +439,7 @@ pub fn close_stream_v0<'info>(
     stream_account_info: &AccountInfo<'info>,
     fee_treasury_account_info: &AccountInfo<'info>,
     fee_treasury_token_account_info: &AccountInfo<'info>,
+    fee_config: &FeeConfig,
     msp_account_info: &AccountInfo<'info>,
     associated_token_program_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>,
@@ -420,7 +454,7 @@ pub fn close_stream_v0<'info>(
         &treasurer_token_account_info, &beneficiary_account_info, &beneficiary_token_account_info,
         &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
         &treasury_pool_mint_info, &stream_account_info, &fee_treasury_token_account_info,
-        &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
+        &fee_config.fee_treasury_address, &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
         &rent_account_info, &system_account_info
     )?;
 
@@ -430,7 +464,7 @@ pub fn close_stream_v0<'info>(
     let mut escrow_vested_amount = get_beneficiary_withdrawable_amount_v0(
         &stream, &clock, associated_token_mint.decimals.into()
     )?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
     let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
 
     if stream.total_deposits < 0.0 {
@@ -444,8 +478,8 @@ pub fn close_stream_v0<'info>(
     let mut stream_allocation = 0;
 
     if stream.total_deposits >= stream.total_withdrawals {
-        stream_allocation = ((stream.total_deposits * pow) as u64)
-            .checked_sub((stream.total_withdrawals * pow) as u64)
+        stream_allocation = scale.to_base_units(stream.total_deposits)
+            .checked_sub(scale.to_base_units(stream.total_withdrawals))
             .ok_or(StreamError::Overflow)?;
     }
 
@@ -458,7 +492,7 @@ pub fn close_stream_v0<'info>(
     }
 
     // Pausing the stream
-    stream.escrow_vested_amount_snap = escrow_vested_amount as f64 / pow;
+    stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount);
     stream.escrow_vested_amount_snap_block_height = clock.slot as u64;
     stream.escrow_vested_amount_snap_block_time = clock.unix_timestamp as u64;
 
@@ -468,16 +502,19 @@ pub fn close_stream_v0<'info>(
             &beneficiary_account_info, &beneficiary_token_account_info, &associated_token_mint_info,
             &fee_treasury_account_info, &fee_treasury_token_account_info, &msp_account_info,
             &associated_token_program_account_info, &token_program_account_info,
-            &rent_account_info, &system_account_info, escrow_vested_amount
+            &rent_account_info, &system_account_info, escrow_vested_amount,
+            fee_config.close_stream_percent_fee
         )?;
     }
     // Debit fees from the initializer of the instruction
-    let _ = transfer_sol_fee(
+    transfer_sol_fee(
         &system_account_info, &initializer_account_info,
-        &fee_treasury_account_info, CLOSE_STREAM_FLAT_FEE
-    );
+        &fee_treasury_account_info, fee_config.close_stream_flat_fee
+    )?;
+
+    let treasury_closed = close_treasury && stream.treasurer_address.eq(initializer_account_info.key);
 
-    if close_treasury == true && stream.treasurer_address.eq(initializer_account_info.key) {
+    if treasury_closed {
         let _ = close_treasury_v0(
             program_id, &treasurer_account_info, &treasurer_token_account_info,
             &treasurer_treasury_pool_token_account_info, &associated_token_mint_info,
@@ -494,6 +531,20 @@ pub fn close_stream_v0<'info>(
         .checked_add(stream_lamports)
         .ok_or(StreamError::Overflow)?;
 
+    MspEvent::StreamClosed {
+        stream: *stream_account_info.key,
+        treasury: *treasury_account_info.key,
+        initializer: *initializer_account_info.key,
+        treasury_closed
+    }.emit();
+
+    if treasury_closed {
+        MspEvent::TreasuryClosed {
+            treasury: *treasury_account_info.key,
+            treasurer: *treasurer_account_info.key
+        }.emit();
+    }
+
     Ok(())
 }
 
@@ -524,8 +575,8 @@ pub fn create_deposit_receipt_v0<'info>(
         &[treasury_pool_bump_seed]
     ];
 
-    let pow = num_traits::pow(10f64, treasury_pool_mint.decimals.into());
-    let mint_amount = (amount * pow) as u64;
+    let scale = TokenScale::new(treasury_pool_mint.decimals.into());
+    let mint_amount = scale.to_base_units(amount);
 
     let mint_to_ix = spl_token::instruction::mint_to(
         token_program_account_info.key,
@@ -564,22 +615,20 @@ pub fn add_funds_update_stream_v0<'info>(
         &stream, &clock, associated_token_mint.decimals.try_into().unwrap()
     )?;
 
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-    let max_vested_amount = ((stream.total_deposits * pow) as u64)
-        .checked_sub((stream.total_withdrawals * pow) as u64)
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    let max_vested_amount = scale.to_base_units(stream.total_deposits)
+        .checked_sub(scale.to_base_units(stream.total_withdrawals))
         .ok_or(StreamError::Overflow)?;
 
     // Pause because the allocation amount was reached
     if escrow_vested_amount == max_vested_amount
     {
-        stream.escrow_vested_amount_snap = escrow_vested_amount as f64 / pow;
+        stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount);
         stream.escrow_vested_amount_snap_block_height = current_block_height;
         stream.escrow_vested_amount_snap_block_time = current_block_time;
     }
 
-    stream.total_deposits = ((stream.total_deposits * pow) as u64)
-        .checked_add((amount * pow) as u64)
-        .ok_or(StreamError::Overflow)? as f64 / pow;
+    stream.total_deposits = scale.checked_add(stream.total_deposits, scale.to_base_units(amount))?;
 
     // if it was paused before because of lack of money then resume it again 
     if escrow_vested_amount == max_vested_amount
@@ -604,14 +653,14 @@ pub fn withdraw_funds_update_stream_v0<'info>(
 ) -> ProgramResult {
 
     let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
 
     // Update stream account data
     let escrow_vested_amount_snap = vested_amount
         .checked_sub(transfer_amount)
         .ok_or(StreamError::Overflow)?;
 
-    stream.escrow_vested_amount_snap = escrow_vested_amount_snap as f64 / pow;
+    stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount_snap);
     let status = get_stream_status_v0(stream, clock)?;
 
     if status == StreamStatus::Running {
@@ -619,9 +668,7 @@ pub fn withdraw_funds_update_stream_v0<'info>(
         stream.stream_resumed_block_time = clock.unix_timestamp as u64;
     }
 
-    stream.total_withdrawals = ((stream.total_withdrawals * pow) as u64)
-        .checked_add(transfer_amount)
-        .ok_or(StreamError::Overflow)? as f64 / pow;    
+    stream.total_withdrawals = scale.checked_add(stream.total_withdrawals, transfer_amount)?;
 
     // Save
     Stream::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
@@ -643,7 +690,8 @@ pub fn close_stream_transfer_vested_amount_v0<'info>(
     token_program_account_info: &AccountInfo<'info>,
     rent_account_info: &AccountInfo<'info>,
     system_account_info: &AccountInfo<'info>,
-    vested_amount: u64
+    vested_amount: u64,
+    close_stream_percent_fee: u64
 
 ) -> ProgramResult {
 
@@ -655,7 +703,7 @@ pub fn close_stream_transfer_vested_amount_v0<'info>(
         );
     }
 
-    let fee = (CLOSE_STREAM_PERCENT_FEE * vested_amount as f64 / 100f64) as u64;
+    let fee = get_percent_fee(vested_amount, close_stream_percent_fee)?;
     let transfer_amount = vested_amount
         .checked_sub(fee)
         .ok_or(StreamError::Overflow)?;
@@ -683,4 +731,118 @@ pub fn close_stream_transfer_vested_amount_v0<'info>(
         &fee_treasury_token_account_info,
         fee
     )
-}
\ No newline at end of file
+}
+
+pub fn migrate_stream_v0(
+    stream: &Stream,
+    decimals: u64
+
+) -> Result<StreamV1, StreamError> {
+
+    let scale = TokenScale::new(decimals);
+    let total_deposits = scale.to_base_units(stream.total_deposits);
+    let allocation_left = total_deposits
+        .checked_sub(scale.to_base_units(stream.total_withdrawals))
+        .ok_or(StreamError::Overflow)?;
+
+    Ok(StreamV1 {
+        initialized: stream.initialized,
+        stream_name: stream.stream_name.clone(),
+        treasurer_address: stream.treasurer_address,
+        rate_amount: stream.rate_amount,
+        rate_interval_in_seconds: stream.rate_interval_in_seconds,
+        funded_on_utc: stream.funded_on_utc,
+        start_utc: stream.start_utc,
+        rate_cliff_in_seconds: stream.rate_cliff_in_seconds,
+        cliff_vest_amount: stream.cliff_vest_amount,
+        cliff_vest_percent: stream.cliff_vest_percent,
+        beneficiary_address: stream.beneficiary_address,
+        beneficiary_associated_token: stream.beneficiary_associated_token,
+        treasury_address: stream.treasury_address,
+        treasury_estimated_depletion_utc: stream.treasury_estimated_depletion_utc,
+        // Legacy streams have no reserved allocation, everything deposited is assigned
+        allocation_reserved: 0.0,
+        allocation_left: scale.from_base_units(allocation_left),
+        escrow_vested_amount_snap: stream.escrow_vested_amount_snap,
+        // The legacy block height snaps were always taken from the clock slot
+        escrow_vested_amount_snap_slot: stream.escrow_vested_amount_snap_block_height,
+        escrow_vested_amount_snap_block_time: stream.escrow_vested_amount_snap_block_time,
+        stream_resumed_slot: stream.stream_resumed_block_height,
+        stream_resumed_block_time: stream.stream_resumed_block_time,
+        auto_pause_in_seconds: stream.auto_pause_in_seconds,
+        allocation_assigned: scale.from_base_units(total_deposits),
+        push_vested_enabled: false,
+        push_vested_tip: 0,
+        push_vested_tip_block_time: 0
+    })
+}
+
+pub fn migrate_treasury_v0<'info>(
+    treasury: &Treasury,
+    associated_token_mint: &Pubkey,
+    stream_account_infos: &[AccountInfo<'info>],
+    clock: &Clock,
+    balance: u64,
+    decimals: u64
+
+) -> Result<TreasuryV1, ProgramError> {
+
+    let scale = TokenScale::new(decimals);
+    let mut allocation_assigned = 0u64;
+    let mut allocation_left = 0u64;
+    let mut allocation_reserved = 0u64;
+    let mut depletion_rate = 0u64;
+
+    for stream_account_info in stream_account_infos.iter() {
+
+        let stream = match AccountKind::detect(&stream_account_info) {
+            AccountKind::Stream => migrate_stream_v0(
+                &Stream::unpack_from_slice(&stream_account_info.data.borrow())?, decimals
+            )?,
+            _ => StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?
+        };
+
+        allocation_assigned = allocation_assigned
+            .checked_add(scale.to_base_units(stream.allocation_assigned))
+            .ok_or(StreamError::Overflow)?;
+
+        allocation_left = allocation_left
+            .checked_add(scale.to_base_units(stream.allocation_left))
+            .ok_or(StreamError::Overflow)?;
+
+        allocation_reserved = allocation_reserved
+            .checked_add(scale.to_base_units(stream.allocation_reserved))
+            .ok_or(StreamError::Overflow)?;
+
+        // Paused streams are not depleting the treasury
+        if get_stream_status(&stream, clock)? != StreamStatus::Paused && stream.rate_interval_in_seconds > 0 {
+            let rate = stream.rate_amount / stream.rate_interval_in_seconds as f64;
+            depletion_rate = depletion_rate
+                .checked_add(scale.to_base_units(rate))
+                .ok_or(StreamError::Overflow)?;
+        }
+    }
+
+    Ok(TreasuryV1 {
+        initialized: treasury.initialized,
+        slot: treasury.treasury_block_height,
+        treasurer_address: treasury.treasury_base_address,
+        associated_token_address: *associated_token_mint,
+        mint_address: treasury.treasury_mint_address,
+        label: "\0".repeat(32), // Legacy treasuries had no label, the field is kept blank
+        balance: scale.from_base_units(balance),
+        allocation_reserved: scale.from_base_units(allocation_reserved),
+        allocation_left: scale.from_base_units(allocation_left),
+        streams_amount: stream_account_infos.len() as u64,
+        created_on_utc: 0, // Legacy treasuries did not keep track of the creation date
+        depletion_rate: scale.from_base_units(depletion_rate),
+        treasury_type: 0,
+        auto_close: false,
+        allocation_assigned: scale.from_base_units(allocation_assigned),
+        seed_address: treasury.treasury_base_address,
+        bump: 0,
+        estimated_depletion_utc: get_depletion_utc(get_depletion_time(
+            balance, depletion_rate as u128 * RATE_PRECISION, clock.unix_timestamp as u64
+        ))
+    })
+}
@@ -1,14 +1,37 @@
 // Accounts
 pub const FEE_TREASURY_ACCOUNT: &str = "3TD6SWY9M1mLY2kZWJNavPLhwXvcRsWdnZLRaMzERJBw";
-// Fee constants
-pub const CREATE_TREASURY_FLAT_FEE: f64 = 0.00001;
-pub const CREATE_STREAM_FLAT_FEE: f64 = 0.00001;
-pub const ADD_FUNDS_FLAT_FEE: f64 = 0.000025;
-pub const WITHDRAW_PERCENT_FEE: f64 = 0.25;
-pub const PROPOSE_UPDATE_FLAT_FEE: f64 = 0.00001;
-pub const CLOSE_STREAM_FLAT_FEE: f64 = 0.00001;
-pub const CLOSE_STREAM_PERCENT_FEE: f64 = 0.25;
-pub const CLOSE_TREASURY_FLAT_FEE: f64 = 0.00001;
-pub const CLOSE_TREASURY_PERCENT_FEE: f64 = 0.25;
+// Fee constants (defaults until the fee config account is initialized)
+pub const CREATE_TREASURY_FLAT_FEE: u64 = 10_000; // lamports
+pub const CREATE_STREAM_FLAT_FEE: u64 = 10_000; // lamports
+pub const ADD_FUNDS_FLAT_FEE: u64 = 25_000; // lamports
+pub const WITHDRAW_PERCENT_FEE: u64 = 25; // basis points
+pub const PROPOSE_UPDATE_FLAT_FEE: u64 = 10_000; // lamports
+pub const CLOSE_STREAM_FLAT_FEE: u64 = 10_000; // lamports
+pub const CLOSE_STREAM_PERCENT_FEE: u64 = 25; // basis points
+pub const CLOSE_TREASURY_FLAT_FEE: u64 = 10_000; // lamports
+pub const CLOSE_TREASURY_PERCENT_FEE: u64 = 25; // basis points
+pub const PUSH_VESTED_MAX_TIP_PERCENT: u64 = 1_000; // basis points of the pushed amount
 pub const LAMPORTS_PER_SOL: u64 = 1000000000;
-pub const TREASURY_POOL_MINT_DECIMALS: u8 = 6;
\ No newline at end of file
+pub const TREASURY_POOL_MINT_DECIMALS: u8 = 6;
+// Fixed point constants
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const RATE_PRECISION: u128 = 1_000_000_000;
+// Account header constants (legacy accounts start with the `initialized` flag, so discriminators skip 0 and 1)
+pub const STREAM_ACCOUNT_DISCRIMINATOR: u8 = 2;
+pub const TREASURY_ACCOUNT_DISCRIMINATOR: u8 = 3;
+pub const STREAM_ACCOUNT_VERSION: u8 = 2;
+pub const TREASURY_ACCOUNT_VERSION: u8 = 2;
+pub const STREAM_TERMS_ACCOUNT_DISCRIMINATOR: u8 = 4;
+pub const STREAM_TERMS_ACCOUNT_VERSION: u8 = 1;
+pub const FEE_CONFIG_ACCOUNT_DISCRIMINATOR: u8 = 5;
+pub const FEE_CONFIG_ACCOUNT_VERSION: u8 = 1;
+pub const FEE_TIER_ACCOUNT_DISCRIMINATOR: u8 = 6;
+pub const FEE_TIER_ACCOUNT_VERSION: u8 = 1;
+pub const TREASURY_ROLES_ACCOUNT_DISCRIMINATOR: u8 = 7;
+pub const TREASURY_ROLES_ACCOUNT_VERSION: u8 = 1;
+pub const MAX_TREASURY_ROLE_MEMBERS: usize = 10;
+// PDA seeds
+pub const STREAM_TERMS_SEED: &[u8] = b"stream_terms";
+pub const FEE_CONFIG_SEED: &[u8] = b"fee_config";
+pub const FEE_TIER_SEED: &[u8] = b"fee_tier";
+pub const TREASURY_ROLES_SEED: &[u8] = b"treasury_roles";
@@ -10,8 +10,7 @@ use solana_program::{
 
 use crate::{ 
     processor::Processor,
-    error::StreamError,
-    constants::FEE_TREASURY_ACCOUNT
+    error::StreamError
 };
 
 entrypoint!(process_instruction);
@@ -23,13 +22,6 @@ pub fn process_instruction(
 
 ) -> ProgramResult {
 
-    let msp_ops_account = FEE_TREASURY_ACCOUNT.parse().unwrap();
-    
-    if let Err(error) = verify_msp_ops_account(&msp_ops_account, accounts) {
-        error.print::<StreamError>();
-        return Err(error);
-    }
-
     if let Err(error) = Processor::process(program_id, accounts, instruction_data) {
         error.print::<StreamError>();
         return Err(error);
@@ -37,17 +29,3 @@ pub fn process_instruction(
 
     Ok(())
 }
-
-fn verify_msp_ops_account(
-    account_to_verify: &Pubkey,
-    accounts: &[AccountInfo]
-) -> ProgramResult {
-
-    let msp_account_valid = accounts.iter().any(|a| a.key.eq(account_to_verify));
-
-    if !msp_account_valid {
-        return Err(StreamError::InstructionNotAuthorized.into());
-    }
-
-    Ok(())
-}
\ No newline at end of file
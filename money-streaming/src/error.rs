@@ -108,6 +108,27 @@ pub enum StreamError {
 
     #[error("InvalidAssignedAllocation")]
     InvalidAssignedAllocation,
+
+    #[error("InvalidStreamStatus")]
+    InvalidStreamStatus,
+
+    #[error("VestedAmountMismatch")]
+    VestedAmountMismatch,
+
+    #[error("InvalidFeeConfig")]
+    InvalidFeeConfig,
+
+    #[error("InvalidFeeTier")]
+    InvalidFeeTier,
+
+    #[error("InvalidTreasuryRoles")]
+    InvalidTreasuryRoles,
+
+    #[error("TreasuryRolesFull")]
+    TreasuryRolesFull,
+
+    #[error("PushVestedNotEnabled")]
+    PushVestedNotEnabled,
 }
 
 impl From<StreamError> for ProgramError {
@@ -157,6 +178,13 @@ impl PrintProgramError for StreamError {
             Self::InvalidStreamAccount => msg!("Error: Invalid stream account is not valid"),
             Self::InvalidAssociatedTokenAccount => msg!("Error: The associated token account is not valid"),
             Self::InvalidAssignedAllocation => msg!("Error: The allocation assigned is not valid"),
+            Self::InvalidStreamStatus => msg!("Error: The current status of the stream does not allow this action"),
+            Self::VestedAmountMismatch => msg!("Error: The vested amount changed while migrating the account"),
+            Self::InvalidFeeConfig => msg!("Error: The fee config account is not valid"),
+            Self::InvalidFeeTier => msg!("Error: The fee tier account is not valid for this treasury"),
+            Self::InvalidTreasuryRoles => msg!("Error: The treasury roles account is not valid for this treasury"),
+            Self::TreasuryRolesFull => msg!("Error: The treasury roles account has no room for more members"),
+            Self::PushVestedNotEnabled => msg!("Error: The stream does not allow pushing the vested funds"),
         }
     }
 }
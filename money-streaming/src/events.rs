@@ -0,0 +1,160 @@
+// Program events
+
+use borsh::{ BorshSerialize, BorshDeserialize };
+use base64::{ Engine as _, engine::general_purpose::STANDARD };
+use solana_program::{
+    pubkey::Pubkey,
+    log::sol_log_data
+};
+
+/// First data item of every event log, used to tell MSP events apart from other `Program data` logs
+pub const MSP_EVENT_TAG: &[u8] = b"msp_event";
+/// Prefix the runtime adds to the logs emitted with `sol_log_data`
+pub const PROGRAM_DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// Events emitted by the processor once an instruction succeeds. Token amounts are
+/// always in base units and timestamps are unix time in seconds.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum MspEvent {
+    StreamCreated {
+        stream: Pubkey,
+        treasury: Pubkey,
+        treasurer: Pubkey,
+        beneficiary: Pubkey,
+        mint: Pubkey,
+        rate_amount: u64,
+        rate_interval_in_seconds: u64,
+        allocation_assigned: u64,
+        start_utc: u64
+    },
+    FundsAdded {
+        treasury: Pubkey,
+        stream: Pubkey,
+        contributor: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+        allocation_type: u8
+    },
+    Withdrawn {
+        stream: Pubkey,
+        treasury: Pubkey,
+        beneficiary: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+        fee: u64
+    },
+    Paused {
+        stream: Pubkey,
+        initializer: Pubkey,
+        vested_amount: u64,
+        timestamp: u64
+    },
+    Resumed {
+        stream: Pubkey,
+        initializer: Pubkey,
+        timestamp: u64
+    },
+    StreamClosed {
+        stream: Pubkey,
+        treasury: Pubkey,
+        initializer: Pubkey,
+        treasury_closed: bool
+    },
+    TreasuryCreated {
+        treasury: Pubkey,
+        treasurer: Pubkey,
+        treasury_pool_mint: Pubkey,
+        treasury_type: u8,
+        auto_close: bool
+    },
+    TreasuryClosed {
+        treasury: Pubkey,
+        treasurer: Pubkey
+    },
+    VestedPushed {
+        stream: Pubkey,
+        treasury: Pubkey,
+        beneficiary: Pubkey,
+        keeper: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+        fee: u64,
+        keeper_tip: u64
+    },
+    TreasuryWithdrawn {
+        treasury: Pubkey,
+        treasurer: Pubkey,
+        destination: Pubkey,
+        mint: Pubkey,
+        amount: u64
+    },
+    FundsAllocated {
+        treasury: Pubkey,
+        stream: Pubkey,
+        treasurer: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+        allocation_type: u8
+    },
+    Redeemed {
+        treasury: Pubkey,
+        contributor: Pubkey,
+        mint: Pubkey,
+        burned_amount: u64, // treasury pool tokens
+        amount: u64
+    },
+    StreamTransferred {
+        stream: Pubkey,
+        previous_beneficiary: Pubkey,
+        new_beneficiary: Pubkey,
+        settled_amount: u64
+    },
+    TreasuryTransferred {
+        treasury: Pubkey,
+        previous_treasurer: Pubkey,
+        new_treasurer: Pubkey
+    },
+    StreamMigrated {
+        stream: Pubkey,
+        initializer: Pubkey
+    },
+    TreasuryMigrated {
+        treasury: Pubkey,
+        initializer: Pubkey,
+        balance: u64
+    }
+}
+
+impl MspEvent {
+
+    /// Logs the event as `Program data: <base64 tag> <base64 event>`
+    pub fn emit(&self) {
+        let data = self.try_to_vec().unwrap();
+        sol_log_data(&[MSP_EVENT_TAG, &data]);
+    }
+
+    /// Parses a single transaction log line, returns `None` if it is not an MSP event
+    pub fn from_log(log: &str) -> Option<Self> {
+        let mut items = log.strip_prefix(PROGRAM_DATA_LOG_PREFIX)?.split(' ');
+        let tag = STANDARD.decode(items.next()?).ok()?;
+
+        if tag != MSP_EVENT_TAG {
+            return None;
+        }
+
+        let data = STANDARD.decode(items.next()?).ok()?;
+
+        if items.next().is_some() {
+            return None;
+        }
+
+        Self::try_from_slice(&data).ok()
+    }
+}
+
+/// Parses all the MSP events out of the logs of a transaction, in the order they were emitted
+pub fn decode_events<S: AsRef<str>>(logs: &[S]) -> Vec<MspEvent> {
+    logs.iter()
+        .filter_map(|log| MspEvent::from_log(log.as_ref()))
+        .collect()
+}
@@ -1,17 +1,19 @@
-use num_traits;
-use std::{ convert::TryInto };
+use std::{ convert::{ TryFrom, TryInto } };
 use crate::instruction::*;
+use crate::accounts::CloseTreasuryAccounts;
 use crate::error::StreamError;
 use crate::state::*;
-use crate::constants::*;
 use crate::utils::*;
+use crate::math::{ get_treasury_depletion_time, get_treasury_depletion_time_v2 };
 use solana_program::{
     // msg,
     system_instruction,
     program::{ invoke, invoke_signed },
+    instruction::Instruction,
     pubkey::Pubkey,
     account_info::AccountInfo,
     entrypoint::ProgramResult,
+    program_error::ProgramError,
     program_pack::{ Pack },
     sysvar::{ clock::Clock, rent::Rent, Sysvar } 
 };
@@ -21,18 +23,19 @@ pub fn create_stream_account<'info>(
     stream_account_info: &AccountInfo<'info>,
     msp_account_info: &AccountInfo<'info>,
     rent_account_info: &AccountInfo<'info>,
-    system_account_info: &AccountInfo<'info>
+    system_account_info: &AccountInfo<'info>,
+    stream_size: usize
 
 ) -> ProgramResult {
 
     let rent = &Rent::from_account_info(rent_account_info)?;
     // Create stream account
-    let stream_balance = rent.minimum_balance(StreamV1::LEN);
+    let stream_balance = rent.minimum_balance(stream_size);
     let create_stream_ix = system_instruction::create_account(
         treasurer_account_info.key,
         stream_account_info.key,
         stream_balance,
-        u64::from_le_bytes(StreamV1::LEN.to_le_bytes()),
+        u64::from_le_bytes(stream_size.to_le_bytes()),
         msp_account_info.key
     );
 
@@ -47,34 +50,26 @@ pub fn create_stream_account<'info>(
 pub fn create_stream_update_treasury<'info>(
     treasury_account_info: &AccountInfo<'info>,
     stream: &StreamV1,
-    decimals: usize
+    decimals: u64
 
 ) -> ProgramResult {
 
     let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
-    let pow = num_traits::pow(10f64, decimals.into());
+    let scale = TokenScale::new(decimals);
     let rate = stream.rate_amount / stream.rate_interval_in_seconds as f64;
-    let depletion_rate = ((treasury.depletion_rate * pow) as u64)
-        .checked_add((rate * pow) as u64)
-        .ok_or(StreamError::Overflow)? as f64 / pow;
+    let depletion_rate = scale.checked_add(treasury.depletion_rate, scale.to_base_units(rate))?;
 
     treasury.depletion_rate = depletion_rate;        
     treasury.streams_amount = treasury.streams_amount.checked_add(1).ok_or(StreamError::Overflow)?;
 
     if stream.allocation_assigned > 0.0 {
-        treasury.allocation_assigned = ((treasury.allocation_assigned * pow) as u64)
-            .checked_add((stream.allocation_assigned * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_assigned = scale.checked_add(treasury.allocation_assigned, scale.to_base_units(stream.allocation_assigned))?;
 
-        treasury.allocation_left = ((treasury.allocation_left * pow) as u64)
-            .checked_add((stream.allocation_left * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_left = scale.checked_add(treasury.allocation_left, scale.to_base_units(stream.allocation_left))?;
     }
 
     if stream.allocation_reserved > 0.0 {
-        treasury.allocation_reserved = ((treasury.allocation_reserved * pow) as u64)
-            .checked_add((stream.allocation_reserved * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_reserved = scale.checked_add(treasury.allocation_reserved, scale.to_base_units(stream.allocation_reserved))?;
     }
 
     // Save treasury
@@ -83,6 +78,70 @@ pub fn create_stream_update_treasury<'info>(
     Ok(())
 }
 
+pub fn create_stream_update_treasury_v2<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    stream: &StreamV2
+
+) -> ProgramResult {
+
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let rate = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+
+    treasury.depletion_rate = treasury.depletion_rate.checked_add(rate).ok_or(StreamError::Overflow)?;
+    treasury.streams_amount = treasury.streams_amount.checked_add(1).ok_or(StreamError::Overflow)?;
+    treasury.allocation_assigned = treasury.allocation_assigned
+        .checked_add(stream.allocation_assigned)
+        .ok_or(StreamError::Overflow)?;
+
+    treasury.allocation_left = treasury.allocation_left
+        .checked_add(stream.allocation_left)
+        .ok_or(StreamError::Overflow)?;
+
+    treasury.allocation_reserved = treasury.allocation_reserved
+        .checked_add(stream.allocation_reserved)
+        .ok_or(StreamError::Overflow)?;
+
+    // Save treasury
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
+pub fn create_streams_batch_update_treasury_v2<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    streams: &[StreamV2]
+
+) -> ProgramResult {
+
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+    for stream in streams {
+        let rate = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+
+        treasury.depletion_rate = treasury.depletion_rate.checked_add(rate).ok_or(StreamError::Overflow)?;
+        treasury.allocation_assigned = treasury.allocation_assigned
+            .checked_add(stream.allocation_assigned)
+            .ok_or(StreamError::Overflow)?;
+
+        treasury.allocation_left = treasury.allocation_left
+            .checked_add(stream.allocation_left)
+            .ok_or(StreamError::Overflow)?;
+
+        treasury.allocation_reserved = treasury.allocation_reserved
+            .checked_add(stream.allocation_reserved)
+            .ok_or(StreamError::Overflow)?;
+    }
+
+    treasury.streams_amount = treasury.streams_amount
+        .checked_add(streams.len() as u64)
+        .ok_or(StreamError::Overflow)?;
+
+    // Save treasury
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
 pub fn create_deposit_receipt<'info>(
     treasury_account_info: &AccountInfo<'info>,
     treasury_pool_mint_info: &AccountInfo<'info>,
@@ -94,23 +153,17 @@ pub fn create_deposit_receipt<'info>(
 ) -> ProgramResult {
 
     let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
-    let (_, treasury_pool_bump_seed) = Pubkey::find_program_address(
-        &[
-            treasury.treasurer_address.as_ref(),
-            &treasury.slot.to_le_bytes()
-        ], 
-        msp_account_info.key
-    );    
+    let (_, treasury_pool_bump_seed) = treasury.find_address(msp_account_info.key);    
     // Mint just if there is a treasury pool
     let treasury_pool_mint = spl_token::state::Mint::unpack_from_slice(&treasury_pool_mint_info.data.borrow())?;
     let treasury_pool_mint_signer_seed: &[&[_]] = &[
-        treasury.treasurer_address.as_ref(),
+        treasury.seed_address.as_ref(),
         &treasury.slot.to_le_bytes(),
         &[treasury_pool_bump_seed]
     ];
 
-    let pow = num_traits::pow(10f64, treasury_pool_mint.decimals.into());
-    let mint_amount = (amount * pow) as u64;
+    let scale = TokenScale::new(treasury_pool_mint.decimals.into());
+    let mint_amount = scale.to_base_units(amount);
     let mint_to_ix = spl_token::instruction::mint_to(
         token_program_account_info.key,
         treasury_pool_mint_info.key,
@@ -131,6 +184,53 @@ pub fn create_deposit_receipt<'info>(
     )
 }
 
+pub fn create_deposit_receipt_v2<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_pool_mint_info: &AccountInfo<'info>,
+    dest_pool_token_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    amount: u64,
+    decimals: u8
+
+) -> ProgramResult {
+
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let (_, treasury_pool_bump_seed) = treasury.find_address(msp_account_info.key);
+
+    let treasury_pool_mint = spl_token::state::Mint::unpack_from_slice(&treasury_pool_mint_info.data.borrow())?;
+    let treasury_pool_mint_signer_seed: &[&[_]] = &[
+        treasury.seed_address.as_ref(),
+        &treasury.slot.to_le_bytes(),
+        &[treasury_pool_bump_seed]
+    ];
+    // Convert the deposited base units into treasury pool token base units
+    let mint_amount = (amount as u128)
+        .checked_mul(10u128.pow(treasury_pool_mint.decimals.into()))
+        .ok_or(StreamError::Overflow)?
+        .checked_div(10u128.pow(decimals.into()))
+        .ok_or(StreamError::Overflow)?;
+
+    let mint_to_ix = spl_token::instruction::mint_to(
+        token_program_account_info.key,
+        treasury_pool_mint_info.key,
+        dest_pool_token_account_info.key,
+        treasury_account_info.key,
+        &[],
+        u64::try_from(mint_amount).map_err(|_| StreamError::Overflow)?
+    )?;
+
+    invoke_signed(&mint_to_ix,
+        &[
+            token_program_account_info.clone(),
+            treasury_pool_mint_info.clone(),
+            dest_pool_token_account_info.clone(),
+            treasury_account_info.clone()
+        ],
+        &[treasury_pool_mint_signer_seed]
+    )
+}
+
 pub fn add_funds_update_treasury<'info>(
     treasury_account_info: &AccountInfo<'info>,
     associated_token_mint_info: &AccountInfo<'info>,
@@ -141,34 +241,24 @@ pub fn add_funds_update_treasury<'info>(
 
     let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
     let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-    let balance = ((treasury.balance * pow) as u64)
-        .checked_add((amount * pow) as u64)
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    let balance = scale.to_base_units(treasury.balance)
+        .checked_add(scale.to_base_units(amount))
         .ok_or(StreamError::Overflow)?;
 
-    treasury.balance = balance as f64 / pow;
+    treasury.balance = scale.from_base_units(balance);
 
     if allocation_type == 0 {
-        treasury.allocation_assigned = ((treasury.allocation_assigned * pow) as u64)
-            .checked_add((amount * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_assigned = scale.checked_add(treasury.allocation_assigned, scale.to_base_units(amount))?;
         
-        treasury.allocation_left = ((treasury.allocation_left * pow) as u64)
-            .checked_add((amount * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_left = scale.checked_add(treasury.allocation_left, scale.to_base_units(amount))?;
 
     } else if allocation_type == 1 {   
-        treasury.allocation_assigned = ((treasury.allocation_assigned * pow) as u64)
-            .checked_add((amount * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_assigned = scale.checked_add(treasury.allocation_assigned, scale.to_base_units(amount))?;
 
-        treasury.allocation_left = ((treasury.allocation_left * pow) as u64)
-            .checked_add((amount * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_left = scale.checked_add(treasury.allocation_left, scale.to_base_units(amount))?;
 
-        treasury.allocation_reserved = ((treasury.allocation_reserved * pow) as u64)
-            .checked_add((amount * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_reserved = scale.checked_add(treasury.allocation_reserved, scale.to_base_units(amount))?;
     }
 
     treasury.associated_token_address = *associated_token_mint_info.key;
@@ -178,6 +268,41 @@ pub fn add_funds_update_treasury<'info>(
     Ok(())
 }
 
+pub fn add_funds_update_treasury_v2<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    allocation_type: u8,
+    amount: u64
+
+) -> ProgramResult {
+
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+    treasury.balance = treasury.balance.checked_add(amount).ok_or(StreamError::Overflow)?;
+
+    if allocation_type == 0 || allocation_type == 1 {
+        treasury.allocation_assigned = treasury.allocation_assigned
+            .checked_add(amount)
+            .ok_or(StreamError::Overflow)?;
+
+        treasury.allocation_left = treasury.allocation_left
+            .checked_add(amount)
+            .ok_or(StreamError::Overflow)?;
+    }
+
+    if allocation_type == 1 {
+        treasury.allocation_reserved = treasury.allocation_reserved
+            .checked_add(amount)
+            .ok_or(StreamError::Overflow)?;
+    }
+
+    treasury.associated_token_address = *associated_token_mint_info.key;
+    // Save
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
 pub fn add_funds_update_stream<'info>(
     stream_account_info: &AccountInfo<'info>,
     associated_token_mint_info: &AccountInfo<'info>,
@@ -195,29 +320,23 @@ pub fn add_funds_update_stream<'info>(
     let escrow_vested_amount = get_beneficiary_withdrawable_amount(
         &stream, &clock, associated_token_mint.decimals.try_into().unwrap()
     )?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-    let allocation_assigned = (stream.allocation_assigned * pow) as u64;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    let allocation_assigned = scale.to_base_units(stream.allocation_assigned);
     // Pause because the allocation amount was reached
     if escrow_vested_amount > allocation_assigned {
-        stream.escrow_vested_amount_snap = escrow_vested_amount as f64 / pow;
+        stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount);
         stream.escrow_vested_amount_snap_slot = current_slot;
         stream.escrow_vested_amount_snap_block_time = current_block_time;
     }
 
-    stream.allocation_assigned = allocation_assigned
-            .checked_add((amount * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+    stream.allocation_assigned = scale.checked_add(stream.allocation_assigned, scale.to_base_units(amount))?;
 
-    stream.allocation_left = ((stream.allocation_left * pow) as u64)
-        .checked_add((amount * pow) as u64)
-        .ok_or(StreamError::Overflow)? as f64 / pow;
+    stream.allocation_left = scale.checked_add(stream.allocation_left, scale.to_base_units(amount))?;
 
     if allocation_type == 1 && allocation_stream_address.ne(&Pubkey::default()) && 
        stream_account_info.key.eq(&allocation_stream_address)
     {
-        stream.allocation_reserved = ((stream.allocation_reserved * pow) as u64)
-            .checked_add((amount * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        stream.allocation_reserved = scale.checked_add(stream.allocation_reserved, scale.to_base_units(amount))?;
     }
 
     // if it was paused before because of lack of money then resume it again 
@@ -231,6 +350,47 @@ pub fn add_funds_update_stream<'info>(
     Ok(())
 }
 
+pub fn add_funds_update_stream_v2<'info>(
+    stream_account_info: &AccountInfo<'info>,
+    clock: &Clock,
+    allocation_stream_address: &Pubkey,
+    allocation_type: u8,
+    amount: u64
+
+) -> ProgramResult {
+
+    let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+    let status = get_stream_status_v2(&stream, clock)?;
+    let escrow_vested_amount = get_stream_vested_amount_v2(&stream, clock)?;
+    // If the stream ran out of funds while running then snap the vested amount 
+    // and resume it again so the time without allocation is not vested
+    if status == StreamStatus::Running && escrow_vested_amount >= stream.allocation_left {
+        stream.escrow_vested_amount_snap = escrow_vested_amount;
+        stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+        stream.escrow_vested_amount_snap_block_time = clock.unix_timestamp as u64;
+        stream.stream_resumed_slot = clock.slot as u64;
+        stream.stream_resumed_block_time = clock.unix_timestamp as u64;
+    }
+
+    stream.allocation_assigned = stream.allocation_assigned
+        .checked_add(amount)
+        .ok_or(StreamError::Overflow)?;
+
+    stream.allocation_left = stream.allocation_left
+        .checked_add(amount)
+        .ok_or(StreamError::Overflow)?;
+
+    if allocation_type == 1 && stream_account_info.key.eq(allocation_stream_address) {
+        stream.allocation_reserved = stream.allocation_reserved
+            .checked_add(amount)
+            .ok_or(StreamError::Overflow)?;
+    }
+
+    StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
 pub fn transfer_tokens<'info>(
     source_owner_account_info: &AccountInfo<'info>,
     source_token_account_info: &AccountInfo<'info>,
@@ -242,8 +402,8 @@ pub fn transfer_tokens<'info>(
 ) -> ProgramResult {
 
     let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-    let transfer_amount = (amount * pow) as u64;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    let transfer_amount = scale.to_base_units(amount);
 
     let transfer_ix = spl_token::instruction::transfer(
         token_program_account_info.key,
@@ -262,6 +422,92 @@ pub fn transfer_tokens<'info>(
     ])
 }
 
+pub fn transfer_tokens_v2<'info>(
+    source_owner_account_info: &AccountInfo<'info>,
+    source_token_account_info: &AccountInfo<'info>,
+    dest_token_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    amount: u64
+
+) -> ProgramResult {
+
+    let transfer_ix = spl_token::instruction::transfer(
+        token_program_account_info.key,
+        source_token_account_info.key,
+        dest_token_account_info.key,
+        source_owner_account_info.key,
+        &[],
+        amount
+    )?;
+
+    invoke(&transfer_ix, &[
+        source_owner_account_info.clone(),
+        dest_token_account_info.clone(),
+        source_token_account_info.clone(),
+        token_program_account_info.clone()
+    ])
+}
+
+/// Transfers lamports to a native mint token account and syncs its token amount
+pub fn wrap_native_tokens<'info>(
+    source_account_info: &AccountInfo<'info>,
+    dest_token_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    amount: u64
+
+) -> ProgramResult {
+
+    let transfer_ix = system_instruction::transfer(
+        source_account_info.key,
+        dest_token_account_info.key,
+        amount
+    );
+
+    invoke(&transfer_ix, &[
+        source_account_info.clone(),
+        dest_token_account_info.clone(),
+        system_account_info.clone()
+    ])?;
+
+    let sync_native_ix = spl_token::instruction::sync_native(
+        token_program_account_info.key,
+        dest_token_account_info.key
+    )?;
+
+    invoke(&sync_native_ix, &[
+        dest_token_account_info.clone(),
+        token_program_account_info.clone()
+    ])
+}
+
+/// Closes a native mint token account so the owner receives its balance as lamports
+pub fn unwrap_native_tokens<'info>(
+    owner_account_info: &AccountInfo<'info>,
+    token_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    if !owner_account_info.is_signer {
+        return Err(StreamError::MissingInstructionSignature.into());
+    }
+
+    let close_ix = spl_token::instruction::close_account(
+        token_program_account_info.key,
+        token_account_info.key,
+        owner_account_info.key,
+        owner_account_info.key,
+        &[]
+    )?;
+
+    invoke(&close_ix, &[
+        token_account_info.clone(),
+        owner_account_info.clone(),
+        token_program_account_info.clone()
+    ])
+}
+
 pub fn withdraw_funds_update_stream<'info>(
     stream: &mut StreamV1,
     stream_account_info: &AccountInfo<'info>,
@@ -273,14 +519,14 @@ pub fn withdraw_funds_update_stream<'info>(
 ) -> ProgramResult {
 
     let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
 
     // Update stream account data
     let escrow_vested_amount_snap = vested_amount
         .checked_sub(transfer_amount)
         .ok_or(StreamError::Overflow)?;
 
-    stream.escrow_vested_amount_snap = escrow_vested_amount_snap as f64 / pow;
+    stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount_snap);
     let status = get_stream_status(stream, clock)?;
 
     if status == StreamStatus::Running {
@@ -288,16 +534,12 @@ pub fn withdraw_funds_update_stream<'info>(
         stream.stream_resumed_block_time = clock.unix_timestamp as u64;
     }
 
-    stream.allocation_left = ((stream.allocation_left * pow) as u64)
-        .checked_sub(transfer_amount)
-        .ok_or(StreamError::Overflow)? as f64 / pow;
+    stream.allocation_left = scale.checked_sub(stream.allocation_left, transfer_amount)?;
     
-    let stream_allocation_reserved = (stream.allocation_reserved * pow) as u64;
+    let stream_allocation_reserved = scale.to_base_units(stream.allocation_reserved);
 
     if stream_allocation_reserved >= transfer_amount {
-        stream.allocation_reserved = stream_allocation_reserved
-            .checked_sub(transfer_amount)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        stream.allocation_reserved = scale.checked_sub(stream.allocation_reserved, transfer_amount)?;
     }
     // Save
     StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
@@ -305,6 +547,40 @@ pub fn withdraw_funds_update_stream<'info>(
     Ok(())
 }
 
+pub fn withdraw_funds_update_stream_v2<'info>(
+    stream: &mut StreamV2,
+    stream_account_info: &AccountInfo<'info>,
+    clock: &Clock,
+    vested_amount: u64,
+    transfer_amount: u64
+
+) -> ProgramResult {
+
+    // Update stream account data
+    stream.escrow_vested_amount_snap = vested_amount
+        .checked_sub(transfer_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    let status = get_stream_status_v2(stream, clock)?;
+
+    if status == StreamStatus::Running {
+        stream.stream_resumed_slot = clock.slot as u64;
+        stream.stream_resumed_block_time = clock.unix_timestamp as u64;
+    }
+
+    stream.allocation_left = stream.allocation_left
+        .checked_sub(transfer_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    stream.allocation_reserved = stream.allocation_reserved
+        .saturating_sub(transfer_amount);
+
+    // Save
+    StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
 pub fn withdraw_funds_update_treasury<'info>(
     treasury_account_info: &AccountInfo<'info>,
     associated_token_mint_info: &AccountInfo<'info>,
@@ -314,29 +590,23 @@ pub fn withdraw_funds_update_treasury<'info>(
 
     let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
     let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-    let treasury_allocation_left = (treasury.allocation_left * pow) as u64;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    let treasury_allocation_left = scale.to_base_units(treasury.allocation_left);
 
     if treasury_allocation_left >= transfer_amount {
-        treasury.allocation_left = treasury_allocation_left
-            .checked_sub(transfer_amount)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_left = scale.checked_sub(treasury.allocation_left, transfer_amount)?;
     }
 
-    let treasury_allocation_reserved = (treasury.allocation_reserved * pow) as u64;
+    let treasury_allocation_reserved = scale.to_base_units(treasury.allocation_reserved);
 
     if treasury_allocation_reserved >= transfer_amount {
-        treasury.allocation_reserved = treasury_allocation_reserved
-            .checked_sub(transfer_amount)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.allocation_reserved = scale.checked_sub(treasury.allocation_reserved, transfer_amount)?;
     }
 
-    let treasury_balance = (treasury.balance * pow) as u64;
+    let treasury_balance = scale.to_base_units(treasury.balance);
 
     if treasury_balance >= transfer_amount {
-        treasury.balance = treasury_balance
-            .checked_sub(transfer_amount)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.balance = scale.checked_sub(treasury.balance, transfer_amount)?;
     }
     // Save
     TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
@@ -344,84 +614,292 @@ pub fn withdraw_funds_update_treasury<'info>(
     Ok(())
 }
 
-pub fn close_stream_transfer_vested_amount<'info>(
-    initializer_account_info: &AccountInfo<'info>,
+pub fn withdraw_funds_update_treasury_v2<'info>(
     treasury_account_info: &AccountInfo<'info>,
-    treasury_token_account_info: &AccountInfo<'info>,
-    beneficiary_account_info: &AccountInfo<'info>,
-    beneficiary_token_account_info: &AccountInfo<'info>,
-    associated_token_mint_info: &AccountInfo<'info>,
-    fee_treasury_account_info: &AccountInfo<'info>,
-    fee_treasury_token_account_info: &AccountInfo<'info>,
-    msp_account_info: &AccountInfo<'info>,
-    associated_token_program_account_info: &AccountInfo<'info>,
-    token_program_account_info: &AccountInfo<'info>,
-    rent_account_info: &AccountInfo<'info>,
-    system_account_info: &AccountInfo<'info>,
-    vested_amount: u64
+    transfer_amount: u64,
+    reserved_amount: u64
 
 ) -> ProgramResult {
 
-    if beneficiary_token_account_info.data_len() == 0 {
-        let _ = create_ata_account(
-            &system_account_info, &rent_account_info, &associated_token_program_account_info,
-            &token_program_account_info, &initializer_account_info, &beneficiary_account_info,
-            &beneficiary_token_account_info, &associated_token_mint_info
-        );
-    }
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
 
-    let fee = (CLOSE_STREAM_PERCENT_FEE * vested_amount as f64 / 100f64) as u64;
-    let transfer_amount = vested_amount.checked_sub(fee).ok_or(StreamError::Overflow)?;
-    // Credit vested amount minus fee to the beneficiary
-    let _ = claim_treasury_funds(
-        &msp_account_info, &token_program_account_info, &treasury_account_info,
-        &treasury_token_account_info, &beneficiary_token_account_info, transfer_amount
-    )?;
+    treasury.allocation_left = treasury.allocation_left
+        .checked_sub(transfer_amount)
+        .ok_or(StreamError::Overflow)?;
 
-    if fee_treasury_token_account_info.data_len() == 0 { // Create treasury associated token account if doesn't exist
-        let _ = create_ata_account(
-            &system_account_info, &rent_account_info, &associated_token_program_account_info,
-            &token_program_account_info, &initializer_account_info, &fee_treasury_account_info,
-            &fee_treasury_token_account_info, &associated_token_mint_info
-        )?;
-    }
-    // Pay fee by the beneficiary from the vested amount
-    claim_treasury_funds(
-        &msp_account_info, &token_program_account_info, &treasury_account_info,
-        &treasury_token_account_info, &fee_treasury_token_account_info, fee
-    )
+    treasury.allocation_reserved = treasury.allocation_reserved
+        .checked_sub(reserved_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    treasury.balance = treasury.balance
+        .checked_sub(transfer_amount)
+        .ok_or(StreamError::Overflow)?;
+    // Save
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
 }
 
-pub fn close_stream_update_treasury<'info>(
-    treasury: &mut TreasuryV1,
-    stream: &StreamV1,
+/// Pays `transfer_amount` base units of the vested amount of the stream out of the treasury
+/// and updates the stream and the treasury accounting. Shared by `Withdraw` and `TransferStream`
+pub fn withdraw_funds_transfer_vested_amount<'info>(
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    destination_token_account_info: &AccountInfo<'info>,
     associated_token_mint_info: &AccountInfo<'info>,
+    stream: &mut StreamV1,
+    stream_account_info: &AccountInfo<'info>,
+    clock: &Clock,
     vested_amount: u64,
-    unvested_amount: u64
+    transfer_amount: u64
 
 ) -> ProgramResult {
 
+    claim_treasury_funds(
+        &msp_account_info, &token_program_account_info, &treasury_account_info,
+        &treasury_token_account_info, &destination_token_account_info, transfer_amount
+    )?;
+
+    withdraw_funds_update_stream(
+        stream, &stream_account_info, &associated_token_mint_info,
+        &clock, vested_amount, transfer_amount
+    )?;
+
+    withdraw_funds_update_treasury(
+        &treasury_account_info, &associated_token_mint_info, transfer_amount
+    )
+}
+
+/// Pays `transfer_amount` base units of the vested amount of the stream out of the treasury
+/// and updates the stream and the treasury accounting. Shared by `Withdraw` and `TransferStream`
+pub fn withdraw_funds_transfer_vested_amount_v2<'info>(
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    destination_token_account_info: &AccountInfo<'info>,
+    stream: &mut StreamV2,
+    stream_account_info: &AccountInfo<'info>,
+    clock: &Clock,
+    vested_amount: u64,
+    transfer_amount: u64
+
+) -> ProgramResult {
+
+    claim_treasury_funds_v2(
+        &msp_account_info, &token_program_account_info, &treasury_account_info,
+        &treasury_token_account_info, &destination_token_account_info, transfer_amount
+    )?;
+
+    let reserved_amount = std::cmp::min(stream.allocation_reserved, transfer_amount);
+    withdraw_funds_update_stream_v2(
+        stream, &stream_account_info, &clock, vested_amount, transfer_amount
+    )?;
+
+    withdraw_funds_update_treasury_v2(
+        &treasury_account_info, transfer_amount, reserved_amount
+    )
+}
+
+/// Takes the `PushVested` keeper tip out of the unallocated balance of the treasury.
+/// The tip is paid at most once per rate interval and only for pushes of at least ten times the tip,
+/// so neither back to back nor dust pushes can drain the treasury.
+/// Returns the tip to pay, zero when it is not due or the unallocated balance can not cover it
+pub fn push_vested_tip_update_treasury<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    stream: &mut StreamV1,
+    stream_account_info: &AccountInfo<'info>,
+    pushed_amount: u64,
+    clock: &Clock
+
+) -> Result<u64, ProgramError> {
+
+    let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
     let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
-    let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());    
-    let treasury_balance = (treasury.balance * pow) as u64;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    let treasury_balance = scale.to_base_units(treasury.balance);
+    let unallocated_balance = treasury_balance.saturating_sub(scale.to_base_units(treasury.allocation_left));
+    let keeper_tip = stream.push_vested_tip;
+
+    if keeper_tip == 0 || keeper_tip > unallocated_balance ||
+       !is_push_vested_tip_due(
+           keeper_tip, pushed_amount, stream.push_vested_tip_block_time, stream.rate_interval_in_seconds, clock
+       )?
+    {
+        return Ok(0);
+    }
+
+    treasury.balance = scale.checked_sub(treasury.balance, keeper_tip)?;
+
+    stream.push_vested_tip_block_time = clock.unix_timestamp as u64;
+    // Save
+    TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+    StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+
+    Ok(keeper_tip)
+}
+
+/// Takes the `PushVested` keeper tip out of the unallocated balance of the treasury.
+/// The tip is paid at most once per rate interval and only for pushes of at least ten times the tip,
+/// so neither back to back nor dust pushes can drain the treasury.
+/// Returns the tip to pay, zero when it is not due or the unallocated balance can not cover it
+pub fn push_vested_tip_update_treasury_v2<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    stream: &mut StreamV2,
+    stream_account_info: &AccountInfo<'info>,
+    pushed_amount: u64,
+    clock: &Clock
+
+) -> Result<u64, ProgramError> {
+
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let unallocated_balance = treasury.balance.saturating_sub(treasury.allocation_left);
+    let keeper_tip = stream.push_vested_tip;
 
-    treasury.balance = treasury_balance
-        .checked_sub(vested_amount).unwrap().checked_sub(unvested_amount)
-        .ok_or(StreamError::Overflow)? as f64 / pow;
+    if keeper_tip == 0 || keeper_tip > unallocated_balance ||
+       !is_push_vested_tip_due(
+           keeper_tip, pushed_amount, stream.push_vested_tip_block_time, stream.rate_interval_in_seconds, clock
+       )?
+    {
+        return Ok(0);
+    }
+
+    treasury.balance = treasury.balance
+        .checked_sub(keeper_tip)
+        .ok_or(StreamError::Overflow)?;
+
+    stream.push_vested_tip_block_time = clock.unix_timestamp as u64;
+    // Save
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+    StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+
+    Ok(keeper_tip)
+}
+
+pub fn close_stream_transfer_vested_amount<'info>(
+    initializer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    beneficiary_account_info: &AccountInfo<'info>,
+    beneficiary_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    fee_treasury_account_info: &AccountInfo<'info>,
+    fee_treasury_token_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    associated_token_program_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    vested_amount: u64,
+    close_stream_percent_fee: u64
 
-    let treasury_allocation_left = (treasury.allocation_left * pow) as u64;
+) -> ProgramResult {
 
-    treasury.allocation_left = treasury_allocation_left
-        .checked_sub(vested_amount).unwrap().checked_sub(unvested_amount)
-        .ok_or(StreamError::Overflow)? as f64 / pow;
+    if beneficiary_token_account_info.data_len() == 0 {
+        let _ = create_ata_account(
+            &system_account_info, &rent_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &initializer_account_info, &beneficiary_account_info,
+            &beneficiary_token_account_info, &associated_token_mint_info
+        );
+    }
 
-    let treasury_allocation_reserved = (treasury.allocation_reserved * pow) as u64;
+    let fee = get_percent_fee(vested_amount, close_stream_percent_fee)?;
+    let transfer_amount = vested_amount.checked_sub(fee).ok_or(StreamError::Overflow)?;
+    // Credit vested amount minus fee to the beneficiary
+    let _ = claim_treasury_funds(
+        &msp_account_info, &token_program_account_info, &treasury_account_info,
+        &treasury_token_account_info, &beneficiary_token_account_info, transfer_amount
+    )?;
 
-    if treasury_allocation_reserved >= vested_amount {
-        treasury.allocation_reserved = treasury_allocation_reserved
-            .checked_sub(vested_amount).unwrap().checked_sub(unvested_amount)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+    if fee_treasury_token_account_info.data_len() == 0 { // Create treasury associated token account if doesn't exist
+        let _ = create_ata_account(
+            &system_account_info, &rent_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &initializer_account_info, &fee_treasury_account_info,
+            &fee_treasury_token_account_info, &associated_token_mint_info
+        )?;
     }
+    // Pay fee by the beneficiary from the vested amount
+    claim_treasury_funds(
+        &msp_account_info, &token_program_account_info, &treasury_account_info,
+        &treasury_token_account_info, &fee_treasury_token_account_info, fee
+    )
+}
+
+pub fn close_stream_transfer_vested_amount_v2<'info>(
+    initializer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    beneficiary_account_info: &AccountInfo<'info>,
+    beneficiary_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    fee_treasury_account_info: &AccountInfo<'info>,
+    fee_treasury_token_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    associated_token_program_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    vested_amount: u64,
+    close_stream_percent_fee: u64
+
+) -> ProgramResult {
+
+    if beneficiary_token_account_info.data_len() == 0 {
+        create_ata_account(
+            &system_account_info, &rent_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &initializer_account_info, &beneficiary_account_info,
+            &beneficiary_token_account_info, &associated_token_mint_info
+        )?;
+    }
+
+    let fee = get_percent_fee(vested_amount, close_stream_percent_fee)?;
+    let transfer_amount = vested_amount.checked_sub(fee).ok_or(StreamError::Overflow)?;
+    // Credit vested amount minus fee to the beneficiary
+    claim_treasury_funds_v2(
+        &msp_account_info, &token_program_account_info, &treasury_account_info,
+        &treasury_token_account_info, &beneficiary_token_account_info, transfer_amount
+    )?;
+
+    if fee_treasury_token_account_info.data_len() == 0 { // Create treasury associated token account if doesn't exist
+        create_ata_account(
+            &system_account_info, &rent_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &initializer_account_info, &fee_treasury_account_info,
+            &fee_treasury_token_account_info, &associated_token_mint_info
+        )?;
+    }
+    // Pay fee by the beneficiary from the vested amount
+    claim_treasury_funds_v2(
+        &msp_account_info, &token_program_account_info, &treasury_account_info,
+        &treasury_token_account_info, &fee_treasury_token_account_info, fee
+    )
+}
+
+pub fn close_stream_update_treasury<'info>(
+    treasury: &mut TreasuryV1,
+    stream: &StreamV1,
+    associated_token_mint_info: &AccountInfo<'info>,
+    vested_amount: u64,
+    unvested_amount: u64
+
+) -> ProgramResult {
+
+    let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    treasury.balance = scale.checked_sub(treasury.balance, vested_amount)?;
+    // The vested amount is paid out and the unvested amount is released back to the treasury
+    let treasury_allocation_left = scale.to_base_units(treasury.allocation_left);
+
+    let allocation_left = treasury_allocation_left
+        .checked_sub(vested_amount)
+        .and_then(|allocation_left| allocation_left.checked_sub(unvested_amount))
+        .ok_or(StreamError::Overflow)?;
+
+    treasury.allocation_left = scale.from_base_units(allocation_left);
+
+    treasury.allocation_reserved = scale.saturating_sub(treasury.allocation_reserved, scale.to_base_units(stream.allocation_reserved));
 
     let stream_rate = match stream.rate_interval_in_seconds {
         k if k > 0 => stream.rate_amount / (stream.rate_interval_in_seconds as f64),
@@ -429,9 +907,36 @@ pub fn close_stream_update_treasury<'info>(
     };
 
     if treasury.depletion_rate >= stream_rate {
-        treasury.depletion_rate = ((treasury.depletion_rate * pow) as u64)
-            .checked_sub((stream_rate * pow) as u64)
-            .ok_or(StreamError::Overflow)? as f64 / pow;
+        treasury.depletion_rate = scale.checked_sub(treasury.depletion_rate, scale.to_base_units(stream_rate))?;
+    }
+
+    treasury.streams_amount = treasury.streams_amount.checked_sub(1).ok_or(StreamError::Overflow)?;
+
+    Ok(())
+}
+
+pub fn close_stream_update_treasury_v2(
+    treasury: &mut TreasuryV2,
+    stream: &StreamV2,
+    status: &StreamStatus,
+    vested_amount: u64
+
+) -> ProgramResult {
+
+    treasury.balance = treasury.balance
+        .checked_sub(vested_amount)
+        .ok_or(StreamError::Overflow)?;
+    // The vested amount is paid out and the unvested amount is released back to the treasury
+    treasury.allocation_left = treasury.allocation_left
+        .checked_sub(stream.allocation_left)
+        .ok_or(StreamError::Overflow)?;
+
+    treasury.allocation_reserved = treasury.allocation_reserved
+        .saturating_sub(stream.allocation_reserved);
+    // Paused and ended streams were already discounted from the depletion rate
+    if *status != StreamStatus::Paused && *status != StreamStatus::Ended {
+        let stream_rate = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+        treasury.depletion_rate = treasury.depletion_rate.saturating_sub(stream_rate);
     }
 
     treasury.streams_amount = treasury.streams_amount.checked_sub(1).ok_or(StreamError::Overflow)?;
@@ -451,7 +956,11 @@ pub fn close_stream_close_treasury<'info>(
     fee_treasury_account_info: &AccountInfo<'info>,
     fee_treasury_token_account_info: &AccountInfo<'info>,
     msp_account_info: &AccountInfo<'info>,
-    token_program_account_info: &AccountInfo<'info>
+    token_program_account_info: &AccountInfo<'info>,
+    associated_token_program_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    fee_config_account_info: &AccountInfo<'info>
 
 ) -> ProgramResult {
 
@@ -461,37 +970,39 @@ pub fn close_stream_close_treasury<'info>(
         return Err(StreamError::InstructionNotAuthorized.into());
     }
 
-    let (treasury_pool_address, treasury_pool_bump_seed) = Pubkey::find_program_address(
-        &[
-            treasury.treasurer_address.as_ref(),
-            &treasury.slot.to_le_bytes()
-        ], 
-        msp_account_info.key
-    );
+    let (treasury_pool_address, treasury_pool_bump_seed) = treasury.find_address(msp_account_info.key);
 
     if treasury_pool_address.ne(treasury_account_info.key) {
         return Err(StreamError::InvalidTreasuryData.into());
     }
 
     let treasury_pool_signer_seed: &[&[_]] = &[
-        treasury.treasurer_address.as_ref(),
+        treasury.seed_address.as_ref(),
         &treasury.slot.to_le_bytes(),
         &treasury_pool_bump_seed.to_le_bytes()
     ];
 
-    let close_treasury_ix = close_treasury(
-        *treasurer_account_info.key,
-        *treasurer_token_account_info.key,
-        *treasurer_treasury_pool_token_account_info.key,
-        *associated_token_mint_info.key,
-        *treasury_account_info.key,
-        *treasury_token_account_info.key,
-        *treasury_pool_mint_info.key,
-        *fee_treasury_account_info.key,
-        *fee_treasury_token_account_info.key,
-        *token_program_account_info.key,
-        program_id
-    )?;
+    let close_treasury_ix = Instruction {
+        program_id: *program_id,
+        accounts: CloseTreasuryAccounts {
+            treasurer: *treasurer_account_info.key,
+            treasurer_token: *treasurer_token_account_info.key,
+            treasurer_treasury_pool_token: *treasurer_treasury_pool_token_account_info.key,
+            associated_token_mint: *associated_token_mint_info.key,
+            treasury: *treasury_account_info.key,
+            treasury_token: *treasury_token_account_info.key,
+            treasury_pool_mint: *treasury_pool_mint_info.key,
+            fee_treasury: *fee_treasury_account_info.key,
+            fee_treasury_token: *fee_treasury_token_account_info.key,
+            msp: *program_id,
+            token_program: *token_program_account_info.key,
+            associated_token_program: *associated_token_program_account_info.key,
+            system_program: *system_account_info.key,
+            rent: *rent_account_info.key,
+            fee_config: *fee_config_account_info.key
+        }.to_account_metas(),
+        data: StreamInstruction::CloseTreasury.pack()
+    };
 
     invoke_signed(&close_treasury_ix, 
         &[
@@ -505,7 +1016,11 @@ pub fn close_stream_close_treasury<'info>(
             fee_treasury_account_info.clone(),
             fee_treasury_token_account_info.clone(),
             token_program_account_info.clone(),
-            msp_account_info.clone()
+            msp_account_info.clone(),
+            associated_token_program_account_info.clone(),
+            system_account_info.clone(),
+            rent_account_info.clone(),
+            fee_config_account_info.clone()
         ],
         &[treasury_pool_signer_seed]
     )
@@ -522,13 +1037,7 @@ pub fn close_treasury_pool_token_account<'info>(
 
 ) -> ProgramResult {
 
-    let (treasury_pool_address, treasury_pool_bump_seed) = Pubkey::find_program_address(
-        &[
-            treasury.treasurer_address.as_ref(),
-            &treasury.slot.to_le_bytes()
-        ], 
-        msp_account_info.key
-    );
+    let (treasury_pool_address, treasury_pool_bump_seed) = treasury.find_address(msp_account_info.key);
 
     if treasury_pool_address.ne(treasury_account_info.key)
     {
@@ -536,7 +1045,7 @@ pub fn close_treasury_pool_token_account<'info>(
     }
 
     let treasury_pool_signer_seed: &[&[_]] = &[
-        treasury.treasurer_address.as_ref(),
+        treasury.seed_address.as_ref(),
         &treasury.slot.to_le_bytes(),
         &treasury_pool_bump_seed.to_le_bytes()
     ];
@@ -612,13 +1121,7 @@ pub fn close_treasury_token_account<'info>(
         );      
     }
 
-    let (treasury_pool_address, treasury_pool_bump_seed) = Pubkey::find_program_address(
-        &[
-            treasury.treasurer_address.as_ref(),
-            &treasury.slot.to_le_bytes()
-        ], 
-        msp_account_info.key
-    );
+    let (treasury_pool_address, treasury_pool_bump_seed) = treasury.find_address(msp_account_info.key);
 
     if treasury_pool_address.ne(treasury_account_info.key)
     {
@@ -626,7 +1129,7 @@ pub fn close_treasury_token_account<'info>(
     }
 
     let treasury_pool_signer_seed: &[&[_]] = &[
-        treasury.treasurer_address.as_ref(),
+        treasury.seed_address.as_ref(),
         &treasury.slot.to_le_bytes(),
         &treasury_pool_bump_seed.to_le_bytes()
     ];
@@ -649,4 +1152,525 @@ pub fn close_treasury_token_account<'info>(
         ],
         &[treasury_pool_signer_seed]
     )
-}
\ No newline at end of file
+}
+
+pub fn close_treasury_pool_token_account_v2<'info>(
+    treasurer_account_info: &AccountInfo<'info>,
+    treasurer_treasury_pool_token_account_info: &AccountInfo<'info>,
+    treasury_pool_mint_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    if treasurer_treasury_pool_token_account_info.data_len() == spl_token::state::Account::LEN
+    {
+        let treasurer_treasury_pool_token = spl_token::state::Account::unpack_from_slice(
+            &treasurer_treasury_pool_token_account_info.data.borrow()
+        )?;
+        // Burn treasury tokens from the treasurer treasury pool token account
+        let burn_ix = spl_token::instruction::burn(
+            token_program_account_info.key,
+            treasurer_treasury_pool_token_account_info.key,
+            treasury_pool_mint_info.key,
+            treasurer_account_info.key,
+            &[],
+            treasurer_treasury_pool_token.amount
+        )?;
+
+        invoke(&burn_ix, &[
+            token_program_account_info.clone(),
+            treasurer_treasury_pool_token_account_info.clone(),
+            treasury_pool_mint_info.clone(),
+            treasurer_account_info.clone()
+        ])?;
+        // Close treasurer treasury pool token account
+        let treasurer_treasury_pool_token_close_ix = spl_token::instruction::close_account(
+            token_program_account_info.key, 
+            treasurer_treasury_pool_token_account_info.key, 
+            treasurer_account_info.key, 
+            treasurer_account_info.key,
+            &[]
+        )?;
+
+        invoke(&treasurer_treasury_pool_token_close_ix, &[
+            treasurer_treasury_pool_token_account_info.clone(),
+            treasurer_account_info.clone(),
+            token_program_account_info.clone(),
+        ])?;
+    }
+
+    Ok(())
+}
+
+pub fn close_treasury_token_account_v2<'info>(
+    treasury: &TreasuryV2,
+    treasurer_account_info: &AccountInfo<'info>,
+    treasurer_token_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+
+) -> ProgramResult {
+
+    let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
+
+    if treasury_token.amount > 0 { // Credit all treasury token amount to treasurer
+        claim_treasury_funds_v2(
+            &msp_account_info, &token_program_account_info, &treasury_account_info,
+            &treasury_token_account_info, &treasurer_token_account_info, treasury_token.amount
+        )?;
+    }
+
+    let (treasury_pool_address, treasury_pool_bump_seed) = treasury.find_address(msp_account_info.key);
+
+    if treasury_pool_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let treasury_pool_signer_seed: &[&[_]] = &[
+        treasury.seed_address.as_ref(),
+        &treasury.slot.to_le_bytes(),
+        &treasury_pool_bump_seed.to_le_bytes()
+    ];
+    // Close treasury token account
+    let close_token_ix = spl_token::instruction::close_account(
+        token_program_account_info.key, 
+        treasury_token_account_info.key, 
+        treasurer_account_info.key, 
+        treasury_account_info.key, 
+        &[]
+    )?;
+
+    invoke_signed(&close_token_ix, 
+        &[
+            treasury_account_info.clone(),
+            treasury_token_account_info.clone(),
+            treasurer_account_info.clone(),
+            token_program_account_info.clone(),
+        ],
+        &[treasury_pool_signer_seed]
+    )
+}
+
+pub fn close_treasury_account<'info>(
+    treasurer_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    let treasurer_lamports = treasurer_account_info.lamports();
+    let treasury_lamports = treasury_account_info.lamports();
+
+    **treasury_account_info.lamports.borrow_mut() = 0;
+    **treasurer_account_info.lamports.borrow_mut() = treasurer_lamports
+        .checked_add(treasury_lamports)
+        .ok_or(StreamError::Overflow)?;
+
+    Ok(())
+}
+
+/// Charges the close treasury fees: the flat fee and a percent of the tokens returned to the treasurer
+/// Closes the treasury roles account, crediting its rent to the treasurer
+pub fn close_treasury_roles_account<'info>(
+    treasurer_account_info: &AccountInfo<'info>,
+    treasury_roles_account_info: &AccountInfo<'info>
+
+) -> ProgramResult {
+
+    treasury_roles_account_info.data.borrow_mut().fill(0);
+    close_treasury_account(treasurer_account_info, treasury_roles_account_info)
+}
+
+pub fn close_treasury_transfer_fees<'info>(
+    treasurer_account_info: &AccountInfo<'info>,
+    treasurer_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    fee_treasury_account_info: &AccountInfo<'info>,
+    fee_treasury_token_account_info: &AccountInfo<'info>,
+    associated_token_program_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    system_account_info: &AccountInfo<'info>,
+    fee_config: &FeeConfig,
+    returned_amount: u64
+
+) -> ProgramResult {
+
+    let fee = get_percent_fee(returned_amount, fee_config.close_treasury_percent_fee)?;
+
+    if fee > 0 {
+        let fee_treasury_token_address = spl_associated_token_account::get_associated_token_address(
+            fee_treasury_account_info.key, associated_token_mint_info.key
+        );
+
+        if fee_treasury_token_address.ne(fee_treasury_token_account_info.key) {
+            return Err(StreamError::InvalidMspOpsToken.into());
+        }
+
+        if fee_treasury_token_account_info.data_len() == 0 { // Create fee treasury associated token account if doesn't exist
+            create_ata_account(
+                &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                &token_program_account_info, &treasurer_account_info, &fee_treasury_account_info,
+                &fee_treasury_token_account_info, &associated_token_mint_info
+            )?;
+        }
+
+        transfer_token_fee(
+            &token_program_account_info, &treasurer_token_account_info,
+            &fee_treasury_token_account_info, &treasurer_account_info, fee
+        )?;
+    }
+
+    transfer_sol_fee(
+        &system_account_info, &treasurer_account_info,
+        &fee_treasury_account_info, fee_config.close_treasury_flat_fee
+    )
+}
+
+pub fn answer_update_update_stream_v2(
+    stream: &mut StreamV2,
+    treasury: &mut TreasuryV2,
+    stream_terms: &StreamTerms,
+    clock: &Clock
+
+) -> ProgramResult {
+
+    let status = get_stream_status_v2(stream, clock)?;
+    // Stepped streams keep the tranches unlocked at the old rate
+    stream.unlocked_amount_snap = get_stream_unlocked_amount_v2(stream, clock)?;
+
+    // Snap the vested amount so the new rate only applies from now on
+    if status == StreamStatus::Running {
+        let escrow_vested_amount = get_stream_vested_amount_v2(stream, clock)?;
+        stream.escrow_vested_amount_snap = escrow_vested_amount;
+        stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+        stream.escrow_vested_amount_snap_block_time = clock.unix_timestamp as u64;
+        stream.stream_resumed_slot = clock.slot as u64;
+        stream.stream_resumed_block_time = clock.unix_timestamp as u64;
+    }
+
+    // Paused streams are not depleting the treasury
+    if status != StreamStatus::Paused {
+        let old_rate = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+        let new_rate = get_stream_rate_per_second(stream_terms.rate_amount, stream_terms.rate_interval_in_seconds)?;
+
+        treasury.depletion_rate = treasury.depletion_rate
+            .saturating_sub(old_rate)
+            .checked_add(new_rate)
+            .ok_or(StreamError::Overflow)?;
+    }
+
+    stream.stream_name = stream_terms.stream_name.clone();
+    stream.rate_amount = stream_terms.rate_amount;
+    stream.rate_interval_in_seconds = stream_terms.rate_interval_in_seconds;
+
+    Ok(())
+}
+
+pub fn auto_pause_update_stream<'info>(
+    stream_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    clock: &Clock
+
+) -> ProgramResult {
+
+    let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+    let is_manual_pause = stream.escrow_vested_amount_snap_block_time > stream.stream_resumed_block_time;
+
+    if is_manual_pause || get_stream_status(&stream, clock)? != StreamStatus::Paused {
+        return Ok(());
+    }
+    // The stream was auto paused, turn it into a regular pause at the auto pause time
+    let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    let escrow_vested_amount = get_beneficiary_withdrawable_amount(
+        &stream, clock, associated_token_mint.decimals.into()
+    )?;
+
+    stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount);
+    stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+    stream.escrow_vested_amount_snap_block_time = get_stream_auto_pause_block_time(
+        stream.auto_pause_in_seconds, stream.stream_resumed_block_time
+    ).ok_or(StreamError::Overflow)?;
+
+    StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+    // The stream is not depleting the treasury anymore
+    let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let stream_rate = stream.rate_amount / stream.rate_interval_in_seconds as f64;
+
+    treasury.depletion_rate = scale.saturating_sub(treasury.depletion_rate, scale.to_base_units(stream_rate));
+
+    TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
+pub fn auto_pause_update_stream_v2<'info>(
+    stream_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    clock: &Clock
+
+) -> ProgramResult {
+
+    let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+    let is_manual_pause = stream.escrow_vested_amount_snap_block_time > stream.stream_resumed_block_time;
+
+    if is_manual_pause || get_stream_status_v2(&stream, clock)? != StreamStatus::Paused {
+        return Ok(());
+    }
+    // The stream was auto paused, turn it into a regular pause at the auto pause time
+    stream.unlocked_amount_snap = get_stream_unlocked_amount_v2(&stream, clock)?;
+    stream.escrow_vested_amount_snap = get_stream_vested_amount_v2(&stream, clock)?;
+    stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+    stream.escrow_vested_amount_snap_block_time = get_stream_auto_pause_block_time(
+        stream.auto_pause_in_seconds, stream.stream_resumed_block_time
+    ).ok_or(StreamError::Overflow)?;
+
+    StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+    // The stream is not depleting the treasury anymore
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let stream_rate = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+
+    treasury.depletion_rate = treasury.depletion_rate.saturating_sub(stream_rate);
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
+pub fn end_update_stream_v2<'info>(
+    stream_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    clock: &Clock
+
+) -> ProgramResult {
+
+    let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+
+    if get_stream_status_v2(&stream, clock)? != StreamStatus::Ended {
+        return Ok(());
+    }
+
+    let end_block_time = get_stream_end_block_time(stream.end_utc).ok_or(StreamError::Overflow)?;
+    let is_manual_pause = stream.escrow_vested_amount_snap_block_time > stream.stream_resumed_block_time;
+    // The stream was already settled at the end time
+    if is_manual_pause && stream.escrow_vested_amount_snap_block_time >= end_block_time {
+        return Ok(());
+    }
+    // Snap the amount unlocked until the end time and release the remainder back to the treasury
+    let escrow_vested_amount = get_beneficiary_withdrawable_amount_v2(&stream, clock)?;
+    let unvested_amount = stream.allocation_left
+        .checked_sub(escrow_vested_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    let unvested_reserved_amount = stream.allocation_reserved.saturating_sub(escrow_vested_amount);
+
+    stream.escrow_vested_amount_snap = escrow_vested_amount;
+    stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+    stream.escrow_vested_amount_snap_block_time = end_block_time;
+    stream.allocation_left = escrow_vested_amount;
+    stream.allocation_assigned = stream.allocation_assigned
+        .checked_sub(unvested_amount)
+        .ok_or(StreamError::Overflow)?;
+    stream.allocation_reserved = stream.allocation_reserved
+        .checked_sub(unvested_reserved_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+    treasury.allocation_left = treasury.allocation_left
+        .checked_sub(unvested_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    treasury.allocation_reserved = treasury.allocation_reserved
+        .saturating_sub(unvested_reserved_amount);
+    // Running (or not yet settled auto paused) streams stop depleting the treasury
+    if !is_manual_pause {
+        let stream_rate = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+        treasury.depletion_rate = treasury.depletion_rate.saturating_sub(stream_rate);
+    }
+
+    StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
+pub fn treasury_withdraw_update_treasury<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    transfer_amount: u64
+
+) -> ProgramResult {
+
+    let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    // Only the unallocated balance is withdrawn so the allocations are not affected
+    treasury.balance = scale.checked_sub(treasury.balance, transfer_amount)?;
+
+    TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
+pub fn treasury_withdraw_update_treasury_v2<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    transfer_amount: u64
+
+) -> ProgramResult {
+
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    // Only the unallocated balance is withdrawn so the allocations are not affected
+    treasury.balance = treasury.balance
+        .checked_sub(transfer_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
+pub fn allocate_update_treasury<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    allocation_type: u8,
+    amount: u64
+
+) -> ProgramResult {
+
+    let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+    let scale = TokenScale::new(associated_token_mint.decimals.into());
+    // The funds are already in the treasury so only the allocations change
+    treasury.allocation_assigned = scale.checked_add(treasury.allocation_assigned, amount)?;
+
+    treasury.allocation_left = scale.checked_add(treasury.allocation_left, amount)?;
+
+    if allocation_type == 1 {
+        treasury.allocation_reserved = scale.checked_add(treasury.allocation_reserved, amount)?;
+    }
+    // Save
+    TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
+pub fn allocate_update_treasury_v2<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    allocation_type: u8,
+    amount: u64
+
+) -> ProgramResult {
+
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    // The funds are already in the treasury so only the allocations change
+    treasury.allocation_assigned = treasury.allocation_assigned
+        .checked_add(amount)
+        .ok_or(StreamError::Overflow)?;
+
+    treasury.allocation_left = treasury.allocation_left
+        .checked_add(amount)
+        .ok_or(StreamError::Overflow)?;
+
+    if allocation_type == 1 {
+        treasury.allocation_reserved = treasury.allocation_reserved
+            .checked_add(amount)
+            .ok_or(StreamError::Overflow)?;
+    }
+    // Save
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    Ok(())
+}
+
+// Recomputes when the treasury balance runs out at its current depletion rate and keeps
+// it in the treasury and in the stream touched by the instruction, if any
+pub fn update_estimated_depletion<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>,
+    stream_account_info: Option<&AccountInfo<'info>>,
+    clock: &Clock
+
+) -> ProgramResult {
+
+    // Closed by the instruction or not migrated yet
+    if treasury_account_info.lamports() == 0 || AccountKind::detect(treasury_account_info) != AccountKind::TreasuryV1 {
+        return Ok(());
+    }
+
+    let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+    let depletion_time = get_treasury_depletion_time(
+        &treasury, clock.unix_timestamp as u64, associated_token_mint.decimals.into()
+    );
+
+    treasury.estimated_depletion_utc = get_depletion_utc(depletion_time);
+    TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    if let Some(stream_account_info) = stream_account_info {
+        if stream_account_info.lamports() > 0 && AccountKind::detect(stream_account_info) == AccountKind::StreamV1 {
+            let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+            stream.treasury_estimated_depletion_utc = treasury.estimated_depletion_utc;
+            StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn update_estimated_depletion_v2<'info>(
+    treasury_account_info: &AccountInfo<'info>,
+    stream_account_info: Option<&AccountInfo<'info>>,
+    clock: &Clock
+
+) -> ProgramResult {
+
+    // Closed by the instruction
+    if treasury_account_info.lamports() == 0 || AccountKind::detect(treasury_account_info) != AccountKind::TreasuryV2 {
+        return Ok(());
+    }
+
+    let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let depletion_time = get_treasury_depletion_time_v2(&treasury, clock.unix_timestamp as u64);
+
+    treasury.estimated_depletion_utc = get_depletion_utc(depletion_time);
+    TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+    if let Some(stream_account_info) = stream_account_info {
+        if stream_account_info.lamports() > 0 && AccountKind::detect(stream_account_info) == AccountKind::StreamV2 {
+            let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            stream.treasury_estimated_depletion_utc = treasury.estimated_depletion_utc;
+            StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn burn_treasury_pool_tokens<'info>(
+    owner_account_info: &AccountInfo<'info>,
+    treasury_pool_token_account_info: &AccountInfo<'info>,
+    treasury_pool_mint_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    amount: u64
+
+) -> ProgramResult {
+
+    let burn_ix = spl_token::instruction::burn(
+        token_program_account_info.key,
+        treasury_pool_token_account_info.key,
+        treasury_pool_mint_info.key,
+        owner_account_info.key,
+        &[],
+        amount
+    )?;
+
+    invoke(&burn_ix, &[
+        token_program_account_info.clone(),
+        treasury_pool_token_account_info.clone(),
+        treasury_pool_mint_info.clone(),
+        owner_account_info.clone()
+    ])
+}
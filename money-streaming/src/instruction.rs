@@ -3,13 +3,16 @@ use std::{ mem::size_of };
 use solana_program::{
     // msg,
     system_program,
+    sysvar,
     pubkey::Pubkey,
     instruction::{ AccountMeta, Instruction }
 };
+use spl_associated_token_account::get_associated_token_address;
 
 use crate::{
     check_program_account,
     utils::*,
+    accounts::*,
     error::StreamError
 };
 
@@ -17,15 +20,18 @@ pub enum StreamInstruction {
 
     /// Initialize a new stream contract
     ///
-    /// 0. `[signer]` The treasurer account (The creator of the money stream).
-    /// 1. `[]` The treasury account (The stream contract treasury account).
+    /// 0. `[signer, writable]` The treasurer account (The creator of the money stream).
+    /// 1. `[writable]` The treasury account (The stream contract treasury account).
     /// 2. `[]` The beneficiary associated token mint account.
-    /// 3. `[writable]` The stream account (The stream contract account).
-    /// 4. `[]` The beneficiary account (The beneficiary of money stream).
-    /// 5.  [writable] The Money Streaming Program operating account (Fees account).
-    /// 6.  [] The Money Streaming Program account.
+    /// 3. `[]` The beneficiary account (The beneficiary of money stream).
+    /// 4. `[signer, writable]` The stream account (The stream contract account).
+    /// 5. `[writable]` The Money Streaming Program operating account (Fees account).
+    /// 6. `[]` The Money Streaming Program account.
     /// 7. `[]` The System Program account.
     /// 8. `[]` Rent sysvar account.
+    /// 9. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 10. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    /// 11. `[]` The treasury roles account when the signer is a stream creator of the treasury (OPTIONAL).
     CreateStream {
         stream_name: String,        
         rate_amount: f64,
@@ -42,74 +48,104 @@ pub enum StreamInstruction {
 
     /// Adds a specific amount of funds to a stream
     ///
-    /// 0. `[signer]` The contributor account
+    /// 0. `[signer, writable]` The contributor account
     /// 1. `[writable]` The contributor token account
     /// 2. `[writable]` The contributor treasury pool token account (the account of the token issued by the treasury and owned by the contributor)
-    /// 4. `[writable]` The treasury account (Stream treasury account).
-    /// 5. `[writable]` The treasury token account.
-    /// 3. `[]` The treasury associated token account
+    /// 3. `[writable]` The treasury account (Stream treasury account).
+    /// 4. `[writable]` The treasury token account.
+    /// 5. `[]` The associated token mint account
     /// 6. `[writable]` The treasury pool mint account (the mint of the treasury pool token)
-    /// 7.  [writable] The Money Streaming Program operating account (Fees account).
-    /// 8.  [] The Money Streaming Program account.
-    /// 9. `[]` The Associated Token Program account.
-    /// 10. `[]` The Token Program account.
-    /// 11. `[]` The System Program account.
-    /// 12. `[]` Rent sysvar account.
+    /// 7. `[writable]` The stream account (The stream to allocate the funds to).
+    /// 8. `[writable]` The Money Streaming Program operating account (Fees account).
+    /// 9. `[]` The Money Streaming Program account.
+    /// 10. `[]` The Associated Token Program account.
+    /// 11. `[]` The Token Program account.
+    /// 12. `[]` The System Program account.
+    /// 13. `[]` Rent sysvar account.
+    /// 14. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 15. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
     AddFunds {
         amount: f64,
         allocation_type: u8,
         allocation_stream_address: Pubkey
     },
 
-    /// 0. `[signer]` The beneficiary account
+    /// Withdraws a specific amount of the vested funds of a stream
+    ///
+    /// 0. `[signer, writable]` The beneficiary account
     /// 1. `[writable]` The beneficiary token account (the recipient of the money)
     /// 2. `[]` The associated token mint account
-    /// 3. `[]` The treasury account
+    /// 3. `[writable]` The treasury account
     /// 4. `[writable]` The treasury token account
     /// 5. `[writable]` The stream account (The stream contract account).
-    /// 6.  [writable] The Money Streaming Program operating token account.
-    /// 7. `[]` The Money Streaming Program account.
-    /// 8. `[]` The Token Program account.
+    /// 6. `[]` The Money Streaming Program operating account.
+    /// 7. `[writable]` The Money Streaming Program operating token account.
+    /// 8. `[]` The Money Streaming Program account.
+    /// 9. `[]` The Associated Token Program account.
+    /// 10. `[]` The Token Program account.
+    /// 11. `[]` Rent sysvar account.
+    /// 12. `[]` The System Program account.
+    /// 13. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 14. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
     Withdraw { 
         amount: f64
     },
 
-    /// 0. `[signer]` The initializer of the transaction (treasurer or beneficiary)
-    /// 1. `[writable]` The stream account (The stream contract account).
-    /// 2. `[writable]` The Money Streaming Program operating account.
-    /// 3. `[]` System Program account.
+    /// 0. `[signer]` The initializer of the transaction (treasurer, beneficiary or pauser)
+    /// 1. `[writable]` The treasury account
+    /// 2. `[]` The associated token mint account
+    /// 3. `[writable]` The stream account (The stream contract account).
+    /// 4. `[]` The Money Streaming Program operating account (the fee treasury of the fee config).
+    /// 5. `[]` The Money Streaming Program account.
+    /// 6. `[]` The fee config account.
+    /// 7. `[]` The treasury roles account when the initializer is a pauser of the treasury (OPTIONAL).
     PauseStream,
 
-    /// 0. `[signer]` The initializer of the transaction (treasurer or beneficiary)
-    /// 1. `[writable]` The stream account (The stream contract account).
-    /// 2. `[writable]` The Money Streaming Program operating account.
-    /// 3. `[]` System Program account.
+    /// 0. `[signer]` The initializer of the transaction (treasurer, beneficiary or pauser)
+    /// 1. `[writable]` The treasury account
+    /// 2. `[]` The associated token mint account
+    /// 3. `[writable]` The stream account (The stream contract account).
+    /// 4. `[]` The Money Streaming Program operating account (the fee treasury of the fee config).
+    /// 5. `[]` The Money Streaming Program account.
+    /// 6. `[]` The fee config account.
+    /// 7. `[]` The treasury roles account when the initializer is a pauser of the treasury (OPTIONAL).
     ResumeStream,
 
     /// 0. `[signer, writable]` The initializer account (treasurer/beneficiary)
     /// 1. `[writable]` The treasurer account (the creator of the treasury)
-    /// 2. `[writable]` The beneficiary token account (the recipient of the money)
-    /// 3. `[]` The associated token mint account
-    /// 4. `[writable]` The treasury account
-    /// 5. `[writable]` The treasury token account
-    /// 6. `[writable]` The stream account (The stream contract account).
-    /// 7. `[writable]` The Money Streaming Program operating account.
-    /// 8. `[writable]` The Money Streaming Program operating token account.
-    /// 9. `[]` The Money Streaming Program account
-    /// 10. `[]` The Token Program account.
-    /// 11. `[]` System Program account.
+    /// 2. `[writable]` The treasurer token account
+    /// 3. `[writable]` The treasurer treasury pool token account
+    /// 4. `[writable]` The beneficiary account (has to sign when unwrapping native SOL)
+    /// 5. `[writable]` The beneficiary token account (the recipient of the money)
+    /// 6. `[]` The associated token mint account
+    /// 7. `[writable]` The treasury account
+    /// 8. `[writable]` The treasury token account
+    /// 9. `[writable]` The treasury pool mint account
+    /// 10. `[writable]` The stream account (The stream contract account).
+    /// 11. `[writable]` The Money Streaming Program operating account.
+    /// 12. `[writable]` The Money Streaming Program operating token account.
+    /// 13. `[]` The Money Streaming Program account
+    /// 14. `[]` The Associated Token Program account.
+    /// 15. `[]` The Token Program account.
+    /// 16. `[]` Rent sysvar account.
+    /// 17. `[]` System Program account.
+    /// 18. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 19. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
     CloseStream {
-        auto_close_treasury: bool
+        auto_close_treasury: bool,
+        unwrap_native: bool // OPTIONAL (native mint only, the beneficiary has to sign)
     },
 
-    /// 0. `[signer]` The treasurer account (the creator of the treasury)
+    /// 0. `[signer, writable]` The treasurer account (the creator of the treasury)
     /// 1. `[writable]` The treasury account
     /// 2. `[writable]` The treasury pool token mint account (The mint account of the treasury pool token issued by the treasury).
-    /// 3. `[]` The Money Streaming Program operations account.
+    /// 3. `[writable]` The Money Streaming Program operations account.
     /// 4. `[]` The Money Streaming Program account.
     /// 5. `[]` The Token Program account.    
     /// 6. `[]` System Program account.
     /// 7. `[]` SysvarRent account.
+    /// 8. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 9. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
     CreateTreasury {
         slot: u64,
         label: String,
@@ -117,21 +153,496 @@ pub enum StreamInstruction {
         auto_close: bool
     },
 
-    /// 0. `[signer]` The treasurer account (the creator of the treasury)
+    /// 0. `[signer, writable]` The treasurer account (the creator of the treasury)
     /// 1. `[writable]` The treasurer token account
     /// 2. `[writable]` The treasurer treasury pool token account
-    /// 3. `[]` The associated token account.
+    /// 3. `[]` The associated token mint account.
     /// 4. `[writable]` The treasury account
     /// 5. `[writable]` The treasury token account
     /// 6. `[writable]` The treasury pool mint account
-    /// 7. `[]` The Money Streaming Operation account.
+    /// 7. `[writable]` The Money Streaming Operation account.
     /// 8. `[writable]` The Money Streaming Operation token account.
     /// 9. `[]` The Money Streaming Program account
     /// 10. `[]` The token program account
+    /// 11. `[]` The associated token program account
+    /// 12. `[]` The system program account
+    /// 13. `[]` Rent sysvar account
+    /// 14. `[]` The fee config account
+    /// 15. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
     CloseTreasury,
 
+    /// Updates the treasury balance with the amount held by the treasury token account
     ///
+    /// 0. `[signer]` The treasurer account
+    /// 1. `[]` The associated token mint account
+    /// 2. `[writable]` The treasury account
+    /// 3. `[]` The treasury token account
     RefreshTreasuryBalance,
+
+    /// Initialize a new stream contract with amounts expressed in token base units
+    ///
+    /// 0. `[signer, writable]` The treasurer account (The creator of the money stream).
+    /// 1. `[writable]` The treasury account (The stream contract treasury account).
+    /// 2. `[]` The beneficiary associated token mint account.
+    /// 3. `[]` The beneficiary account (The beneficiary of money stream).
+    /// 4. `[signer, writable]` The stream account (The stream contract account).
+    /// 5. `[writable]` The Money Streaming Program operating account (Fees account).
+    /// 6. `[]` The Money Streaming Program account.
+    /// 7. `[]` The System Program account.
+    /// 8. `[]` Rent sysvar account.
+    /// 9. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 10. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    /// 11. `[]` The treasury roles account when the signer is a stream creator of the treasury (OPTIONAL).
+    CreateStreamV2 {
+        stream_name: String,
+        rate_amount: u64,
+        rate_interval_in_seconds: u64,
+        allocation_reserved: u64,
+        allocation_assigned: u64,
+        funded_on_utc: u64,
+        start_utc: u64,
+        rate_cliff_in_seconds: u64,
+        cliff_vest_amount: u64, // OPTIONAL
+        cliff_vest_percent: u64, // OPTIONAL (basis points)
+        auto_pause_in_seconds: u64
+    },
+
+    /// Adds a specific amount of funds (in token base units) to a treasury and optionally allocates them to a stream.
+    /// For the native mint, passing the contributor account as the contributor token account contributes lamports
+    ///
+    /// 0. `[signer]` The contributor account
+    /// 1. `[writable]` The contributor token account
+    /// 2. `[writable]` The contributor treasury pool token account
+    /// 3. `[writable]` The treasury account (Stream treasury account).
+    /// 4. `[writable]` The treasury token account.
+    /// 5. `[]` The associated token mint account
+    /// 6. `[writable]` The treasury pool mint account (the mint of the treasury pool token)
+    /// 7. `[writable]` The stream account (The stream to allocate the funds to).
+    /// 8. `[writable]` The Money Streaming Program operating account (Fees account).
+    /// 9. `[]` The Money Streaming Program account.
+    /// 10. `[]` The Associated Token Program account.
+    /// 11. `[]` The Token Program account.
+    /// 12. `[]` The System Program account.
+    /// 13. `[]` Rent sysvar account.
+    /// 14. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 15. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    AddFundsV2 {
+        amount: u64,
+        allocation_type: u8,
+        allocation_stream_address: Pubkey
+    },
+
+    /// Withdraws a specific amount (in token base units) of the vested funds of a stream. For the
+    /// native mint, `unwrap_native` closes the beneficiary token account so the funds arrive as SOL
+    ///
+    /// 0. `[signer]` The beneficiary account
+    /// 1. `[writable]` The beneficiary token account (the recipient of the money)
+    /// 2. `[]` The associated token mint account
+    /// 3. `[writable]` The treasury account
+    /// 4. `[writable]` The treasury token account
+    /// 5. `[writable]` The stream account (The stream contract account).
+    /// 6. `[]` The Money Streaming Program operating account.
+    /// 7. `[writable]` The Money Streaming Program operating token account.
+    /// 8. `[]` The Money Streaming Program account.
+    /// 9. `[]` The Associated Token Program account.
+    /// 10. `[]` The Token Program account.
+    /// 11. `[]` Rent sysvar account.
+    /// 12. `[]` The System Program account.
+    /// 13. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 14. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    WithdrawV2 {
+        amount: u64,
+        unwrap_native: bool // OPTIONAL (native mint only)
+    },
+
+    /// Initialize a new treasury which keeps its amounts in token base units
+    ///
+    /// 0. `[signer, writable]` The treasurer account (the creator of the treasury)
+    /// 1. `[writable]` The treasury account
+    /// 2. `[writable]` The treasury pool token mint account (The mint account of the treasury pool token issued by the treasury).
+    /// 3. `[writable]` The Money Streaming Program operations account.
+    /// 4. `[]` The Money Streaming Program account.
+    /// 5. `[]` The Token Program account.
+    /// 6. `[]` System Program account.
+    /// 7. `[]` SysvarRent account.
+    /// 8. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 9. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    CreateTreasuryV2 {
+        slot: u64,
+        label: String,
+        treasury_type: u8,
+        auto_close: bool
+    },
+
+    /// Migrates a legacy stream account (v0) to the StreamV1 layout in place
+    ///
+    /// 0. `[signer, writable]` The initializer of the transaction (treasurer or beneficiary). Pays the extra rent.
+    /// 1. `[]` The treasurer account of the stream.
+    /// 2. `[]` The associated token mint account of the stream.
+    /// 3. `[writable]` The stream account (The stream contract account).
+    /// 4. `[]` The Money Streaming Program account.
+    /// 5. `[]` The System Program account.
+    /// 6. `[]` Rent sysvar account.
+    MigrateStream,
+
+    /// Migrates a legacy treasury account (v0) to the TreasuryV1 layout in place. Every stream
+    /// of the treasury has to be passed so the treasury allocation and depletion rate can be computed
+    ///
+    /// 0. `[signer, writable]` The initializer of the transaction (treasurer or MSP operations account). Pays the extra rent.
+    /// 1. `[]` The treasurer account of the treasury.
+    /// 2. `[]` The associated token mint account of the treasury.
+    /// 3. `[writable]` The treasury account.
+    /// 4. `[]` The treasury token account.
+    /// 5. `[]` The Money Streaming Program account.
+    /// 6. `[]` The System Program account.
+    /// 7. `[]` Rent sysvar account.
+    /// 8. ..`[]` The stream accounts of the treasury (Stream or StreamV1).
+    MigrateTreasury,
+
+    /// Proposes new terms for a stream. The counterparty has to accept or reject them.
+    /// Only streams with the V2 layout (created with `CreateStreamV2`) can be updated, V1 and legacy
+    /// streams keep their terms for their lifetime (close them and create a V2 stream instead)
+    ///
+    /// 0. `[signer, writable]` The proposer account (treasurer or beneficiary of the stream).
+    /// 1. `[writable]` The stream terms account (PDA derived from the stream address).
+    /// 2. `[]` The treasury account (The stream contract treasury account).
+    /// 3. `[]` The stream account (The stream contract account).
+    /// 4. `[writable]` The Money Streaming Program operating account (Fees account).
+    /// 5. `[]` The Money Streaming Program account.
+    /// 6. `[]` The System Program account.
+    /// 7. `[]` Rent sysvar account.
+    /// 8. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 9. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    ProposeUpdate {
+        stream_name: String,
+        rate_amount: u64,
+        rate_interval_in_seconds: u64
+    },
+
+    /// Accepts or rejects the terms proposed for a stream and closes the stream terms account.
+    /// Accepting snapshots the vested amount and applies the new rate from then on (V2 streams only)
+    ///
+    /// 0. `[signer]` The counterparty account (treasurer or beneficiary of the stream).
+    /// 1. `[writable]` The proposer account (receives the stream terms account rent).
+    /// 2. `[writable]` The stream terms account.
+    /// 3. `[writable]` The treasury account.
+    /// 4. `[writable]` The stream account (The stream contract account).
+    /// 5. `[]` The Money Streaming Program account.
+    AnswerUpdate {
+        approve: bool
+    },
+
+    /// Transfers a stream to a new beneficiary, optionally withdrawing the vested amount to the current beneficiary first.
+    /// The settled amount pays the regular withdraw fee
+    ///
+    /// 0. `[signer]` The current beneficiary account
+    /// 1. `[writable]` The current beneficiary token account (the recipient of the vested amount)
+    /// 2. `[]` The associated token mint account
+    /// 3. `[writable]` The treasury account
+    /// 4. `[writable]` The treasury token account
+    /// 5. `[writable]` The stream account (The stream contract account).
+    /// 6. `[]` The Money Streaming Program operating account.
+    /// 7. `[writable]` The Money Streaming Program operating token account.
+    /// 8. `[]` The Money Streaming Program account.
+    /// 9. `[]` The Associated Token Program account.
+    /// 10. `[]` The Token Program account.
+    /// 11. `[]` Rent sysvar account.
+    /// 12. `[]` The System Program account.
+    /// 13. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 14. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    TransferStream {
+        new_beneficiary_address: Pubkey,
+        settle_vested: bool
+    },
+
+    /// Transfers the control of a treasury (and its streams) to a new treasurer. The treasury PDA and its funds do not move.
+    /// The stream checks read the treasurer from the treasury, so the streams do not need to be passed. The ones passed
+    /// get their `treasurer_address` copy refreshed. The roles granted by the current treasurer do not carry over,
+    /// the treasury roles account is closed and its rent goes back to the current treasurer
+    ///
+    /// 0. `[signer, writable]` The current treasurer account
+    /// 1. `[writable]` The treasury account
+    /// 2. `[writable]` The treasury roles account (PDA derived from the treasury address, may not exist).
+    /// 3. `[]` The Money Streaming Program account.
+    /// 4. ..`[writable]` The stream accounts of the treasury to refresh (OPTIONAL, any subset).
+    TransferTreasury {
+        new_treasurer_address: Pubkey
+    },
+
+    /// Initialize a new stream contract that stops vesting at a fixed end date. Once ended,
+    /// the unvested allocation is released back to the treasury
+    ///
+    /// 0. `[signer, writable]` The treasurer account (The creator of the money stream).
+    /// 1. `[writable]` The treasury account (The stream contract treasury account).
+    /// 2. `[]` The beneficiary associated token mint account.
+    /// 3. `[]` The beneficiary account (The beneficiary of money stream).
+    /// 4. `[signer, writable]` The stream account (The stream contract account).
+    /// 5. `[writable]` The Money Streaming Program operating account (Fees account).
+    /// 6. `[]` The Money Streaming Program account.
+    /// 7. `[]` The System Program account.
+    /// 8. `[]` Rent sysvar account.
+    /// 9. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 10. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    /// 11. `[]` The treasury roles account when the signer is a stream creator of the treasury (OPTIONAL).
+    CreateStreamWithEndDate {
+        stream_name: String,
+        rate_amount: u64,
+        rate_interval_in_seconds: u64,
+        allocation_reserved: u64,
+        allocation_assigned: u64,
+        funded_on_utc: u64,
+        start_utc: u64,
+        rate_cliff_in_seconds: u64,
+        cliff_vest_amount: u64, // OPTIONAL
+        cliff_vest_percent: u64, // OPTIONAL (basis points)
+        auto_pause_in_seconds: u64,
+        end_utc: u64 // OPTIONAL
+    },
+
+    /// Initialize a new stream contract with an optional end date and a vesting mode. Continuous
+    /// streams vest every second while stepped streams unlock the whole rate amount at every interval
+    ///
+    /// 0. `[signer, writable]` The treasurer account (The creator of the money stream).
+    /// 1. `[writable]` The treasury account (The stream contract treasury account).
+    /// 2. `[]` The beneficiary associated token mint account.
+    /// 3. `[]` The beneficiary account (The beneficiary of money stream).
+    /// 4. `[signer, writable]` The stream account (The stream contract account).
+    /// 5. `[writable]` The Money Streaming Program operating account (Fees account).
+    /// 6. `[]` The Money Streaming Program account.
+    /// 7. `[]` The System Program account.
+    /// 8. `[]` Rent sysvar account.
+    /// 9. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 10. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    /// 11. `[]` The treasury roles account when the signer is a stream creator of the treasury (OPTIONAL).
+    CreateStreamWithSchedule {
+        stream_name: String,
+        rate_amount: u64,
+        rate_interval_in_seconds: u64,
+        allocation_reserved: u64,
+        allocation_assigned: u64,
+        funded_on_utc: u64,
+        start_utc: u64,
+        rate_cliff_in_seconds: u64,
+        cliff_vest_amount: u64, // OPTIONAL
+        cliff_vest_percent: u64, // OPTIONAL (basis points)
+        auto_pause_in_seconds: u64,
+        end_utc: u64, // OPTIONAL
+        vesting_mode: u8 // 0 = Continuous, 1 = Stepped
+    },
+
+    /// Withdraws funds (in token base units) that are not allocated to any stream from a treasury
+    ///
+    /// 0. `[signer]` The treasurer account
+    /// 1. `[writable]` The destination token account (any token account of the treasury mint)
+    /// 2. `[]` The associated token mint account
+    /// 3. `[writable]` The treasury account
+    /// 4. `[writable]` The treasury token account
+    /// 5. `[]` The Money Streaming Program account.
+    /// 6. `[]` The Token Program account.
+    TreasuryWithdraw {
+        amount: u64
+    },
+
+    /// Allocates funds (in token base units) that are already in a treasury to one of its streams
+    ///
+    /// 0. `[signer]` The treasurer account
+    /// 1. `[]` The associated token mint account
+    /// 2. `[writable]` The treasury account
+    /// 3. `[writable]` The stream account (The stream contract account).
+    /// 4. `[]` The Money Streaming Program account.
+    Allocate {
+        amount: u64,
+        allocation_type: u8 // 0 = assigned, 1 = reserved
+    },
+
+    /// Burns treasury pool tokens (in pool token base units) and pays out the pro-rata share of
+    /// the treasury unallocated balance to the contributor
+    ///
+    /// 0. `[signer]` The contributor account
+    /// 1. `[writable]` The contributor token account (any token account of the treasury mint)
+    /// 2. `[writable]` The contributor treasury pool token account
+    /// 3. `[]` The associated token mint account
+    /// 4. `[writable]` The treasury account
+    /// 5. `[writable]` The treasury token account
+    /// 6. `[writable]` The treasury pool mint account
+    /// 7. `[]` The Money Streaming Program account.
+    /// 8. `[]` The Token Program account.
+    Redeem {
+        amount: u64
+    },
+
+    /// Creates the fee config account. Until it exists the program charges the default fees
+    ///
+    /// 0. `[signer, writable]` The Money Streaming Program operating account. Pays the rent.
+    /// 1. `[writable]` The fee config account (PDA derived from the fee config seed).
+    /// 2. `[]` The Money Streaming Program account.
+    /// 3. `[]` The System Program account.
+    /// 4. `[]` Rent sysvar account.
+    InitFeeConfig {
+        authority: Pubkey,
+        fee_treasury_address: Pubkey,
+        create_treasury_flat_fee: u64, // lamports
+        create_stream_flat_fee: u64, // lamports
+        add_funds_flat_fee: u64, // lamports
+        withdraw_percent_fee: u64, // basis points
+        propose_update_flat_fee: u64, // lamports
+        close_stream_flat_fee: u64, // lamports
+        close_stream_percent_fee: u64, // basis points
+        close_treasury_flat_fee: u64, // lamports
+        close_treasury_percent_fee: u64 // basis points
+    },
+
+    /// Updates the fees, the fee recipient and the authority of the fee config account
+    ///
+    /// 0. `[signer]` The fee config authority account.
+    /// 1. `[writable]` The fee config account.
+    /// 2. `[]` The Money Streaming Program account.
+    UpdateFeeConfig {
+        authority: Pubkey,
+        fee_treasury_address: Pubkey,
+        create_treasury_flat_fee: u64, // lamports
+        create_stream_flat_fee: u64, // lamports
+        add_funds_flat_fee: u64, // lamports
+        withdraw_percent_fee: u64, // basis points
+        propose_update_flat_fee: u64, // lamports
+        close_stream_flat_fee: u64, // lamports
+        close_stream_percent_fee: u64, // basis points
+        close_treasury_flat_fee: u64, // lamports
+        close_treasury_percent_fee: u64 // basis points
+    },
+
+    /// Creates or updates the fee tier of a treasurer or a treasury. Until the tier expires
+    /// `flat_fee` replaces every global flat fee and `withdraw_percent_fee` replaces every
+    /// global percent fee, including the close stream and close treasury percent fees
+    ///
+    /// 0. `[signer, writable]` The fee config authority account. Pays the rent.
+    /// 1. `[]` The fee config account.
+    /// 2. `[writable]` The fee tier account (PDA derived from the fee tier seed and the subject address).
+    /// 3. `[]` The Money Streaming Program account.
+    /// 4. `[]` The System Program account.
+    /// 5. `[]` Rent sysvar account.
+    SetFeeTier {
+        subject_address: Pubkey, // treasurer or treasury
+        flat_fee: u64, // lamports
+        withdraw_percent_fee: u64, // basis points
+        expiry_utc: u64 // OPTIONAL
+    },
+
+    /// Closes a fee tier account. The treasurer or treasury goes back to the global fees
+    ///
+    /// 0. `[signer, writable]` The fee config authority account (receives the fee tier account rent).
+    /// 1. `[]` The fee config account.
+    /// 2. `[writable]` The fee tier account.
+    /// 3. `[]` The Money Streaming Program account.
+    CloseFeeTier,
+
+    /// Initialize several V2 streams of a treasury at once, e.g. a payroll run. The allocations
+    /// are validated once against the treasury unallocated balance and the treasury is updated once.
+    /// The number of streams is bounded by the transaction size, every stream adds a signature.
+    /// Every stream takes the same terms as `CreateStreamWithSchedule` (reserved allocation, cliff,
+    /// auto-pause and vesting mode) except `funded_on_utc`, which is the time of the batch
+    ///
+    /// 0. `[signer, writable]` The treasurer or a stream creator of the treasury (The creator of the money streams).
+    /// 1. `[writable]` The treasury account (The stream contract treasury account).
+    /// 2. `[]` The beneficiary associated token mint account.
+    /// 3. `[writable]` The Money Streaming Program operating account (Fees account).
+    /// 4. `[]` The Money Streaming Program account.
+    /// 5. `[]` The System Program account.
+    /// 6. `[]` Rent sysvar account.
+    /// 7. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 8. `[]` The fee tier account of the treasurer or the treasury (ignored if it does not exist).
+    /// 9. `[]` The treasury roles account (ignored if it does not exist).
+    /// 10.. For every stream, in the order of `streams`:
+    ///     `[signer, writable]` The stream account (The stream contract account).
+    ///     `[]` The beneficiary account (The beneficiary of money stream).
+    CreateStreamsBatch {
+        streams: Vec<BatchStreamTerms>
+    },
+
+    /// Grants roles of a treasury to a member, creating the treasury roles account if needed.
+    /// Only the treasurer grants `Admin`, admins grant the other roles
+    ///
+    /// 0. `[signer, writable]` The treasurer or an admin of the treasury. Pays the rent.
+    /// 1. `[]` The treasury account.
+    /// 2. `[writable]` The treasury roles account (PDA derived from the treasury roles seed and the treasury address).
+    /// 3. `[]` The Money Streaming Program account.
+    /// 4. `[]` The System Program account.
+    /// 5. `[]` Rent sysvar account.
+    GrantTreasuryRoles {
+        member: Pubkey,
+        roles: u8 // `TreasuryRole` flags
+    },
+
+    /// Revokes roles of a treasury from a member. Only the treasurer revokes `Admin`,
+    /// admins revoke the other roles
+    ///
+    /// 0. `[signer]` The treasurer or an admin of the treasury.
+    /// 1. `[]` The treasury account.
+    /// 2. `[writable]` The treasury roles account.
+    /// 3. `[]` The Money Streaming Program account.
+    RevokeTreasuryRoles {
+        member: Pubkey,
+        roles: u8 // `TreasuryRole` flags
+    },
+
+    /// Opts a stream in or out of `PushVested`. The treasurer sets the keeper tip, the
+    /// beneficiary can only opt the stream out
+    ///
+    /// 0. `[signer]` The treasurer or the beneficiary of the stream.
+    /// 1. `[]` The treasury account.
+    /// 2. `[writable]` The stream account.
+    /// 3. `[]` The Money Streaming Program account.
+    SetPushVested {
+        enabled: bool,
+        keeper_tip: u64 // token base units paid to the keeper at most once per rate interval
+    },
+
+    /// Transfers the vested funds of a stream to the beneficiary associated token account
+    /// (created if missing). Anyone can send it for the streams that opted in. The withdraw
+    /// fee is taken from the pushed amount and the keeper tip (if any) from the unallocated
+    /// balance of the treasury. The tip is paid at most once per rate interval of the stream, only
+    /// when it is at most 10% of the pushed amount, and skipped when the unallocated balance can
+    /// not cover it
+    ///
+    /// 0. `[signer, writable]` The keeper account. Pays the rent of the missing token accounts.
+    /// 1. `[writable]` The keeper token account (receives the keeper tip).
+    /// 2. `[]` The beneficiary account.
+    /// 3. `[writable]` The beneficiary associated token account.
+    /// 4. `[]` The associated token mint account.
+    /// 5. `[writable]` The treasury account.
+    /// 6. `[writable]` The treasury token account.
+    /// 7. `[writable]` The stream account (The stream contract account).
+    /// 8. `[]` The Money Streaming Program operating account.
+    /// 9. `[writable]` The Money Streaming Program operating token account.
+    /// 10. `[]` The Money Streaming Program account.
+    /// 11. `[]` The Associated Token Program account.
+    /// 12. `[]` The Token Program account.
+    /// 13. `[]` Rent sysvar account.
+    /// 14. `[]` The System Program account.
+    /// 15. `[]` The fee config account (PDA derived from the fee config seed).
+    /// 16. `[]` The fee tier account of the treasurer or the treasury (OPTIONAL).
+    PushVested,
+}
+
+/// The terms of every stream of a `CreateStreamsBatch` instruction (amounts in token base units)
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchStreamTerms {
+    pub stream_name: String,
+    pub rate_amount: u64,
+    pub rate_interval_in_seconds: u64,
+    pub allocation_reserved: u64,
+    pub allocation_assigned: u64,
+    pub start_utc: u64,
+    pub rate_cliff_in_seconds: u64,
+    pub cliff_vest_amount: u64, // OPTIONAL
+    pub cliff_vest_percent: u64, // OPTIONAL (basis points)
+    pub auto_pause_in_seconds: u64,
+    pub end_utc: u64, // OPTIONAL
+    pub vesting_mode: u8 // 0 = Continuous, 1 = Stepped
+}
+
+impl BatchStreamTerms {
+    pub const LEN: usize = 113;
 }
 
 impl StreamInstruction {
@@ -153,6 +664,30 @@ impl StreamInstruction {
             6 => Self::unpack_create_treasury(result)?,
             7 => Ok(Self::CloseTreasury)?,
             8 => Ok(Self::RefreshTreasuryBalance)?,
+            9 => Self::unpack_create_stream_v2(result)?,
+            10 => Self::unpack_add_funds_v2(result)?,
+            11 => Self::unpack_withdraw_v2(result)?,
+            12 => Self::unpack_create_treasury_v2(result)?,
+            13 => Ok(Self::MigrateStream)?,
+            14 => Ok(Self::MigrateTreasury)?,
+            15 => Self::unpack_propose_update(result)?,
+            16 => Self::unpack_answer_update(result)?,
+            17 => Self::unpack_transfer_stream(result)?,
+            18 => Self::unpack_transfer_treasury(result)?,
+            19 => Self::unpack_create_stream_with_end_date(result)?,
+            20 => Self::unpack_create_stream_with_schedule(result)?,
+            21 => Self::unpack_treasury_withdraw(result)?,
+            22 => Self::unpack_allocate(result)?,
+            23 => Self::unpack_redeem(result)?,
+            24 => Self::unpack_init_fee_config(result)?,
+            25 => Self::unpack_update_fee_config(result)?,
+            26 => Self::unpack_set_fee_tier(result)?,
+            27 => Ok(Self::CloseFeeTier)?,
+            28 => Self::unpack_create_streams_batch(result)?,
+            29 => Self::unpack_grant_treasury_roles(result)?,
+            30 => Self::unpack_revoke_treasury_roles(result)?,
+            31 => Self::unpack_set_push_vested(result)?,
+            32 => Ok(Self::PushVested)?,
 
             _ => return Err(StreamError::InvalidStreamInstruction.into()),
         })
@@ -215,7 +750,7 @@ impl StreamInstruction {
 
             &Self::ResumeStream => buf.push(4),
 
-            &Self::CloseStream { auto_close_treasury } => {
+            &Self::CloseStream { auto_close_treasury, unwrap_native } => {
                 buf.push(5);
 
                 let close_treasury = match auto_close_treasury {
@@ -224,6 +759,7 @@ impl StreamInstruction {
                 };
 
                 buf.push(close_treasury[0] as u8);
+                buf.push(unwrap_native as u8);
             },
             
             Self::CreateTreasury {
@@ -251,126 +787,1600 @@ impl StreamInstruction {
             Self::CloseTreasury => buf.push(7),
 
             Self::RefreshTreasuryBalance => buf.push(8),
-        };
 
-        buf
-    }
+            Self::CreateStreamV2 {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds,
+                allocation_reserved,
+                allocation_assigned,
+                funded_on_utc,
+                start_utc,
+                rate_cliff_in_seconds,
+                cliff_vest_amount,
+                cliff_vest_percent,
+                auto_pause_in_seconds
 
-    fn unpack_create_stream(input: &[u8]) -> Result<Self, StreamError> {
+            } => {
 
-        let (stream_name, result) = unpack_string(input)?;
-        let (rate_amount, result) = result.split_at(8);
-        let rate_amount = unpack_f64(rate_amount)?;
-        let (rate_interval_in_seconds, result) = result.split_at(8);
-        let rate_interval_in_seconds = unpack_u64(rate_interval_in_seconds)?;
-        let (allocation_reserved, result) = result.split_at(8);
-        let allocation_reserved = unpack_f64(allocation_reserved)?;
-        let (allocation_assigned, result) = result.split_at(8);
-        let allocation_assigned = unpack_f64(allocation_assigned)?;
-        let (funded_on_utc, result) = result.split_at(8);
-        let funded_on_utc = unpack_u64(funded_on_utc)?;
-        let (start_utc, result) = result.split_at(8);
-        let start_utc = unpack_u64(start_utc)?;
-        let (rate_cliff_in_seconds, result) = result.split_at(8);
-        let rate_cliff_in_seconds = unpack_u64(rate_cliff_in_seconds)?;
-        let (cliff_vest_amount, result) = result.split_at(8);
-        let cliff_vest_amount = unpack_f64(cliff_vest_amount)?;
-        let (cliff_vest_percent, result) = result.split_at(8);
-        let cliff_vest_percent = unpack_f64(cliff_vest_percent)?;
-        let (auto_pause_in_seconds, _result) = result.split_at(8);
-        let auto_pause_in_seconds = unpack_u64(auto_pause_in_seconds)?;
+                buf.push(9);
 
-        Ok(Self::CreateStream {
-            stream_name,
-            rate_amount,
-            rate_interval_in_seconds,
-            allocation_reserved,
-            allocation_assigned,
-            funded_on_utc,
-            start_utc,
-            rate_cliff_in_seconds,
-            cliff_vest_amount,
-            cliff_vest_percent,
-            auto_pause_in_seconds
-        })
-    }
+                buf.extend_from_slice(stream_name.as_ref());
+                buf.extend_from_slice(&rate_amount.to_le_bytes());
+                buf.extend_from_slice(&rate_interval_in_seconds.to_le_bytes());
+                buf.extend_from_slice(&allocation_reserved.to_le_bytes());
+                buf.extend_from_slice(&allocation_assigned.to_le_bytes());
+                buf.extend_from_slice(&funded_on_utc.to_le_bytes());
+                buf.extend_from_slice(&start_utc.to_le_bytes());
+                buf.extend_from_slice(&rate_cliff_in_seconds.to_le_bytes());
+                buf.extend_from_slice(&cliff_vest_amount.to_le_bytes());
+                buf.extend_from_slice(&cliff_vest_percent.to_le_bytes());
+                buf.extend_from_slice(&auto_pause_in_seconds.to_le_bytes());
+            },
 
-    fn unpack_add_funds(input: &[u8]) -> Result<Self, StreamError> {
+            &Self::AddFundsV2 {
+                amount,
+                allocation_type,
+                allocation_stream_address
 
-        let (amount, result) = input.split_at(8);
-        let amount = unpack_f64(amount)?;
-        let (allocation_type, result) = result.split_at(1);
-        let allocation_type = unpack_u8(allocation_type)?;
-        let (allocation_stream_address, _result) = unpack_pubkey(result)?;
+            } => {
+                buf.push(10);
 
-        Ok(Self::AddFunds { 
-            amount,
-            allocation_type,
-            allocation_stream_address
-        })
-    }
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&allocation_type.to_le_bytes());
+                buf.extend_from_slice(&allocation_stream_address.as_ref());
+            },
 
-    fn unpack_withdraw(input: &[u8]) -> Result<Self, StreamError> {
+            &Self::WithdrawV2 { amount, unwrap_native } => {
+                buf.push(11);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(unwrap_native as u8);
+            },
 
-        let (amount, _result) = input.split_at(8);
-        let amount = unpack_f64(amount)?;
+            Self::CreateTreasuryV2 {
+                slot,
+                label,
+                treasury_type,
+                auto_close
 
-        Ok(Self::Withdraw { amount })
-    }
+            } => {
 
-    fn unpack_create_treasury(input: &[u8]) -> Result<Self, StreamError> {
+                buf.push(12);
 
-        let (slot, result) = input.split_at(8);
-        let slot = unpack_u64(slot)?;        
-        let (label, result) = unpack_string(result)?;
-        let (treasury_type, result) = result.split_at(1);
-        let treasury_type = unpack_u8(treasury_type)?;
-        let (auto_close, _result) = result.split_at(1);
+                buf.extend_from_slice(&slot.to_le_bytes());
+                buf.extend_from_slice(label.as_ref());
+                buf.extend_from_slice(&treasury_type.to_le_bytes());
+
+                let auto_close = match auto_close {
+                    false => [0],
+                    true => [1]
+                };
+
+                buf.push(auto_close[0] as u8);
+            },
+
+            Self::MigrateStream => buf.push(13),
+
+            Self::MigrateTreasury => buf.push(14),
+
+            Self::ProposeUpdate {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds
+
+            } => {
+
+                buf.push(15);
+
+                buf.extend_from_slice(stream_name.as_ref());
+                buf.extend_from_slice(&rate_amount.to_le_bytes());
+                buf.extend_from_slice(&rate_interval_in_seconds.to_le_bytes());
+            },
+
+            &Self::AnswerUpdate { approve } => {
+                buf.push(16);
+
+                let approve = match approve {
+                    false => [0],
+                    true => [1]
+                };
+
+                buf.push(approve[0] as u8);
+            },
+
+            &Self::TransferStream {
+                new_beneficiary_address,
+                settle_vested
+
+            } => {
+                buf.push(17);
+
+                buf.extend_from_slice(&new_beneficiary_address.as_ref());
+
+                let settle_vested = match settle_vested {
+                    false => [0],
+                    true => [1]
+                };
+
+                buf.push(settle_vested[0] as u8);
+            },
+
+            &Self::TransferTreasury { new_treasurer_address } => {
+                buf.push(18);
+                buf.extend_from_slice(&new_treasurer_address.as_ref());
+            },
+
+            Self::CreateStreamWithEndDate {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds,
+                allocation_reserved,
+                allocation_assigned,
+                funded_on_utc,
+                start_utc,
+                rate_cliff_in_seconds,
+                cliff_vest_amount,
+                cliff_vest_percent,
+                auto_pause_in_seconds,
+                end_utc
+
+            } => {
+
+                buf.push(19);
+
+                buf.extend_from_slice(stream_name.as_ref());
+                buf.extend_from_slice(&rate_amount.to_le_bytes());
+                buf.extend_from_slice(&rate_interval_in_seconds.to_le_bytes());
+                buf.extend_from_slice(&allocation_reserved.to_le_bytes());
+                buf.extend_from_slice(&allocation_assigned.to_le_bytes());
+                buf.extend_from_slice(&funded_on_utc.to_le_bytes());
+                buf.extend_from_slice(&start_utc.to_le_bytes());
+                buf.extend_from_slice(&rate_cliff_in_seconds.to_le_bytes());
+                buf.extend_from_slice(&cliff_vest_amount.to_le_bytes());
+                buf.extend_from_slice(&cliff_vest_percent.to_le_bytes());
+                buf.extend_from_slice(&auto_pause_in_seconds.to_le_bytes());
+                buf.extend_from_slice(&end_utc.to_le_bytes());
+            },
+
+            Self::CreateStreamWithSchedule {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds,
+                allocation_reserved,
+                allocation_assigned,
+                funded_on_utc,
+                start_utc,
+                rate_cliff_in_seconds,
+                cliff_vest_amount,
+                cliff_vest_percent,
+                auto_pause_in_seconds,
+                end_utc,
+                vesting_mode
+
+            } => {
+
+                buf.push(20);
+
+                buf.extend_from_slice(stream_name.as_ref());
+                buf.extend_from_slice(&rate_amount.to_le_bytes());
+                buf.extend_from_slice(&rate_interval_in_seconds.to_le_bytes());
+                buf.extend_from_slice(&allocation_reserved.to_le_bytes());
+                buf.extend_from_slice(&allocation_assigned.to_le_bytes());
+                buf.extend_from_slice(&funded_on_utc.to_le_bytes());
+                buf.extend_from_slice(&start_utc.to_le_bytes());
+                buf.extend_from_slice(&rate_cliff_in_seconds.to_le_bytes());
+                buf.extend_from_slice(&cliff_vest_amount.to_le_bytes());
+                buf.extend_from_slice(&cliff_vest_percent.to_le_bytes());
+                buf.extend_from_slice(&auto_pause_in_seconds.to_le_bytes());
+                buf.extend_from_slice(&end_utc.to_le_bytes());
+                buf.push(*vesting_mode);
+            },
+
+            &Self::TreasuryWithdraw { amount } => {
+                buf.push(21);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            },
+
+            &Self::Allocate { amount, allocation_type } => {
+                buf.push(22);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(allocation_type);
+            },
+
+            &Self::Redeem { amount } => {
+                buf.push(23);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            },
+
+            Self::InitFeeConfig {
+                authority,
+                fee_treasury_address,
+                create_treasury_flat_fee,
+                create_stream_flat_fee,
+                add_funds_flat_fee,
+                withdraw_percent_fee,
+                propose_update_flat_fee,
+                close_stream_flat_fee,
+                close_stream_percent_fee,
+                close_treasury_flat_fee,
+                close_treasury_percent_fee
+
+            } => {
+
+                buf.push(24);
+
+                buf.extend_from_slice(authority.as_ref());
+                buf.extend_from_slice(fee_treasury_address.as_ref());
+                buf.extend_from_slice(&create_treasury_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&create_stream_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&add_funds_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&withdraw_percent_fee.to_le_bytes());
+                buf.extend_from_slice(&propose_update_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&close_stream_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&close_stream_percent_fee.to_le_bytes());
+                buf.extend_from_slice(&close_treasury_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&close_treasury_percent_fee.to_le_bytes());
+            },
+
+            Self::UpdateFeeConfig {
+                authority,
+                fee_treasury_address,
+                create_treasury_flat_fee,
+                create_stream_flat_fee,
+                add_funds_flat_fee,
+                withdraw_percent_fee,
+                propose_update_flat_fee,
+                close_stream_flat_fee,
+                close_stream_percent_fee,
+                close_treasury_flat_fee,
+                close_treasury_percent_fee
+
+            } => {
+
+                buf.push(25);
+
+                buf.extend_from_slice(authority.as_ref());
+                buf.extend_from_slice(fee_treasury_address.as_ref());
+                buf.extend_from_slice(&create_treasury_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&create_stream_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&add_funds_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&withdraw_percent_fee.to_le_bytes());
+                buf.extend_from_slice(&propose_update_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&close_stream_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&close_stream_percent_fee.to_le_bytes());
+                buf.extend_from_slice(&close_treasury_flat_fee.to_le_bytes());
+                buf.extend_from_slice(&close_treasury_percent_fee.to_le_bytes());
+            },
+
+            &Self::SetFeeTier {
+                subject_address,
+                flat_fee,
+                withdraw_percent_fee,
+                expiry_utc
+
+            } => {
+
+                buf.push(26);
+
+                buf.extend_from_slice(subject_address.as_ref());
+                buf.extend_from_slice(&flat_fee.to_le_bytes());
+                buf.extend_from_slice(&withdraw_percent_fee.to_le_bytes());
+                buf.extend_from_slice(&expiry_utc.to_le_bytes());
+            },
+
+            Self::CloseFeeTier => buf.push(27),
+
+            Self::CreateStreamsBatch { streams } => {
+                buf.push(28);
+                buf.push(streams.len() as u8);
+
+                for terms in streams {
+                    buf.extend_from_slice(terms.stream_name.as_ref());
+                    buf.extend_from_slice(&terms.rate_amount.to_le_bytes());
+                    buf.extend_from_slice(&terms.rate_interval_in_seconds.to_le_bytes());
+                    buf.extend_from_slice(&terms.allocation_reserved.to_le_bytes());
+                    buf.extend_from_slice(&terms.allocation_assigned.to_le_bytes());
+                    buf.extend_from_slice(&terms.start_utc.to_le_bytes());
+                    buf.extend_from_slice(&terms.rate_cliff_in_seconds.to_le_bytes());
+                    buf.extend_from_slice(&terms.cliff_vest_amount.to_le_bytes());
+                    buf.extend_from_slice(&terms.cliff_vest_percent.to_le_bytes());
+                    buf.extend_from_slice(&terms.auto_pause_in_seconds.to_le_bytes());
+                    buf.extend_from_slice(&terms.end_utc.to_le_bytes());
+                    buf.push(terms.vesting_mode);
+                }
+            },
+
+            &Self::GrantTreasuryRoles { member, roles } => {
+                buf.push(29);
+                buf.extend_from_slice(member.as_ref());
+                buf.push(roles);
+            },
+
+            &Self::RevokeTreasuryRoles { member, roles } => {
+                buf.push(30);
+                buf.extend_from_slice(member.as_ref());
+                buf.push(roles);
+            },
+
+            &Self::SetPushVested { enabled, keeper_tip } => {
+                buf.push(31);
+                buf.push(enabled as u8);
+                buf.extend_from_slice(&keeper_tip.to_le_bytes());
+            },
+
+            Self::PushVested => buf.push(32),
+        };
+
+        buf
+    }
+
+    fn unpack_create_stream(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (stream_name, result) = unpack_string(input)?;
+        let (rate_amount, result) = result.split_at(8);
+        let rate_amount = unpack_f64(rate_amount)?;
+        let (rate_interval_in_seconds, result) = result.split_at(8);
+        let rate_interval_in_seconds = unpack_u64(rate_interval_in_seconds)?;
+        let (allocation_reserved, result) = result.split_at(8);
+        let allocation_reserved = unpack_f64(allocation_reserved)?;
+        let (allocation_assigned, result) = result.split_at(8);
+        let allocation_assigned = unpack_f64(allocation_assigned)?;
+        let (funded_on_utc, result) = result.split_at(8);
+        let funded_on_utc = unpack_u64(funded_on_utc)?;
+        let (start_utc, result) = result.split_at(8);
+        let start_utc = unpack_u64(start_utc)?;
+        let (rate_cliff_in_seconds, result) = result.split_at(8);
+        let rate_cliff_in_seconds = unpack_u64(rate_cliff_in_seconds)?;
+        let (cliff_vest_amount, result) = result.split_at(8);
+        let cliff_vest_amount = unpack_f64(cliff_vest_amount)?;
+        let (cliff_vest_percent, result) = result.split_at(8);
+        let cliff_vest_percent = unpack_f64(cliff_vest_percent)?;
+        let (auto_pause_in_seconds, _result) = result.split_at(8);
+        let auto_pause_in_seconds = unpack_u64(auto_pause_in_seconds)?;
+
+        Ok(Self::CreateStream {
+            stream_name,
+            rate_amount,
+            rate_interval_in_seconds,
+            allocation_reserved,
+            allocation_assigned,
+            funded_on_utc,
+            start_utc,
+            rate_cliff_in_seconds,
+            cliff_vest_amount,
+            cliff_vest_percent,
+            auto_pause_in_seconds
+        })
+    }
+
+    fn unpack_add_funds(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (amount, result) = input.split_at(8);
+        let amount = unpack_f64(amount)?;
+        let (allocation_type, result) = result.split_at(1);
+        let allocation_type = unpack_u8(allocation_type)?;
+        let (allocation_stream_address, _result) = unpack_pubkey(result)?;
+
+        Ok(Self::AddFunds { 
+            amount,
+            allocation_type,
+            allocation_stream_address
+        })
+    }
+
+    fn unpack_withdraw(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (amount, _result) = input.split_at(8);
+        let amount = unpack_f64(amount)?;
+
+        Ok(Self::Withdraw { amount })
+    }
+
+    fn unpack_create_treasury(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (slot, result) = input.split_at(8);
+        let slot = unpack_u64(slot)?;        
+        let (label, result) = unpack_string(result)?;
+        let (treasury_type, result) = result.split_at(1);
+        let treasury_type = unpack_u8(treasury_type)?;
+        let (auto_close, _result) = result.split_at(1);
         let auto_close = match auto_close {
             [0] => false,
             [1] => true,
             _ => false
         };
 
-        Ok(Self::CreateTreasury { 
-            slot,
-            label,
-            treasury_type,
-            auto_close
-        })
+        Ok(Self::CreateTreasury { 
+            slot,
+            label,
+            treasury_type,
+            auto_close
+        })
+    }
+
+    fn unpack_close_stream(input: &[u8]) -> Result<Self, StreamError> {
+        let (auto_close_treasury, result) = input.split_at(1);
+        let auto_close_treasury = match auto_close_treasury {
+            [0] => false,
+            [1] => true,
+            _ => false
+        };
+        // Older clients do not send the unwrap flag
+        let unwrap_native = match result.first() {
+            Some(1) => true,
+            _ => false
+        };
+
+        Ok(Self::CloseStream { auto_close_treasury, unwrap_native })
+    }
+
+    fn unpack_create_stream_v2(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (stream_name, result) = unpack_string(input)?;
+        let (rate_amount, result) = result.split_at(8);
+        let rate_amount = unpack_u64(rate_amount)?;
+        let (rate_interval_in_seconds, result) = result.split_at(8);
+        let rate_interval_in_seconds = unpack_u64(rate_interval_in_seconds)?;
+        let (allocation_reserved, result) = result.split_at(8);
+        let allocation_reserved = unpack_u64(allocation_reserved)?;
+        let (allocation_assigned, result) = result.split_at(8);
+        let allocation_assigned = unpack_u64(allocation_assigned)?;
+        let (funded_on_utc, result) = result.split_at(8);
+        let funded_on_utc = unpack_u64(funded_on_utc)?;
+        let (start_utc, result) = result.split_at(8);
+        let start_utc = unpack_u64(start_utc)?;
+        let (rate_cliff_in_seconds, result) = result.split_at(8);
+        let rate_cliff_in_seconds = unpack_u64(rate_cliff_in_seconds)?;
+        let (cliff_vest_amount, result) = result.split_at(8);
+        let cliff_vest_amount = unpack_u64(cliff_vest_amount)?;
+        let (cliff_vest_percent, result) = result.split_at(8);
+        let cliff_vest_percent = unpack_u64(cliff_vest_percent)?;
+        let (auto_pause_in_seconds, _result) = result.split_at(8);
+        let auto_pause_in_seconds = unpack_u64(auto_pause_in_seconds)?;
+
+        Ok(Self::CreateStreamV2 {
+            stream_name,
+            rate_amount,
+            rate_interval_in_seconds,
+            allocation_reserved,
+            allocation_assigned,
+            funded_on_utc,
+            start_utc,
+            rate_cliff_in_seconds,
+            cliff_vest_amount,
+            cliff_vest_percent,
+            auto_pause_in_seconds
+        })
+    }
+
+    fn unpack_add_funds_v2(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (amount, result) = input.split_at(8);
+        let amount = unpack_u64(amount)?;
+        let (allocation_type, result) = result.split_at(1);
+        let allocation_type = unpack_u8(allocation_type)?;
+        let (allocation_stream_address, _result) = unpack_pubkey(result)?;
+
+        Ok(Self::AddFundsV2 {
+            amount,
+            allocation_type,
+            allocation_stream_address
+        })
+    }
+
+    fn unpack_withdraw_v2(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (amount, result) = input.split_at(8);
+        let amount = unpack_u64(amount)?;
+        // Older clients do not send the unwrap flag
+        let unwrap_native = match result.first() {
+            Some(1) => true,
+            _ => false
+        };
+
+        Ok(Self::WithdrawV2 { amount, unwrap_native })
+    }
+
+    fn unpack_create_treasury_v2(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (slot, result) = input.split_at(8);
+        let slot = unpack_u64(slot)?;
+        let (label, result) = unpack_string(result)?;
+        let (treasury_type, result) = result.split_at(1);
+        let treasury_type = unpack_u8(treasury_type)?;
+        let (auto_close, _result) = result.split_at(1);
+        let auto_close = match auto_close {
+            [0] => false,
+            [1] => true,
+            _ => false
+        };
+
+        Ok(Self::CreateTreasuryV2 {
+            slot,
+            label,
+            treasury_type,
+            auto_close
+        })
+    }
+
+    fn unpack_propose_update(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (stream_name, result) = unpack_string(input)?;
+        let (rate_amount, result) = result.split_at(8);
+        let rate_amount = unpack_u64(rate_amount)?;
+        let (rate_interval_in_seconds, _result) = result.split_at(8);
+        let rate_interval_in_seconds = unpack_u64(rate_interval_in_seconds)?;
+
+        Ok(Self::ProposeUpdate {
+            stream_name,
+            rate_amount,
+            rate_interval_in_seconds
+        })
+    }
+
+    fn unpack_answer_update(input: &[u8]) -> Result<Self, StreamError> {
+        let (approve, _result) = input.split_at(1);
+        let approve = match approve {
+            [0] => false,
+            [1] => true,
+            _ => false
+        };
+
+        Ok(Self::AnswerUpdate { approve })
+    }
+
+    fn unpack_transfer_stream(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (new_beneficiary_address, result) = unpack_pubkey(input)?;
+        let (settle_vested, _result) = result.split_at(1);
+        let settle_vested = match settle_vested {
+            [0] => false,
+            [1] => true,
+            _ => false
+        };
+
+        Ok(Self::TransferStream {
+            new_beneficiary_address,
+            settle_vested
+        })
+    }
+
+    fn unpack_transfer_treasury(input: &[u8]) -> Result<Self, StreamError> {
+        let (new_treasurer_address, _result) = unpack_pubkey(input)?;
+        Ok(Self::TransferTreasury { new_treasurer_address })
+    }
+
+    fn unpack_create_stream_with_end_date(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (stream_name, result) = unpack_string(input)?;
+        let (rate_amount, result) = result.split_at(8);
+        let rate_amount = unpack_u64(rate_amount)?;
+        let (rate_interval_in_seconds, result) = result.split_at(8);
+        let rate_interval_in_seconds = unpack_u64(rate_interval_in_seconds)?;
+        let (allocation_reserved, result) = result.split_at(8);
+        let allocation_reserved = unpack_u64(allocation_reserved)?;
+        let (allocation_assigned, result) = result.split_at(8);
+        let allocation_assigned = unpack_u64(allocation_assigned)?;
+        let (funded_on_utc, result) = result.split_at(8);
+        let funded_on_utc = unpack_u64(funded_on_utc)?;
+        let (start_utc, result) = result.split_at(8);
+        let start_utc = unpack_u64(start_utc)?;
+        let (rate_cliff_in_seconds, result) = result.split_at(8);
+        let rate_cliff_in_seconds = unpack_u64(rate_cliff_in_seconds)?;
+        let (cliff_vest_amount, result) = result.split_at(8);
+        let cliff_vest_amount = unpack_u64(cliff_vest_amount)?;
+        let (cliff_vest_percent, result) = result.split_at(8);
+        let cliff_vest_percent = unpack_u64(cliff_vest_percent)?;
+        let (auto_pause_in_seconds, result) = result.split_at(8);
+        let auto_pause_in_seconds = unpack_u64(auto_pause_in_seconds)?;
+        let (end_utc, _result) = result.split_at(8);
+        let end_utc = unpack_u64(end_utc)?;
+
+        Ok(Self::CreateStreamWithEndDate {
+            stream_name,
+            rate_amount,
+            rate_interval_in_seconds,
+            allocation_reserved,
+            allocation_assigned,
+            funded_on_utc,
+            start_utc,
+            rate_cliff_in_seconds,
+            cliff_vest_amount,
+            cliff_vest_percent,
+            auto_pause_in_seconds,
+            end_utc
+        })
+    }
+
+    fn unpack_create_stream_with_schedule(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (stream_name, result) = unpack_string(input)?;
+        let (rate_amount, result) = result.split_at(8);
+        let rate_amount = unpack_u64(rate_amount)?;
+        let (rate_interval_in_seconds, result) = result.split_at(8);
+        let rate_interval_in_seconds = unpack_u64(rate_interval_in_seconds)?;
+        let (allocation_reserved, result) = result.split_at(8);
+        let allocation_reserved = unpack_u64(allocation_reserved)?;
+        let (allocation_assigned, result) = result.split_at(8);
+        let allocation_assigned = unpack_u64(allocation_assigned)?;
+        let (funded_on_utc, result) = result.split_at(8);
+        let funded_on_utc = unpack_u64(funded_on_utc)?;
+        let (start_utc, result) = result.split_at(8);
+        let start_utc = unpack_u64(start_utc)?;
+        let (rate_cliff_in_seconds, result) = result.split_at(8);
+        let rate_cliff_in_seconds = unpack_u64(rate_cliff_in_seconds)?;
+        let (cliff_vest_amount, result) = result.split_at(8);
+        let cliff_vest_amount = unpack_u64(cliff_vest_amount)?;
+        let (cliff_vest_percent, result) = result.split_at(8);
+        let cliff_vest_percent = unpack_u64(cliff_vest_percent)?;
+        let (auto_pause_in_seconds, result) = result.split_at(8);
+        let auto_pause_in_seconds = unpack_u64(auto_pause_in_seconds)?;
+        let (end_utc, result) = result.split_at(8);
+        let end_utc = unpack_u64(end_utc)?;
+        let (vesting_mode, _result) = result.split_at(1);
+        let vesting_mode = unpack_u8(vesting_mode)?;
+
+        Ok(Self::CreateStreamWithSchedule {
+            stream_name,
+            rate_amount,
+            rate_interval_in_seconds,
+            allocation_reserved,
+            allocation_assigned,
+            funded_on_utc,
+            start_utc,
+            rate_cliff_in_seconds,
+            cliff_vest_amount,
+            cliff_vest_percent,
+            auto_pause_in_seconds,
+            end_utc,
+            vesting_mode
+        })
+    }
+
+    fn unpack_treasury_withdraw(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (amount, _result) = input.split_at(8);
+        let amount = unpack_u64(amount)?;
+
+        Ok(Self::TreasuryWithdraw { amount })
+    }
+
+    fn unpack_allocate(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (amount, result) = input.split_at(8);
+        let amount = unpack_u64(amount)?;
+        let (allocation_type, _result) = result.split_at(1);
+        let allocation_type = unpack_u8(allocation_type)?;
+
+        Ok(Self::Allocate { amount, allocation_type })
+    }
+
+    fn unpack_redeem(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (amount, _result) = input.split_at(8);
+        let amount = unpack_u64(amount)?;
+
+        Ok(Self::Redeem { amount })
+    }
+
+    fn unpack_fee_config_fields(input: &[u8]) -> Result<(Pubkey, Pubkey, [u64; 9]), StreamError> {
+
+        let (authority, result) = unpack_pubkey(input)?;
+        let (fee_treasury_address, mut result) = unpack_pubkey(result)?;
+        let mut fees = [0u64; 9];
+
+        for fee in fees.iter_mut() {
+            if result.len() < 8 {
+                return Err(StreamError::InvalidStreamInstruction.into());
+            }
+
+            let (value, rest) = result.split_at(8);
+            *fee = unpack_u64(value)?;
+            result = rest;
+        }
+
+        Ok((authority, fee_treasury_address, fees))
+    }
+
+    fn unpack_init_fee_config(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (authority, fee_treasury_address, fees) = Self::unpack_fee_config_fields(input)?;
+
+        Ok(Self::InitFeeConfig {
+            authority,
+            fee_treasury_address,
+            create_treasury_flat_fee: fees[0],
+            create_stream_flat_fee: fees[1],
+            add_funds_flat_fee: fees[2],
+            withdraw_percent_fee: fees[3],
+            propose_update_flat_fee: fees[4],
+            close_stream_flat_fee: fees[5],
+            close_stream_percent_fee: fees[6],
+            close_treasury_flat_fee: fees[7],
+            close_treasury_percent_fee: fees[8]
+        })
+    }
+
+    fn unpack_update_fee_config(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (authority, fee_treasury_address, fees) = Self::unpack_fee_config_fields(input)?;
+
+        Ok(Self::UpdateFeeConfig {
+            authority,
+            fee_treasury_address,
+            create_treasury_flat_fee: fees[0],
+            create_stream_flat_fee: fees[1],
+            add_funds_flat_fee: fees[2],
+            withdraw_percent_fee: fees[3],
+            propose_update_flat_fee: fees[4],
+            close_stream_flat_fee: fees[5],
+            close_stream_percent_fee: fees[6],
+            close_treasury_flat_fee: fees[7],
+            close_treasury_percent_fee: fees[8]
+        })
+    }
+
+    fn unpack_set_fee_tier(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (subject_address, result) = unpack_pubkey(input)?;
+        let (flat_fee, result) = result.split_at(8);
+        let flat_fee = unpack_u64(flat_fee)?;
+        let (withdraw_percent_fee, result) = result.split_at(8);
+        let withdraw_percent_fee = unpack_u64(withdraw_percent_fee)?;
+        let (expiry_utc, _result) = result.split_at(8);
+        let expiry_utc = unpack_u64(expiry_utc)?;
+
+        Ok(Self::SetFeeTier {
+            subject_address,
+            flat_fee,
+            withdraw_percent_fee,
+            expiry_utc
+        })
+    }
+
+    fn unpack_create_streams_batch(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (&streams_count, mut result) = input
+            .split_first()
+            .ok_or(StreamError::InvalidStreamInstruction)?;
+
+        if result.len() < streams_count as usize * BatchStreamTerms::LEN {
+            return Err(StreamError::InvalidStreamInstruction);
+        }
+
+        let mut streams = Vec::with_capacity(streams_count as usize);
+
+        for _ in 0..streams_count {
+            let (stream_name, rest) = unpack_string(result)?;
+            let (rate_amount, rest) = rest.split_at(8);
+            let rate_amount = unpack_u64(rate_amount)?;
+            let (rate_interval_in_seconds, rest) = rest.split_at(8);
+            let rate_interval_in_seconds = unpack_u64(rate_interval_in_seconds)?;
+            let (allocation_reserved, rest) = rest.split_at(8);
+            let allocation_reserved = unpack_u64(allocation_reserved)?;
+            let (allocation_assigned, rest) = rest.split_at(8);
+            let allocation_assigned = unpack_u64(allocation_assigned)?;
+            let (start_utc, rest) = rest.split_at(8);
+            let start_utc = unpack_u64(start_utc)?;
+            let (rate_cliff_in_seconds, rest) = rest.split_at(8);
+            let rate_cliff_in_seconds = unpack_u64(rate_cliff_in_seconds)?;
+            let (cliff_vest_amount, rest) = rest.split_at(8);
+            let cliff_vest_amount = unpack_u64(cliff_vest_amount)?;
+            let (cliff_vest_percent, rest) = rest.split_at(8);
+            let cliff_vest_percent = unpack_u64(cliff_vest_percent)?;
+            let (auto_pause_in_seconds, rest) = rest.split_at(8);
+            let auto_pause_in_seconds = unpack_u64(auto_pause_in_seconds)?;
+            let (end_utc, rest) = rest.split_at(8);
+            let end_utc = unpack_u64(end_utc)?;
+            let (vesting_mode, rest) = rest.split_at(1);
+            let vesting_mode = unpack_u8(vesting_mode)?;
+
+            streams.push(BatchStreamTerms {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds,
+                allocation_reserved,
+                allocation_assigned,
+                start_utc,
+                rate_cliff_in_seconds,
+                cliff_vest_amount,
+                cliff_vest_percent,
+                auto_pause_in_seconds,
+                end_utc,
+                vesting_mode
+            });
+
+            result = rest;
+        }
+
+        Ok(Self::CreateStreamsBatch { streams })
+    }
+
+    fn unpack_grant_treasury_roles(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (member, result) = unpack_pubkey(input)?;
+        let roles = unpack_u8(result)?;
+
+        Ok(Self::GrantTreasuryRoles { member, roles })
+    }
+
+    fn unpack_revoke_treasury_roles(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (member, result) = unpack_pubkey(input)?;
+        let roles = unpack_u8(result)?;
+
+        Ok(Self::RevokeTreasuryRoles { member, roles })
+    }
+
+    fn unpack_set_push_vested(input: &[u8]) -> Result<Self, StreamError> {
+
+        let (enabled, result) = input
+            .split_first()
+            .ok_or(StreamError::InvalidStreamInstruction)?;
+        let enabled = match enabled {
+            0 => false,
+            1 => true,
+            _ => return Err(StreamError::InvalidStreamInstruction)
+        };
+        let keeper_tip = unpack_u64(result)?;
+
+        Ok(Self::SetPushVested { enabled, keeper_tip })
+    }
+ }
+
+ /// The fee tier account goes after the instruction accounts when the treasurer or the treasury has one
+ fn with_fee_tier(
+    program_id: &Pubkey,
+    mut accounts: Vec<AccountMeta>,
+    fee_tier_subject: Option<Pubkey>
+
+ ) -> Vec<AccountMeta> {
+
+    if let Some(subject_address) = fee_tier_subject {
+        let (fee_tier, _) = find_fee_tier_address(program_id, &subject_address);
+        accounts.push(AccountMeta::new_readonly(fee_tier, false));
+    }
+
+    accounts
+ }
+
+ /// Appends the treasury roles account so a delegated signer (a stream creator or a
+ /// pauser of the treasury) can act in place of the treasurer
+ pub fn with_treasury_roles(program_id: &Pubkey, mut instruction: Instruction, treasury: Pubkey) -> Instruction {
+    let (treasury_roles, _) = find_treasury_roles_address(program_id, &treasury);
+    instruction.accounts.push(AccountMeta::new_readonly(treasury_roles, false));
+    instruction
+ }
+
+ fn create_stream_accounts(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    treasury: Pubkey,
+    associated_token_mint: Pubkey,
+    beneficiary: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey
+
+ ) -> CreateStreamAccounts<Pubkey> {
+
+    CreateStreamAccounts {
+        treasurer,
+        treasury,
+        associated_token_mint,
+        beneficiary,
+        stream,
+        fee_treasury,
+        msp: *program_id,
+        system_program: system_program::id(),
+        rent: sysvar::rent::id(),
+        fee_config: find_fee_config_address(program_id).0
+    }
+ }
+
+ fn add_funds_accounts(
+    program_id: &Pubkey,
+    contributor: Pubkey,
+    contributor_token: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    treasury_pool_mint: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey
+
+ ) -> AddFundsAccounts<Pubkey> {
+
+    AddFundsAccounts {
+        contributor,
+        contributor_token,
+        contributor_treasury_pool_token: get_associated_token_address(&contributor, &treasury_pool_mint),
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint),
+        associated_token_mint,
+        treasury_pool_mint,
+        stream,
+        fee_treasury,
+        msp: *program_id,
+        associated_token_program: spl_associated_token_account::id(),
+        token_program: spl_token::id(),
+        system_program: system_program::id(),
+        rent: sysvar::rent::id(),
+        fee_config: find_fee_config_address(program_id).0
+    }
+ }
+
+ fn withdraw_accounts(
+    program_id: &Pubkey,
+    beneficiary: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey
+
+ ) -> WithdrawAccounts<Pubkey> {
+
+    WithdrawAccounts {
+        beneficiary,
+        beneficiary_token: get_associated_token_address(&beneficiary, &associated_token_mint),
+        associated_token_mint,
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint),
+        stream,
+        fee_treasury,
+        fee_treasury_token: get_associated_token_address(&fee_treasury, &associated_token_mint),
+        msp: *program_id,
+        associated_token_program: spl_associated_token_account::id(),
+        token_program: spl_token::id(),
+        rent: sysvar::rent::id(),
+        system_program: system_program::id(),
+        fee_config: find_fee_config_address(program_id).0
+    }
+ }
+
+ fn pause_or_resume_stream_accounts(
+    program_id: &Pubkey,
+    initializer: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey
+
+ ) -> PauseOrResumeStreamAccounts<Pubkey> {
+
+    PauseOrResumeStreamAccounts {
+        initializer,
+        treasury,
+        associated_token_mint,
+        stream,
+        fee_treasury,
+        msp: *program_id,
+        fee_config: find_fee_config_address(program_id).0
+    }
+ }
+
+ fn create_treasury_accounts(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    fee_treasury: Pubkey,
+    slot: u64
+
+ ) -> CreateTreasuryAccounts<Pubkey> {
+
+    let (treasury, _) = find_treasury_address(program_id, &treasurer, slot);
+    let (treasury_pool_mint, _) = find_treasury_pool_mint_address(program_id, &treasurer, &treasury, slot);
+
+    CreateTreasuryAccounts {
+        treasurer,
+        treasury,
+        treasury_pool_mint,
+        fee_treasury,
+        msp: *program_id,
+        token_program: spl_token::id(),
+        system_program: system_program::id(),
+        rent: sysvar::rent::id(),
+        fee_config: find_fee_config_address(program_id).0
+    }
+ }
+
+ pub fn create_stream(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    treasury: Pubkey,
+    associated_token_mint: Pubkey,
+    beneficiary: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    stream_name: String,
+    rate_amount: f64,
+    rate_interval_in_seconds: u64,
+    allocation_reserved: f64,
+    allocation_assigned: f64,
+    funded_on_utc: u64,
+    start_utc: u64,
+    rate_cliff_in_seconds: u64,
+    cliff_vest_amount: f64,
+    cliff_vest_percent: f64,
+    auto_pause_in_seconds: u64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CreateStream {
+        stream_name, rate_amount, rate_interval_in_seconds, allocation_reserved,
+        allocation_assigned, funded_on_utc, start_utc, rate_cliff_in_seconds,
+        cliff_vest_amount, cliff_vest_percent, auto_pause_in_seconds
+    }.pack();
+
+    let accounts = create_stream_accounts(
+        program_id, treasurer, treasury, associated_token_mint, beneficiary, stream, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn create_stream_v2(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    treasury: Pubkey,
+    associated_token_mint: Pubkey,
+    beneficiary: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    stream_name: String,
+    rate_amount: u64,
+    rate_interval_in_seconds: u64,
+    allocation_reserved: u64,
+    allocation_assigned: u64,
+    funded_on_utc: u64,
+    start_utc: u64,
+    rate_cliff_in_seconds: u64,
+    cliff_vest_amount: u64,
+    cliff_vest_percent: u64,
+    auto_pause_in_seconds: u64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CreateStreamV2 {
+        stream_name, rate_amount, rate_interval_in_seconds, allocation_reserved,
+        allocation_assigned, funded_on_utc, start_utc, rate_cliff_in_seconds,
+        cliff_vest_amount, cliff_vest_percent, auto_pause_in_seconds
+    }.pack();
+
+    let accounts = create_stream_accounts(
+        program_id, treasurer, treasury, associated_token_mint, beneficiary, stream, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn create_stream_with_end_date(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    treasury: Pubkey,
+    associated_token_mint: Pubkey,
+    beneficiary: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    stream_name: String,
+    rate_amount: u64,
+    rate_interval_in_seconds: u64,
+    allocation_reserved: u64,
+    allocation_assigned: u64,
+    funded_on_utc: u64,
+    start_utc: u64,
+    rate_cliff_in_seconds: u64,
+    cliff_vest_amount: u64,
+    cliff_vest_percent: u64,
+    auto_pause_in_seconds: u64,
+    end_utc: u64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CreateStreamWithEndDate {
+        stream_name, rate_amount, rate_interval_in_seconds, allocation_reserved,
+        allocation_assigned, funded_on_utc, start_utc, rate_cliff_in_seconds,
+        cliff_vest_amount, cliff_vest_percent, auto_pause_in_seconds, end_utc
+    }.pack();
+
+    let accounts = create_stream_accounts(
+        program_id, treasurer, treasury, associated_token_mint, beneficiary, stream, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn create_stream_with_schedule(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    treasury: Pubkey,
+    associated_token_mint: Pubkey,
+    beneficiary: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    stream_name: String,
+    rate_amount: u64,
+    rate_interval_in_seconds: u64,
+    allocation_reserved: u64,
+    allocation_assigned: u64,
+    funded_on_utc: u64,
+    start_utc: u64,
+    rate_cliff_in_seconds: u64,
+    cliff_vest_amount: u64,
+    cliff_vest_percent: u64,
+    auto_pause_in_seconds: u64,
+    end_utc: u64,
+    vesting_mode: u8
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CreateStreamWithSchedule {
+        stream_name, rate_amount, rate_interval_in_seconds, allocation_reserved,
+        allocation_assigned, funded_on_utc, start_utc, rate_cliff_in_seconds,
+        cliff_vest_amount, cliff_vest_percent, auto_pause_in_seconds, end_utc, vesting_mode
+    }.pack();
+
+    let accounts = create_stream_accounts(
+        program_id, treasurer, treasury, associated_token_mint, beneficiary, stream, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn add_funds(
+    program_id: &Pubkey,
+    contributor: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    treasury_pool_mint: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    amount: f64,
+    allocation_type: u8,
+    allocation_stream_address: Pubkey
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::AddFunds { 
+        amount,
+        allocation_type,
+        allocation_stream_address
+
+    }.pack();
+
+    let accounts = add_funds_accounts(
+        program_id, contributor, get_associated_token_address(&contributor, &associated_token_mint),
+        associated_token_mint, treasury, treasury_pool_mint, allocation_stream_address, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ /// With `contribute_native` the funds are taken from the lamports of the contributor (native mint only)
+ pub fn add_funds_v2(
+    program_id: &Pubkey,
+    contributor: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    treasury_pool_mint: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    amount: u64,
+    allocation_type: u8,
+    allocation_stream_address: Pubkey,
+    contribute_native: bool
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::AddFundsV2 { 
+        amount,
+        allocation_type,
+        allocation_stream_address
+
+    }.pack();
+
+    let contributor_token = match contribute_native {
+        true => contributor,
+        false => get_associated_token_address(&contributor, &associated_token_mint)
+    };
+
+    let accounts = add_funds_accounts(
+        program_id, contributor, contributor_token, associated_token_mint,
+        treasury, treasury_pool_mint, allocation_stream_address, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn withdraw(
+    program_id: &Pubkey,
+    beneficiary: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    amount: f64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::Withdraw { amount }.pack();
+    let accounts = withdraw_accounts(
+        program_id, beneficiary, associated_token_mint, treasury, stream, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn withdraw_v2(
+    program_id: &Pubkey,
+    beneficiary: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    amount: u64,
+    unwrap_native: bool
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::WithdrawV2 { amount, unwrap_native }.pack();
+    let accounts = withdraw_accounts(
+        program_id, beneficiary, associated_token_mint, treasury, stream, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn pause_stream(
+    program_id: &Pubkey,
+    initializer: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::PauseStream.pack();
+    let accounts = pause_or_resume_stream_accounts(
+        program_id, initializer, associated_token_mint, treasury, stream, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn resume_stream(
+    program_id: &Pubkey,
+    initializer: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::ResumeStream.pack();
+    let accounts = pause_or_resume_stream_accounts(
+        program_id, initializer, associated_token_mint, treasury, stream, fee_treasury
+    ).to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn close_stream(
+    program_id: &Pubkey,
+    initializer: Pubkey,
+    treasurer: Pubkey,
+    beneficiary: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    treasury_pool_mint: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    auto_close_treasury: bool,
+    unwrap_native: bool
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CloseStream { auto_close_treasury, unwrap_native }.pack();
+    let mut accounts = CloseStreamAccounts {
+        initializer,
+        treasurer,
+        treasurer_token: get_associated_token_address(&treasurer, &associated_token_mint),
+        treasurer_treasury_pool_token: get_associated_token_address(&treasurer, &treasury_pool_mint),
+        beneficiary,
+        beneficiary_token: get_associated_token_address(&beneficiary, &associated_token_mint),
+        associated_token_mint,
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint),
+        treasury_pool_mint,
+        stream,
+        fee_treasury,
+        fee_treasury_token: get_associated_token_address(&fee_treasury, &associated_token_mint),
+        msp: *program_id,
+        associated_token_program: spl_associated_token_account::id(),
+        token_program: spl_token::id(),
+        rent: sysvar::rent::id(),
+        system_program: system_program::id(),
+        fee_config: find_fee_config_address(program_id).0
+    }.to_account_metas();
+
+    // Unwrapping closes the beneficiary token account so the beneficiary has to sign
+    if unwrap_native {
+        accounts[4].is_signer = true;
+    }
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn create_treasury(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    slot: u64,
+    label: String,
+    treasury_type: u8,
+    auto_close: bool
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CreateTreasury { slot, label, treasury_type, auto_close }.pack();
+    let accounts = create_treasury_accounts(program_id, treasurer, fee_treasury, slot).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn create_treasury_v2(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    slot: u64,
+    label: String,
+    treasury_type: u8,
+    auto_close: bool
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CreateTreasuryV2 { slot, label, treasury_type, auto_close }.pack();
+    let accounts = create_treasury_accounts(program_id, treasurer, fee_treasury, slot).to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn close_treasury(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    treasury_pool_mint: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CloseTreasury.pack();
+    let accounts = CloseTreasuryAccounts {
+        treasurer,
+        treasurer_token: get_associated_token_address(&treasurer, &associated_token_mint),
+        treasurer_treasury_pool_token: get_associated_token_address(&treasurer, &treasury_pool_mint),
+        associated_token_mint,
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint),
+        treasury_pool_mint,
+        fee_treasury,
+        fee_treasury_token: get_associated_token_address(&fee_treasury, &associated_token_mint),
+        msp: *program_id,
+        token_program: spl_token::id(),
+        associated_token_program: spl_associated_token_account::id(),
+        system_program: system_program::id(),
+        rent: sysvar::rent::id(),
+        fee_config: find_fee_config_address(program_id).0
+    }.to_account_metas();
+
+    Ok(Instruction { 
+        program_id: *program_id, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
+        data 
+    })
+ }
+
+ pub fn refresh_treasury_balance(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
     }
 
-    fn unpack_close_stream(input: &[u8]) -> Result<Self, StreamError> {
-        let (auto_close_treasury, _result) = input.split_at(1);
-        let auto_close_treasury = match auto_close_treasury {
-            [0] => false,
-            [1] => true,
-            _ => false
-        };
+    let data = StreamInstruction::RefreshTreasuryBalance.pack();
+    let accounts = RefreshTreasuryBalanceAccounts {
+        treasurer,
+        associated_token_mint,
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint)
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn migrate_stream(
+    program_id: &Pubkey,
+    initializer: Pubkey,
+    treasurer: Pubkey,
+    associated_token_mint: Pubkey,
+    stream: Pubkey
+
+ ) -> Result<Instruction, StreamError> {
 
-        Ok(Self::CloseStream { auto_close_treasury })
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
     }
+
+    let data = StreamInstruction::MigrateStream.pack();
+    let accounts = MigrateStreamAccounts {
+        initializer,
+        treasurer,
+        associated_token_mint,
+        stream,
+        msp: *program_id,
+        system_program: system_program::id(),
+        rent: sysvar::rent::id()
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
  }
 
- pub fn create_stream(
+ pub fn migrate_treasury(
     program_id: &Pubkey,
+    initializer: Pubkey,
     treasurer: Pubkey,
-    beneficiary: Pubkey,
     associated_token_mint: Pubkey,
     treasury: Pubkey,
+    streams: &[Pubkey]
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::MigrateTreasury.pack();
+    let mut accounts = MigrateTreasuryAccounts {
+        initializer,
+        treasurer,
+        associated_token_mint,
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint),
+        msp: *program_id,
+        system_program: system_program::id(),
+        rent: sysvar::rent::id()
+    }.to_account_metas();
+
+    accounts.extend(streams.iter().map(|stream| AccountMeta::new_readonly(*stream, false)));
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn propose_update(
+    program_id: &Pubkey,
+    proposer: Pubkey,
+    treasury: Pubkey,
     stream: Pubkey,
     fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
     stream_name: String,
-    rate_amount: f64,
-    rate_interval_in_seconds: u64,
-    allocation_reserved: f64,
-    allocation_assigned: f64,
-    funded_on_utc: u64,
-    start_utc: u64,
-    rate_cliff_in_seconds: u64,
-    cliff_vest_amount: f64,
-    cliff_vest_percent: f64,
-    auto_pause_in_seconds: u64
+    rate_amount: u64,
+    rate_interval_in_seconds: u64
 
  ) -> Result<Instruction, StreamError> {
 
@@ -378,39 +2388,33 @@ impl StreamInstruction {
         return Err(StreamError::IncorrectProgramId.into());
     }
 
-    let data = StreamInstruction::CreateStream {
-        stream_name, rate_amount, rate_interval_in_seconds, allocation_reserved,
-        allocation_assigned, funded_on_utc, start_utc, rate_cliff_in_seconds,
-        cliff_vest_amount, cliff_vest_percent, auto_pause_in_seconds
-    }.pack();
-
-    let accounts = vec![
-        AccountMeta::new_readonly(treasurer, true),
-        AccountMeta::new_readonly(treasury, false),
-        AccountMeta::new_readonly(associated_token_mint, false),
-        AccountMeta::new(stream, false),
-        AccountMeta::new_readonly(beneficiary, false),
-        AccountMeta::new(fee_treasury, false),
-        AccountMeta::new_readonly(*program_id, false),
-        AccountMeta::new_readonly(system_program::id(), false),
-        AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false)
-    ];
+    let data = StreamInstruction::ProposeUpdate { stream_name, rate_amount, rate_interval_in_seconds }.pack();
+    let accounts = ProposeUpdateAccounts {
+        proposer,
+        stream_terms: find_stream_terms_address(program_id, &stream).0,
+        treasury,
+        stream,
+        fee_treasury,
+        msp: *program_id,
+        system_program: system_program::id(),
+        rent: sysvar::rent::id(),
+        fee_config: find_fee_config_address(program_id).0
+    }.to_account_metas();
 
     Ok(Instruction { 
         program_id: *program_id, 
-        accounts, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
         data 
     })
  }
 
- pub fn add_funds(
+ pub fn answer_update(
     program_id: &Pubkey,
-    stream_address: &Pubkey,
-    treasury_address: &Pubkey,
-    contribution_token_address: &Pubkey,
-    amount: f64,
-    allocation_type: u8,
-    allocation_stream_address: Pubkey
+    answerer: Pubkey,
+    proposer: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    approve: bool
 
  ) -> Result<Instruction, StreamError> {
 
@@ -418,36 +2422,54 @@ impl StreamInstruction {
         return Err(StreamError::IncorrectProgramId.into());
     }
 
-    let data = StreamInstruction::AddFunds { 
-        amount,
-        allocation_type,
-        allocation_stream_address
+    let data = StreamInstruction::AnswerUpdate { approve }.pack();
+    let accounts = AnswerUpdateAccounts {
+        answerer,
+        proposer,
+        stream_terms: find_stream_terms_address(program_id, &stream).0,
+        treasury,
+        stream,
+        msp: *program_id
+    }.to_account_metas();
 
-    }.pack();
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn transfer_stream(
+    program_id: &Pubkey,
+    beneficiary: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    new_beneficiary_address: Pubkey,
+    settle_vested: bool
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
 
-    let accounts = vec![
-        AccountMeta::new(*contribution_token_address, true),
-        AccountMeta::new(*stream_address, false),
-        AccountMeta::new_readonly(*treasury_address, false)
-    ];
+    let data = StreamInstruction::TransferStream { new_beneficiary_address, settle_vested }.pack();
+    let accounts = withdraw_accounts(
+        program_id, beneficiary, associated_token_mint, treasury, stream, fee_treasury
+    ).to_account_metas();
 
     Ok(Instruction { 
         program_id: *program_id, 
-        accounts, 
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject), 
         data 
     })
  }
 
- pub fn withdraw(
+ pub fn transfer_treasury(
     program_id: &Pubkey,
-    beneficiary_address: Pubkey,
-    beneficiary_token_address: Pubkey,
-    beneficiary_token_mint_address: Pubkey,
-    treasury_address: Pubkey,
-    treasury_token_address: Pubkey,
-    stream_account_address: Pubkey,
-    msp_ops_token_address: Pubkey,
-    amount: f64,
+    treasurer: Pubkey,
+    treasury: Pubkey,
+    streams: &[Pubkey],
+    new_treasurer_address: Pubkey
 
  ) -> Result<Instruction, StreamError> {
 
@@ -455,33 +2477,25 @@ impl StreamInstruction {
         return Err(StreamError::IncorrectProgramId.into());
     }
 
-    let data = StreamInstruction::Withdraw { amount }.pack();
-    let accounts = vec![
-        AccountMeta::new_readonly(beneficiary_address, false),
-        AccountMeta::new(beneficiary_token_address, false),
-        AccountMeta::new(beneficiary_token_mint_address, false),
-        AccountMeta::new(treasury_address, false),
-        AccountMeta::new(treasury_token_address, false),
-        AccountMeta::new(stream_account_address, false),
-        AccountMeta::new(msp_ops_token_address, false),
-        AccountMeta::new_readonly(*program_id, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
+    let data = StreamInstruction::TransferTreasury { new_treasurer_address }.pack();
+    let mut accounts = TransferTreasuryAccounts {
+        treasurer,
+        treasury,
+        treasury_roles: find_treasury_roles_address(program_id, &treasury).0,
+        msp: *program_id
+    }.to_account_metas();
 
-    Ok(Instruction { 
-        program_id: *program_id, 
-        accounts, 
-        data 
-    })
+    accounts.extend(streams.iter().map(|stream| AccountMeta::new(*stream, false)));
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
  }
 
- pub fn close_stream(
-    initializer_account_key: &Pubkey,
-    stream_account_key: &Pubkey,
-    counterparty_account_key: &Pubkey,
-    treasury_account_key: &Pubkey,
-    auto_close_treasury: bool,
+ pub fn treasury_withdraw(
     program_id: &Pubkey,
+    treasurer: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    amount: u64
 
  ) -> Result<Instruction, StreamError> {
 
@@ -489,29 +2503,28 @@ impl StreamInstruction {
         return Err(StreamError::IncorrectProgramId.into());
     }
 
-    let data = StreamInstruction::CloseStream { auto_close_treasury }.pack();
-    let accounts = vec![
-        AccountMeta::new(*initializer_account_key, true),
-        AccountMeta::new(*stream_account_key, false),
-        AccountMeta::new_readonly(*counterparty_account_key, false),
-        AccountMeta::new_readonly(*treasury_account_key, false)
-    ];
+    let data = StreamInstruction::TreasuryWithdraw { amount }.pack();
+    let accounts = TreasuryWithdrawAccounts {
+        treasurer,
+        destination_token: get_associated_token_address(&treasurer, &associated_token_mint),
+        associated_token_mint,
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint),
+        msp: *program_id,
+        token_program: spl_token::id()
+    }.to_account_metas();
 
     Ok(Instruction { program_id: *program_id, accounts, data })
  }
 
- pub fn close_treasury(
-    treasurer_account_address: Pubkey,
-    treasurer_token_account_address: Pubkey,
-    treasurer_treasury_pool_token_account_address: Pubkey,
-    associated_token_mint_address: Pubkey,
-    treasury_account_address: Pubkey,
-    treasury_token_account_address: Pubkey,
-    treasury_pool_mint_address: Pubkey,
-    msp_ops_account_address: Pubkey,
-    msp_ops_token_account_address: Pubkey,
-    token_program_account_address: Pubkey,
+ pub fn allocate(
     program_id: &Pubkey,
+    treasurer: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    amount: u64,
+    allocation_type: u8
 
  ) -> Result<Instruction, StreamError> {
 
@@ -519,20 +2532,330 @@ impl StreamInstruction {
         return Err(StreamError::IncorrectProgramId.into());
     }
 
-    let data = StreamInstruction::CloseTreasury.pack();
-    let accounts = vec![
-        AccountMeta::new(treasurer_account_address, true),
-        AccountMeta::new(treasurer_token_account_address, false),
-        AccountMeta::new(treasurer_treasury_pool_token_account_address, false),
-        AccountMeta::new_readonly(associated_token_mint_address, false),
-        AccountMeta::new(treasury_account_address, false),
-        AccountMeta::new(treasury_token_account_address, false),
-        AccountMeta::new(treasury_pool_mint_address, false),
-        AccountMeta::new(msp_ops_account_address, false),
-        AccountMeta::new(msp_ops_token_account_address, false),
-        AccountMeta::new_readonly(*program_id, false),
-        AccountMeta::new_readonly(token_program_account_address, false)
-    ];
+    let data = StreamInstruction::Allocate { amount, allocation_type }.pack();
+    let accounts = AllocateAccounts {
+        treasurer,
+        associated_token_mint,
+        treasury,
+        stream,
+        msp: *program_id
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn redeem(
+    program_id: &Pubkey,
+    contributor: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    treasury_pool_mint: Pubkey,
+    amount: u64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::Redeem { amount }.pack();
+    let accounts = RedeemAccounts {
+        contributor,
+        contributor_token: get_associated_token_address(&contributor, &associated_token_mint),
+        contributor_treasury_pool_token: get_associated_token_address(&contributor, &treasury_pool_mint),
+        associated_token_mint,
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint),
+        treasury_pool_mint,
+        msp: *program_id,
+        token_program: spl_token::id()
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn init_fee_config(
+    program_id: &Pubkey,
+    initializer: Pubkey,
+    authority: Pubkey,
+    fee_treasury_address: Pubkey,
+    create_treasury_flat_fee: u64,
+    create_stream_flat_fee: u64,
+    add_funds_flat_fee: u64,
+    withdraw_percent_fee: u64,
+    propose_update_flat_fee: u64,
+    close_stream_flat_fee: u64,
+    close_stream_percent_fee: u64,
+    close_treasury_flat_fee: u64,
+    close_treasury_percent_fee: u64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::InitFeeConfig {
+        authority, fee_treasury_address, create_treasury_flat_fee, create_stream_flat_fee,
+        add_funds_flat_fee, withdraw_percent_fee, propose_update_flat_fee,
+        close_stream_flat_fee, close_stream_percent_fee, close_treasury_flat_fee, close_treasury_percent_fee
+    }.pack();
+
+    let accounts = InitFeeConfigAccounts {
+        initializer,
+        fee_config: find_fee_config_address(program_id).0,
+        msp: *program_id,
+        system_program: system_program::id(),
+        rent: sysvar::rent::id()
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn update_fee_config(
+    program_id: &Pubkey,
+    authority: Pubkey,
+    new_authority: Pubkey,
+    fee_treasury_address: Pubkey,
+    create_treasury_flat_fee: u64,
+    create_stream_flat_fee: u64,
+    add_funds_flat_fee: u64,
+    withdraw_percent_fee: u64,
+    propose_update_flat_fee: u64,
+    close_stream_flat_fee: u64,
+    close_stream_percent_fee: u64,
+    close_treasury_flat_fee: u64,
+    close_treasury_percent_fee: u64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::UpdateFeeConfig {
+        authority: new_authority, fee_treasury_address, create_treasury_flat_fee, create_stream_flat_fee,
+        add_funds_flat_fee, withdraw_percent_fee, propose_update_flat_fee,
+        close_stream_flat_fee, close_stream_percent_fee, close_treasury_flat_fee, close_treasury_percent_fee
+    }.pack();
+
+    let accounts = UpdateFeeConfigAccounts {
+        authority,
+        fee_config: find_fee_config_address(program_id).0,
+        msp: *program_id
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn set_fee_tier(
+    program_id: &Pubkey,
+    authority: Pubkey,
+    subject_address: Pubkey,
+    flat_fee: u64,
+    withdraw_percent_fee: u64,
+    expiry_utc: u64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::SetFeeTier { subject_address, flat_fee, withdraw_percent_fee, expiry_utc }.pack();
+    let accounts = SetFeeTierAccounts {
+        authority,
+        fee_config: find_fee_config_address(program_id).0,
+        fee_tier: find_fee_tier_address(program_id, &subject_address).0,
+        msp: *program_id,
+        system_program: system_program::id(),
+        rent: sysvar::rent::id()
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn close_fee_tier(
+    program_id: &Pubkey,
+    authority: Pubkey,
+    subject_address: Pubkey
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::CloseFeeTier.pack();
+    let accounts = CloseFeeTierAccounts {
+        authority,
+        fee_config: find_fee_config_address(program_id).0,
+        fee_tier: find_fee_tier_address(program_id, &subject_address).0,
+        msp: *program_id
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn create_streams_batch(
+    program_id: &Pubkey,
+    treasurer: Pubkey,
+    treasury: Pubkey,
+    associated_token_mint: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>,
+    streams: &[(Pubkey, Pubkey, BatchStreamTerms)] // (stream, beneficiary, terms)
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    if streams.is_empty() || streams.len() > u8::MAX as usize {
+        return Err(StreamError::InvalidArgument.into());
+    }
+
+    let mut accounts = CreateStreamsBatchAccounts {
+        treasurer,
+        treasury,
+        associated_token_mint,
+        fee_treasury,
+        msp: *program_id,
+        system_program: system_program::id(),
+        rent: sysvar::rent::id(),
+        fee_config: find_fee_config_address(program_id).0,
+        fee_tier: find_fee_tier_address(program_id, &fee_tier_subject.unwrap_or(treasurer)).0,
+        treasury_roles: find_treasury_roles_address(program_id, &treasury).0
+    }.to_account_metas();
+
+    for (stream, beneficiary, _) in streams {
+        accounts.push(AccountMeta::new(*stream, true));
+        accounts.push(AccountMeta::new_readonly(*beneficiary, false));
+    }
+
+    let data = StreamInstruction::CreateStreamsBatch {
+        streams: streams.iter().map(|(_, _, terms)| terms.clone()).collect()
+    }.pack();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn grant_treasury_roles(
+    program_id: &Pubkey,
+    authority: Pubkey,
+    treasury: Pubkey,
+    member: Pubkey,
+    roles: u8
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::GrantTreasuryRoles { member, roles }.pack();
+    let accounts = GrantTreasuryRolesAccounts {
+        authority,
+        treasury,
+        treasury_roles: find_treasury_roles_address(program_id, &treasury).0,
+        msp: *program_id,
+        system_program: system_program::id(),
+        rent: sysvar::rent::id()
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn revoke_treasury_roles(
+    program_id: &Pubkey,
+    authority: Pubkey,
+    treasury: Pubkey,
+    member: Pubkey,
+    roles: u8
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::RevokeTreasuryRoles { member, roles }.pack();
+    let accounts = RevokeTreasuryRolesAccounts {
+        authority,
+        treasury,
+        treasury_roles: find_treasury_roles_address(program_id, &treasury).0,
+        msp: *program_id
+    }.to_account_metas();
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+ }
+
+ pub fn set_push_vested(
+    program_id: &Pubkey,
+    authority: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    enabled: bool,
+    keeper_tip: u64
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::SetPushVested { enabled, keeper_tip }.pack();
+    let accounts = SetPushVestedAccounts {
+        authority,
+        treasury,
+        stream,
+        msp: *program_id
+    }.to_account_metas();
 
     Ok(Instruction { program_id: *program_id, accounts, data })
- }
\ No newline at end of file
+ }
+
+ pub fn push_vested(
+    program_id: &Pubkey,
+    keeper: Pubkey,
+    keeper_token: Pubkey,
+    beneficiary: Pubkey,
+    associated_token_mint: Pubkey,
+    treasury: Pubkey,
+    stream: Pubkey,
+    fee_treasury: Pubkey,
+    fee_tier_subject: Option<Pubkey>
+
+ ) -> Result<Instruction, StreamError> {
+
+    if let Err(_error) = check_program_account(program_id) {
+        return Err(StreamError::IncorrectProgramId.into());
+    }
+
+    let data = StreamInstruction::PushVested.pack();
+    let accounts = PushVestedAccounts {
+        keeper,
+        keeper_token,
+        beneficiary,
+        beneficiary_token: get_associated_token_address(&beneficiary, &associated_token_mint),
+        associated_token_mint,
+        treasury,
+        treasury_token: get_associated_token_address(&treasury, &associated_token_mint),
+        stream,
+        fee_treasury,
+        fee_treasury_token: get_associated_token_address(&fee_treasury, &associated_token_mint),
+        msp: *program_id,
+        associated_token_program: spl_associated_token_account::id(),
+        token_program: spl_token::id(),
+        rent: sysvar::rent::id(),
+        system_program: system_program::id(),
+        fee_config: find_fee_config_address(program_id).0
+    }.to_account_metas();
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: with_fee_tier(program_id, accounts, fee_tier_subject),
+        data
+    })
+ }
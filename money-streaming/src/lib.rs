@@ -8,6 +8,9 @@ pub mod constants;
 pub mod account_validations;
 pub mod extensions;
 pub mod backwards_comp;
+pub mod events;
+pub mod accounts;
+pub mod math;
 
 #[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
@@ -0,0 +1,313 @@
+// Stream and treasury math. Takes unix timestamps (in seconds) instead of the Clock sysvar so
+// the same functions answer projections off-chain, e.g. how much will be withdrawable at a given date
+use std::{ cmp, convert::TryInto };
+use crate::error::StreamError;
+use crate::state::*;
+use crate::constants::RATE_PRECISION;
+use crate::utils::{
+    get_stream_auto_pause_block_time,
+    get_stream_end_block_time,
+    get_stream_rate_per_second,
+    TokenScale
+};
+
+pub fn get_stream_status_at(
+    stream: &StreamV1,
+    now: u64
+
+) -> StreamStatus {
+
+    if stream.start_utc > now.saturating_mul(1000u64) {
+        return StreamStatus::Scheduled;
+    }
+
+    if stream.stream_resumed_block_time >= stream.escrow_vested_amount_snap_block_time {
+        // Auto pause once the stream has been running for `auto_pause_in_seconds` since the last resume
+        let auto_pause_block_time = get_stream_auto_pause_block_time(
+            stream.auto_pause_in_seconds, stream.stream_resumed_block_time
+        );
+
+        if let Some(auto_pause_block_time) = auto_pause_block_time {
+            if now >= auto_pause_block_time {
+                return StreamStatus::Paused;
+            }
+        }
+
+        return StreamStatus::Running;
+    }
+
+    StreamStatus::Paused
+}
+
+pub fn get_withdrawable_amount_at(
+    stream: &StreamV1,
+    now: u64,
+    decimals: u64
+
+) -> Result<u64, StreamError> {
+
+    let status = get_stream_status_at(stream, now);
+
+    //Check if SCHEDULED
+    if status == StreamStatus::Scheduled {
+        return Ok(0);
+    }
+
+    //Check if PAUSED
+    let scale = TokenScale::new(decimals);
+    let is_manual_pause = stream.escrow_vested_amount_snap_block_time > stream.stream_resumed_block_time;
+    if status == StreamStatus::Paused && is_manual_pause {
+        return Ok(scale.to_base_units(stream.escrow_vested_amount_snap));
+    }
+
+    //Check if RUNNING (an auto paused stream vests until the auto pause time)
+    if stream.rate_interval_in_seconds == 0 || stream.rate_amount <= 0.0 {
+        return Err(StreamError::InvalidArgument);
+    }
+
+    let rate_amount_per_second = stream.rate_amount / (stream.rate_interval_in_seconds as f64);
+    let block_time_at_last_snap_or_resume = cmp::max(stream.stream_resumed_block_time, stream.escrow_vested_amount_snap_block_time);
+    let block_time_at_auto_pause = get_stream_auto_pause_block_time(
+        stream.auto_pause_in_seconds, stream.stream_resumed_block_time
+    );
+    let elapsed_time_since_last_snap_or_resume = cmp::min(now, block_time_at_auto_pause.unwrap_or(u64::MAX))
+        .checked_sub(block_time_at_last_snap_or_resume)
+        .ok_or(StreamError::Overflow)?;
+    let vested_amount_since_last_snap_or_resume = rate_amount_per_second * elapsed_time_since_last_snap_or_resume as f64;
+    let allocation_left_vested_amount = scale.to_base_units(stream.escrow_vested_amount_snap)
+        .checked_add(scale.to_base_units(vested_amount_since_last_snap_or_resume))
+        .ok_or(StreamError::Overflow)?;
+    let stream_allocation_left = scale.to_base_units(stream.allocation_left);
+
+    Ok(cmp::min(stream_allocation_left, allocation_left_vested_amount))
+}
+
+/// The allocation left in the stream that is not vested yet (in token base units)
+pub fn get_unvested_amount_at(
+    stream: &StreamV1,
+    now: u64,
+    decimals: u64
+
+) -> Result<u64, StreamError> {
+
+    let scale = TokenScale::new(decimals);
+    let stream_allocation_left = scale.to_base_units(stream.allocation_left);
+    let withdrawable = get_withdrawable_amount_at(stream, now, decimals)?;
+
+    Ok(stream_allocation_left.saturating_sub(withdrawable))
+}
+
+pub fn get_stream_status_at_v2(
+    stream: &StreamV2,
+    now: u64
+
+) -> StreamStatus {
+
+    let now_utc = now.saturating_mul(1000u64);
+
+    if stream.start_utc > now_utc {
+        return StreamStatus::Scheduled;
+    }
+
+    if stream.end_utc > 0 && now_utc >= stream.end_utc {
+        return StreamStatus::Ended;
+    }
+
+    if stream.stream_resumed_block_time >= stream.escrow_vested_amount_snap_block_time {
+        // Auto pause once the stream has been running for `auto_pause_in_seconds` since the last resume
+        let auto_pause_block_time = get_stream_auto_pause_block_time(
+            stream.auto_pause_in_seconds, stream.stream_resumed_block_time
+        );
+
+        if let Some(auto_pause_block_time) = auto_pause_block_time {
+            if now >= auto_pause_block_time {
+                return StreamStatus::Paused;
+            }
+        }
+
+        return StreamStatus::Running;
+    }
+
+    StreamStatus::Paused
+}
+
+pub fn get_vested_amount_at_v2(
+    stream: &StreamV2,
+    now: u64
+
+) -> Result<u64, StreamError> {
+
+    let status = get_stream_status_at_v2(stream, now);
+
+    //Check if SCHEDULED
+    if status == StreamStatus::Scheduled {
+        return Ok(0);
+    }
+
+    //Check if PAUSED (or ENDED while paused)
+    let is_manual_pause = stream.escrow_vested_amount_snap_block_time > stream.stream_resumed_block_time;
+    if status != StreamStatus::Running && is_manual_pause {
+        return Ok(cmp::min(stream.allocation_left, stream.escrow_vested_amount_snap));
+    }
+
+    //Check if RUNNING (an auto paused or ended stream vests until the auto pause or end time)
+    if stream.rate_interval_in_seconds == 0 || stream.rate_amount == 0 {
+        return Err(StreamError::InvalidArgument);
+    }
+
+    let block_time_at_last_snap_or_resume = cmp::max(stream.stream_resumed_block_time, stream.escrow_vested_amount_snap_block_time);
+    let elapsed_time_since_last_snap_or_resume = cmp::min(now, get_stream_stop_block_time_v2(stream))
+        .checked_sub(block_time_at_last_snap_or_resume)
+        .ok_or(StreamError::Overflow)?;
+    let vested_amount_since_last_snap_or_resume = (stream.rate_amount as u128)
+        .checked_mul(elapsed_time_since_last_snap_or_resume as u128)
+        .ok_or(StreamError::Overflow)?
+        .checked_div(stream.rate_interval_in_seconds as u128)
+        .ok_or(StreamError::Overflow)?;
+    let vested_amount = (stream.escrow_vested_amount_snap as u128)
+        .checked_add(vested_amount_since_last_snap_or_resume)
+        .ok_or(StreamError::Overflow)?;
+    let vested_amount = cmp::min(stream.allocation_left as u128, vested_amount);
+
+    Ok(vested_amount as u64)
+}
+
+/// The amount unlocked since the stream started, withdrawals included (in token base units).
+/// Stepped vesting unlocks the whole `rate_amount` at every interval boundary, counting the
+/// tranches from the amount unlocked at the last rate change or pause (the cliff amount at first)
+pub fn get_unlocked_amount_at_v2(
+    stream: &StreamV2,
+    now: u64
+
+) -> Result<u64, StreamError> {
+
+    let vested_amount = get_vested_amount_at_v2(stream, now)?;
+    let withdrawn_amount = stream.allocation_assigned.saturating_sub(stream.allocation_left);
+    let streamed_amount = vested_amount
+        .checked_add(withdrawn_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    if stream.vesting_mode != VestingMode::Stepped as u8 {
+        return Ok(streamed_amount);
+    }
+
+    // The cliff amount is unlocked from the start
+    let unlocked_amount_snap = cmp::max(stream.unlocked_amount_snap, stream.cliff_vest_amount);
+
+    streamed_amount
+        .saturating_sub(unlocked_amount_snap)
+        .checked_div(stream.rate_amount)
+        .unwrap_or(0)
+        .checked_mul(stream.rate_amount)
+        .ok_or(StreamError::Overflow)?
+        .checked_add(unlocked_amount_snap)
+        .ok_or(StreamError::Overflow)
+}
+
+pub fn get_withdrawable_amount_at_v2(
+    stream: &StreamV2,
+    now: u64
+
+) -> Result<u64, StreamError> {
+
+    let vested_amount = get_vested_amount_at_v2(stream, now)?;
+
+    if stream.vesting_mode != VestingMode::Stepped as u8 {
+        return Ok(vested_amount);
+    }
+
+    let withdrawn_amount = stream.allocation_assigned.saturating_sub(stream.allocation_left);
+    let withdrawable = get_unlocked_amount_at_v2(stream, now)?.saturating_sub(withdrawn_amount);
+
+    Ok(cmp::min(withdrawable, vested_amount))
+}
+
+/// The allocation left in the stream that is not vested yet (in token base units)
+pub fn get_unvested_amount_at_v2(
+    stream: &StreamV2,
+    now: u64
+
+) -> Result<u64, StreamError> {
+
+    let vested_amount = get_vested_amount_at_v2(stream, now)?;
+
+    Ok(stream.allocation_left.saturating_sub(vested_amount))
+}
+
+/// The time at which an amount runs out when it is consumed at `rate_per_second`
+/// (scaled by `RATE_PRECISION`) starting at `now`. `None` if nothing consumes it
+pub fn get_depletion_time(
+    amount: u64,
+    rate_per_second: u128,
+    now: u64
+
+) -> Option<u64> {
+
+    if rate_per_second == 0 {
+        return None;
+    }
+
+    // Round up, the amount is not depleted until the last base unit is consumed
+    let seconds = (amount as u128)
+        .checked_mul(RATE_PRECISION)?
+        .checked_add(rate_per_second - 1)?
+        .checked_div(rate_per_second)?;
+
+    let seconds: u64 = seconds.try_into().ok()?;
+    now.checked_add(seconds)
+}
+
+/// The time at which the treasury balance runs out at the current depletion rate
+pub fn get_treasury_depletion_time(
+    treasury: &TreasuryV1,
+    now: u64,
+    decimals: u64
+
+) -> Option<u64> {
+
+    let scale = TokenScale::new(decimals);
+    let balance = scale.to_base_units(treasury.balance);
+    let rate_per_second = scale.to_rate(treasury.depletion_rate);
+
+    get_depletion_time(balance, rate_per_second, now)
+}
+
+/// The time at which the treasury balance runs out at the current depletion rate
+pub fn get_treasury_depletion_time_v2(
+    treasury: &TreasuryV2,
+    now: u64
+
+) -> Option<u64> {
+
+    get_depletion_time(treasury.balance, treasury.depletion_rate, now)
+}
+
+/// The time at which the stream vests its whole allocation left if it keeps running.
+/// `None` if the stream is not running or stops (auto pause or end date) before that
+pub fn get_stream_depletion_time_v2(
+    stream: &StreamV2,
+    now: u64
+
+) -> Result<Option<u64>, StreamError> {
+
+    if get_stream_status_at_v2(stream, now) != StreamStatus::Running {
+        return Ok(None);
+    }
+
+    let unvested_amount = get_unvested_amount_at_v2(stream, now)?;
+    let rate_per_second = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+
+    Ok(get_depletion_time(unvested_amount, rate_per_second, now)
+        .filter(|depletion_time| *depletion_time <= get_stream_stop_block_time_v2(stream)))
+}
+
+// The earliest of the auto pause and end times of a stream
+fn get_stream_stop_block_time_v2(stream: &StreamV2) -> u64 {
+
+    let block_time_at_auto_pause = get_stream_auto_pause_block_time(
+        stream.auto_pause_in_seconds, stream.stream_resumed_block_time
+    );
+    let block_time_at_end = get_stream_end_block_time(stream.end_utc);
+
+    cmp::min(block_time_at_auto_pause.unwrap_or(u64::MAX), block_time_at_end.unwrap_or(u64::MAX))
+}
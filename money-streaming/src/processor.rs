@@ -1,19 +1,21 @@
 // Program
-use num_traits;
+use std::cmp;
 use crate::error::StreamError;
 use crate::utils::*;
-use crate::instruction::{ StreamInstruction };
+use crate::instruction::{ StreamInstruction, BatchStreamTerms };
 use crate::state::*;
 use crate::constants::*;
 use crate::account_validations::*;
 use crate::extensions::*;
 use crate::backwards_comp::*;
+use crate::events::MspEvent;
+use crate::accounts::*;
 use solana_program::{
     msg,
     program::{ invoke },
     pubkey::Pubkey,
     entrypoint::ProgramResult,
-    account_info::{ next_account_info, AccountInfo },
+    account_info::AccountInfo,
     program_pack::{ Pack },
     sysvar::{ clock::Clock, Sysvar } 
 };
@@ -88,9 +90,9 @@ impl Processor {
                 Self::process_resume_stream(accounts, program_id)
             },
 
-            StreamInstruction::CloseStream { auto_close_treasury } => {
+            StreamInstruction::CloseStream { auto_close_treasury, unwrap_native } => {
                 msg!("Instruction: CloseStream");
-                Self::process_close_stream(accounts, program_id, auto_close_treasury)
+                Self::process_close_stream(accounts, program_id, auto_close_treasury, unwrap_native)
             },
 
             StreamInstruction::CreateTreasury { 
@@ -116,6 +118,283 @@ impl Processor {
                 msg!("Instruction: RefreshTreasuryBalance");
                 Self::process_refresh_treasury_balance(accounts, program_id)
             },
+
+            StreamInstruction::CreateStreamV2 {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds,
+                allocation_reserved,
+                allocation_assigned,
+                funded_on_utc,
+                start_utc,
+                rate_cliff_in_seconds,
+                cliff_vest_amount,
+                cliff_vest_percent,
+                auto_pause_in_seconds
+
+            } => {
+
+                msg!("Instruction: CreateStreamV2");
+
+                Self::process_create_stream_v2(
+                    accounts, program_id, stream_name,
+                    rate_amount, rate_interval_in_seconds,
+                    allocation_reserved, allocation_assigned,
+                    funded_on_utc, start_utc, rate_cliff_in_seconds,
+                    cliff_vest_amount, cliff_vest_percent, auto_pause_in_seconds, 0,
+                    VestingMode::Continuous as u8
+                )
+            },
+
+            StreamInstruction::AddFundsV2 {
+                amount,
+                allocation_type,
+                allocation_stream_address
+
+            } => {
+                msg!("Instruction: AddFundsV2");
+
+                Self::process_add_funds_v2(
+                    accounts, program_id, amount,
+                    allocation_type, allocation_stream_address
+                )
+            },
+
+            StreamInstruction::WithdrawV2 { amount, unwrap_native } => {
+                msg!("Instruction: WithdrawV2");
+                Self::process_withdraw_v2(accounts, program_id, amount, unwrap_native)
+            },
+
+            StreamInstruction::CreateTreasuryV2 {
+                slot,
+                label,
+                treasury_type,
+                auto_close
+
+            } => {
+
+                msg!("Instruction: CreateTreasuryV2");
+                Self::process_create_treasury_v2(
+                    accounts, program_id, slot, label, treasury_type, auto_close
+                )
+            },
+
+            StreamInstruction::MigrateStream => {
+                msg!("Instruction: MigrateStream");
+                Self::process_migrate_stream(accounts, program_id)
+            },
+
+            StreamInstruction::MigrateTreasury => {
+                msg!("Instruction: MigrateTreasury");
+                Self::process_migrate_treasury(accounts, program_id)
+            },
+
+            StreamInstruction::ProposeUpdate {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds
+
+            } => {
+
+                msg!("Instruction: ProposeUpdate");
+
+                Self::process_propose_update(
+                    accounts, program_id, stream_name,
+                    rate_amount, rate_interval_in_seconds
+                )
+            },
+
+            StreamInstruction::AnswerUpdate { approve } => {
+                msg!("Instruction: AnswerUpdate");
+                Self::process_answer_update(accounts, program_id, approve)
+            },
+
+            StreamInstruction::TransferStream {
+                new_beneficiary_address,
+                settle_vested
+
+            } => {
+                msg!("Instruction: TransferStream");
+
+                Self::process_transfer_stream(
+                    accounts, program_id, new_beneficiary_address, settle_vested
+                )
+            },
+
+            StreamInstruction::TransferTreasury { new_treasurer_address } => {
+                msg!("Instruction: TransferTreasury");
+                Self::process_transfer_treasury(accounts, program_id, new_treasurer_address)
+            },
+
+            StreamInstruction::CreateStreamWithEndDate {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds,
+                allocation_reserved,
+                allocation_assigned,
+                funded_on_utc,
+                start_utc,
+                rate_cliff_in_seconds,
+                cliff_vest_amount,
+                cliff_vest_percent,
+                auto_pause_in_seconds,
+                end_utc
+
+            } => {
+
+                msg!("Instruction: CreateStreamWithEndDate");
+
+                Self::process_create_stream_v2(
+                    accounts, program_id, stream_name,
+                    rate_amount, rate_interval_in_seconds,
+                    allocation_reserved, allocation_assigned,
+                    funded_on_utc, start_utc, rate_cliff_in_seconds,
+                    cliff_vest_amount, cliff_vest_percent, auto_pause_in_seconds, end_utc,
+                    VestingMode::Continuous as u8
+                )
+            },
+
+            StreamInstruction::CreateStreamWithSchedule {
+                stream_name,
+                rate_amount,
+                rate_interval_in_seconds,
+                allocation_reserved,
+                allocation_assigned,
+                funded_on_utc,
+                start_utc,
+                rate_cliff_in_seconds,
+                cliff_vest_amount,
+                cliff_vest_percent,
+                auto_pause_in_seconds,
+                end_utc,
+                vesting_mode
+
+            } => {
+
+                msg!("Instruction: CreateStreamWithSchedule");
+
+                Self::process_create_stream_v2(
+                    accounts, program_id, stream_name,
+                    rate_amount, rate_interval_in_seconds,
+                    allocation_reserved, allocation_assigned,
+                    funded_on_utc, start_utc, rate_cliff_in_seconds,
+                    cliff_vest_amount, cliff_vest_percent, auto_pause_in_seconds, end_utc,
+                    vesting_mode
+                )
+            },
+
+            StreamInstruction::TreasuryWithdraw { amount } => {
+                msg!("Instruction: TreasuryWithdraw");
+                Self::process_treasury_withdraw(accounts, program_id, amount)
+            },
+
+            StreamInstruction::Allocate { amount, allocation_type } => {
+                msg!("Instruction: Allocate");
+                Self::process_allocate(accounts, program_id, amount, allocation_type)
+            },
+
+            StreamInstruction::Redeem { amount } => {
+                msg!("Instruction: Redeem");
+                Self::process_redeem(accounts, program_id, amount)
+            },
+
+            StreamInstruction::InitFeeConfig {
+                authority,
+                fee_treasury_address,
+                create_treasury_flat_fee,
+                create_stream_flat_fee,
+                add_funds_flat_fee,
+                withdraw_percent_fee,
+                propose_update_flat_fee,
+                close_stream_flat_fee,
+                close_stream_percent_fee,
+                close_treasury_flat_fee,
+                close_treasury_percent_fee
+
+            } => {
+
+                msg!("Instruction: InitFeeConfig");
+
+                Self::process_init_fee_config(
+                    accounts, program_id, authority, fee_treasury_address,
+                    create_treasury_flat_fee, create_stream_flat_fee, add_funds_flat_fee,
+                    withdraw_percent_fee, propose_update_flat_fee,
+                    close_stream_flat_fee, close_stream_percent_fee,
+                    close_treasury_flat_fee, close_treasury_percent_fee
+                )
+            },
+
+            StreamInstruction::UpdateFeeConfig {
+                authority,
+                fee_treasury_address,
+                create_treasury_flat_fee,
+                create_stream_flat_fee,
+                add_funds_flat_fee,
+                withdraw_percent_fee,
+                propose_update_flat_fee,
+                close_stream_flat_fee,
+                close_stream_percent_fee,
+                close_treasury_flat_fee,
+                close_treasury_percent_fee
+
+            } => {
+
+                msg!("Instruction: UpdateFeeConfig");
+
+                Self::process_update_fee_config(
+                    accounts, program_id, authority, fee_treasury_address,
+                    create_treasury_flat_fee, create_stream_flat_fee, add_funds_flat_fee,
+                    withdraw_percent_fee, propose_update_flat_fee,
+                    close_stream_flat_fee, close_stream_percent_fee,
+                    close_treasury_flat_fee, close_treasury_percent_fee
+                )
+            },
+
+            StreamInstruction::SetFeeTier {
+                subject_address,
+                flat_fee,
+                withdraw_percent_fee,
+                expiry_utc
+
+            } => {
+
+                msg!("Instruction: SetFeeTier");
+
+                Self::process_set_fee_tier(
+                    accounts, program_id, subject_address,
+                    flat_fee, withdraw_percent_fee, expiry_utc
+                )
+            },
+
+            StreamInstruction::CloseFeeTier => {
+                msg!("Instruction: CloseFeeTier");
+                Self::process_close_fee_tier(accounts, program_id)
+            },
+
+            StreamInstruction::CreateStreamsBatch { streams } => {
+                msg!("Instruction: CreateStreamsBatch");
+                Self::process_create_streams_batch(accounts, program_id, streams)
+            },
+
+            StreamInstruction::GrantTreasuryRoles { member, roles } => {
+                msg!("Instruction: GrantTreasuryRoles");
+                Self::process_grant_treasury_roles(accounts, program_id, member, roles)
+            },
+
+            StreamInstruction::RevokeTreasuryRoles { member, roles } => {
+                msg!("Instruction: RevokeTreasuryRoles");
+                Self::process_revoke_treasury_roles(accounts, program_id, member, roles)
+            },
+
+            StreamInstruction::SetPushVested { enabled, keeper_tip } => {
+                msg!("Instruction: SetPushVested");
+                Self::process_set_push_vested(accounts, program_id, enabled, keeper_tip)
+            },
+
+            StreamInstruction::PushVested => {
+                msg!("Instruction: PushVested");
+                Self::process_push_vested(accounts, program_id)
+            },
         }
     }
 
@@ -138,37 +417,49 @@ impl Processor {
 
         // Get accounts
         let account_info_iter = &mut accounts.iter();
-        let treasurer_account_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;
-        let associated_token_mint_info = next_account_info(account_info_iter)?;
-        let beneficiary_account_info = next_account_info(account_info_iter)?;
-        let stream_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_account_info = next_account_info(account_info_iter)?;
-        let msp_account_info = next_account_info(account_info_iter)?;
-        let system_account_info = next_account_info(account_info_iter)?;
-        let rent_account_info = next_account_info(account_info_iter)?;
+        let CreateStreamAccounts {
+            treasurer: treasurer_account_info,
+            treasury: treasury_account_info,
+            associated_token_mint: associated_token_mint_info,
+            beneficiary: beneficiary_account_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = CreateStreamAccounts::next(account_info_iter)?;
+
+        let (fee_tier_account_info, treasury_roles_account_info) = next_fee_tier_and_treasury_roles(account_info_iter);
+        // The signer may be a stream creator of the treasury instead of the treasurer
+        let treasurer_address = get_treasurer_address(&treasury_account_info)?;
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, fee_tier_account_info,
+            &treasurer_address, treasury_account_info.key, &Clock::get()?
+        )?;
 
         // Verify the correct MSP Operations Account 
-        if fee_treasury_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap()) {
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
             return Err(StreamError::InstructionNotAuthorized.into());
         }
 
         let _ = check_can_create_stream(
             program_id, &treasurer_account_info, &treasury_account_info, 
             &associated_token_mint_info, &stream_account_info, &msp_account_info,
-            &system_account_info, &rent_account_info, allocation_assigned, allocation_reserved
+            &system_account_info, &rent_account_info, treasury_roles_account_info,
+            allocation_assigned, allocation_reserved
         )?;        
         // Create stream account
         let _ = create_stream_account(
             &treasurer_account_info, &stream_account_info, &msp_account_info,
-            &rent_account_info, &system_account_info
+            &rent_account_info, &system_account_info, StreamV1::LEN
         )?;
 
         let clock = Clock::get()?;
         let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
         // Updating stream data
         stream.stream_name = stream_name;
-        stream.treasurer_address = *treasurer_account_info.key;
+        stream.treasurer_address = treasurer_address;
         stream.rate_amount = rate_amount;
         stream.rate_interval_in_seconds = rate_interval_in_seconds;
         stream.allocation_reserved = allocation_reserved;
@@ -216,8 +507,25 @@ impl Processor {
             &system_account_info,
             &treasurer_account_info,
             &fee_treasury_account_info, 
-            CREATE_STREAM_FLAT_FEE
-        )
+            fee_config.create_stream_flat_fee
+        )?;
+
+        update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, Some(stream_account_info), &clock)?;
+
+        let scale = TokenScale::new(associated_token_mint.decimals.into());
+        MspEvent::StreamCreated {
+            stream: *stream_account_info.key,
+            treasury: *treasury_account_info.key,
+            treasurer: treasurer_address,
+            beneficiary: *beneficiary_account_info.key,
+            mint: *associated_token_mint_info.key,
+            rate_amount: scale.to_base_units(stream.rate_amount),
+            rate_interval_in_seconds,
+            allocation_assigned: scale.to_base_units(stream.allocation_assigned),
+            start_utc: stream.start_utc / 1000u64
+        }.emit();
+
+        Ok(())
     }
 
     fn process_add_funds(
@@ -230,29 +538,37 @@ impl Processor {
     ) -> ProgramResult {
 
         let account_info_iter = &mut accounts.iter();
-        let contributor_account_info = next_account_info(account_info_iter)?;
-        let contributor_token_account_info = next_account_info(account_info_iter)?;
-        let contributor_treasury_pool_token_account_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;
-        let treasury_token_account_info = next_account_info(account_info_iter)?;
-        let associated_token_mint_info = next_account_info(account_info_iter)?;    
-        let treasury_pool_mint_info = next_account_info(account_info_iter)?;
-        let stream_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_account_info = next_account_info(account_info_iter)?;
-        let msp_account_info = next_account_info(account_info_iter)?;
-        let associated_token_program_account_info = next_account_info(account_info_iter)?;
-        let token_program_account_info = next_account_info(account_info_iter)?;
-        let system_account_info = next_account_info(account_info_iter)?;
-        let rent_account_info = next_account_info(account_info_iter)?;
+        let AddFundsAccounts {
+            contributor: contributor_account_info,
+            contributor_token: contributor_token_account_info,
+            contributor_treasury_pool_token: contributor_treasury_pool_token_account_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury_pool_mint: treasury_pool_mint_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = AddFundsAccounts::next(account_info_iter)?;
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &Clock::get()?
+        )?;
 
         // Verify the correct MSP Operations Account 
-        if fee_treasury_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap()) {
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
             return Err(StreamError::InstructionNotAuthorized.into());
         }
 
-        if treasury_account_info.data_len() == Treasury::LEN {
+        if AccountKind::detect(&treasury_account_info) == AccountKind::Treasury {
             return add_funds_v0(
-                program_id, msp_account_info, fee_treasury_account_info,
+                program_id, msp_account_info, fee_treasury_account_info, &fee_config,
                 associated_token_program_account_info, token_program_account_info,
                 system_account_info, rent_account_info, contributor_account_info,
                 contributor_token_account_info, contributor_treasury_pool_token_account_info,
@@ -268,6 +584,12 @@ impl Processor {
             &treasury_pool_mint_info, &stream_account_info, &associated_token_program_account_info,
             &token_program_account_info, &rent_account_info, &system_account_info
         )?;
+
+        if AccountKind::detect(&stream_account_info) == AccountKind::StreamV1 {
+            auto_pause_update_stream(
+                &stream_account_info, &treasury_account_info, &associated_token_mint_info, &Clock::get()?
+            )?;
+        }
         // Create contributor deposit receipt
         let _ = create_deposit_receipt(
             &treasury_account_info, &treasury_pool_mint_info,
@@ -285,7 +607,7 @@ impl Processor {
             &treasury_account_info, &associated_token_mint_info, allocation_type, amount
         )?;
 
-        if stream_account_info.data_len() == StreamV1::LEN {
+        if AccountKind::detect(&stream_account_info) == AccountKind::StreamV1 {
             let clock = Clock::get()?;
             let _ = add_funds_update_stream(
                 &stream_account_info, &associated_token_mint_info,
@@ -297,8 +619,23 @@ impl Processor {
             &system_account_info,
             &contributor_account_info,
             &fee_treasury_account_info, 
-            ADD_FUNDS_FLAT_FEE
-        )
+            fee_config.add_funds_flat_fee
+        )?;
+
+        update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, Some(stream_account_info), &Clock::get()?)?;
+
+        let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+        let scale = TokenScale::new(associated_token_mint.decimals.into());
+        MspEvent::FundsAdded {
+            treasury: *treasury_account_info.key,
+            stream: allocation_stream_address,
+            contributor: *contributor_account_info.key,
+            mint: *associated_token_mint_info.key,
+            amount: scale.to_base_units(amount),
+            allocation_type
+        }.emit();
+
+        Ok(())
     }
 
     fn process_withdraw(
@@ -309,31 +646,39 @@ impl Processor {
     ) -> ProgramResult {
 
         let account_info_iter = &mut accounts.iter();
-        let beneficiary_account_info = next_account_info(account_info_iter)?;
-        let beneficiary_token_account_info = next_account_info(account_info_iter)?;
-        let associated_token_mint_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;
-        let treasury_token_account_info = next_account_info(account_info_iter)?;
-        let stream_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_token_account_info = next_account_info(account_info_iter)?;
-        let msp_account_info = next_account_info(account_info_iter)?;
-        let associated_token_program_account_info = next_account_info(account_info_iter)?;
-        let token_program_account_info = next_account_info(account_info_iter)?;
-        let rent_account_info = next_account_info(account_info_iter)?;
-        let system_account_info = next_account_info(account_info_iter)?;
+        let WithdrawAccounts {
+            beneficiary: beneficiary_account_info,
+            beneficiary_token: beneficiary_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            rent: rent_account_info,
+            system_program: system_account_info,
+            fee_config: fee_config_account_info
+        } = WithdrawAccounts::next(account_info_iter)?;
         let clock = Clock::get()?;
 
-        if fee_treasury_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap()){
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
             return Err(StreamError::InstructionNotAuthorized.into());
         }
 
-        if treasury_account_info.data_len() == Treasury::LEN && stream_account_info.data_len() == Stream::LEN {
+        if AccountKind::detect(&treasury_account_info) == AccountKind::Treasury && AccountKind::detect(&stream_account_info) == AccountKind::Stream {
             return withdraw_v0(
                 program_id, &beneficiary_account_info, &beneficiary_token_account_info,
                 &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
                 &stream_account_info, &fee_treasury_account_info, &fee_treasury_token_account_info,
-                &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
+                &fee_config, &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
                 &rent_account_info, &system_account_info, &clock, amount
             );
         }
@@ -341,18 +686,22 @@ impl Processor {
         let _ = check_can_withdraw_funds(
             program_id, &beneficiary_account_info, &beneficiary_token_account_info,
             &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
-            &stream_account_info, &fee_treasury_token_account_info, &msp_account_info,
-            &associated_token_program_account_info, &token_program_account_info,
+            &stream_account_info, &fee_treasury_token_account_info, &fee_config.fee_treasury_address,
+            &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
             &rent_account_info, &system_account_info
         )?;
 
+        auto_pause_update_stream(
+            &stream_account_info, &treasury_account_info, &associated_token_mint_info, &clock
+        )?;
+
         let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
         let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;        
         let escrow_vested_amount = get_beneficiary_withdrawable_amount(
             &stream, &clock, associated_token_mint.decimals.into()
         )?;
-        let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-        let transfer_amount = (amount * pow) as u64;
+        let scale = TokenScale::new(associated_token_mint.decimals.into());
+        let transfer_amount = scale.to_base_units(amount);
 
         if transfer_amount > escrow_vested_amount {
             return Err(StreamError::NotAllowedWithdrawalAmount.into());
@@ -365,19 +714,11 @@ impl Processor {
                 &beneficiary_token_account_info, &associated_token_mint_info
             )?;
         }
-        // Withdraw
-        let _ = claim_treasury_funds(
+        // Withdraw and update the stream and treasury data
+        withdraw_funds_transfer_vested_amount(
             &msp_account_info, &token_program_account_info, &treasury_account_info,
-            &treasury_token_account_info, &beneficiary_token_account_info, transfer_amount
-        )?;
-        // Update stream data
-        let _ = withdraw_funds_update_stream(
-            &mut stream, &stream_account_info, &associated_token_mint_info,
-            &clock, escrow_vested_amount, transfer_amount
-        )?;
-        // Update treasury account data
-        let _ = withdraw_funds_update_treasury(
-            &treasury_account_info, &associated_token_mint_info, transfer_amount
+            &treasury_token_account_info, &beneficiary_token_account_info, &associated_token_mint_info,
+            &mut stream, &stream_account_info, &clock, escrow_vested_amount, transfer_amount
         )?;
 
         if fee_treasury_token_account_info.data_len() == 0 { // Create fee treasury associated token account if doesn't exist
@@ -388,15 +729,27 @@ impl Processor {
             )?;
         }
         
-        let fee = WITHDRAW_PERCENT_FEE * transfer_amount as f64 / 100f64;
+        let fee = get_percent_fee(transfer_amount, fee_config.withdraw_percent_fee)?;
         // Pay fees
         transfer_token_fee(
             &token_program_account_info,
             &beneficiary_token_account_info,
             &fee_treasury_token_account_info,
             &beneficiary_account_info,
-            fee as u64
-        )
+            fee
+        )?;
+        update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::Withdrawn {
+            stream: *stream_account_info.key,
+            treasury: *treasury_account_info.key,
+            beneficiary: *beneficiary_account_info.key,
+            mint: *associated_token_mint_info.key,
+            amount: transfer_amount,
+            fee
+        }.emit();
+
+        Ok(())
     }
 
     fn process_pause_stream(
@@ -406,32 +759,50 @@ impl Processor {
     ) -> ProgramResult {
 
         let account_info_iter = &mut accounts.iter();
-        let initializer_account_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;
-        let associated_token_mint_info = next_account_info(account_info_iter)?;
-        let stream_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_account_info = next_account_info(account_info_iter)?;
-        let msp_account_info = next_account_info(account_info_iter)?;
+        let PauseOrResumeStreamAccounts {
+            initializer: initializer_account_info,
+            treasury: treasury_account_info,
+            associated_token_mint: associated_token_mint_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            fee_config: fee_config_account_info
+        } = PauseOrResumeStreamAccounts::next(account_info_iter)?;
         let clock = Clock::get()?;
+        let fee_config = get_fee_config(program_id, &fee_config_account_info)?;
 
-        if fee_treasury_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap()) {
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
             return Err(StreamError::InstructionNotAuthorized.into());
         }
 
+        if AccountKind::detect(&stream_account_info) == AccountKind::StreamV2 {
+            return Self::process_pause_stream_v2(accounts, program_id);
+        }
+
         let _ = check_can_pause_or_resume_stream(
             program_id, &initializer_account_info, &treasury_account_info,
-            &associated_token_mint_info, &stream_account_info, &msp_account_info
+            &associated_token_mint_info, &stream_account_info, &msp_account_info,
+            account_info_iter.next()
+        )?;
+
+        auto_pause_update_stream(
+            &stream_account_info, &treasury_account_info, &associated_token_mint_info, &clock
         )?;
 
         let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
         let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+
+        if get_stream_status(&stream, &clock)? == StreamStatus::Paused {
+            return Err(StreamError::InvalidStreamStatus.into());
+        }
+
         let mut escrow_vested_amount = get_beneficiary_withdrawable_amount(
             &stream, &clock, associated_token_mint.decimals.into()
         )?;
         let current_slot = clock.slot as u64;
         let current_block_time = clock.unix_timestamp as u64;
-        let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-        let stream_allocation_left = (stream.allocation_left * pow) as u64;
+        let scale = TokenScale::new(associated_token_mint.decimals.into());
+        let stream_allocation_left = scale.to_base_units(stream.allocation_left);
         
         if escrow_vested_amount > stream_allocation_left {
             escrow_vested_amount = stream_allocation_left;
@@ -441,18 +812,24 @@ impl Processor {
         let stream_rate = stream.rate_amount / stream.rate_interval_in_seconds as f64;
 
         if treasury.depletion_rate >= stream_rate {
-            let treasury_depletion_rate = ((treasury.depletion_rate * pow) as u64)
-                .checked_sub((stream_rate * pow) as u64)
-                .ok_or(StreamError::Overflow)? as f64 / pow;
+            let treasury_depletion_rate = scale.checked_sub(treasury.depletion_rate, scale.to_base_units(stream_rate))?;
                 
             treasury.depletion_rate = treasury_depletion_rate;
         }
 
         TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
-        stream.escrow_vested_amount_snap = escrow_vested_amount as f64 / pow;
+        stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount);
         stream.escrow_vested_amount_snap_slot = current_slot;
         stream.escrow_vested_amount_snap_block_time = current_block_time;
         StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::Paused {
+            stream: *stream_account_info.key,
+            initializer: *initializer_account_info.key,
+            vested_amount: escrow_vested_amount,
+            timestamp: clock.unix_timestamp as u64
+        }.emit();
 
         Ok(())
     }
@@ -464,36 +841,48 @@ impl Processor {
     ) -> ProgramResult {
 
         let account_info_iter = &mut accounts.iter();
-        let initializer_account_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;
-        let associated_token_mint_info = next_account_info(account_info_iter)?;
-        let stream_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_account_info = next_account_info(account_info_iter)?;
-        let msp_account_info = next_account_info(account_info_iter)?;
+        let PauseOrResumeStreamAccounts {
+            initializer: initializer_account_info,
+            treasury: treasury_account_info,
+            associated_token_mint: associated_token_mint_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            fee_config: fee_config_account_info
+        } = PauseOrResumeStreamAccounts::next(account_info_iter)?;
         let clock = Clock::get()?;
+        let fee_config = get_fee_config(program_id, &fee_config_account_info)?;
 
-        if fee_treasury_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap()) {
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
             return Err(StreamError::InstructionNotAuthorized.into());
         }
 
+        if AccountKind::detect(&stream_account_info) == AccountKind::StreamV2 {
+            return Self::process_resume_stream_v2(accounts, program_id);
+        }
+
         let _ = check_can_pause_or_resume_stream(
             program_id, &initializer_account_info, &treasury_account_info,
-            &associated_token_mint_info, &stream_account_info, &msp_account_info
+            &associated_token_mint_info, &stream_account_info, &msp_account_info,
+            account_info_iter.next()
+        )?;
+
+        auto_pause_update_stream(
+            &stream_account_info, &treasury_account_info, &associated_token_mint_info, &clock
         )?;
 
         let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
         let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
         let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
-        let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
-        let stream_rate = stream.rate_amount / stream.rate_interval_in_seconds as f64;
-        
-        if treasury.depletion_rate >= stream_rate {
-            let treasury_depletion_rate = ((treasury.depletion_rate * pow) as u64)
-                .checked_sub((stream_rate * pow) as u64)
-                .ok_or(StreamError::Overflow)? as f64 / pow;
-                
-            treasury.depletion_rate = treasury_depletion_rate;
+
+        if get_stream_status(&stream, &clock)? != StreamStatus::Paused {
+            return Err(StreamError::InvalidStreamStatus.into());
         }
+
+        let scale = TokenScale::new(associated_token_mint.decimals.into());
+        let stream_rate = stream.rate_amount / stream.rate_interval_in_seconds as f64;
+        // The stream starts depleting the treasury again
+        treasury.depletion_rate = scale.checked_add(treasury.depletion_rate, scale.to_base_units(stream_rate))?;
     
         TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
         // Resuming the stream and updating data
@@ -501,6 +890,13 @@ impl Processor {
         stream.stream_resumed_block_time = clock.unix_timestamp as u64;
         // Save
         StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::Resumed {
+            stream: *stream_account_info.key,
+            initializer: *initializer_account_info.key,
+            timestamp: clock.unix_timestamp as u64
+        }.emit();
 
         Ok(())
     }
@@ -508,123 +904,136 @@ impl Processor {
     fn process_close_stream(
         accounts: &[AccountInfo],
         program_id: &Pubkey,
-        auto_close_treasury: bool
-
-    ) -> ProgramResult {
-
-        let account_info_iter = &mut accounts.iter();
-        let initializer_account_info = next_account_info(account_info_iter)?;
-        let treasurer_account_info = next_account_info(account_info_iter)?;
-        let treasurer_token_account_info = next_account_info(account_info_iter)?;
-        let treasurer_treasury_pool_token_account_info = next_account_info(account_info_iter)?;
-        let beneficiary_account_info = next_account_info(account_info_iter)?;
-        let beneficiary_token_account_info = next_account_info(account_info_iter)?;
-        let associated_token_mint_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;  
-        let treasury_token_account_info = next_account_info(account_info_iter)?;
-        let treasury_pool_mint_info = next_account_info(account_info_iter)?;
-        let stream_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_token_account_info = next_account_info(account_info_iter)?;
-        let msp_account_info = next_account_info(account_info_iter)?;
-        let associated_token_program_account_info = next_account_info(account_info_iter)?;
-        let token_program_account_info = next_account_info(account_info_iter)?;
-        let rent_account_info = next_account_info(account_info_iter)?;
-        let system_account_info = next_account_info(account_info_iter)?;
-
-        if fee_treasury_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap()) {
+        auto_close_treasury: bool,
+        unwrap_native: bool
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let CloseStreamAccounts {
+            initializer: initializer_account_info,
+            treasurer: treasurer_account_info,
+            treasurer_token: treasurer_token_account_info,
+            treasurer_treasury_pool_token: treasurer_treasury_pool_token_account_info,
+            beneficiary: beneficiary_account_info,
+            beneficiary_token: beneficiary_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            treasury_pool_mint: treasury_pool_mint_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            rent: rent_account_info,
+            system_program: system_account_info,
+            fee_config: fee_config_account_info
+        } = CloseStreamAccounts::next(account_info_iter)?;
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
             return Err(StreamError::InstructionNotAuthorized.into());
         }
 
-        if stream_account_info.data_len() == Stream::LEN {
+        // Native SOL unwrapping is only supported by V2 streams
+        if unwrap_native && AccountKind::detect(&stream_account_info) != AccountKind::StreamV2 {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        if AccountKind::detect(&stream_account_info) == AccountKind::Stream {
             return close_stream_v0(
                 program_id, &initializer_account_info, &treasurer_account_info,
                 &treasurer_token_account_info, &treasurer_treasury_pool_token_account_info,
                 &beneficiary_account_info, &beneficiary_token_account_info, &associated_token_mint_info,
                 &treasury_account_info, &treasury_token_account_info, &treasury_pool_mint_info,
                 &stream_account_info, &fee_treasury_account_info, &fee_treasury_token_account_info,
-                &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
+                &fee_config, &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
                 &rent_account_info, &system_account_info, auto_close_treasury,
             );
         }
 
+        if AccountKind::detect(&stream_account_info) == AccountKind::StreamV2 {
+            return Self::process_close_stream_v2(accounts, program_id, auto_close_treasury, unwrap_native);
+        }
+
         let _ = check_can_close_stream(
             program_id, &initializer_account_info, &treasurer_account_info,
             &treasurer_token_account_info, &beneficiary_account_info, &beneficiary_token_account_info,
             &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
             &treasury_pool_mint_info, &stream_account_info, &fee_treasury_token_account_info,
-            &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
-            &rent_account_info, &system_account_info
+            &fee_config.fee_treasury_address, &msp_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &rent_account_info, &system_account_info
         )?;
 
         let clock = Clock::get()?;
+        auto_pause_update_stream(
+            &stream_account_info, &treasury_account_info, &associated_token_mint_info, &clock
+        )?;
+
         let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;        
         let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;  
         let mut escrow_vested_amount = get_beneficiary_withdrawable_amount(
             &stream, &clock, associated_token_mint.decimals.into()
         )?;
         let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
-        let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
+        let scale = TokenScale::new(associated_token_mint.decimals.into());
         let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
-
-        if stream.allocation_left < 0f64 { // TODO: Remove (this is temp)
-            stream.allocation_left = 0f64;
-        }
-
-        let stream_allocation_left = (stream.allocation_left * pow) as u64;
+        let stream_allocation_left = scale.to_base_units(stream.allocation_left);
 
         if escrow_vested_amount > stream_allocation_left {
             escrow_vested_amount = stream_allocation_left;
         }
-        
-        if escrow_vested_amount > treasury_token.amount { // TODO: Remove (this is temp)
-            escrow_vested_amount = treasury_token.amount;
+
+        if escrow_vested_amount > treasury_token.amount {
+            return Err(StreamError::InsufficientFunds.into());
         }
         // Pausing the stream
-        stream.escrow_vested_amount_snap = escrow_vested_amount as f64 / pow;
+        stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount);
         stream.escrow_vested_amount_snap_slot = clock.slot as u64;
         stream.escrow_vested_amount_snap_block_time = clock.unix_timestamp as u64;
 
         if escrow_vested_amount > 0u64 { // Transfer vested amount to beneficiary and deduct fee
-            let _ = close_stream_transfer_vested_amount(
+            close_stream_transfer_vested_amount(
                 &initializer_account_info, &treasury_account_info, &treasury_token_account_info,
                 &beneficiary_account_info, &beneficiary_token_account_info, &associated_token_mint_info,
                 &fee_treasury_account_info, &fee_treasury_token_account_info, &msp_account_info,
                 &associated_token_program_account_info, &token_program_account_info, &rent_account_info,
-                &system_account_info, escrow_vested_amount
+                &system_account_info, escrow_vested_amount, fee_config.close_stream_percent_fee
             )?;
         }
 
-        let mut escrow_unvested_amount = 0;
-        let stream_allocation_left = (stream.allocation_left * pow) as u64;
+        let escrow_unvested_amount = stream_allocation_left.saturating_sub(escrow_vested_amount);
 
-        if escrow_vested_amount > 0u64 && escrow_vested_amount <= stream_allocation_left {
-            escrow_unvested_amount = stream_allocation_left
-                .checked_sub(escrow_vested_amount)
-                .ok_or(StreamError::Overflow)?;
-        }
-        
-        let _ = close_stream_update_treasury(
+        close_stream_update_treasury(
             &mut treasury, &stream, &associated_token_mint_info,
             escrow_vested_amount, escrow_unvested_amount
         )?;
         // Save
         TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
 
-        if auto_close_treasury == true && stream.treasurer_address.eq(initializer_account_info.key) {
-            let _ = close_stream_close_treasury(
+        let treasury_closed = auto_close_treasury && treasury.treasurer_address.eq(initializer_account_info.key);
+
+        if treasury_closed {
+            close_stream_close_treasury(
                 program_id, &treasurer_account_info, &treasurer_token_account_info,
                 &treasurer_treasury_pool_token_account_info, &associated_token_mint_info,
                 &treasury_account_info, &treasury_token_account_info, &treasury_pool_mint_info,
                 &fee_treasury_account_info, &fee_treasury_token_account_info, 
-                &msp_account_info, &token_program_account_info
+                &msp_account_info, &token_program_account_info, &associated_token_program_account_info,
+                &system_account_info, &rent_account_info, &fee_config_account_info
             )?;
         }
         // Debit fees from the initializer of the instruction
-        let _ = transfer_sol_fee(
+        transfer_sol_fee(
             &system_account_info, &initializer_account_info,
-            &fee_treasury_account_info, CLOSE_STREAM_FLAT_FEE
-        );
+            &fee_treasury_account_info, fee_config.close_stream_flat_fee
+        )?;
         // Close stream account
         let treasurer_lamports = treasurer_account_info.lamports();
         let stream_lamports = stream_account_info.lamports();
@@ -633,6 +1042,21 @@ impl Processor {
         **treasurer_account_info.lamports.borrow_mut() = treasurer_lamports
             .checked_add(stream_lamports)
             .ok_or(StreamError::Overflow)?;
+        update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, None, &clock)?;
+
+        MspEvent::StreamClosed {
+            stream: *stream_account_info.key,
+            treasury: *treasury_account_info.key,
+            initializer: *initializer_account_info.key,
+            treasury_closed
+        }.emit();
+
+        if treasury_closed {
+            MspEvent::TreasuryClosed {
+                treasury: *treasury_account_info.key,
+                treasurer: *treasurer_account_info.key
+            }.emit();
+        }
 
         Ok(())
     }
@@ -648,15 +1072,26 @@ impl Processor {
     ) -> ProgramResult {
 
         let account_info_iter = &mut accounts.iter();
-        let treasurer_account_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;
-        let treasury_pool_token_mint_info = next_account_info(account_info_iter)?;
-        let fee_treasury_account_info = next_account_info(account_info_iter)?;
-        let msp_account_info = next_account_info(account_info_iter)?;
-        let token_program_account_info = next_account_info(account_info_iter)?;
-        let system_account_info = next_account_info(account_info_iter)?;
-        let rent_account_info = next_account_info(account_info_iter)?;
+        let CreateTreasuryAccounts {
+            treasurer: treasurer_account_info,
+            treasury: treasury_account_info,
+            treasury_pool_mint: treasury_pool_token_mint_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            token_program: token_program_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = CreateTreasuryAccounts::next(account_info_iter)?;
         let clock = Clock::get()?;
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            treasurer_account_info.key, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
 
         if msp_account_info.key.ne(program_id) {
             return Err(StreamError::IncorrectProgramId.into());
@@ -667,7 +1102,7 @@ impl Processor {
         }
 
         // Create Treasury PDA
-        let (treasury_address, bump_seed) = Pubkey::find_program_address(
+        let (treasury_address, treasury_bump_seed) = Pubkey::find_program_address(
             &[
                 treasurer_account_info.key.as_ref(), 
                 &slot.to_le_bytes()
@@ -682,7 +1117,7 @@ impl Processor {
         let treasury_pool_signer_seed: &[&[_]] = &[
             treasurer_account_info.key.as_ref(),
             &slot.to_le_bytes(),
-            &bump_seed.to_le_bytes()
+            &treasury_bump_seed.to_le_bytes()
         ];
 
         let _ = create_pda_account(
@@ -745,6 +1180,8 @@ impl Processor {
         treasury.depletion_rate = 0.0;
         treasury.treasury_type = treasury_type;
         treasury.auto_close = auto_close;
+        treasury.seed_address = *treasurer_account_info.key;
+        treasury.bump = treasury_bump_seed;
         treasury.initialized = true;
         // Save
         TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
@@ -754,8 +1191,18 @@ impl Processor {
             &system_account_info,
             &treasurer_account_info,
             &fee_treasury_account_info,
-            CREATE_TREASURY_FLAT_FEE
-        )
+            fee_config.create_treasury_flat_fee
+        )?;
+
+        MspEvent::TreasuryCreated {
+            treasury: *treasury_account_info.key,
+            treasurer: *treasurer_account_info.key,
+            treasury_pool_mint: *treasury_pool_token_mint_info.key,
+            treasury_type,
+            auto_close
+        }.emit();
+
+        Ok(())
     }
 
     fn process_close_treasury(
@@ -765,29 +1212,51 @@ impl Processor {
     ) -> ProgramResult {
 
         let account_info_iter = &mut accounts.iter();
-        let treasurer_account_info = next_account_info(account_info_iter)?;
-        let treasurer_token_account_info = next_account_info(account_info_iter)?;
-        let treasurer_treasury_pool_token_account_info = next_account_info(account_info_iter)?;
-        let associated_token_mint_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;  
-        let treasury_token_account_info = next_account_info(account_info_iter)?;
-        let treasury_pool_mint_info = next_account_info(account_info_iter)?;
-        let fee_treasury_account_info = next_account_info(account_info_iter)?;
-        let fee_treasury_token_account_info = next_account_info(account_info_iter)?;
-        let msp_account_info = next_account_info(account_info_iter)?;
-        let token_program_account_info = next_account_info(account_info_iter)?;
+        let CloseTreasuryAccounts {
+            treasurer: treasurer_account_info,
+            treasurer_token: treasurer_token_account_info,
+            treasurer_treasury_pool_token: treasurer_treasury_pool_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            treasury_pool_mint: treasury_pool_mint_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            token_program: token_program_account_info,
+            associated_token_program: associated_token_program_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = CloseTreasuryAccounts::next(account_info_iter)?;
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &Clock::get()?
+        )?;
 
-        if fee_treasury_account_info.key.ne(&FEE_TREASURY_ACCOUNT.parse().unwrap()) {
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
             return Err(StreamError::InstructionNotAuthorized.into());
         }
 
-        if treasury_account_info.data_len() == Treasury::LEN { // close treasury
-            return close_treasury_v0(
+        if AccountKind::detect(&treasury_account_info) == AccountKind::Treasury { // close treasury
+            close_treasury_v0(
                 program_id, &treasurer_account_info, &treasurer_token_account_info,
                 &treasurer_treasury_pool_token_account_info, &associated_token_mint_info,
                 &treasury_account_info, &treasury_token_account_info, &treasury_pool_mint_info,
                 &fee_treasury_token_account_info, &msp_account_info, &token_program_account_info
-            );
+            )?;
+
+            MspEvent::TreasuryClosed {
+                treasury: *treasury_account_info.key,
+                treasurer: *treasurer_account_info.key
+            }.emit();
+
+            return Ok(());
+        }
+
+        if AccountKind::detect(&treasury_account_info) == AccountKind::TreasuryV2 {
+            return Self::process_close_treasury_v2(accounts, program_id);
         }
 
         let _ = check_can_close_treasury(
@@ -803,14 +1272,27 @@ impl Processor {
             &treasury_account_info, &treasury_pool_mint_info, &msp_account_info, &token_program_account_info
         )?;
 
-        if treasury.associated_token_address.eq(associated_token_mint_info.key) {
+        // Tokens credited back to the treasurer, the close treasury percent fee applies to them
+        let returned_amount = if treasury.associated_token_address.eq(associated_token_mint_info.key) {
+            let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
             let _ = close_treasury_token_account(
                 &treasury, &treasurer_account_info, &treasurer_token_account_info,
                 &treasury_account_info, &treasury_token_account_info,
                 &msp_account_info, &token_program_account_info,
             )?;
-        }
-        // Close treasury account
+
+            treasury_token.amount
+        } else {
+            0
+        };
+
+        close_treasury_transfer_fees(
+            &treasurer_account_info, &treasurer_token_account_info, &associated_token_mint_info,
+            &fee_treasury_account_info, &fee_treasury_token_account_info,
+            &associated_token_program_account_info, &token_program_account_info,
+            &rent_account_info, &system_account_info, &fee_config, returned_amount
+        )?;
+        // Close treasury account
         let treasurer_lamports = treasurer_account_info.lamports();
         let treasury_lamports = treasury_account_info.lamports();
 
@@ -819,25 +1301,36 @@ impl Processor {
             .checked_add(treasury_lamports)
             .ok_or(StreamError::Overflow)?;
 
+        MspEvent::TreasuryClosed {
+            treasury: *treasury_account_info.key,
+            treasurer: *treasurer_account_info.key
+        }.emit();
+
         Ok(())
     }
 
     fn process_refresh_treasury_balance(
         accounts: &[AccountInfo],
-        _program_id: &Pubkey
+        program_id: &Pubkey
 
     ) -> ProgramResult {
 
         let account_info_iter = &mut accounts.iter();
-        let treasurer_account_info = next_account_info(account_info_iter)?;
-        let associated_token_mint_info = next_account_info(account_info_iter)?;
-        let treasury_account_info = next_account_info(account_info_iter)?;
-        let treasury_token_account_info = next_account_info(account_info_iter)?;
+        let RefreshTreasuryBalanceAccounts {
+            treasurer: treasurer_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info
+        } = RefreshTreasuryBalanceAccounts::next(account_info_iter)?;
 
         if !treasurer_account_info.is_signer {
             return Err(StreamError::InstructionNotAuthorized.into());
         }
 
+        if AccountKind::detect(&treasury_account_info) == AccountKind::TreasuryV2 {
+            return Self::process_refresh_treasury_balance_v2(accounts, program_id);
+        }
+
         let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
 
         msg!("treasury associated token address => {:?}", treasury.associated_token_address);
@@ -850,9 +1343,9 @@ impl Processor {
         else {
             let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
             let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
-            let pow = num_traits::pow(10f64, associated_token_mint.decimals.into());
+            let scale = TokenScale::new(associated_token_mint.decimals.into());
 
-            treasury.balance = treasury_token.amount as f64 / pow;
+            treasury.balance = scale.from_base_units(treasury_token.amount);
             
             if treasury.associated_token_address.eq(&Pubkey::default()) {
                 treasury.associated_token_address = *associated_token_mint_info.key;
@@ -861,6 +1354,2316 @@ impl Processor {
             TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
         }
 
+        update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, None, &Clock::get()?)?;
+
+        Ok(())
+    }
+
+    fn process_create_stream_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        stream_name: String,
+        rate_amount: u64,
+        rate_interval_in_seconds: u64,
+        allocation_reserved: u64,
+        allocation_assigned: u64,
+        funded_on_utc: u64,
+        start_utc: u64,
+        rate_cliff_in_seconds: u64,
+        cliff_vest_amount: u64,
+        cliff_vest_percent: u64,
+        auto_pause_in_seconds: u64,
+        end_utc: u64,
+        vesting_mode: u8
+        
+    ) -> ProgramResult {
+
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let CreateStreamAccounts {
+            treasurer: treasurer_account_info,
+            treasury: treasury_account_info,
+            associated_token_mint: associated_token_mint_info,
+            beneficiary: beneficiary_account_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = CreateStreamAccounts::next(account_info_iter)?;
+
+        let (fee_tier_account_info, treasury_roles_account_info) = next_fee_tier_and_treasury_roles(account_info_iter);
+        // The signer may be a stream creator of the treasury instead of the treasurer
+        let treasurer_address = get_treasurer_address(&treasury_account_info)?;
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, fee_tier_account_info,
+            &treasurer_address, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        // Verify the correct MSP Operations Account 
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        // A zero rate never vests, so the allocation of the stream could never be released
+        if rate_amount == 0 || rate_interval_in_seconds == 0 || cliff_vest_percent > BPS_DENOMINATOR {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        if vesting_mode != VestingMode::Continuous as u8 && vesting_mode != VestingMode::Stepped as u8 {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        check_can_create_stream_v2(
+            program_id, &treasurer_account_info, &treasury_account_info, 
+            &associated_token_mint_info, &stream_account_info, &msp_account_info,
+            &system_account_info, &rent_account_info, treasury_roles_account_info,
+            allocation_assigned, allocation_reserved
+        )?;
+
+        let clock = Clock::get()?;
+        // The end date (if any) must be after the start of the stream
+        let now = clock.unix_timestamp as u64 * 1000u64;
+        if end_utc > 0 && end_utc <= cmp::max(start_utc, now) {
+            return Err(StreamError::InvalidArgument.into());
+        }
+        // Create stream account
+        create_stream_account(
+            &treasurer_account_info, &stream_account_info, &msp_account_info,
+            &rent_account_info, &system_account_info, StreamV2::LEN
+        )?;
+
+        let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+        // Updating stream data
+        stream.stream_name = stream_name;
+        stream.treasurer_address = treasurer_address;
+        stream.rate_amount = rate_amount;
+        stream.rate_interval_in_seconds = rate_interval_in_seconds;
+        stream.allocation_reserved = allocation_reserved;
+        stream.allocation_assigned = allocation_assigned;
+        stream.allocation_left = allocation_assigned;
+        stream.funded_on_utc = funded_on_utc;
+        stream.start_utc = start_utc;
+        stream.rate_cliff_in_seconds = rate_cliff_in_seconds;
+        stream.cliff_vest_amount = cliff_vest_amount;
+        stream.cliff_vest_percent = cliff_vest_percent;
+        stream.beneficiary_address = *beneficiary_account_info.key;
+        stream.beneficiary_associated_token = *associated_token_mint_info.key;
+        stream.treasury_address = *treasury_account_info.key;
+        stream.treasury_estimated_depletion_utc = 0;
+        stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+        stream.escrow_vested_amount_snap_block_time = clock.unix_timestamp as u64;
+        stream.stream_resumed_slot = clock.slot;
+        stream.stream_resumed_block_time = clock.unix_timestamp as u64;
+        stream.auto_pause_in_seconds = auto_pause_in_seconds;
+        stream.end_utc = end_utc;
+        stream.vesting_mode = vesting_mode;
+
+        let status = get_stream_status_v2(&stream, &clock)?;
+
+        if status == StreamStatus::Scheduled {
+            stream.stream_resumed_block_time = start_utc / 1000u64;
+        }
+
+        // if there is a cliff amount then assign it the escrow vested snap so
+        // that way is included in the calculation of the vested amount
+        let mut cliff_amount = cliff_vest_amount;
+        
+        if cliff_vest_percent > 0 {
+            cliff_amount = get_percent_fee(allocation_assigned, cliff_vest_percent)?;
+        }
+
+        if cliff_amount > allocation_assigned {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        // Keep the cliff amount so stepped streams can unlock the tranches on top of it
+        stream.cliff_vest_amount = cliff_amount;
+        stream.escrow_vested_amount_snap = cliff_amount;
+        create_stream_update_treasury_v2(&treasury_account_info, &stream)?;
+        // Save stream
+        stream.initialized = true;
+        StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+
+        // Pay fee
+        transfer_sol_fee(
+            &system_account_info,
+            &treasurer_account_info,
+            &fee_treasury_account_info, 
+            fee_config.create_stream_flat_fee
+        )?;
+        update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::StreamCreated {
+            stream: *stream_account_info.key,
+            treasury: *treasury_account_info.key,
+            treasurer: treasurer_address,
+            beneficiary: *beneficiary_account_info.key,
+            mint: *associated_token_mint_info.key,
+            rate_amount,
+            rate_interval_in_seconds,
+            allocation_assigned,
+            start_utc: stream.start_utc / 1000u64
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_add_funds_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        amount: u64,
+        allocation_type: u8,
+        allocation_stream_address: Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let AddFundsAccounts {
+            contributor: contributor_account_info,
+            contributor_token: contributor_token_account_info,
+            contributor_treasury_pool_token: contributor_treasury_pool_token_account_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury_pool_mint: treasury_pool_mint_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = AddFundsAccounts::next(account_info_iter)?;
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        // Verify the correct MSP Operations Account 
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        if amount == 0 {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        check_can_add_funds_v2(
+            program_id, &msp_account_info, &contributor_account_info,
+            &contributor_token_account_info, &contributor_treasury_pool_token_account_info,
+            &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
+            &treasury_pool_mint_info, &stream_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &rent_account_info, &system_account_info, allocation_type
+        )?;
+
+        if allocation_type == 0 || allocation_type == 1 {
+            let clock = Clock::get()?;
+            auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+            end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+
+            let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            // Ended streams can not be allocated more funds
+            if get_stream_status_v2(&stream, &clock)? == StreamStatus::Ended {
+                return Err(StreamError::InvalidStreamStatus.into());
+            }
+        }
+
+        let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+        // Create contributor deposit receipt
+        create_deposit_receipt_v2(
+            &treasury_account_info, &treasury_pool_mint_info,
+            &contributor_treasury_pool_token_account_info, &msp_account_info,
+            &token_program_account_info, amount, associated_token_mint.decimals
+        )?;
+        // Transfer tokens from contributor to treasury associated token account
+        if is_native_contribution(&contributor_account_info, &contributor_token_account_info, &associated_token_mint_info) {
+            wrap_native_tokens(
+                &contributor_account_info, &treasury_token_account_info,
+                &system_account_info, &token_program_account_info, amount
+            )?;
+        } else {
+            transfer_tokens_v2(
+                &contributor_account_info, &contributor_token_account_info,
+                &treasury_token_account_info, &token_program_account_info, amount
+            )?;
+        }
+        // Update and save treasury
+        add_funds_update_treasury_v2(
+            &treasury_account_info, &associated_token_mint_info, allocation_type, amount
+        )?;
+
+        if allocation_type == 0 || allocation_type == 1 {
+            let clock = Clock::get()?;
+            add_funds_update_stream_v2(
+                &stream_account_info, &clock, &allocation_stream_address, allocation_type, amount
+            )?;
+        }
+        // Pay fees
+        transfer_sol_fee(
+            &system_account_info,
+            &contributor_account_info,
+            &fee_treasury_account_info, 
+            fee_config.add_funds_flat_fee
+        )?;
+        update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &Clock::get()?)?;
+
+        MspEvent::FundsAdded {
+            treasury: *treasury_account_info.key,
+            stream: allocation_stream_address,
+            contributor: *contributor_account_info.key,
+            mint: *associated_token_mint_info.key,
+            amount: amount,
+            allocation_type
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_withdraw_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        amount: u64,
+        unwrap_native: bool
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let WithdrawAccounts {
+            beneficiary: beneficiary_account_info,
+            beneficiary_token: beneficiary_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            rent: rent_account_info,
+            system_program: system_account_info,
+            fee_config: fee_config_account_info
+        } = WithdrawAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        check_can_withdraw_funds_v2(
+            program_id, &beneficiary_account_info, &beneficiary_token_account_info,
+            &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
+            &stream_account_info, &fee_treasury_token_account_info, &fee_config.fee_treasury_address,
+            &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
+            &rent_account_info, &system_account_info
+        )?;
+
+        auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+        end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+
+        let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+        let escrow_vested_amount = get_stream_vested_amount_v2(&stream, &clock)?;
+        let withdrawable_amount = get_beneficiary_withdrawable_amount_v2(&stream, &clock)?;
+
+        if amount == 0 || amount > withdrawable_amount {
+            return Err(StreamError::NotAllowedWithdrawalAmount.into());
+        }
+
+        if unwrap_native && associated_token_mint_info.key.ne(&spl_token::native_mint::id()) {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        if beneficiary_token_account_info.data_len() == 0 { // Create beneficiary associated token account if doesn't exist
+            create_ata_account(
+                &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                &token_program_account_info, &beneficiary_account_info, &beneficiary_account_info,
+                &beneficiary_token_account_info, &associated_token_mint_info
+            )?;
+        }
+        // Withdraw and update the stream and treasury data
+        withdraw_funds_transfer_vested_amount_v2(
+            &msp_account_info, &token_program_account_info, &treasury_account_info,
+            &treasury_token_account_info, &beneficiary_token_account_info,
+            &mut stream, &stream_account_info, &clock, escrow_vested_amount, amount
+        )?;
+
+        if fee_treasury_token_account_info.data_len() == 0 { // Create fee treasury associated token account if doesn't exist
+            create_ata_account(
+                &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                &token_program_account_info, &beneficiary_account_info, &fee_treasury_account_info,
+                &fee_treasury_token_account_info, &associated_token_mint_info
+            )?;
+        }
+        
+        let fee = get_percent_fee(amount, fee_config.withdraw_percent_fee)?;
+        // Pay fees
+        transfer_token_fee(
+            &token_program_account_info,
+            &beneficiary_token_account_info,
+            &fee_treasury_token_account_info,
+            &beneficiary_account_info,
+            fee
+        )?;
+
+        if unwrap_native { // Credit the withdrawn amount as SOL to the beneficiary
+            unwrap_native_tokens(
+                &beneficiary_account_info, &beneficiary_token_account_info, &token_program_account_info
+            )?;
+        }
+        update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::Withdrawn {
+            stream: *stream_account_info.key,
+            treasury: *treasury_account_info.key,
+            beneficiary: *beneficiary_account_info.key,
+            mint: *associated_token_mint_info.key,
+            amount: amount,
+            fee
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_pause_stream_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let PauseOrResumeStreamAccounts {
+            initializer: initializer_account_info,
+            treasury: treasury_account_info,
+            associated_token_mint: associated_token_mint_info,
+            stream: stream_account_info,
+            fee_treasury: _fee_treasury_account_info,
+            msp: msp_account_info,
+            fee_config: _fee_config_account_info
+        } = PauseOrResumeStreamAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        check_can_pause_or_resume_stream_v2(
+            program_id, &initializer_account_info, &treasury_account_info,
+            &associated_token_mint_info, &stream_account_info, &msp_account_info,
+            account_info_iter.next()
+        )?;
+
+        auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+        end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+
+        let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+        let status = get_stream_status_v2(&stream, &clock)?;
+
+        if status == StreamStatus::Paused || status == StreamStatus::Ended {
+            return Err(StreamError::InvalidStreamStatus.into());
+        }
+
+        let escrow_vested_amount = get_stream_vested_amount_v2(&stream, &clock)?;
+        let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+        let stream_rate = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+
+        treasury.depletion_rate = treasury.depletion_rate.saturating_sub(stream_rate);
+        TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+        // Pausing the stream
+        stream.unlocked_amount_snap = get_stream_unlocked_amount_v2(&stream, &clock)?;
+        stream.escrow_vested_amount_snap = escrow_vested_amount;
+        stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+        stream.escrow_vested_amount_snap_block_time = clock.unix_timestamp as u64;
+        StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::Paused {
+            stream: *stream_account_info.key,
+            initializer: *initializer_account_info.key,
+            vested_amount: escrow_vested_amount,
+            timestamp: clock.unix_timestamp as u64
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_resume_stream_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let PauseOrResumeStreamAccounts {
+            initializer: initializer_account_info,
+            treasury: treasury_account_info,
+            associated_token_mint: associated_token_mint_info,
+            stream: stream_account_info,
+            fee_treasury: _fee_treasury_account_info,
+            msp: msp_account_info,
+            fee_config: _fee_config_account_info
+        } = PauseOrResumeStreamAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        check_can_pause_or_resume_stream_v2(
+            program_id, &initializer_account_info, &treasury_account_info,
+            &associated_token_mint_info, &stream_account_info, &msp_account_info,
+            account_info_iter.next()
+        )?;
+
+        auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+        end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+
+        let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+
+        if get_stream_status_v2(&stream, &clock)? != StreamStatus::Paused {
+            return Err(StreamError::InvalidStreamStatus.into());
+        }
+
+        let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+        let stream_rate = get_stream_rate_per_second(stream.rate_amount, stream.rate_interval_in_seconds)?;
+
+        treasury.depletion_rate = treasury.depletion_rate
+            .checked_add(stream_rate)
+            .ok_or(StreamError::Overflow)?;
+
+        TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+        // Resuming the stream and updating data
+        stream.stream_resumed_slot = clock.slot as u64;
+        stream.stream_resumed_block_time = clock.unix_timestamp as u64;
+        // Save
+        StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::Resumed {
+            stream: *stream_account_info.key,
+            initializer: *initializer_account_info.key,
+            timestamp: clock.unix_timestamp as u64
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_close_stream_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        auto_close_treasury: bool,
+        unwrap_native: bool
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let CloseStreamAccounts {
+            initializer: initializer_account_info,
+            treasurer: treasurer_account_info,
+            treasurer_token: treasurer_token_account_info,
+            treasurer_treasury_pool_token: treasurer_treasury_pool_token_account_info,
+            beneficiary: beneficiary_account_info,
+            beneficiary_token: beneficiary_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            treasury_pool_mint: treasury_pool_mint_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            rent: rent_account_info,
+            system_program: system_account_info,
+            fee_config: fee_config_account_info
+        } = CloseStreamAccounts::next(account_info_iter)?;
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        check_can_close_stream_v2(
+            program_id, &initializer_account_info, &treasurer_account_info,
+            &treasurer_token_account_info, &beneficiary_account_info, &beneficiary_token_account_info,
+            &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
+            &treasury_pool_mint_info, &stream_account_info, &fee_treasury_token_account_info,
+            &fee_config.fee_treasury_address, &msp_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &rent_account_info, &system_account_info
+        )?;
+
+        let clock = Clock::get()?;
+        auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+        end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+
+        let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+        let status = get_stream_status_v2(&stream, &clock)?;
+        let escrow_vested_amount = get_beneficiary_withdrawable_amount_v2(&stream, &clock)?;
+        let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+        let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
+
+        if escrow_vested_amount > treasury_token.amount {
+            return Err(StreamError::InsufficientFunds.into());
+        }
+
+        if unwrap_native && associated_token_mint_info.key.ne(&spl_token::native_mint::id()) {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        if escrow_vested_amount > 0 { // Transfer vested amount to beneficiary and deduct fee
+            close_stream_transfer_vested_amount_v2(
+                &initializer_account_info, &treasury_account_info, &treasury_token_account_info,
+                &beneficiary_account_info, &beneficiary_token_account_info, &associated_token_mint_info,
+                &fee_treasury_account_info, &fee_treasury_token_account_info, &msp_account_info,
+                &associated_token_program_account_info, &token_program_account_info, &rent_account_info,
+                &system_account_info, escrow_vested_amount, fee_config.close_stream_percent_fee
+            )?;
+        }
+
+        if unwrap_native && beneficiary_token_account_info.data_len() > 0 { // Credit the vested amount as SOL to the beneficiary
+            unwrap_native_tokens(
+                &beneficiary_account_info, &beneficiary_token_account_info, &token_program_account_info
+            )?;
+        }
+
+        close_stream_update_treasury_v2(
+            &mut treasury, &stream, &status, escrow_vested_amount
+        )?;
+        // Save
+        TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+        let treasury_closed = auto_close_treasury && treasury.treasurer_address.eq(initializer_account_info.key);
+
+        if treasury_closed {
+            close_treasury_pool_token_account_v2(
+                &treasurer_account_info, &treasurer_treasury_pool_token_account_info,
+                &treasury_pool_mint_info, &token_program_account_info
+            )?;
+
+            if treasury.streams_amount > 0 {
+                return Err(StreamError::CloseTreasuryWithStreams.into());
+            }
+
+            let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
+            close_treasury_token_account_v2(
+                &treasury, &treasurer_account_info, &treasurer_token_account_info,
+                &treasury_account_info, &treasury_token_account_info,
+                &msp_account_info, &token_program_account_info,
+            )?;
+
+            close_treasury_transfer_fees(
+                &treasurer_account_info, &treasurer_token_account_info, &associated_token_mint_info,
+                &fee_treasury_account_info, &fee_treasury_token_account_info,
+                &associated_token_program_account_info, &token_program_account_info,
+                &rent_account_info, &system_account_info, &fee_config, treasury_token.amount
+            )?;
+            close_treasury_account(&treasurer_account_info, &treasury_account_info)?;
+        }
+        // Debit fees from the initializer of the instruction
+        transfer_sol_fee(
+            &system_account_info, &initializer_account_info,
+            &fee_treasury_account_info, fee_config.close_stream_flat_fee
+        )?;
+        // Close stream account
+        let treasurer_lamports = treasurer_account_info.lamports();
+        let stream_lamports = stream_account_info.lamports();
+
+        **stream_account_info.lamports.borrow_mut() = 0;
+        **treasurer_account_info.lamports.borrow_mut() = treasurer_lamports
+            .checked_add(stream_lamports)
+            .ok_or(StreamError::Overflow)?;
+        update_estimated_depletion_v2(&treasury_account_info, None, &clock)?;
+
+        MspEvent::StreamClosed {
+            stream: *stream_account_info.key,
+            treasury: *treasury_account_info.key,
+            initializer: *initializer_account_info.key,
+            treasury_closed
+        }.emit();
+
+        if treasury_closed {
+            MspEvent::TreasuryClosed {
+                treasury: *treasury_account_info.key,
+                treasurer: *treasurer_account_info.key
+            }.emit();
+        }
+
+        Ok(())
+    }
+
+    fn process_create_treasury_v2(
+        accounts: &[AccountInfo], 
+        program_id: &Pubkey,
+        slot: u64,
+        label: String,
+        treasury_type: u8,
+        auto_close: bool,
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let CreateTreasuryAccounts {
+            treasurer: treasurer_account_info,
+            treasury: treasury_account_info,
+            treasury_pool_mint: treasury_pool_token_mint_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            token_program: token_program_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = CreateTreasuryAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        if msp_account_info.key.ne(program_id) {
+            return Err(StreamError::IncorrectProgramId.into());
+        }
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            treasurer_account_info.key, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        if !treasurer_account_info.is_signer {
+            return Err(StreamError::MissingInstructionSignature.into());
+        }
+
+        check_system_accounts(
+            Option::None, Option::Some(token_program_account_info), 
+            Option::Some(rent_account_info), Option::Some(system_account_info)
+        )?;
+
+        // Create Treasury PDA
+        let (treasury_address, treasury_bump_seed) = Pubkey::find_program_address(
+            &[
+                treasurer_account_info.key.as_ref(), 
+                &slot.to_le_bytes()
+            ], 
+            msp_account_info.key
+        );
+    
+        if treasury_address.ne(treasury_account_info.key) {
+            return Err(StreamError::InvalidTreasuryPool.into());
+        }
+
+        let treasury_pool_signer_seed: &[&[_]] = &[
+            treasurer_account_info.key.as_ref(),
+            &slot.to_le_bytes(),
+            &treasury_bump_seed.to_le_bytes()
+        ];
+
+        create_pda_account(
+            &system_account_info, &rent_account_info, &msp_account_info,
+            &treasury_account_info, &treasurer_account_info,
+            TreasuryV2::LEN, &[treasury_pool_signer_seed]
+        )?;
+        // Create Treasury Pool Mint PDA
+        let (treasury_pool_mint_address, bump_seed) = Pubkey::find_program_address(
+            &[
+                treasurer_account_info.key.as_ref(), 
+                treasury_account_info.key.as_ref(), 
+                &slot.to_le_bytes()
+            ], 
+            msp_account_info.key
+        );
+    
+        if treasury_pool_mint_address.ne(treasury_pool_token_mint_info.key) {
+            return Err(StreamError::InvalidTreasuryPoolMint.into());
+        }
+
+        let treasury_pool_mint_signer_seed: &[&[_]] = &[
+            treasurer_account_info.key.as_ref(),
+            treasury_account_info.key.as_ref(),
+            &slot.to_le_bytes(),
+            &bump_seed.to_le_bytes()
+        ];
+
+        create_pda_account(
+            &system_account_info, &rent_account_info, &token_program_account_info,
+            &treasury_pool_token_mint_info, &treasurer_account_info,
+            spl_token::state::Mint::LEN, &[treasury_pool_mint_signer_seed]
+        )?;
+        // Initialize pool treasury mint
+        let init_treasury_pool_mint_ix = spl_token::instruction::initialize_mint(
+            token_program_account_info.key, treasury_pool_token_mint_info.key,
+            treasury_account_info.key, None, TREASURY_POOL_MINT_DECIMALS
+        )?;
+
+        invoke(&init_treasury_pool_mint_ix, &[
+            token_program_account_info.clone(),
+            treasury_pool_token_mint_info.clone(),
+            treasury_account_info.clone(),
+            rent_account_info.clone()
+        ])?;
+
+        // Update Treasury data
+        let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+        treasury.slot = slot;
+        treasury.treasurer_address = *treasurer_account_info.key;
+        treasury.mint_address = *treasury_pool_token_mint_info.key;
+        treasury.label = label;
+        treasury.balance = 0;
+        treasury.allocation_reserved = 0;
+        treasury.allocation_left = 0;
+        treasury.allocation_assigned = 0;
+        treasury.streams_amount = 0;
+        treasury.created_on_utc = clock.unix_timestamp as u64 * 1000u64;
+        treasury.depletion_rate = 0;
+        treasury.treasury_type = treasury_type;
+        treasury.auto_close = auto_close;
+        treasury.seed_address = *treasurer_account_info.key;
+        treasury.bump = treasury_bump_seed;
+        treasury.initialized = true;
+        // Save
+        TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+        // Debit fees from treasurer
+        transfer_sol_fee(
+            &system_account_info,
+            &treasurer_account_info,
+            &fee_treasury_account_info,
+            fee_config.create_treasury_flat_fee
+        )?;
+
+        MspEvent::TreasuryCreated {
+            treasury: *treasury_account_info.key,
+            treasurer: *treasurer_account_info.key,
+            treasury_pool_mint: *treasury_pool_token_mint_info.key,
+            treasury_type,
+            auto_close
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_close_treasury_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let CloseTreasuryAccounts {
+            treasurer: treasurer_account_info,
+            treasurer_token: treasurer_token_account_info,
+            treasurer_treasury_pool_token: treasurer_treasury_pool_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            treasury_pool_mint: treasury_pool_mint_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            token_program: token_program_account_info,
+            associated_token_program: associated_token_program_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = CloseTreasuryAccounts::next(account_info_iter)?;
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &Clock::get()?
+        )?;
+
+        check_can_close_treasury_v2(
+            &program_id, &treasurer_account_info, &treasury_account_info,
+            &treasury_pool_mint_info, &msp_account_info, &token_program_account_info
+        )?;
+
+        let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+        // Close treaurer treasury pool token account
+        close_treasury_pool_token_account_v2(
+            &treasurer_account_info, &treasurer_treasury_pool_token_account_info,
+            &treasury_pool_mint_info, &token_program_account_info
+        )?;
+
+        // Tokens credited back to the treasurer, the close treasury percent fee applies to them
+        let returned_amount = if treasury.associated_token_address.eq(associated_token_mint_info.key) &&
+           treasury_token_account_info.data_len() == spl_token::state::Account::LEN
+        {
+            let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
+            close_treasury_token_account_v2(
+                &treasury, &treasurer_account_info, &treasurer_token_account_info,
+                &treasury_account_info, &treasury_token_account_info,
+                &msp_account_info, &token_program_account_info,
+            )?;
+
+            treasury_token.amount
+        } else {
+            0
+        };
+
+        close_treasury_transfer_fees(
+            &treasurer_account_info, &treasurer_token_account_info, &associated_token_mint_info,
+            &fee_treasury_account_info, &fee_treasury_token_account_info,
+            &associated_token_program_account_info, &token_program_account_info,
+            &rent_account_info, &system_account_info, &fee_config, returned_amount
+        )?;
+        // Close treasury account
+        close_treasury_account(&treasurer_account_info, &treasury_account_info)?;
+
+        MspEvent::TreasuryClosed {
+            treasury: *treasury_account_info.key,
+            treasurer: *treasurer_account_info.key
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_refresh_treasury_balance_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let RefreshTreasuryBalanceAccounts {
+            treasurer: treasurer_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info
+        } = RefreshTreasuryBalanceAccounts::next(account_info_iter)?;
+
+        if !treasurer_account_info.is_signer {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        if treasury_account_info.owner != program_id {
+            return Err(StreamError::InvalidTreasuryData.into());
+        }
+
+        let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+        if treasury.associated_token_address.ne(&Pubkey::default()) && 
+           treasury.associated_token_address.ne(associated_token_mint_info.key) 
+        {
+            return Err(StreamError::InvalidTreasuryAssociatedToken.into());
+        }
+
+        let treasury_token_address = spl_associated_token_account::get_associated_token_address(
+            treasury_account_info.key, associated_token_mint_info.key
+        );
+
+        if treasury_token_address.ne(treasury_token_account_info.key) {
+            return Err(StreamError::InvalidTreasuryAccount.into());
+        }
+
+        let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
+
+        treasury.balance = treasury_token.amount;
+        treasury.associated_token_address = *associated_token_mint_info.key;
+        TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+        update_estimated_depletion_v2(&treasury_account_info, None, &Clock::get()?)?;
+
+        Ok(())
+    }
+
+    fn process_migrate_stream(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let MigrateStreamAccounts {
+            initializer: initializer_account_info,
+            treasurer: treasurer_account_info,
+            associated_token_mint: associated_token_mint_info,
+            stream: stream_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info
+        } = MigrateStreamAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        check_can_migrate_stream(
+            program_id, &initializer_account_info, &treasurer_account_info,
+            &associated_token_mint_info, &stream_account_info, &msp_account_info,
+            &system_account_info, &rent_account_info
+        )?;
+
+        let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+        let decimals = associated_token_mint.decimals as u64;
+        let stream = Stream::unpack_from_slice(&stream_account_info.data.borrow())?;
+        let escrow_vested_amount = get_beneficiary_withdrawable_amount_v0(&stream, &clock, decimals)?;
+        let mut migrated_stream = migrate_stream_v0(&stream, decimals)?;
+
+        // Legacy streams ignored the auto pause, so its countdown starts with the migration
+        if migrated_stream.auto_pause_in_seconds > 0 && get_stream_status_v0(&stream, &clock)? == StreamStatus::Running {
+            let scale = TokenScale::new(associated_token_mint.decimals.into());
+            migrated_stream.escrow_vested_amount_snap = scale.from_base_units(escrow_vested_amount);
+            migrated_stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+            migrated_stream.escrow_vested_amount_snap_block_time = clock.unix_timestamp as u64;
+            migrated_stream.stream_resumed_slot = clock.slot as u64;
+            migrated_stream.stream_resumed_block_time = clock.unix_timestamp as u64;
+        }
+
+        let migrated_vested_amount = get_beneficiary_withdrawable_amount(&migrated_stream, &clock, decimals)?;
+
+        // The beneficiary must not win or lose anything because of the migration
+        if migrated_vested_amount != escrow_vested_amount {
+            return Err(StreamError::VestedAmountMismatch.into());
+        }
+        // Grow the account to the StreamV1 size and save the migrated data
+        resize_account(
+            &system_account_info, &rent_account_info, &initializer_account_info,
+            &stream_account_info, StreamV1::LEN
+        )?;
+
+        StreamV1::pack_into_slice(&migrated_stream, &mut stream_account_info.data.borrow_mut());
+
+        MspEvent::StreamMigrated {
+            stream: *stream_account_info.key,
+            initializer: *initializer_account_info.key
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_migrate_treasury(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let MigrateTreasuryAccounts {
+            initializer: initializer_account_info,
+            treasurer: treasurer_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info
+        } = MigrateTreasuryAccounts::next(account_info_iter)?;
+        let stream_account_infos = &accounts[8..];
+        let clock = Clock::get()?;
+
+        check_can_migrate_treasury(
+            program_id, &initializer_account_info, &treasurer_account_info,
+            &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
+            &msp_account_info, &system_account_info, &rent_account_info, stream_account_infos
+        )?;
+
+        let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+        let mut balance = 0u64;
+
+        if treasury_token_account_info.data_len() == spl_token::state::Account::LEN {
+            let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
+            balance = treasury_token.amount;
+        }
+
+        let treasury = Treasury::unpack_from_slice(&treasury_account_info.data.borrow())?;
+        let migrated_treasury = migrate_treasury_v0(
+            &treasury, associated_token_mint_info.key, stream_account_infos,
+            &clock, balance, associated_token_mint.decimals as u64
+        )?;
+        // Grow the account to the TreasuryV1 size and save the migrated data
+        resize_account(
+            &system_account_info, &rent_account_info, &initializer_account_info,
+            &treasury_account_info, TreasuryV1::LEN
+        )?;
+
+        TreasuryV1::pack_into_slice(&migrated_treasury, &mut treasury_account_info.data.borrow_mut());
+
+        MspEvent::TreasuryMigrated {
+            treasury: *treasury_account_info.key,
+            initializer: *initializer_account_info.key,
+            balance
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_propose_update(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        stream_name: String,
+        rate_amount: u64,
+        rate_interval_in_seconds: u64
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let ProposeUpdateAccounts {
+            proposer: proposer_account_info,
+            stream_terms: stream_terms_account_info,
+            treasury: treasury_account_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info
+        } = ProposeUpdateAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        let fee_config = get_fee_config(program_id, &fee_config_account_info)?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        if rate_amount == 0 || rate_interval_in_seconds == 0 {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        check_can_propose_update(
+            program_id, &proposer_account_info, &stream_terms_account_info, &treasury_account_info,
+            &stream_account_info, &msp_account_info, &system_account_info, &rent_account_info
+        )?;
+
+        // Create Stream Terms PDA
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[stream_account_info.key.as_ref(), STREAM_TERMS_SEED],
+            msp_account_info.key
+        );
+
+        let stream_terms_signer_seed: &[&[_]] = &[
+            stream_account_info.key.as_ref(),
+            STREAM_TERMS_SEED,
+            &bump_seed.to_le_bytes()
+        ];
+
+        create_pda_account(
+            &system_account_info, &rent_account_info, &msp_account_info,
+            &stream_terms_account_info, &proposer_account_info,
+            StreamTerms::LEN, &[stream_terms_signer_seed]
+        )?;
+
+        let mut stream_terms = StreamTerms::unpack_from_slice(&stream_terms_account_info.data.borrow())?;
+
+        stream_terms.proposed_by = *proposer_account_info.key;
+        stream_terms.stream_id = *stream_account_info.key;
+        stream_terms.stream_name = stream_name;
+        stream_terms.rate_amount = rate_amount;
+        stream_terms.rate_interval_in_seconds = rate_interval_in_seconds;
+        stream_terms.created_on_utc = clock.unix_timestamp as u64 * 1000u64;
+        stream_terms.initialized = true;
+        // Save
+        StreamTerms::pack_into_slice(&stream_terms, &mut stream_terms_account_info.data.borrow_mut());
+
+        let fee_config = apply_fee_tier(
+            program_id, fee_config, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &clock
+        )?;
+        // Debit fees from the proposer
+        transfer_sol_fee(
+            &system_account_info,
+            &proposer_account_info,
+            &fee_treasury_account_info,
+            fee_config.propose_update_flat_fee
+        )
+    }
+
+    fn process_answer_update(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        approve: bool
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let AnswerUpdateAccounts {
+            answerer: answerer_account_info,
+            proposer: proposer_account_info,
+            stream_terms: stream_terms_account_info,
+            treasury: treasury_account_info,
+            stream: stream_account_info,
+            msp: msp_account_info
+        } = AnswerUpdateAccounts::next(account_info_iter)?;
+
+        check_can_answer_update(
+            program_id, &answerer_account_info, &proposer_account_info,
+            &stream_terms_account_info, &treasury_account_info,
+            &stream_account_info, &msp_account_info
+        )?;
+
+        if approve == true {
+            let clock = Clock::get()?;
+            auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+            end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+
+            let stream_terms = StreamTerms::unpack_from_slice(&stream_terms_account_info.data.borrow())?;
+            let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+            if get_stream_status_v2(&stream, &clock)? == StreamStatus::Ended {
+                return Err(StreamError::InvalidStreamStatus.into());
+            }
+
+            answer_update_update_stream_v2(&mut stream, &mut treasury, &stream_terms, &clock)?;
+            // Save
+            StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+            TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+            update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &clock)?;
+        }
+        // Close stream terms account
+        let proposer_lamports = proposer_account_info.lamports();
+        let stream_terms_lamports = stream_terms_account_info.lamports();
+
+        **stream_terms_account_info.lamports.borrow_mut() = 0;
+        **proposer_account_info.lamports.borrow_mut() = proposer_lamports
+            .checked_add(stream_terms_lamports)
+            .ok_or(StreamError::Overflow)?;
+
+        Ok(())
+    }
+
+    fn process_transfer_stream(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        new_beneficiary_address: Pubkey,
+        settle_vested: bool
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let WithdrawAccounts {
+            beneficiary: beneficiary_account_info,
+            beneficiary_token: beneficiary_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            rent: rent_account_info,
+            system_program: system_account_info,
+            fee_config: fee_config_account_info
+        } = WithdrawAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        check_can_transfer_stream(
+            program_id, &beneficiary_account_info, &stream_account_info,
+            &msp_account_info, &new_beneficiary_address
+        )?;
+
+        let is_stream_v2 = AccountKind::detect(&stream_account_info) == AccountKind::StreamV2;
+        let mut settled_amount = 0;
+
+        if settle_vested { // Pay the vested amount to the current beneficiary before the transfer
+            let fee_config = apply_fee_tier(
+                program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+                &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &clock
+            )?;
+
+            if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+                return Err(StreamError::InstructionNotAuthorized.into());
+            }
+
+            if is_stream_v2 {
+                check_can_withdraw_funds_v2(
+                    program_id, &beneficiary_account_info, &beneficiary_token_account_info,
+                    &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
+                    &stream_account_info, &fee_treasury_token_account_info, &fee_config.fee_treasury_address,
+                    &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
+                    &rent_account_info, &system_account_info
+                )?;
+
+                auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+                end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+            } else {
+                check_can_withdraw_funds(
+                    program_id, &beneficiary_account_info, &beneficiary_token_account_info,
+                    &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
+                    &stream_account_info, &fee_treasury_token_account_info, &fee_config.fee_treasury_address,
+                    &msp_account_info, &associated_token_program_account_info, &token_program_account_info,
+                    &rent_account_info, &system_account_info
+                )?;
+
+                auto_pause_update_stream(
+                    &stream_account_info, &treasury_account_info, &associated_token_mint_info, &clock
+                )?;
+            }
+
+            if beneficiary_token_account_info.data_len() == 0 { // Create beneficiary associated token account if doesn't exist
+                create_ata_account(
+                    &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                    &token_program_account_info, &beneficiary_account_info, &beneficiary_account_info,
+                    &beneficiary_token_account_info, &associated_token_mint_info
+                )?;
+            }
+
+            if is_stream_v2 {
+                let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+                let escrow_vested_amount = get_stream_vested_amount_v2(&stream, &clock)?;
+                settled_amount = get_beneficiary_withdrawable_amount_v2(&stream, &clock)?;
+
+                if settled_amount > 0 {
+                    withdraw_funds_transfer_vested_amount_v2(
+                        &msp_account_info, &token_program_account_info, &treasury_account_info,
+                        &treasury_token_account_info, &beneficiary_token_account_info,
+                        &mut stream, &stream_account_info, &clock, escrow_vested_amount, settled_amount
+                    )?;
+                    update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &clock)?;
+                }
+            } else {
+                let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+                let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+                settled_amount = get_beneficiary_withdrawable_amount(
+                    &stream, &clock, associated_token_mint.decimals.into()
+                )?;
+
+                if settled_amount > 0 {
+                    withdraw_funds_transfer_vested_amount(
+                        &msp_account_info, &token_program_account_info, &treasury_account_info,
+                        &treasury_token_account_info, &beneficiary_token_account_info, &associated_token_mint_info,
+                        &mut stream, &stream_account_info, &clock, settled_amount, settled_amount
+                    )?;
+                    update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, Some(stream_account_info), &clock)?;
+                }
+            }
+
+            let fee = get_percent_fee(settled_amount, fee_config.withdraw_percent_fee)?;
+
+            if fee > 0 {
+                if fee_treasury_token_account_info.data_len() == 0 { // Create fee treasury associated token account if doesn't exist
+                    create_ata_account(
+                        &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                        &token_program_account_info, &beneficiary_account_info, &fee_treasury_account_info,
+                        &fee_treasury_token_account_info, &associated_token_mint_info
+                    )?;
+                }
+                // Pay the withdraw fee like a regular withdraw
+                transfer_token_fee(
+                    &token_program_account_info,
+                    &beneficiary_token_account_info,
+                    &fee_treasury_token_account_info,
+                    &beneficiary_account_info,
+                    fee
+                )?;
+            }
+        }
+
+        if is_stream_v2 {
+            let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            stream.beneficiary_address = new_beneficiary_address;
+            StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        } else {
+            let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+            stream.beneficiary_address = new_beneficiary_address;
+            StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        }
+
+        MspEvent::StreamTransferred {
+            stream: *stream_account_info.key,
+            previous_beneficiary: *beneficiary_account_info.key,
+            new_beneficiary: new_beneficiary_address,
+            settled_amount
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_transfer_treasury(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        new_treasurer_address: Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let TransferTreasuryAccounts {
+            treasurer: treasurer_account_info,
+            treasury: treasury_account_info,
+            treasury_roles: treasury_roles_account_info,
+            msp: msp_account_info
+        } = TransferTreasuryAccounts::next(account_info_iter)?;
+        let stream_account_infos = &accounts[4..];
+
+        check_can_transfer_treasury(
+            program_id, &treasurer_account_info, &treasury_account_info, &treasury_roles_account_info,
+            &msp_account_info, stream_account_infos, &new_treasurer_address
+        )?;
+
+        if treasury_roles_account_info.data_len() > 0 { // The roles granted by the current treasurer do not carry over
+            close_treasury_roles_account(&treasurer_account_info, &treasury_roles_account_info)?;
+        }
+
+        if AccountKind::detect(&treasury_account_info) == AccountKind::TreasuryV2 {
+            let mut treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            treasury.treasurer_address = new_treasurer_address;
+            TreasuryV2::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+            for stream_account_info in stream_account_infos.iter() {
+                let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+                stream.treasurer_address = new_treasurer_address;
+                StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+            }
+        } else {
+            let mut treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            treasury.treasurer_address = new_treasurer_address;
+            TreasuryV1::pack_into_slice(&treasury, &mut treasury_account_info.data.borrow_mut());
+
+            for stream_account_info in stream_account_infos.iter() {
+                let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+                stream.treasurer_address = new_treasurer_address;
+                StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+            }
+        }
+
+        MspEvent::TreasuryTransferred {
+            treasury: *treasury_account_info.key,
+            previous_treasurer: *treasurer_account_info.key,
+            new_treasurer: new_treasurer_address
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_treasury_withdraw(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        amount: u64
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let TreasuryWithdrawAccounts {
+            treasurer: treasurer_account_info,
+            destination_token: destination_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            msp: msp_account_info,
+            token_program: token_program_account_info
+        } = TreasuryWithdrawAccounts::next(account_info_iter)?;
+
+        check_can_treasury_withdraw(
+            program_id, &treasurer_account_info, &destination_token_account_info,
+            &associated_token_mint_info, &treasury_account_info, &treasury_token_account_info,
+            &msp_account_info, &token_program_account_info, amount
+        )?;
+
+        if AccountKind::detect(&treasury_account_info) == AccountKind::TreasuryV2 {
+            claim_treasury_funds_v2(
+                &msp_account_info, &token_program_account_info, &treasury_account_info,
+                &treasury_token_account_info, &destination_token_account_info, amount
+            )?;
+
+            treasury_withdraw_update_treasury_v2(&treasury_account_info, amount)?;
+            update_estimated_depletion_v2(&treasury_account_info, None, &Clock::get()?)?;
+        } else {
+            claim_treasury_funds(
+                &msp_account_info, &token_program_account_info, &treasury_account_info,
+                &treasury_token_account_info, &destination_token_account_info, amount
+            )?;
+
+            treasury_withdraw_update_treasury(&treasury_account_info, &associated_token_mint_info, amount)?;
+            update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, None, &Clock::get()?)?;
+        }
+
+        MspEvent::TreasuryWithdrawn {
+            treasury: *treasury_account_info.key,
+            treasurer: *treasurer_account_info.key,
+            destination: *destination_token_account_info.key,
+            mint: *associated_token_mint_info.key,
+            amount
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_allocate(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        amount: u64,
+        allocation_type: u8
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let AllocateAccounts {
+            treasurer: treasurer_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            stream: stream_account_info,
+            msp: msp_account_info
+        } = AllocateAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        check_can_allocate(
+            program_id, &treasurer_account_info, &associated_token_mint_info,
+            &treasury_account_info, &stream_account_info, &msp_account_info,
+            amount, allocation_type
+        )?;
+
+        if AccountKind::detect(&stream_account_info) == AccountKind::StreamV2 {
+            auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+            end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+
+            let stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            // Ended streams can not be allocated more funds
+            if get_stream_status_v2(&stream, &clock)? == StreamStatus::Ended {
+                return Err(StreamError::InvalidStreamStatus.into());
+            }
+
+            allocate_update_treasury_v2(&treasury_account_info, allocation_type, amount)?;
+            // Resumes the stream if it was paused because it ran out of funds
+            add_funds_update_stream_v2(
+                &stream_account_info, &clock, stream_account_info.key, allocation_type, amount
+            )?;
+
+            update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &clock)?;
+        } else {
+            auto_pause_update_stream(
+                &stream_account_info, &treasury_account_info, &associated_token_mint_info, &clock
+            )?;
+
+            allocate_update_treasury(
+                &treasury_account_info, &associated_token_mint_info, allocation_type, amount
+            )?;
+
+            let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+            let scale = TokenScale::new(associated_token_mint.decimals.into());
+            // Resumes the stream if it was paused because it ran out of funds
+            add_funds_update_stream(
+                &stream_account_info, &associated_token_mint_info, &clock,
+                stream_account_info.key, allocation_type, scale.from_base_units(amount)
+            )?;
+
+            update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, Some(stream_account_info), &clock)?;
+        }
+
+        MspEvent::FundsAllocated {
+            treasury: *treasury_account_info.key,
+            stream: *stream_account_info.key,
+            treasurer: *treasurer_account_info.key,
+            mint: *associated_token_mint_info.key,
+            amount,
+            allocation_type
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_redeem(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        amount: u64
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let RedeemAccounts {
+            contributor: contributor_account_info,
+            contributor_token: contributor_token_account_info,
+            contributor_treasury_pool_token: contributor_treasury_pool_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            treasury_pool_mint: treasury_pool_mint_info,
+            msp: msp_account_info,
+            token_program: token_program_account_info
+        } = RedeemAccounts::next(account_info_iter)?;
+
+        check_can_redeem(
+            program_id, &contributor_account_info, &contributor_token_account_info,
+            &contributor_treasury_pool_token_account_info, &associated_token_mint_info,
+            &treasury_account_info, &treasury_token_account_info, &treasury_pool_mint_info,
+            &msp_account_info, &token_program_account_info, amount
+        )?;
+
+        let is_treasury_v2 = AccountKind::detect(&treasury_account_info) == AccountKind::TreasuryV2;
+        let unallocated_balance = if is_treasury_v2 {
+            let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            treasury.balance.checked_sub(treasury.allocation_left).ok_or(StreamError::Overflow)?
+        } else {
+            let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+            let scale = TokenScale::new(associated_token_mint.decimals.into());
+
+            scale.to_base_units(treasury.balance)
+                .checked_sub(scale.to_base_units(treasury.allocation_left))
+                .ok_or(StreamError::Overflow)?
+        };
+        // The pool tokens are a pro-rata claim on the funds not allocated to any stream
+        let treasury_pool_mint = spl_token::state::Mint::unpack_from_slice(&treasury_pool_mint_info.data.borrow())?;
+        let treasury_token = spl_token::state::Account::unpack_from_slice(&treasury_token_account_info.data.borrow())?;
+        let redeem_amount = cmp::min(
+            get_treasury_pool_redeem_amount(unallocated_balance, amount, treasury_pool_mint.supply)?,
+            treasury_token.amount
+        );
+
+        if redeem_amount == 0 {
+            return Err(StreamError::InsufficientFunds.into());
+        }
+
+        burn_treasury_pool_tokens(
+            &contributor_account_info, &contributor_treasury_pool_token_account_info,
+            &treasury_pool_mint_info, &token_program_account_info, amount
+        )?;
+
+        if is_treasury_v2 {
+            claim_treasury_funds_v2(
+                &msp_account_info, &token_program_account_info, &treasury_account_info,
+                &treasury_token_account_info, &contributor_token_account_info, redeem_amount
+            )?;
+
+            treasury_withdraw_update_treasury_v2(&treasury_account_info, redeem_amount)?;
+            update_estimated_depletion_v2(&treasury_account_info, None, &Clock::get()?)?;
+        } else {
+            claim_treasury_funds(
+                &msp_account_info, &token_program_account_info, &treasury_account_info,
+                &treasury_token_account_info, &contributor_token_account_info, redeem_amount
+            )?;
+
+            treasury_withdraw_update_treasury(&treasury_account_info, &associated_token_mint_info, redeem_amount)?;
+            update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, None, &Clock::get()?)?;
+        }
+
+        MspEvent::Redeemed {
+            treasury: *treasury_account_info.key,
+            contributor: *contributor_account_info.key,
+            mint: *associated_token_mint_info.key,
+            burned_amount: amount,
+            amount: redeem_amount
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_init_fee_config(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        authority: Pubkey,
+        fee_treasury_address: Pubkey,
+        create_treasury_flat_fee: u64,
+        create_stream_flat_fee: u64,
+        add_funds_flat_fee: u64,
+        withdraw_percent_fee: u64,
+        propose_update_flat_fee: u64,
+        close_stream_flat_fee: u64,
+        close_stream_percent_fee: u64,
+        close_treasury_flat_fee: u64,
+        close_treasury_percent_fee: u64
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let InitFeeConfigAccounts {
+            initializer: initializer_account_info,
+            fee_config: fee_config_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info
+        } = InitFeeConfigAccounts::next(account_info_iter)?;
+
+        if withdraw_percent_fee > BPS_DENOMINATOR ||
+           close_stream_percent_fee > BPS_DENOMINATOR ||
+           close_treasury_percent_fee > BPS_DENOMINATOR
+        {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        check_can_init_fee_config(
+            program_id, &initializer_account_info, &fee_config_account_info,
+            &msp_account_info, &system_account_info, &rent_account_info
+        )?;
+
+        // Create Fee Config PDA
+        let (_, bump_seed) = Pubkey::find_program_address(&[FEE_CONFIG_SEED], msp_account_info.key);
+        let fee_config_signer_seed: &[&[_]] = &[FEE_CONFIG_SEED, &bump_seed.to_le_bytes()];
+
+        create_pda_account(
+            &system_account_info, &rent_account_info, &msp_account_info,
+            &fee_config_account_info, &initializer_account_info,
+            FeeConfig::LEN, &[fee_config_signer_seed]
+        )?;
+
+        let mut fee_config = FeeConfig::unpack_from_slice(&fee_config_account_info.data.borrow())?;
+
+        fee_config.authority = authority;
+        fee_config.fee_treasury_address = fee_treasury_address;
+        fee_config.create_treasury_flat_fee = create_treasury_flat_fee;
+        fee_config.create_stream_flat_fee = create_stream_flat_fee;
+        fee_config.add_funds_flat_fee = add_funds_flat_fee;
+        fee_config.withdraw_percent_fee = withdraw_percent_fee;
+        fee_config.propose_update_flat_fee = propose_update_flat_fee;
+        fee_config.close_stream_flat_fee = close_stream_flat_fee;
+        fee_config.close_stream_percent_fee = close_stream_percent_fee;
+        fee_config.close_treasury_flat_fee = close_treasury_flat_fee;
+        fee_config.close_treasury_percent_fee = close_treasury_percent_fee;
+        fee_config.bump = bump_seed;
+        fee_config.initialized = true;
+        // Save
+        FeeConfig::pack_into_slice(&fee_config, &mut fee_config_account_info.data.borrow_mut());
+
+        Ok(())
+    }
+
+    fn process_update_fee_config(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        authority: Pubkey,
+        fee_treasury_address: Pubkey,
+        create_treasury_flat_fee: u64,
+        create_stream_flat_fee: u64,
+        add_funds_flat_fee: u64,
+        withdraw_percent_fee: u64,
+        propose_update_flat_fee: u64,
+        close_stream_flat_fee: u64,
+        close_stream_percent_fee: u64,
+        close_treasury_flat_fee: u64,
+        close_treasury_percent_fee: u64
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let UpdateFeeConfigAccounts {
+            authority: authority_account_info,
+            fee_config: fee_config_account_info,
+            msp: msp_account_info
+        } = UpdateFeeConfigAccounts::next(account_info_iter)?;
+
+        if withdraw_percent_fee > BPS_DENOMINATOR ||
+           close_stream_percent_fee > BPS_DENOMINATOR ||
+           close_treasury_percent_fee > BPS_DENOMINATOR
+        {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        check_can_update_fee_config(
+            program_id, &authority_account_info, &fee_config_account_info, &msp_account_info
+        )?;
+
+        let mut fee_config = FeeConfig::unpack_from_slice(&fee_config_account_info.data.borrow())?;
+
+        fee_config.authority = authority;
+        fee_config.fee_treasury_address = fee_treasury_address;
+        fee_config.create_treasury_flat_fee = create_treasury_flat_fee;
+        fee_config.create_stream_flat_fee = create_stream_flat_fee;
+        fee_config.add_funds_flat_fee = add_funds_flat_fee;
+        fee_config.withdraw_percent_fee = withdraw_percent_fee;
+        fee_config.propose_update_flat_fee = propose_update_flat_fee;
+        fee_config.close_stream_flat_fee = close_stream_flat_fee;
+        fee_config.close_stream_percent_fee = close_stream_percent_fee;
+        fee_config.close_treasury_flat_fee = close_treasury_flat_fee;
+        fee_config.close_treasury_percent_fee = close_treasury_percent_fee;
+        // Save
+        FeeConfig::pack_into_slice(&fee_config, &mut fee_config_account_info.data.borrow_mut());
+
+        Ok(())
+    }
+
+    fn process_set_fee_tier(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        subject_address: Pubkey,
+        flat_fee: u64,
+        withdraw_percent_fee: u64,
+        expiry_utc: u64
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let SetFeeTierAccounts {
+            authority: authority_account_info,
+            fee_config: fee_config_account_info,
+            fee_tier: fee_tier_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info
+        } = SetFeeTierAccounts::next(account_info_iter)?;
+
+        if withdraw_percent_fee > BPS_DENOMINATOR {
+            return Err(StreamError::InvalidArgument.into());
+        }
+
+        check_can_set_fee_tier(
+            program_id, &authority_account_info, &fee_config_account_info, &fee_tier_account_info,
+            &msp_account_info, &system_account_info, &rent_account_info, &subject_address
+        )?;
+
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[FEE_TIER_SEED, subject_address.as_ref()],
+            msp_account_info.key
+        );
+
+        if fee_tier_account_info.data_len() == 0 { // Create Fee Tier PDA
+            let fee_tier_signer_seed: &[&[_]] = &[
+                FEE_TIER_SEED,
+                subject_address.as_ref(),
+                &bump_seed.to_le_bytes()
+            ];
+
+            create_pda_account(
+                &system_account_info, &rent_account_info, &msp_account_info,
+                &fee_tier_account_info, &authority_account_info,
+                FeeTier::LEN, &[fee_tier_signer_seed]
+            )?;
+        }
+
+        let mut fee_tier = FeeTier::unpack_from_slice(&fee_tier_account_info.data.borrow())?;
+
+        fee_tier.subject_address = subject_address;
+        fee_tier.flat_fee = flat_fee;
+        fee_tier.withdraw_percent_fee = withdraw_percent_fee;
+        fee_tier.expiry_utc = expiry_utc;
+        fee_tier.bump = bump_seed;
+        fee_tier.initialized = true;
+        // Save
+        FeeTier::pack_into_slice(&fee_tier, &mut fee_tier_account_info.data.borrow_mut());
+
+        Ok(())
+    }
+
+    fn process_close_fee_tier(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let CloseFeeTierAccounts {
+            authority: authority_account_info,
+            fee_config: fee_config_account_info,
+            fee_tier: fee_tier_account_info,
+            msp: msp_account_info
+        } = CloseFeeTierAccounts::next(account_info_iter)?;
+
+        check_can_close_fee_tier(
+            program_id, &authority_account_info, &fee_config_account_info,
+            &fee_tier_account_info, &msp_account_info
+        )?;
+
+        // Close fee tier account
+        let authority_lamports = authority_account_info.lamports();
+        let fee_tier_lamports = fee_tier_account_info.lamports();
+
+        **fee_tier_account_info.lamports.borrow_mut() = 0;
+        **authority_account_info.lamports.borrow_mut() = authority_lamports
+            .checked_add(fee_tier_lamports)
+            .ok_or(StreamError::Overflow)?;
+
+        Ok(())
+    }
+
+    fn process_create_streams_batch(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        streams: Vec<BatchStreamTerms>
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let CreateStreamsBatchAccounts {
+            treasurer: treasurer_account_info,
+            treasury: treasury_account_info,
+            associated_token_mint: associated_token_mint_info,
+            fee_treasury: fee_treasury_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info,
+            fee_config: fee_config_account_info,
+            fee_tier: fee_tier_account_info,
+            treasury_roles: treasury_roles_account_info
+        } = CreateStreamsBatchAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+        // The signer may be a stream creator of the treasury instead of the treasurer
+        let treasurer_address = get_treasurer_address(&treasury_account_info)?;
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, Some(fee_tier_account_info),
+            &treasurer_address, treasury_account_info.key, &clock
+        )?;
+
+        // Verify the correct MSP Operations Account 
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        // The stream and beneficiary accounts of every stream
+        let stream_account_infos: Vec<&AccountInfo> = account_info_iter.collect();
+
+        check_can_create_streams_batch(
+            program_id, &treasurer_account_info, &treasury_account_info, &associated_token_mint_info,
+            &msp_account_info, &system_account_info, &rent_account_info, &treasury_roles_account_info,
+            &streams, &stream_account_infos
+        )?;
+
+        let now = clock.unix_timestamp as u64 * 1000u64;
+        let mut created_streams = Vec::with_capacity(streams.len());
+
+        for (terms, account_infos) in streams.into_iter().zip(stream_account_infos.chunks(2)) {
+            let (stream_account_info, beneficiary_account_info) = (account_infos[0], account_infos[1]);
+            // The end date (if any) must be after the start of the stream
+            if terms.end_utc > 0 && terms.end_utc <= cmp::max(terms.start_utc, now) {
+                return Err(StreamError::InvalidArgument.into());
+            }
+
+            create_stream_account(
+                &treasurer_account_info, &stream_account_info, &msp_account_info,
+                &rent_account_info, &system_account_info, StreamV2::LEN
+            )?;
+
+            let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            stream.stream_name = terms.stream_name;
+            stream.treasurer_address = treasurer_address;
+            stream.rate_amount = terms.rate_amount;
+            stream.rate_interval_in_seconds = terms.rate_interval_in_seconds;
+            stream.allocation_reserved = terms.allocation_reserved;
+            stream.allocation_assigned = terms.allocation_assigned;
+            stream.allocation_left = terms.allocation_assigned;
+            stream.funded_on_utc = now;
+            stream.start_utc = terms.start_utc;
+            stream.rate_cliff_in_seconds = terms.rate_cliff_in_seconds;
+            stream.beneficiary_address = *beneficiary_account_info.key;
+            stream.beneficiary_associated_token = *associated_token_mint_info.key;
+            stream.treasury_address = *treasury_account_info.key;
+            stream.escrow_vested_amount_snap_slot = clock.slot as u64;
+            stream.escrow_vested_amount_snap_block_time = clock.unix_timestamp as u64;
+            stream.stream_resumed_slot = clock.slot;
+            stream.stream_resumed_block_time = clock.unix_timestamp as u64;
+            stream.auto_pause_in_seconds = terms.auto_pause_in_seconds;
+            stream.end_utc = terms.end_utc;
+            stream.vesting_mode = terms.vesting_mode;
+
+            if get_stream_status_v2(&stream, &clock)? == StreamStatus::Scheduled {
+                stream.stream_resumed_block_time = terms.start_utc / 1000u64;
+            }
+
+            // Same cliff as a single stream, included in the vested amount from the start
+            let mut cliff_amount = terms.cliff_vest_amount;
+
+            if terms.cliff_vest_percent > 0 {
+                cliff_amount = get_percent_fee(terms.allocation_assigned, terms.cliff_vest_percent)?;
+            }
+
+            if cliff_amount > terms.allocation_assigned {
+                return Err(StreamError::InvalidArgument.into());
+            }
+
+            stream.cliff_vest_percent = terms.cliff_vest_percent;
+            stream.cliff_vest_amount = cliff_amount;
+            stream.escrow_vested_amount_snap = cliff_amount;
+
+            created_streams.push(stream);
+        }
+
+        // One treasury update for the whole batch
+        create_streams_batch_update_treasury_v2(&treasury_account_info, &created_streams)?;
+        update_estimated_depletion_v2(&treasury_account_info, None, &clock)?;
+
+        let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+
+        for (mut stream, account_infos) in created_streams.into_iter().zip(stream_account_infos.chunks(2)) {
+            // Save stream
+            stream.treasury_estimated_depletion_utc = treasury.estimated_depletion_utc;
+            stream.initialized = true;
+            StreamV2::pack_into_slice(&stream, &mut account_infos[0].data.borrow_mut());
+
+            MspEvent::StreamCreated {
+                stream: *account_infos[0].key,
+                treasury: *treasury_account_info.key,
+                treasurer: treasurer_address,
+                beneficiary: stream.beneficiary_address,
+                mint: *associated_token_mint_info.key,
+                rate_amount: stream.rate_amount,
+                rate_interval_in_seconds: stream.rate_interval_in_seconds,
+                allocation_assigned: stream.allocation_assigned,
+                start_utc: stream.start_utc / 1000u64
+            }.emit();
+        }
+
+        // Pay the fee of every stream at once
+        let streams_amount = (stream_account_infos.len() / 2) as u64;
+
+        transfer_sol_fee(
+            &system_account_info,
+            &treasurer_account_info,
+            &fee_treasury_account_info,
+            fee_config.create_stream_flat_fee
+                .checked_mul(streams_amount)
+                .ok_or(StreamError::Overflow)?
+        )
+    }
+
+    fn process_grant_treasury_roles(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        member: Pubkey,
+        roles: u8
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let GrantTreasuryRolesAccounts {
+            authority: authority_account_info,
+            treasury: treasury_account_info,
+            treasury_roles: treasury_roles_account_info,
+            msp: msp_account_info,
+            system_program: system_account_info,
+            rent: rent_account_info
+        } = GrantTreasuryRolesAccounts::next(account_info_iter)?;
+
+        check_can_grant_treasury_roles(
+            program_id, &authority_account_info, &treasury_account_info, &treasury_roles_account_info,
+            &msp_account_info, &system_account_info, &rent_account_info, roles
+        )?;
+
+        let (_, bump_seed) = Pubkey::find_program_address(
+            &[TREASURY_ROLES_SEED, treasury_account_info.key.as_ref()],
+            msp_account_info.key
+        );
+
+        if treasury_roles_account_info.data_len() == 0 { // Create Treasury Roles PDA
+            let treasury_roles_signer_seed: &[&[_]] = &[
+                TREASURY_ROLES_SEED,
+                treasury_account_info.key.as_ref(),
+                &bump_seed.to_le_bytes()
+            ];
+
+            create_pda_account(
+                &system_account_info, &rent_account_info, &msp_account_info,
+                &treasury_roles_account_info, &authority_account_info,
+                TreasuryRoles::LEN, &[treasury_roles_signer_seed]
+            )?;
+        }
+
+        let mut treasury_roles = TreasuryRoles::unpack_from_slice(&treasury_roles_account_info.data.borrow())?;
+
+        treasury_roles.grant(&member, roles)?;
+        treasury_roles.treasury_address = *treasury_account_info.key;
+        treasury_roles.bump = bump_seed;
+        treasury_roles.initialized = true;
+        // Save
+        TreasuryRoles::pack_into_slice(&treasury_roles, &mut treasury_roles_account_info.data.borrow_mut());
+
+        Ok(())
+    }
+
+    fn process_revoke_treasury_roles(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        member: Pubkey,
+        roles: u8
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let RevokeTreasuryRolesAccounts {
+            authority: authority_account_info,
+            treasury: treasury_account_info,
+            treasury_roles: treasury_roles_account_info,
+            msp: msp_account_info
+        } = RevokeTreasuryRolesAccounts::next(account_info_iter)?;
+
+        check_can_revoke_treasury_roles(
+            program_id, &authority_account_info, &treasury_account_info,
+            &treasury_roles_account_info, &msp_account_info, roles
+        )?;
+
+        let mut treasury_roles = TreasuryRoles::unpack_from_slice(&treasury_roles_account_info.data.borrow())?;
+
+        treasury_roles.revoke(&member, roles);
+        // Save
+        TreasuryRoles::pack_into_slice(&treasury_roles, &mut treasury_roles_account_info.data.borrow_mut());
+
+        Ok(())
+    }
+
+    fn process_set_push_vested(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey,
+        enabled: bool,
+        keeper_tip: u64
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let SetPushVestedAccounts {
+            authority: authority_account_info,
+            treasury: treasury_account_info,
+            stream: stream_account_info,
+            msp: msp_account_info
+        } = SetPushVestedAccounts::next(account_info_iter)?;
+
+        check_can_set_push_vested(
+            program_id, &authority_account_info, &treasury_account_info,
+            &stream_account_info, &msp_account_info, enabled
+        )?;
+
+        let keeper_tip = if enabled { keeper_tip } else { 0 };
+
+        if AccountKind::detect(&stream_account_info) == AccountKind::StreamV2 {
+            let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+            stream.push_vested_enabled = enabled;
+            stream.push_vested_tip = keeper_tip;
+            StreamV2::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        } else {
+            let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+            stream.push_vested_enabled = enabled;
+            stream.push_vested_tip = keeper_tip;
+            StreamV1::pack_into_slice(&stream, &mut stream_account_info.data.borrow_mut());
+        }
+
+        Ok(())
+    }
+
+    fn process_push_vested(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let PushVestedAccounts {
+            keeper: keeper_account_info,
+            keeper_token: keeper_token_account_info,
+            beneficiary: beneficiary_account_info,
+            beneficiary_token: beneficiary_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            rent: rent_account_info,
+            system_program: system_account_info,
+            fee_config: fee_config_account_info
+        } = PushVestedAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        if AccountKind::detect(&stream_account_info) == AccountKind::StreamV2 {
+            return Self::process_push_vested_v2(accounts, program_id);
+        }
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &clock
+        )?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        check_can_push_vested(
+            program_id, &keeper_account_info, &keeper_token_account_info, &beneficiary_account_info,
+            &beneficiary_token_account_info, &associated_token_mint_info, &treasury_account_info,
+            &treasury_token_account_info, &stream_account_info, &fee_treasury_token_account_info,
+            &fee_config.fee_treasury_address, &msp_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &rent_account_info, &system_account_info
+        )?;
+
+        auto_pause_update_stream(
+            &stream_account_info, &treasury_account_info, &associated_token_mint_info, &clock
+        )?;
+
+        let mut stream = StreamV1::unpack_from_slice(&stream_account_info.data.borrow())?;
+        let associated_token_mint = spl_token::state::Mint::unpack_from_slice(&associated_token_mint_info.data.borrow())?;
+        let escrow_vested_amount = get_beneficiary_withdrawable_amount(
+            &stream, &clock, associated_token_mint.decimals.into()
+        )?;
+        let scale = TokenScale::new(associated_token_mint.decimals.into());
+        let amount = cmp::min(escrow_vested_amount, scale.to_base_units(stream.allocation_left));
+
+        if amount == 0 {
+            return Err(StreamError::NotAllowedWithdrawalAmount.into());
+        }
+
+        if beneficiary_token_account_info.data_len() == 0 { // The keeper pays the beneficiary associated token account
+            create_ata_account(
+                &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                &token_program_account_info, &keeper_account_info, &beneficiary_account_info,
+                &beneficiary_token_account_info, &associated_token_mint_info
+            )?;
+        }
+        // The beneficiary does not sign, the fee is taken before the funds leave the treasury
+        let fee = get_percent_fee(amount, fee_config.withdraw_percent_fee)?;
+
+        claim_treasury_funds(
+            &msp_account_info, &token_program_account_info, &treasury_account_info,
+            &treasury_token_account_info, &beneficiary_token_account_info,
+            amount.checked_sub(fee).ok_or(StreamError::Overflow)?
+        )?;
+
+        if fee > 0 {
+            if fee_treasury_token_account_info.data_len() == 0 { // Create fee treasury associated token account if doesn't exist
+                create_ata_account(
+                    &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                    &token_program_account_info, &keeper_account_info, &fee_treasury_account_info,
+                    &fee_treasury_token_account_info, &associated_token_mint_info
+                )?;
+            }
+
+            claim_treasury_funds(
+                &msp_account_info, &token_program_account_info, &treasury_account_info,
+                &treasury_token_account_info, &fee_treasury_token_account_info, fee
+            )?;
+        }
+        // Update stream data
+        withdraw_funds_update_stream(
+            &mut stream, &stream_account_info, &associated_token_mint_info,
+            &clock, escrow_vested_amount, amount
+        )?;
+        // Update treasury account data
+        withdraw_funds_update_treasury(
+            &treasury_account_info, &associated_token_mint_info, amount
+        )?;
+
+        let keeper_tip = push_vested_tip_update_treasury(
+            &treasury_account_info, &associated_token_mint_info, &mut stream, &stream_account_info, amount, &clock
+        )?;
+
+        if keeper_tip > 0 {
+            claim_treasury_funds(
+                &msp_account_info, &token_program_account_info, &treasury_account_info,
+                &treasury_token_account_info, &keeper_token_account_info, keeper_tip
+            )?;
+        }
+        update_estimated_depletion(&treasury_account_info, &associated_token_mint_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::VestedPushed {
+            stream: *stream_account_info.key,
+            treasury: *treasury_account_info.key,
+            beneficiary: *beneficiary_account_info.key,
+            keeper: *keeper_account_info.key,
+            mint: *associated_token_mint_info.key,
+            amount,
+            fee,
+            keeper_tip
+        }.emit();
+
+        Ok(())
+    }
+
+    fn process_push_vested_v2(
+        accounts: &[AccountInfo],
+        program_id: &Pubkey
+
+    ) -> ProgramResult {
+
+        let account_info_iter = &mut accounts.iter();
+        let PushVestedAccounts {
+            keeper: keeper_account_info,
+            keeper_token: keeper_token_account_info,
+            beneficiary: beneficiary_account_info,
+            beneficiary_token: beneficiary_token_account_info,
+            associated_token_mint: associated_token_mint_info,
+            treasury: treasury_account_info,
+            treasury_token: treasury_token_account_info,
+            stream: stream_account_info,
+            fee_treasury: fee_treasury_account_info,
+            fee_treasury_token: fee_treasury_token_account_info,
+            msp: msp_account_info,
+            associated_token_program: associated_token_program_account_info,
+            token_program: token_program_account_info,
+            rent: rent_account_info,
+            system_program: system_account_info,
+            fee_config: fee_config_account_info
+        } = PushVestedAccounts::next(account_info_iter)?;
+        let clock = Clock::get()?;
+
+        let fee_config = apply_fee_tier(
+            program_id, get_fee_config(program_id, &fee_config_account_info)?, account_info_iter.next(),
+            &get_treasurer_address(&treasury_account_info)?, treasury_account_info.key, &clock
+        )?;
+
+        if fee_treasury_account_info.key.ne(&fee_config.fee_treasury_address) {
+            return Err(StreamError::InstructionNotAuthorized.into());
+        }
+
+        check_can_push_vested_v2(
+            program_id, &keeper_account_info, &keeper_token_account_info, &beneficiary_account_info,
+            &beneficiary_token_account_info, &associated_token_mint_info, &treasury_account_info,
+            &treasury_token_account_info, &stream_account_info, &fee_treasury_token_account_info,
+            &fee_config.fee_treasury_address, &msp_account_info, &associated_token_program_account_info,
+            &token_program_account_info, &rent_account_info, &system_account_info
+        )?;
+
+        auto_pause_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+        end_update_stream_v2(&stream_account_info, &treasury_account_info, &clock)?;
+
+        let mut stream = StreamV2::unpack_from_slice(&stream_account_info.data.borrow())?;
+        let escrow_vested_amount = get_stream_vested_amount_v2(&stream, &clock)?;
+        let amount = get_beneficiary_withdrawable_amount_v2(&stream, &clock)?;
+
+        if amount == 0 {
+            return Err(StreamError::NotAllowedWithdrawalAmount.into());
+        }
+
+        if beneficiary_token_account_info.data_len() == 0 { // The keeper pays the beneficiary associated token account
+            create_ata_account(
+                &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                &token_program_account_info, &keeper_account_info, &beneficiary_account_info,
+                &beneficiary_token_account_info, &associated_token_mint_info
+            )?;
+        }
+        // The beneficiary does not sign, the fee is taken before the funds leave the treasury
+        let fee = get_percent_fee(amount, fee_config.withdraw_percent_fee)?;
+
+        claim_treasury_funds_v2(
+            &msp_account_info, &token_program_account_info, &treasury_account_info,
+            &treasury_token_account_info, &beneficiary_token_account_info,
+            amount.checked_sub(fee).ok_or(StreamError::Overflow)?
+        )?;
+
+        if fee > 0 {
+            if fee_treasury_token_account_info.data_len() == 0 { // Create fee treasury associated token account if doesn't exist
+                create_ata_account(
+                    &system_account_info, &rent_account_info, &associated_token_program_account_info,
+                    &token_program_account_info, &keeper_account_info, &fee_treasury_account_info,
+                    &fee_treasury_token_account_info, &associated_token_mint_info
+                )?;
+            }
+
+            claim_treasury_funds_v2(
+                &msp_account_info, &token_program_account_info, &treasury_account_info,
+                &treasury_token_account_info, &fee_treasury_token_account_info, fee
+            )?;
+        }
+        // Update stream data
+        let reserved_amount = cmp::min(stream.allocation_reserved, amount);
+        withdraw_funds_update_stream_v2(
+            &mut stream, &stream_account_info, &clock, escrow_vested_amount, amount
+        )?;
+        // Update treasury account data
+        withdraw_funds_update_treasury_v2(
+            &treasury_account_info, amount, reserved_amount
+        )?;
+
+        let keeper_tip = push_vested_tip_update_treasury_v2(
+            &treasury_account_info, &mut stream, &stream_account_info, amount, &clock
+        )?;
+
+        if keeper_tip > 0 {
+            claim_treasury_funds_v2(
+                &msp_account_info, &token_program_account_info, &treasury_account_info,
+                &treasury_token_account_info, &keeper_token_account_info, keeper_tip
+            )?;
+        }
+        update_estimated_depletion_v2(&treasury_account_info, Some(stream_account_info), &clock)?;
+
+        MspEvent::VestedPushed {
+            stream: *stream_account_info.key,
+            treasury: *treasury_account_info.key,
+            beneficiary: *beneficiary_account_info.key,
+            keeper: *keeper_account_info.key,
+            mint: *associated_token_mint_info.key,
+            amount,
+            fee,
+            keeper_tip
+        }.emit();
+
         Ok(())
     }
 }
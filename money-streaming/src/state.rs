@@ -4,8 +4,10 @@ use std::{ string::String };
 
 use solana_program::{
     pubkey::Pubkey,
+    account_info::AccountInfo,
     program_error::ProgramError,    
-    program_pack::{ IsInitialized, Pack, Sealed }
+    program_pack::{ IsInitialized, Pack, Sealed },
+    sysvar::clock::Clock
 };
 
 use arrayref::{
@@ -16,13 +18,85 @@ use arrayref::{
 };
 
 use crate::error::{ StreamError, TreasuryError };
+use crate::constants::*;
 
 #[derive(PartialEq)]
 pub enum StreamStatus 
 {
     Scheduled = 0,
     Running = 1,
-    Paused = 2
+    Paused = 2,
+    Ended = 3
+}
+
+#[derive(PartialEq)]
+pub enum VestingMode
+{
+    Continuous = 0,
+    Stepped = 1
+}
+
+/// Account kinds
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountKind
+{
+    Uninitialized,
+    Stream,
+    StreamV1,
+    StreamV2,
+    Treasury,
+    TreasuryV1,
+    TreasuryV2,
+    StreamTerms,
+    FeeConfig,
+    FeeTier,
+    TreasuryRoles,
+    Unknown
+}
+
+impl AccountKind {
+
+    /// Detects the kind of an MSP account. Accounts with a header are detected by their
+    /// discriminator and version (and can grow past their layout), legacy accounts (without
+    /// header) by their length
+    pub fn detect(account_info: &AccountInfo) -> Self {
+
+        let data = account_info.data.borrow();
+
+        if data.len() == 0 {
+            return AccountKind::Uninitialized;
+        }
+
+        let header_kind = match (data[0], data.get(1)) {
+            (STREAM_ACCOUNT_DISCRIMINATOR, Some(&STREAM_ACCOUNT_VERSION)) => Some((AccountKind::StreamV2, StreamV2::LEN)),
+            (TREASURY_ACCOUNT_DISCRIMINATOR, Some(&TREASURY_ACCOUNT_VERSION)) => Some((AccountKind::TreasuryV2, TreasuryV2::LEN)),
+            (STREAM_TERMS_ACCOUNT_DISCRIMINATOR, Some(&STREAM_TERMS_ACCOUNT_VERSION)) => Some((AccountKind::StreamTerms, StreamTerms::LEN)),
+            (FEE_CONFIG_ACCOUNT_DISCRIMINATOR, Some(&FEE_CONFIG_ACCOUNT_VERSION)) => Some((AccountKind::FeeConfig, FeeConfig::LEN)),
+            (FEE_TIER_ACCOUNT_DISCRIMINATOR, Some(&FEE_TIER_ACCOUNT_VERSION)) => Some((AccountKind::FeeTier, FeeTier::LEN)),
+            (TREASURY_ROLES_ACCOUNT_DISCRIMINATOR, Some(&TREASURY_ROLES_ACCOUNT_VERSION)) => Some((AccountKind::TreasuryRoles, TreasuryRoles::LEN)),
+            (0, _) | (1, _) => None,
+            _ => return AccountKind::Unknown
+        };
+
+        if let Some((kind, len)) = header_kind {
+            // Too short to hold the layout its header claims
+            return match data.len() >= len {
+                true => kind,
+                _ => AccountKind::Unknown
+            };
+        }
+
+        match data.len() {
+            Stream::LEN => AccountKind::Stream,
+            StreamV1::LEN => AccountKind::StreamV1,
+            Treasury::LEN => AccountKind::Treasury,
+            TreasuryV1::LEN => AccountKind::TreasuryV1,
+            // Accounts with header that are not initialized yet
+            _ if data.iter().all(|b| *b == 0) => AccountKind::Uninitialized,
+            _ => AccountKind::Unknown
+        }
+    }
 }
 
 /// Stream
@@ -258,7 +332,10 @@ pub struct StreamV1 {
     pub stream_resumed_slot: u64,
     pub stream_resumed_block_time: u64,
     pub auto_pause_in_seconds: u64,
-    pub allocation_assigned: f64
+    pub allocation_assigned: f64,
+    pub push_vested_enabled: bool, // anyone can push the vested funds to the beneficiary
+    pub push_vested_tip: u64, // keeper tip in token base units
+    pub push_vested_tip_block_time: u64 // when the last keeper tip was paid
 }
 
 impl Sealed for StreamV1 {}
@@ -294,7 +371,10 @@ impl Default for StreamV1 {
             stream_resumed_slot: 0,
             stream_resumed_block_time: 0,
             auto_pause_in_seconds: 0,
-            allocation_assigned: 0.0
+            allocation_assigned: 0.0,
+            push_vested_enabled: false,
+            push_vested_tip: 0,
+            push_vested_tip_block_time: 0
         }
     }
 }
@@ -329,9 +409,12 @@ impl Pack for StreamV1 {
             stream_resumed_block_time_output,
             auto_pause_in_seconds_output,
             allocation_assigned_output,
+            push_vested_enabled_output,
+            push_vested_tip_output,
+            push_vested_tip_block_time_output,
             _additional_data
             
-        ) = mut_array_refs![output, 1, 32, 32, 8, 8, 8, 8, 8, 8, 8, 32, 32, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 203];
+        ) = mut_array_refs![output, 1, 32, 32, 8, 8, 8, 8, 8, 8, 8, 32, 32, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 8, 8, 186];
 
         let StreamV1 {
             initialized,
@@ -356,7 +439,10 @@ impl Pack for StreamV1 {
             stream_resumed_slot,
             stream_resumed_block_time,
             auto_pause_in_seconds,
-            allocation_assigned
+            allocation_assigned,
+            push_vested_enabled,
+            push_vested_tip,
+            push_vested_tip_block_time
 
         } = self;
 
@@ -383,6 +469,9 @@ impl Pack for StreamV1 {
         *stream_resumed_block_time_output = stream_resumed_block_time.to_le_bytes();
         *auto_pause_in_seconds_output = auto_pause_in_seconds.to_le_bytes();
         *allocation_assigned_output = allocation_assigned.to_le_bytes();
+        push_vested_enabled_output[0] = *push_vested_enabled as u8;
+        *push_vested_tip_output = push_vested_tip.to_le_bytes();
+        *push_vested_tip_block_time_output = push_vested_tip_block_time.to_le_bytes();
     }
     
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
@@ -412,9 +501,12 @@ impl Pack for StreamV1 {
             stream_resumed_block_time,
             auto_pause_in_seconds,
             allocation_assigned,
+            push_vested_enabled,
+            push_vested_tip,
+            push_vested_tip_block_time,
             _additional_data
             
-        ) = array_refs![input, 1, 32, 32, 8, 8, 8, 8, 8, 8, 8, 32, 32, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 203];
+        ) = array_refs![input, 1, 32, 32, 8, 8, 8, 8, 8, 8, 8, 32, 32, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 8, 8, 186];
 
         let initialized = match initialized {
             [0] => false,
@@ -446,6 +538,9 @@ impl Pack for StreamV1 {
             stream_resumed_block_time: u64::from_le_bytes(*stream_resumed_block_time),
             auto_pause_in_seconds: u64::from_le_bytes(*auto_pause_in_seconds),
             allocation_assigned: f64::from_le_bytes(*allocation_assigned),
+            push_vested_enabled: push_vested_enabled[0] == 1,
+            push_vested_tip: u64::from_le_bytes(*push_vested_tip),
+            push_vested_tip_block_time: u64::from_le_bytes(*push_vested_tip_block_time)
         })
     }
 }
@@ -549,7 +644,10 @@ pub struct TreasuryV1 {
     pub depletion_rate: f64,
     pub treasury_type: u8,
     pub auto_close: bool,
-    pub allocation_assigned: f64
+    pub allocation_assigned: f64,
+    pub seed_address: Pubkey, // The original treasurer, used as the first seed of the treasury PDA
+    pub bump: u8,
+    pub estimated_depletion_utc: u64 // 0 while the treasury is not depleting
 }
 
 impl Sealed for TreasuryV1 {}
@@ -577,7 +675,10 @@ impl Default for TreasuryV1 {
             depletion_rate: 0.0,
             treasury_type: 0,
             auto_close: false,
-            allocation_assigned: 0.0
+            allocation_assigned: 0.0,
+            seed_address: Pubkey::default(),
+            bump: 0,
+            estimated_depletion_utc: 0
         }
     }
 }
@@ -604,9 +705,12 @@ impl Pack for TreasuryV1 {
             treasury_type_output,
             auto_close_output,
             allocation_assigned_output,
+            seed_address_output,
+            bump_output,
+            estimated_depletion_utc_output,
             _additional_data
             
-        ) = mut_array_refs![output, 1, 8, 32, 32, 32, 32, 8, 8, 8, 8, 8, 8, 1, 1, 8, 105];
+        ) = mut_array_refs![output, 1, 8, 32, 32, 32, 32, 8, 8, 8, 8, 8, 8, 1, 1, 8, 32, 1, 8, 64];
 
         let TreasuryV1 {
             initialized,
@@ -623,7 +727,10 @@ impl Pack for TreasuryV1 {
             depletion_rate,
             treasury_type,
             auto_close,
-            allocation_assigned
+            allocation_assigned,
+            seed_address,
+            bump,
+            estimated_depletion_utc
 
         } = self;
 
@@ -642,6 +749,9 @@ impl Pack for TreasuryV1 {
         *treasury_type_output = treasury_type.to_le_bytes();
         auto_close_output[0] = *auto_close as u8;
         *allocation_assigned_output = allocation_assigned.to_le_bytes();
+        seed_address_output.copy_from_slice(seed_address.as_ref());
+        bump_output[0] = *bump;
+        *estimated_depletion_utc_output = estimated_depletion_utc.to_le_bytes();
     }
     
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
@@ -663,9 +773,12 @@ impl Pack for TreasuryV1 {
             treasury_type,
             auto_close,
             allocation_assigned,
+            seed_address,
+            bump,
+            estimated_depletion_utc,
             _additional_data
 
-        ) = array_refs![input, 1, 8, 32, 32, 32, 32, 8, 8, 8, 8, 8, 8, 1, 1, 8, 105];
+        ) = array_refs![input, 1, 8, 32, 32, 32, 32, 8, 8, 8, 8, 8, 8, 1, 1, 8, 32, 1, 8, 64];
 
         let initialized = match initialized {
             [0] => false,
@@ -673,6 +786,12 @@ impl Pack for TreasuryV1 {
             _ => return Err(TreasuryError::InvalidTreasuryData.into()),
         };
 
+        // Treasuries created before the seed address was stored were never transferred
+        let seed_address = match Pubkey::new_from_array(*seed_address) {
+            address if address.eq(&Pubkey::default()) => Pubkey::new_from_array(*treasurer_address),
+            address => address
+        };
+
         let auto_close = match auto_close {
             [0] => false,
             [1] => true,
@@ -694,7 +813,1125 @@ impl Pack for TreasuryV1 {
             depletion_rate: f64::from_le_bytes(*depletion_rate),
             treasury_type: u8::from_le_bytes(*treasury_type),
             auto_close,
-            allocation_assigned: f64::from_le_bytes(*allocation_assigned)
+            allocation_assigned: f64::from_le_bytes(*allocation_assigned),
+            seed_address,
+            bump: bump[0],
+            estimated_depletion_utc: u64::from_le_bytes(*estimated_depletion_utc)
+        })
+    }
+}
+
+impl TreasuryV1 {
+
+    /// Derives the treasury PDA from its original seeds, using the stored bump when available
+    pub fn find_address(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+
+        if self.bump != 0 {
+            if let Ok(address) = Pubkey::create_program_address(
+                &[self.seed_address.as_ref(), &self.slot.to_le_bytes(), &[self.bump]],
+                program_id
+            ) {
+                return (address, self.bump);
+            }
+        }
+
+        Pubkey::find_program_address(
+            &[self.seed_address.as_ref(), &self.slot.to_le_bytes()],
+            program_id
+        )
+    }
+}
+
+/// StreamV2
+
+#[derive(Clone, Debug)]
+pub struct StreamV2 {
+    pub initialized: bool,
+    pub stream_name: String,
+    pub treasurer_address: Pubkey,
+    pub rate_amount: u64,
+    pub rate_interval_in_seconds: u64,
+    pub funded_on_utc: u64,
+    pub start_utc: u64,
+    pub rate_cliff_in_seconds: u64,
+    pub cliff_vest_amount: u64,
+    pub cliff_vest_percent: u64,
+    pub beneficiary_address: Pubkey,
+    pub beneficiary_associated_token: Pubkey,
+    pub treasury_address: Pubkey,
+    pub treasury_estimated_depletion_utc: u64,
+    pub allocation_reserved: u64,
+    pub allocation_left: u64,
+    pub escrow_vested_amount_snap: u64,
+    pub escrow_vested_amount_snap_slot: u64,
+    pub escrow_vested_amount_snap_block_time: u64,
+    pub stream_resumed_slot: u64,
+    pub stream_resumed_block_time: u64,
+    pub auto_pause_in_seconds: u64,
+    pub allocation_assigned: u64,
+    pub end_utc: u64,
+    pub vesting_mode: u8,
+    pub push_vested_enabled: bool, // anyone can push the vested funds to the beneficiary
+    pub push_vested_tip: u64, // keeper tip
+    pub unlocked_amount_snap: u64, // stepped vesting, unlocked at the last rate change or pause
+    pub push_vested_tip_block_time: u64 // when the last keeper tip was paid
+}
+
+impl Sealed for StreamV2 {}
+
+impl IsInitialized for StreamV2 {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for StreamV2 {
+    fn default() -> Self {
+        Self {
+            initialized: false,
+            stream_name: String::default(),
+            treasurer_address: Pubkey::default(),
+            rate_amount: 0,
+            rate_interval_in_seconds: 0,
+            funded_on_utc: 0,
+            start_utc: 0,
+            rate_cliff_in_seconds: 0,
+            cliff_vest_amount: 0,
+            cliff_vest_percent: 0,
+            beneficiary_address: Pubkey::default(),
+            beneficiary_associated_token: Pubkey::default(),
+            treasury_address: Pubkey::default(),
+            treasury_estimated_depletion_utc: 0,
+            allocation_reserved: 0,
+            allocation_left: 0,
+            escrow_vested_amount_snap: 0,
+            escrow_vested_amount_snap_slot: 0,
+            escrow_vested_amount_snap_block_time: 0,
+            stream_resumed_slot: 0,
+            stream_resumed_block_time: 0,
+            auto_pause_in_seconds: 0,
+            allocation_assigned: 0,
+            end_utc: 0,
+            vesting_mode: VestingMode::Continuous as u8,
+            push_vested_enabled: false,
+            push_vested_tip: 0,
+            unlocked_amount_snap: 0,
+            push_vested_tip_block_time: 0
+        }
+    }
+}
+
+impl Pack for StreamV2 {
+    const LEN: usize = 600;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+
+        let output = array_mut_ref![output, 0, StreamV2::LEN];
+        let (
+            discriminator_output,
+            version_output,
+            initialized_output,
+            stream_name_output,
+            treasurer_address_output,
+            rate_amount_output,
+            rate_interval_in_seconds_output,
+            funded_on_utc_output,
+            start_utc_output,
+            rate_cliff_in_seconds_output,
+            cliff_vest_amount_output,
+            cliff_vest_percent_output,
+            beneficiary_address_output,
+            beneficiary_associated_token_output,
+            treasury_address_output,
+            treasury_estimated_depletion_utc_output,
+            allocation_reserved_output,
+            allocation_left_output,
+            escrow_vested_amount_snap_output,
+            escrow_vested_amount_snap_slot_output,
+            escrow_vested_amount_snap_block_time_output,
+            stream_resumed_slot_output,
+            stream_resumed_block_time_output,
+            auto_pause_in_seconds_output,
+            allocation_assigned_output,
+            end_utc_output,
+            vesting_mode_output,
+            push_vested_enabled_output,
+            push_vested_tip_output,
+            unlocked_amount_snap_output,
+            push_vested_tip_block_time_output,
+            _additional_data
+
+        ) = mut_array_refs![output, 1, 1, 1, 32, 32, 8, 8, 8, 8, 8, 8, 8, 32, 32, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 8, 8, 8, 267];
+
+        let StreamV2 {
+            initialized,
+            stream_name,
+            treasurer_address,
+            rate_amount,
+            rate_interval_in_seconds,
+            funded_on_utc,
+            start_utc,
+            rate_cliff_in_seconds,
+            cliff_vest_amount,
+            cliff_vest_percent,
+            beneficiary_address,
+            beneficiary_associated_token,
+            treasury_address,
+            treasury_estimated_depletion_utc,
+            allocation_reserved,
+            allocation_left,
+            escrow_vested_amount_snap,
+            escrow_vested_amount_snap_slot,
+            escrow_vested_amount_snap_block_time,
+            stream_resumed_slot,
+            stream_resumed_block_time,
+            auto_pause_in_seconds,
+            allocation_assigned,
+            end_utc,
+            vesting_mode,
+            push_vested_enabled,
+            push_vested_tip,
+            unlocked_amount_snap,
+            push_vested_tip_block_time
+
+        } = self;
+
+        discriminator_output[0] = STREAM_ACCOUNT_DISCRIMINATOR;
+        version_output[0] = STREAM_ACCOUNT_VERSION;
+        initialized_output[0] = *initialized as u8;
+        stream_name_output.copy_from_slice(stream_name.as_ref());
+        treasurer_address_output.copy_from_slice(treasurer_address.as_ref());
+        *rate_amount_output = rate_amount.to_le_bytes();
+        *rate_interval_in_seconds_output = rate_interval_in_seconds.to_le_bytes();
+        *funded_on_utc_output = funded_on_utc.to_le_bytes();
+        *start_utc_output = start_utc.to_le_bytes();
+        *rate_cliff_in_seconds_output = rate_cliff_in_seconds.to_le_bytes();
+        *cliff_vest_amount_output = cliff_vest_amount.to_le_bytes();
+        *cliff_vest_percent_output = cliff_vest_percent.to_le_bytes();
+        beneficiary_address_output.copy_from_slice(beneficiary_address.as_ref());
+        beneficiary_associated_token_output.copy_from_slice(beneficiary_associated_token.as_ref());
+        treasury_address_output.copy_from_slice(treasury_address.as_ref());
+        *treasury_estimated_depletion_utc_output = treasury_estimated_depletion_utc.to_le_bytes();
+        *allocation_reserved_output = allocation_reserved.to_le_bytes();
+        *allocation_left_output = allocation_left.to_le_bytes();
+        *escrow_vested_amount_snap_output = escrow_vested_amount_snap.to_le_bytes();
+        *escrow_vested_amount_snap_slot_output = escrow_vested_amount_snap_slot.to_le_bytes();
+        *escrow_vested_amount_snap_block_time_output = escrow_vested_amount_snap_block_time.to_le_bytes();
+        *stream_resumed_slot_output = stream_resumed_slot.to_le_bytes();
+        *stream_resumed_block_time_output = stream_resumed_block_time.to_le_bytes();
+        *auto_pause_in_seconds_output = auto_pause_in_seconds.to_le_bytes();
+        *allocation_assigned_output = allocation_assigned.to_le_bytes();
+        *end_utc_output = end_utc.to_le_bytes();
+        vesting_mode_output[0] = *vesting_mode;
+        push_vested_enabled_output[0] = *push_vested_enabled as u8;
+        *push_vested_tip_output = push_vested_tip.to_le_bytes();
+        *unlocked_amount_snap_output = unlocked_amount_snap.to_le_bytes();
+        *push_vested_tip_block_time_output = push_vested_tip_block_time.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+
+        let input = array_ref![input, 0, StreamV2::LEN];
+        let (
+            discriminator,
+            version,
+            initialized,
+            stream_name,
+            treasurer_address,
+            rate_amount,
+            rate_interval_in_seconds,
+            funded_on_utc,
+            start_utc,
+            rate_cliff_in_seconds,
+            cliff_vest_amount,
+            cliff_vest_percent,
+            beneficiary_address,
+            beneficiary_associated_token,
+            treasury_address,
+            treasury_estimated_depletion_utc,
+            allocation_reserved,
+            allocation_left,
+            escrow_vested_amount_snap,
+            escrow_vested_amount_snap_slot,
+            escrow_vested_amount_snap_block_time,
+            stream_resumed_slot,
+            stream_resumed_block_time,
+            auto_pause_in_seconds,
+            allocation_assigned,
+            end_utc,
+            vesting_mode,
+            push_vested_enabled,
+            push_vested_tip,
+            unlocked_amount_snap,
+            push_vested_tip_block_time,
+            _additional_data
+
+        ) = array_refs![input, 1, 1, 1, 32, 32, 8, 8, 8, 8, 8, 8, 8, 32, 32, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 8, 8, 8, 267];
+
+        // Reject accounts of another kind (new accounts are zeroed until the first pack)
+        match (discriminator, version) {
+            ([0], [0]) | ([STREAM_ACCOUNT_DISCRIMINATOR], [STREAM_ACCOUNT_VERSION]) => {},
+            _ => return Err(StreamError::InvalidStreamData.into())
+        };
+
+        let initialized = match initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(StreamError::InvalidStreamData.into()),
+        };
+
+        Ok(StreamV2 {
+            initialized,
+            stream_name: String::from_utf8_lossy(stream_name).to_string(),
+            treasurer_address: Pubkey::new_from_array(*treasurer_address),
+            rate_amount: u64::from_le_bytes(*rate_amount),
+            rate_interval_in_seconds: u64::from_le_bytes(*rate_interval_in_seconds),
+            funded_on_utc: u64::from_le_bytes(*funded_on_utc),
+            start_utc: u64::from_le_bytes(*start_utc),
+            rate_cliff_in_seconds: u64::from_le_bytes(*rate_cliff_in_seconds),
+            cliff_vest_amount: u64::from_le_bytes(*cliff_vest_amount),
+            cliff_vest_percent: u64::from_le_bytes(*cliff_vest_percent),
+            beneficiary_address: Pubkey::new_from_array(*beneficiary_address),
+            beneficiary_associated_token: Pubkey::new_from_array(*beneficiary_associated_token),
+            treasury_address: Pubkey::new_from_array(*treasury_address),
+            treasury_estimated_depletion_utc: u64::from_le_bytes(*treasury_estimated_depletion_utc),
+            allocation_reserved: u64::from_le_bytes(*allocation_reserved),
+            allocation_left: u64::from_le_bytes(*allocation_left),
+            escrow_vested_amount_snap: u64::from_le_bytes(*escrow_vested_amount_snap),
+            escrow_vested_amount_snap_slot: u64::from_le_bytes(*escrow_vested_amount_snap_slot),
+            escrow_vested_amount_snap_block_time: u64::from_le_bytes(*escrow_vested_amount_snap_block_time),
+            stream_resumed_slot: u64::from_le_bytes(*stream_resumed_slot),
+            stream_resumed_block_time: u64::from_le_bytes(*stream_resumed_block_time),
+            auto_pause_in_seconds: u64::from_le_bytes(*auto_pause_in_seconds),
+            allocation_assigned: u64::from_le_bytes(*allocation_assigned),
+            end_utc: u64::from_le_bytes(*end_utc),
+            vesting_mode: vesting_mode[0],
+            push_vested_enabled: push_vested_enabled[0] == 1,
+            push_vested_tip: u64::from_le_bytes(*push_vested_tip),
+            unlocked_amount_snap: u64::from_le_bytes(*unlocked_amount_snap),
+            push_vested_tip_block_time: u64::from_le_bytes(*push_vested_tip_block_time)
         })
     }
-}
\ No newline at end of file
+}
+
+/// Treasury V2
+
+#[derive(Clone, Debug)]
+pub struct TreasuryV2 {
+    pub initialized: bool,
+    pub slot: u64,
+    pub treasurer_address: Pubkey,
+    pub associated_token_address: Pubkey,
+    pub mint_address: Pubkey,
+    pub label: String,
+    pub balance: u64,
+    pub allocation_reserved: u64,
+    pub allocation_left: u64,
+    pub streams_amount: u64,
+    pub created_on_utc: u64,
+    pub depletion_rate: u128, // base units per second scaled by RATE_PRECISION
+    pub treasury_type: u8,
+    pub auto_close: bool,
+    pub allocation_assigned: u64,
+    pub seed_address: Pubkey, // The original treasurer, used as the first seed of the treasury PDA
+    pub bump: u8,
+    pub estimated_depletion_utc: u64 // 0 while the treasury is not depleting
+}
+
+impl Sealed for TreasuryV2 {}
+
+impl IsInitialized for TreasuryV2 {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for TreasuryV2 {
+    fn default() -> Self {
+        Self {
+            initialized: false,
+            slot: 0,
+            treasurer_address: Pubkey::default(),
+            associated_token_address: Pubkey::default(),
+            mint_address: Pubkey::default(),
+            label: String::default(),
+            balance: 0,
+            allocation_reserved: 0,
+            allocation_left: 0,
+            streams_amount: 0,
+            created_on_utc: 0,
+            depletion_rate: 0,
+            treasury_type: 0,
+            auto_close: false,
+            allocation_assigned: 0,
+            seed_address: Pubkey::default(),
+            bump: 0,
+            estimated_depletion_utc: 0
+        }
+    }
+}
+
+impl Pack for TreasuryV2 {
+    const LEN: usize = 400;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+
+        let output = array_mut_ref![output, 0, TreasuryV2::LEN];
+        let (
+            discriminator_output,
+            version_output,
+            initialized_output,
+            slot_output,
+            treasurer_address_output,
+            associated_token_address_output,
+            mint_address_output,
+            label_output,
+            balance_output,
+            allocation_reserved_output,
+            allocation_left_output,
+            streams_amount_output,
+            created_on_utc_output,
+            depletion_rate_output,
+            treasury_type_output,
+            auto_close_output,
+            allocation_assigned_output,
+            seed_address_output,
+            bump_output,
+            estimated_depletion_utc_output,
+            _additional_data
+
+        ) = mut_array_refs![output, 1, 1, 1, 8, 32, 32, 32, 32, 8, 8, 8, 8, 8, 16, 1, 1, 8, 32, 1, 8, 154];
+
+        let TreasuryV2 {
+            initialized,
+            slot,
+            treasurer_address,
+            associated_token_address,
+            mint_address,
+            label,
+            balance,
+            allocation_reserved,
+            allocation_left,
+            streams_amount,
+            created_on_utc,
+            depletion_rate,
+            treasury_type,
+            auto_close,
+            allocation_assigned,
+            seed_address,
+            bump,
+            estimated_depletion_utc
+
+        } = self;
+
+        discriminator_output[0] = TREASURY_ACCOUNT_DISCRIMINATOR;
+        version_output[0] = TREASURY_ACCOUNT_VERSION;
+        initialized_output[0] = *initialized as u8;
+        *slot_output = slot.to_le_bytes();
+        treasurer_address_output.copy_from_slice(treasurer_address.as_ref());
+        associated_token_address_output.copy_from_slice(associated_token_address.as_ref());
+        mint_address_output.copy_from_slice(mint_address.as_ref());
+        label_output.copy_from_slice(label.as_ref());
+        *balance_output = balance.to_le_bytes();
+        *allocation_reserved_output = allocation_reserved.to_le_bytes();
+        *allocation_left_output = allocation_left.to_le_bytes();
+        *streams_amount_output = streams_amount.to_le_bytes();
+        *created_on_utc_output = created_on_utc.to_le_bytes();
+        *depletion_rate_output = depletion_rate.to_le_bytes();
+        *treasury_type_output = treasury_type.to_le_bytes();
+        auto_close_output[0] = *auto_close as u8;
+        *allocation_assigned_output = allocation_assigned.to_le_bytes();
+        seed_address_output.copy_from_slice(seed_address.as_ref());
+        bump_output[0] = *bump;
+        *estimated_depletion_utc_output = estimated_depletion_utc.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+
+        let input = array_ref![input, 0, TreasuryV2::LEN];
+        let (
+            discriminator,
+            version,
+            initialized,
+            slot,
+            treasurer_address,
+            associated_token_address,
+            mint_address,
+            label,
+            balance,
+            allocation_reserved,
+            allocation_left,
+            streams_amount,
+            created_on_utc,
+            depletion_rate,
+            treasury_type,
+            auto_close,
+            allocation_assigned,
+            seed_address,
+            bump,
+            estimated_depletion_utc,
+            _additional_data
+
+        ) = array_refs![input, 1, 1, 1, 8, 32, 32, 32, 32, 8, 8, 8, 8, 8, 16, 1, 1, 8, 32, 1, 8, 154];
+
+        // Reject accounts of another kind (new accounts are zeroed until the first pack)
+        match (discriminator, version) {
+            ([0], [0]) | ([TREASURY_ACCOUNT_DISCRIMINATOR], [TREASURY_ACCOUNT_VERSION]) => {},
+            _ => return Err(TreasuryError::InvalidTreasuryData.into())
+        };
+
+        let initialized = match initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(TreasuryError::InvalidTreasuryData.into()),
+        };
+
+        // Treasuries created before the seed address was stored were never transferred
+        let seed_address = match Pubkey::new_from_array(*seed_address) {
+            address if address.eq(&Pubkey::default()) => Pubkey::new_from_array(*treasurer_address),
+            address => address
+        };
+
+        let auto_close = match auto_close {
+            [0] => false,
+            [1] => true,
+            _ => return Err(TreasuryError::InvalidTreasuryData.into()),
+        };
+
+        Ok(TreasuryV2 {
+            initialized,
+            slot: u64::from_le_bytes(*slot),
+            treasurer_address: Pubkey::new_from_array(*treasurer_address),
+            associated_token_address: Pubkey::new_from_array(*associated_token_address),
+            mint_address: Pubkey::new_from_array(*mint_address),
+            label: String::from_utf8_lossy(label).to_string(),
+            balance: u64::from_le_bytes(*balance),
+            allocation_reserved: u64::from_le_bytes(*allocation_reserved),
+            allocation_left: u64::from_le_bytes(*allocation_left),
+            streams_amount: u64::from_le_bytes(*streams_amount),
+            created_on_utc: u64::from_le_bytes(*created_on_utc),
+            depletion_rate: u128::from_le_bytes(*depletion_rate),
+            treasury_type: u8::from_le_bytes(*treasury_type),
+            auto_close,
+            allocation_assigned: u64::from_le_bytes(*allocation_assigned),
+            seed_address,
+            bump: bump[0],
+            estimated_depletion_utc: u64::from_le_bytes(*estimated_depletion_utc)
+        })
+    }
+}
+
+impl TreasuryV2 {
+
+    /// Derives the treasury PDA from its original seeds, using the stored bump when available
+    pub fn find_address(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+
+        if self.bump != 0 {
+            if let Ok(address) = Pubkey::create_program_address(
+                &[self.seed_address.as_ref(), &self.slot.to_le_bytes(), &[self.bump]],
+                program_id
+            ) {
+                return (address, self.bump);
+            }
+        }
+
+        Pubkey::find_program_address(
+            &[self.seed_address.as_ref(), &self.slot.to_le_bytes()],
+            program_id
+        )
+    }
+}
+
+/// Stream Terms
+
+#[derive(Clone, Debug)]
+pub struct StreamTerms {
+    pub initialized: bool,
+    pub proposed_by: Pubkey,
+    pub stream_id: Pubkey,
+    pub stream_name: String,
+    pub rate_amount: u64,
+    pub rate_interval_in_seconds: u64,
+    pub created_on_utc: u64
+}
+
+impl Sealed for StreamTerms {}
+
+impl IsInitialized for StreamTerms {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for StreamTerms {
+    fn default() -> Self {
+        Self {
+            initialized: false,
+            proposed_by: Pubkey::default(),
+            stream_id: Pubkey::default(),
+            stream_name: String::default(),
+            rate_amount: 0,
+            rate_interval_in_seconds: 0,
+            created_on_utc: 0
+        }
+    }
+}
+
+impl Pack for StreamTerms {
+    const LEN: usize = 200;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+
+        let output = array_mut_ref![output, 0, StreamTerms::LEN];
+        let (
+            discriminator_output,
+            version_output,
+            initialized_output,
+            proposed_by_output,
+            stream_id_output,
+            stream_name_output,
+            rate_amount_output,
+            rate_interval_in_seconds_output,
+            created_on_utc_output,
+            _additional_data
+
+        ) = mut_array_refs![output, 1, 1, 1, 32, 32, 32, 8, 8, 8, 77];
+
+        let StreamTerms {
+            initialized,
+            proposed_by,
+            stream_id,
+            stream_name,
+            rate_amount,
+            rate_interval_in_seconds,
+            created_on_utc
+
+        } = self;
+
+        discriminator_output[0] = STREAM_TERMS_ACCOUNT_DISCRIMINATOR;
+        version_output[0] = STREAM_TERMS_ACCOUNT_VERSION;
+        initialized_output[0] = *initialized as u8;
+        proposed_by_output.copy_from_slice(proposed_by.as_ref());
+        stream_id_output.copy_from_slice(stream_id.as_ref());
+        stream_name_output.copy_from_slice(stream_name.as_ref());
+        *rate_amount_output = rate_amount.to_le_bytes();
+        *rate_interval_in_seconds_output = rate_interval_in_seconds.to_le_bytes();
+        *created_on_utc_output = created_on_utc.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+
+        let input = array_ref![input, 0, StreamTerms::LEN];
+        let (
+            discriminator,
+            version,
+            initialized,
+            proposed_by,
+            stream_id,
+            stream_name,
+            rate_amount,
+            rate_interval_in_seconds,
+            created_on_utc,
+            _additional_data
+
+        ) = array_refs![input, 1, 1, 1, 32, 32, 32, 8, 8, 8, 77];
+
+        // Reject accounts of another kind (new accounts are zeroed until the first pack)
+        match (discriminator, version) {
+            ([0], [0]) | ([STREAM_TERMS_ACCOUNT_DISCRIMINATOR], [STREAM_TERMS_ACCOUNT_VERSION]) => {},
+            _ => return Err(StreamError::InvalidStreamData.into())
+        };
+
+        let initialized = match initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(StreamError::InvalidStreamData.into()),
+        };
+
+        Ok(StreamTerms {
+            initialized,
+            proposed_by: Pubkey::new_from_array(*proposed_by),
+            stream_id: Pubkey::new_from_array(*stream_id),
+            stream_name: String::from_utf8_lossy(stream_name).to_string(),
+            rate_amount: u64::from_le_bytes(*rate_amount),
+            rate_interval_in_seconds: u64::from_le_bytes(*rate_interval_in_seconds),
+            created_on_utc: u64::from_le_bytes(*created_on_utc)
+        })
+    }
+}
+
+/// Fee Config
+
+#[derive(Clone, Debug)]
+pub struct FeeConfig {
+    pub initialized: bool,
+    pub authority: Pubkey,
+    pub fee_treasury_address: Pubkey,
+    pub create_treasury_flat_fee: u64,
+    pub create_stream_flat_fee: u64,
+    pub add_funds_flat_fee: u64,
+    pub withdraw_percent_fee: u64,
+    pub propose_update_flat_fee: u64,
+    pub close_stream_flat_fee: u64,
+    pub close_stream_percent_fee: u64,
+    pub close_treasury_flat_fee: u64,
+    pub close_treasury_percent_fee: u64,
+    pub bump: u8
+}
+
+impl Sealed for FeeConfig {}
+
+impl IsInitialized for FeeConfig {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for FeeConfig {
+    /// The compile-time fees, used until the fee config account is initialized
+    fn default() -> Self {
+        Self {
+            initialized: false,
+            authority: FEE_TREASURY_ACCOUNT.parse().unwrap(),
+            fee_treasury_address: FEE_TREASURY_ACCOUNT.parse().unwrap(),
+            create_treasury_flat_fee: CREATE_TREASURY_FLAT_FEE,
+            create_stream_flat_fee: CREATE_STREAM_FLAT_FEE,
+            add_funds_flat_fee: ADD_FUNDS_FLAT_FEE,
+            withdraw_percent_fee: WITHDRAW_PERCENT_FEE,
+            propose_update_flat_fee: PROPOSE_UPDATE_FLAT_FEE,
+            close_stream_flat_fee: CLOSE_STREAM_FLAT_FEE,
+            close_stream_percent_fee: CLOSE_STREAM_PERCENT_FEE,
+            close_treasury_flat_fee: CLOSE_TREASURY_FLAT_FEE,
+            close_treasury_percent_fee: CLOSE_TREASURY_PERCENT_FEE,
+            bump: 0
+        }
+    }
+}
+
+impl Pack for FeeConfig {
+    const LEN: usize = 200;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+
+        let output = array_mut_ref![output, 0, FeeConfig::LEN];
+        let (
+            discriminator_output,
+            version_output,
+            initialized_output,
+            authority_output,
+            fee_treasury_address_output,
+            create_treasury_flat_fee_output,
+            create_stream_flat_fee_output,
+            add_funds_flat_fee_output,
+            withdraw_percent_fee_output,
+            propose_update_flat_fee_output,
+            close_stream_flat_fee_output,
+            close_stream_percent_fee_output,
+            close_treasury_flat_fee_output,
+            close_treasury_percent_fee_output,
+            bump_output,
+            _additional_data
+
+        ) = mut_array_refs![output, 1, 1, 1, 32, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 60];
+
+        let FeeConfig {
+            initialized,
+            authority,
+            fee_treasury_address,
+            create_treasury_flat_fee,
+            create_stream_flat_fee,
+            add_funds_flat_fee,
+            withdraw_percent_fee,
+            propose_update_flat_fee,
+            close_stream_flat_fee,
+            close_stream_percent_fee,
+            close_treasury_flat_fee,
+            close_treasury_percent_fee,
+            bump
+
+        } = self;
+
+        discriminator_output[0] = FEE_CONFIG_ACCOUNT_DISCRIMINATOR;
+        version_output[0] = FEE_CONFIG_ACCOUNT_VERSION;
+        initialized_output[0] = *initialized as u8;
+        authority_output.copy_from_slice(authority.as_ref());
+        fee_treasury_address_output.copy_from_slice(fee_treasury_address.as_ref());
+        *create_treasury_flat_fee_output = create_treasury_flat_fee.to_le_bytes();
+        *create_stream_flat_fee_output = create_stream_flat_fee.to_le_bytes();
+        *add_funds_flat_fee_output = add_funds_flat_fee.to_le_bytes();
+        *withdraw_percent_fee_output = withdraw_percent_fee.to_le_bytes();
+        *propose_update_flat_fee_output = propose_update_flat_fee.to_le_bytes();
+        *close_stream_flat_fee_output = close_stream_flat_fee.to_le_bytes();
+        *close_stream_percent_fee_output = close_stream_percent_fee.to_le_bytes();
+        *close_treasury_flat_fee_output = close_treasury_flat_fee.to_le_bytes();
+        *close_treasury_percent_fee_output = close_treasury_percent_fee.to_le_bytes();
+        bump_output[0] = *bump;
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+
+        let input = array_ref![input, 0, FeeConfig::LEN];
+        let (
+            discriminator,
+            version,
+            initialized,
+            authority,
+            fee_treasury_address,
+            create_treasury_flat_fee,
+            create_stream_flat_fee,
+            add_funds_flat_fee,
+            withdraw_percent_fee,
+            propose_update_flat_fee,
+            close_stream_flat_fee,
+            close_stream_percent_fee,
+            close_treasury_flat_fee,
+            close_treasury_percent_fee,
+            bump,
+            _additional_data
+
+        ) = array_refs![input, 1, 1, 1, 32, 32, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 60];
+
+        // Reject accounts of another kind (new accounts are zeroed until the first pack)
+        match (discriminator, version) {
+            ([0], [0]) | ([FEE_CONFIG_ACCOUNT_DISCRIMINATOR], [FEE_CONFIG_ACCOUNT_VERSION]) => {},
+            _ => return Err(StreamError::InvalidFeeConfig.into())
+        };
+
+        let initialized = match initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(StreamError::InvalidFeeConfig.into()),
+        };
+
+        Ok(FeeConfig {
+            initialized,
+            authority: Pubkey::new_from_array(*authority),
+            fee_treasury_address: Pubkey::new_from_array(*fee_treasury_address),
+            create_treasury_flat_fee: u64::from_le_bytes(*create_treasury_flat_fee),
+            create_stream_flat_fee: u64::from_le_bytes(*create_stream_flat_fee),
+            add_funds_flat_fee: u64::from_le_bytes(*add_funds_flat_fee),
+            withdraw_percent_fee: u64::from_le_bytes(*withdraw_percent_fee),
+            propose_update_flat_fee: u64::from_le_bytes(*propose_update_flat_fee),
+            close_stream_flat_fee: u64::from_le_bytes(*close_stream_flat_fee),
+            close_stream_percent_fee: u64::from_le_bytes(*close_stream_percent_fee),
+            close_treasury_flat_fee: u64::from_le_bytes(*close_treasury_flat_fee),
+            close_treasury_percent_fee: u64::from_le_bytes(*close_treasury_percent_fee),
+            bump: bump[0]
+        })
+    }
+}
+
+/// Fee Tier
+
+#[derive(Clone, Debug)]
+pub struct FeeTier {
+    pub initialized: bool,
+    pub subject_address: Pubkey, // treasurer or treasury
+    pub flat_fee: u64, // lamports
+    pub withdraw_percent_fee: u64, // basis points
+    pub expiry_utc: u64, // OPTIONAL
+    pub bump: u8
+}
+
+impl Sealed for FeeTier {}
+
+impl IsInitialized for FeeTier {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for FeeTier {
+    fn default() -> Self {
+        Self {
+            initialized: false,
+            subject_address: Pubkey::default(),
+            flat_fee: 0,
+            withdraw_percent_fee: 0,
+            expiry_utc: 0,
+            bump: 0
+        }
+    }
+}
+
+impl Pack for FeeTier {
+    const LEN: usize = 100;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+
+        let output = array_mut_ref![output, 0, FeeTier::LEN];
+        let (
+            discriminator_output,
+            version_output,
+            initialized_output,
+            subject_address_output,
+            flat_fee_output,
+            withdraw_percent_fee_output,
+            expiry_utc_output,
+            bump_output,
+            _additional_data
+
+        ) = mut_array_refs![output, 1, 1, 1, 32, 8, 8, 8, 1, 40];
+
+        let FeeTier {
+            initialized,
+            subject_address,
+            flat_fee,
+            withdraw_percent_fee,
+            expiry_utc,
+            bump
+
+        } = self;
+
+        discriminator_output[0] = FEE_TIER_ACCOUNT_DISCRIMINATOR;
+        version_output[0] = FEE_TIER_ACCOUNT_VERSION;
+        initialized_output[0] = *initialized as u8;
+        subject_address_output.copy_from_slice(subject_address.as_ref());
+        *flat_fee_output = flat_fee.to_le_bytes();
+        *withdraw_percent_fee_output = withdraw_percent_fee.to_le_bytes();
+        *expiry_utc_output = expiry_utc.to_le_bytes();
+        bump_output[0] = *bump;
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+
+        let input = array_ref![input, 0, FeeTier::LEN];
+        let (
+            discriminator,
+            version,
+            initialized,
+            subject_address,
+            flat_fee,
+            withdraw_percent_fee,
+            expiry_utc,
+            bump,
+            _additional_data
+
+        ) = array_refs![input, 1, 1, 1, 32, 8, 8, 8, 1, 40];
+
+        // Reject accounts of another kind (new accounts are zeroed until the first pack)
+        match (discriminator, version) {
+            ([0], [0]) | ([FEE_TIER_ACCOUNT_DISCRIMINATOR], [FEE_TIER_ACCOUNT_VERSION]) => {},
+            _ => return Err(StreamError::InvalidFeeTier.into())
+        };
+
+        let initialized = match initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(StreamError::InvalidFeeTier.into()),
+        };
+
+        Ok(FeeTier {
+            initialized,
+            subject_address: Pubkey::new_from_array(*subject_address),
+            flat_fee: u64::from_le_bytes(*flat_fee),
+            withdraw_percent_fee: u64::from_le_bytes(*withdraw_percent_fee),
+            expiry_utc: u64::from_le_bytes(*expiry_utc),
+            bump: bump[0]
+        })
+    }
+}
+
+impl FeeTier {
+
+    /// Returns the global fee schedule with the fees of this tier applied, unless the tier expired.
+    /// A tier only holds two fees: `flat_fee` replaces every flat fee (create treasury, create
+    /// stream, add funds, propose update, close stream and close treasury) and
+    /// `withdraw_percent_fee` replaces every percent fee (withdraw, close stream and close
+    /// treasury), since closing pays out tokens the same way a withdrawal does. The fee
+    /// recipient and the authority are never changed by a tier
+    pub fn apply(&self, fee_config: &FeeConfig, clock: &Clock) -> FeeConfig {
+
+        let now = clock.unix_timestamp as u64 * 1000u64;
+
+        if !self.initialized || (self.expiry_utc > 0 && now >= self.expiry_utc) {
+            return fee_config.clone();
+        }
+
+        FeeConfig {
+            create_treasury_flat_fee: self.flat_fee,
+            create_stream_flat_fee: self.flat_fee,
+            add_funds_flat_fee: self.flat_fee,
+            withdraw_percent_fee: self.withdraw_percent_fee,
+            propose_update_flat_fee: self.flat_fee,
+            close_stream_flat_fee: self.flat_fee,
+            close_stream_percent_fee: self.withdraw_percent_fee,
+            close_treasury_flat_fee: self.flat_fee,
+            close_treasury_percent_fee: self.withdraw_percent_fee,
+            ..fee_config.clone()
+        }
+    }
+}
+
+/// Treasury Roles
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TreasuryRole
+{
+    Admin = 1, // Grants and revokes the other roles and holds all of them
+    StreamCreator = 2,
+    Pauser = 4
+}
+
+impl TreasuryRole {
+    pub const ALL: u8 = TreasuryRole::Admin as u8 | TreasuryRole::StreamCreator as u8 | TreasuryRole::Pauser as u8;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TreasuryRoleMember {
+    pub address: Pubkey,
+    pub roles: u8 // `TreasuryRole` flags
+}
+
+impl TreasuryRoleMember {
+    pub const LEN: usize = 33;
+}
+
+/// The members of a treasury that can act on its behalf without the treasurer key
+#[derive(Clone, Debug)]
+pub struct TreasuryRoles {
+    pub initialized: bool,
+    pub treasury_address: Pubkey,
+    pub members: [TreasuryRoleMember; MAX_TREASURY_ROLE_MEMBERS],
+    pub bump: u8
+}
+
+impl Sealed for TreasuryRoles {}
+
+impl IsInitialized for TreasuryRoles {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Default for TreasuryRoles {
+    fn default() -> Self {
+        Self {
+            initialized: false,
+            treasury_address: Pubkey::default(),
+            members: [TreasuryRoleMember::default(); MAX_TREASURY_ROLE_MEMBERS],
+            bump: 0
+        }
+    }
+}
+
+impl Pack for TreasuryRoles {
+    const LEN: usize = 420;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+
+        let output = array_mut_ref![output, 0, TreasuryRoles::LEN];
+        let (
+            discriminator_output,
+            version_output,
+            initialized_output,
+            treasury_address_output,
+            members_output,
+            bump_output,
+            _additional_data
+
+        ) = mut_array_refs![output, 1, 1, 1, 32, 330, 1, 54];
+
+        let TreasuryRoles {
+            initialized,
+            treasury_address,
+            members,
+            bump
+
+        } = self;
+
+        discriminator_output[0] = TREASURY_ROLES_ACCOUNT_DISCRIMINATOR;
+        version_output[0] = TREASURY_ROLES_ACCOUNT_VERSION;
+        initialized_output[0] = *initialized as u8;
+        treasury_address_output.copy_from_slice(treasury_address.as_ref());
+
+        for (member, member_output) in members.iter().zip(members_output.chunks_exact_mut(TreasuryRoleMember::LEN)) {
+            member_output[..32].copy_from_slice(member.address.as_ref());
+            member_output[32] = member.roles;
+        }
+
+        bump_output[0] = *bump;
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+
+        let input = array_ref![input, 0, TreasuryRoles::LEN];
+        let (
+            discriminator,
+            version,
+            initialized,
+            treasury_address,
+            members_input,
+            bump,
+            _additional_data
+
+        ) = array_refs![input, 1, 1, 1, 32, 330, 1, 54];
+
+        // Reject accounts of another kind (new accounts are zeroed until the first pack)
+        match (discriminator, version) {
+            ([0], [0]) | ([TREASURY_ROLES_ACCOUNT_DISCRIMINATOR], [TREASURY_ROLES_ACCOUNT_VERSION]) => {},
+            _ => return Err(StreamError::InvalidTreasuryRoles.into())
+        };
+
+        let initialized = match initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(StreamError::InvalidTreasuryRoles.into()),
+        };
+
+        let mut members = [TreasuryRoleMember::default(); MAX_TREASURY_ROLE_MEMBERS];
+
+        for (member, member_input) in members.iter_mut().zip(members_input.chunks_exact(TreasuryRoleMember::LEN)) {
+            member.address = Pubkey::new_from_array(*array_ref![member_input, 0, 32]);
+            member.roles = member_input[32];
+        }
+
+        Ok(TreasuryRoles {
+            initialized,
+            treasury_address: Pubkey::new_from_array(*treasury_address),
+            members,
+            bump: bump[0]
+        })
+    }
+}
+
+impl TreasuryRoles {
+
+    /// Whether `address` holds `role` (admins hold every role)
+    pub fn has_role(&self, address: &Pubkey, role: TreasuryRole) -> bool {
+        self.members.iter().any(|member| {
+            member.roles > 0 && member.address.eq(address) &&
+            member.roles & (role as u8 | TreasuryRole::Admin as u8) > 0
+        })
+    }
+
+    /// Adds `roles` to `address`, taking a free slot if it is not a member yet
+    pub fn grant(&mut self, address: &Pubkey, roles: u8) -> Result<(), StreamError> {
+
+        if let Some(member) = self.members.iter_mut().find(|member| member.roles > 0 && member.address.eq(address)) {
+            member.roles |= roles;
+            return Ok(());
+        }
+
+        let member = self.members
+            .iter_mut()
+            .find(|member| member.roles == 0)
+            .ok_or(StreamError::TreasuryRolesFull)?;
+
+        *member = TreasuryRoleMember { address: *address, roles };
+
+        Ok(())
+    }
+
+    /// Removes `roles` from `address`, freeing its slot once it has no roles left
+    pub fn revoke(&mut self, address: &Pubkey, roles: u8) {
+
+        for member in self.members.iter_mut().filter(|member| member.address.eq(address)) {
+            member.roles &= !roles;
+
+            if member.roles == 0 {
+                *member = TreasuryRoleMember::default();
+            }
+        }
+    }
+}
@@ -1,9 +1,8 @@
-use std::cmp;
-use num_traits;
 use std::{ string::String, convert::TryInto };
 use crate::error::StreamError;
 use crate::state::*;
 use crate::constants::*;
+use crate::math::*;
 use solana_program::{
     // msg,
     system_program,
@@ -12,6 +11,7 @@ use solana_program::{
     pubkey::Pubkey,
     account_info::AccountInfo,
     entrypoint::ProgramResult,
+    program_error::ProgramError,
     program_pack::{ Pack },
     sysvar::{ clock::Clock, rent::Rent, Sysvar } 
 };
@@ -129,6 +129,36 @@ pub fn create_ata_account<'info>(
     Ok(())
 }
 
+pub fn resize_account<'info>(
+    system_account_info: &AccountInfo<'info>,
+    rent_account_info: &AccountInfo<'info>,
+    payer_account_info: &AccountInfo<'info>,
+    account_info: &AccountInfo<'info>,
+    new_size: usize
+
+) -> ProgramResult {
+
+    let rent = &Rent::from_account_info(rent_account_info)?;
+    let new_balance = rent.minimum_balance(new_size);
+    let lamports_diff = new_balance.saturating_sub(account_info.lamports());
+
+    if lamports_diff > 0 {
+        let transfer_ix = system_instruction::transfer(
+            payer_account_info.key,
+            account_info.key,
+            lamports_diff
+        );
+
+        invoke(&transfer_ix, &[
+            payer_account_info.clone(),
+            account_info.clone(),
+            system_account_info.clone()
+        ])?;
+    }
+
+    account_info.realloc(new_size, true)
+}
+
 pub fn claim_treasury_funds<'info>(
     msp_account_info: &AccountInfo<'info>,
     token_program_account_info: &AccountInfo<'info>,
@@ -140,20 +170,14 @@ pub fn claim_treasury_funds<'info>(
 ) -> ProgramResult {
 
     let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
-    let (treasury_pool_address, treasury_pool_bump_seed) = Pubkey::find_program_address(
-        &[
-            treasury.treasurer_address.as_ref(),
-            &treasury.slot.to_le_bytes()
-        ], 
-        msp_account_info.key
-    );
+    let (treasury_pool_address, treasury_pool_bump_seed) = treasury.find_address(msp_account_info.key);
 
     if treasury_pool_address.ne(treasury_account_info.key) {
         return Err(StreamError::InvalidTreasuryData.into());
     }
 
     let treasury_pool_signer_seed: &[&[_]] = &[
-        treasury.treasurer_address.as_ref(),
+        treasury.seed_address.as_ref(),
         &treasury.slot.to_le_bytes(),
         &treasury_pool_bump_seed.to_le_bytes()
     ];
@@ -181,19 +205,232 @@ pub fn claim_treasury_funds<'info>(
     Ok(())
 }
 
+pub fn claim_treasury_funds_v2<'info>(
+    msp_account_info: &AccountInfo<'info>,
+    token_program_account_info: &AccountInfo<'info>,
+    treasury_account_info: &AccountInfo<'info>,
+    treasury_token_account_info: &AccountInfo<'info>,
+    destination_account_info: &AccountInfo<'info>,
+    amount: u64
+
+) -> ProgramResult {
+
+    let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+    let (treasury_pool_address, treasury_pool_bump_seed) = treasury.find_address(msp_account_info.key);
+
+    if treasury_pool_address.ne(treasury_account_info.key) {
+        return Err(StreamError::InvalidTreasuryData.into());
+    }
+
+    let treasury_pool_signer_seed: &[&[_]] = &[
+        treasury.seed_address.as_ref(),
+        &treasury.slot.to_le_bytes(),
+        &treasury_pool_bump_seed.to_le_bytes()
+    ];
+
+    let transfer_ix = spl_token::instruction::transfer(
+        token_program_account_info.key,
+        treasury_token_account_info.key,
+        destination_account_info.key,
+        treasury_account_info.key,
+        &[],
+        amount
+    )?;
+
+    invoke_signed(&transfer_ix, 
+        &[
+            treasury_account_info.clone(),
+            treasury_token_account_info.clone(),
+            destination_account_info.clone(),
+            token_program_account_info.clone(),
+            msp_account_info.clone()
+        ],
+        &[treasury_pool_signer_seed]
+    )
+}
+
+pub fn find_treasury_address(
+    program_id: &Pubkey,
+    treasurer_address: &Pubkey,
+    slot: u64
+
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[treasurer_address.as_ref(), &slot.to_le_bytes()], program_id)
+}
+
+pub fn find_treasury_pool_mint_address(
+    program_id: &Pubkey,
+    treasurer_address: &Pubkey,
+    treasury_address: &Pubkey,
+    slot: u64
+
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[treasurer_address.as_ref(), treasury_address.as_ref(), &slot.to_le_bytes()],
+        program_id
+    )
+}
+
+pub fn find_stream_terms_address(program_id: &Pubkey, stream_address: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[stream_address.as_ref(), STREAM_TERMS_SEED], program_id)
+}
+
+pub fn find_fee_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_CONFIG_SEED], program_id)
+}
+
+pub fn find_fee_tier_address(program_id: &Pubkey, subject_address: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_TIER_SEED, subject_address.as_ref()], program_id)
+}
+
+pub fn find_treasury_roles_address(program_id: &Pubkey, treasury_address: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_ROLES_SEED, treasury_address.as_ref()], program_id)
+}
+
+/// Splits the optional trailing accounts into the fee tier and the treasury roles
+/// accounts, the treasury roles account is recognized by its data
+pub fn next_fee_tier_and_treasury_roles<'a, 'info>(
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'info>>
+
+) -> (Option<&'a AccountInfo<'info>>, Option<&'a AccountInfo<'info>>) {
+
+    let mut fee_tier_account_info = None;
+    let mut treasury_roles_account_info = None;
+
+    for account_info in account_info_iter {
+        if AccountKind::detect(account_info) == AccountKind::TreasuryRoles {
+            treasury_roles_account_info = treasury_roles_account_info.or(Some(account_info));
+        } else {
+            fee_tier_account_info = fee_tier_account_info.or(Some(account_info));
+        }
+    }
+
+    (fee_tier_account_info, treasury_roles_account_info)
+}
+
+/// Reads the fees from the fee config PDA, falling back to the compile-time
+/// fees while the account has not been initialized
+pub fn get_fee_config<'info>(
+    program_id: &Pubkey,
+    fee_config_account_info: &AccountInfo<'info>
+
+) -> Result<FeeConfig, ProgramError> {
+
+    if fee_config_account_info.data_len() == 0 {
+        let (fee_config_address, _) = Pubkey::find_program_address(&[FEE_CONFIG_SEED], program_id);
+
+        if fee_config_address.ne(fee_config_account_info.key) {
+            return Err(StreamError::InvalidPdaAccount.into());
+        }
+
+        return Ok(FeeConfig::default());
+    }
+
+    if fee_config_account_info.owner != program_id ||
+       AccountKind::detect(&fee_config_account_info) != AccountKind::FeeConfig
+    {
+        return Err(StreamError::InvalidFeeConfig.into());
+    }
+
+    let fee_config = FeeConfig::unpack_from_slice(&fee_config_account_info.data.borrow())?;
+    let fee_config_address = Pubkey::create_program_address(
+        &[FEE_CONFIG_SEED, &[fee_config.bump]],
+        program_id
+    )?;
+
+    if fee_config_address.ne(fee_config_account_info.key) {
+        return Err(StreamError::InvalidPdaAccount.into());
+    }
+
+    Ok(fee_config)
+}
+
+/// Applies the fee tier of the treasurer or the treasury (if any) to the global fee schedule
+pub fn apply_fee_tier<'info>(
+    program_id: &Pubkey,
+    fee_config: FeeConfig,
+    fee_tier_account: Option<&AccountInfo<'info>>,
+    treasurer_address: &Pubkey,
+    treasury_address: &Pubkey,
+    clock: &Clock
+
+) -> Result<FeeConfig, ProgramError> {
+
+    let fee_tier_account_info = match fee_tier_account {
+        Some(account_info) if account_info.data_len() > 0 => account_info,
+        _ => return Ok(fee_config)
+    };
+
+    if fee_tier_account_info.owner != program_id ||
+       AccountKind::detect(&fee_tier_account_info) != AccountKind::FeeTier
+    {
+        return Err(StreamError::InvalidFeeTier.into());
+    }
+
+    let fee_tier = FeeTier::unpack_from_slice(&fee_tier_account_info.data.borrow())?;
+
+    if fee_tier.subject_address.ne(treasurer_address) && fee_tier.subject_address.ne(treasury_address) {
+        return Err(StreamError::InvalidFeeTier.into());
+    }
+
+    let fee_tier_address = Pubkey::create_program_address(
+        &[FEE_TIER_SEED, fee_tier.subject_address.as_ref(), &[fee_tier.bump]],
+        program_id
+    )?;
+
+    if fee_tier_address.ne(fee_tier_account_info.key) {
+        return Err(StreamError::InvalidPdaAccount.into());
+    }
+
+    Ok(fee_tier.apply(&fee_config, clock))
+}
+
+pub fn get_treasurer_address<'info>(
+    treasury_account_info: &AccountInfo<'info>
+
+) -> Result<Pubkey, ProgramError> {
+
+    match AccountKind::detect(&treasury_account_info) {
+        AccountKind::Treasury => {
+            let treasury = Treasury::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            Ok(treasury.treasury_base_address)
+        },
+        AccountKind::TreasuryV1 => {
+            let treasury = TreasuryV1::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            Ok(treasury.treasurer_address)
+        },
+        AccountKind::TreasuryV2 => {
+            let treasury = TreasuryV2::unpack_from_slice(&treasury_account_info.data.borrow())?;
+            Ok(treasury.treasurer_address)
+        },
+        _ => Err(StreamError::InvalidTreasuryData.into())
+    }
+}
+
+/// Native SOL is contributed straight from the contributor account instead of a token account
+pub fn is_native_contribution<'info>(
+    contributor_account_info: &AccountInfo<'info>,
+    contributor_token_account_info: &AccountInfo<'info>,
+    associated_token_mint_info: &AccountInfo<'info>
+
+) -> bool {
+
+    associated_token_mint_info.key.eq(&spl_token::native_mint::id()) &&
+    contributor_token_account_info.key.eq(contributor_account_info.key)
+}
+
 pub fn transfer_sol_fee<'info>(
     system_account_info: &AccountInfo<'info>,
     payer_account_info: &AccountInfo<'info>,
     fee_treasury_account_info: &AccountInfo<'info>,
-    amount: f64
+    lamports: u64
 
 ) -> ProgramResult {
 
-    let lamports = amount * LAMPORTS_PER_SOL as f64;
     let pay_fee_ix = system_instruction::transfer(
         payer_account_info.key,
         fee_treasury_account_info.key,
-        lamports as u64
+        lamports
     );
 
     invoke(&pay_fee_ix, &[
@@ -229,65 +466,216 @@ pub fn transfer_token_fee<'info>(
     ])
 }
 
+pub fn get_stream_auto_pause_block_time(
+    auto_pause_in_seconds: u64,
+    stream_resumed_block_time: u64
+
+) -> Option<u64> {
+
+    if auto_pause_in_seconds == 0 {
+        return None;
+    }
+
+    stream_resumed_block_time.checked_add(auto_pause_in_seconds)
+}
+
+pub fn get_stream_end_block_time(
+    end_utc: u64
+
+) -> Option<u64> {
+
+    if end_utc == 0 {
+        return None;
+    }
+
+    Some(end_utc / 1000u64)
+}
+
 pub fn get_stream_status<'info>(
     stream: &StreamV1,
     clock: &Clock
 
 ) -> Result<StreamStatus, StreamError> {
 
-    let now = clock.unix_timestamp as u64 * 1000u64;
+    Ok(get_stream_status_at(stream, clock.unix_timestamp as u64))
+}
 
-    if stream.start_utc > now {
-        return Ok(StreamStatus::Scheduled);
+pub fn get_beneficiary_withdrawable_amount<'info>( 
+    stream: &StreamV1,
+    clock: &Clock,
+    decimals: u64
+
+) -> Result<u64, StreamError> {
+
+    get_withdrawable_amount_at(stream, clock.unix_timestamp as u64, decimals)
+}
+
+/// Scale (`10^decimals` of the mint) of the token amounts kept as `f64` in the V1 and legacy state.
+/// Every conversion of those amounts goes through it so the float handling is in one place: the
+/// stored amounts are whole base units divided by the scale, rounding recovers them where truncating
+/// can lose one, and negative amounts saturate to zero. The math itself is done in base units
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TokenScale {
+    pow: f64
+}
+
+impl TokenScale {
+
+    pub fn new(decimals: u64) -> Self {
+        Self { pow: num_traits::pow(10f64, decimals as usize) }
+    }
+
+    /// Converts a stored amount to base units
+    pub fn to_base_units(&self, amount: f64) -> u64 {
+        (amount * self.pow).round() as u64
+    }
+
+    /// Converts base units to the amount to store
+    pub fn from_base_units(&self, amount: u64) -> f64 {
+        amount as f64 / self.pow
     }
 
-    if stream.stream_resumed_block_time >= stream.escrow_vested_amount_snap_block_time {
-        return Ok(StreamStatus::Running);
+    /// Converts a stored rate (tokens per second) to the fixed point rate of the V2 state
+    pub fn to_rate(&self, rate: f64) -> u128 {
+        (rate * self.pow * RATE_PRECISION as f64) as u128
     }
 
-    return Ok(StreamStatus::Paused);
+    /// Adds base units to a stored amount
+    pub fn checked_add(&self, amount: f64, base_units: u64) -> Result<f64, StreamError> {
+        self.to_base_units(amount)
+            .checked_add(base_units)
+            .map(|amount| self.from_base_units(amount))
+            .ok_or(StreamError::Overflow)
+    }
+
+    /// Subtracts base units from a stored amount, failing if the amount is smaller
+    pub fn checked_sub(&self, amount: f64, base_units: u64) -> Result<f64, StreamError> {
+        self.to_base_units(amount)
+            .checked_sub(base_units)
+            .map(|amount| self.from_base_units(amount))
+            .ok_or(StreamError::Overflow)
+    }
+
+    /// Subtracts base units from a stored amount, down to zero
+    pub fn saturating_sub(&self, amount: f64, base_units: u64) -> f64 {
+        self.from_base_units(self.to_base_units(amount).saturating_sub(base_units))
+    }
 }
 
-pub fn get_beneficiary_withdrawable_amount<'info>( 
-    stream: &StreamV1,
-    clock: &Clock,
-    decimals: u64
+/// Converts a depletion time (unix seconds) to the milliseconds kept in the `*_depletion_utc` fields, 0 if none
+pub fn get_depletion_utc(depletion_time: Option<u64>) -> u64 {
+    depletion_time
+        .and_then(|depletion_time| depletion_time.checked_mul(1000u64))
+        .unwrap_or(0)
+}
+
+/// Whether a `PushVested` keeper tip is due: a full rate interval has passed since the last tip
+/// was paid and the tip is at most `PUSH_VESTED_MAX_TIP_PERCENT` of the pushed amount, so pushing
+/// dust every interval earns nothing
+pub fn is_push_vested_tip_due(
+    keeper_tip: u64,
+    pushed_amount: u64,
+    last_tip_block_time: u64,
+    rate_interval_in_seconds: u64,
+    clock: &Clock
+
+) -> Result<bool, StreamError> {
+
+    if keeper_tip > get_percent_fee(pushed_amount, PUSH_VESTED_MAX_TIP_PERCENT)? {
+        return Ok(false);
+    }
+
+    Ok((clock.unix_timestamp as u64).saturating_sub(last_tip_block_time) >= rate_interval_in_seconds)
+}
+
+pub fn get_percent_fee(
+    amount: u64,
+    fee_bps: u64
 
 ) -> Result<u64, StreamError> {
-    let status = get_stream_status(stream, clock)?;
 
-    //Check if SCHEDULED
-    if status == StreamStatus::Scheduled{
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(StreamError::Overflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(StreamError::Overflow)?;
+
+    Ok(fee as u64)
+}
+
+pub fn get_stream_rate_per_second(
+    rate_amount: u64,
+    rate_interval_in_seconds: u64
+
+) -> Result<u128, StreamError> {
+
+    if rate_interval_in_seconds == 0 {
         return Ok(0);
     }
 
-    //Check if PAUSED
-    let pow = num_traits::pow(10f64, decimals.try_into().unwrap());
-    if status == StreamStatus::Paused {
-        let is_manual_pause = stream.escrow_vested_amount_snap_block_time > stream.stream_resumed_block_time;
-        let paused_withdrawable = match is_manual_pause {
-            true => (stream.escrow_vested_amount_snap * pow) as u64,
-            _ => (stream.allocation_left * pow) as u64
-        };
-        return Ok(paused_withdrawable);
-    }
+    let rate = (rate_amount as u128)
+        .checked_mul(RATE_PRECISION)
+        .ok_or(StreamError::Overflow)?
+        .checked_div(rate_interval_in_seconds as u128)
+        .ok_or(StreamError::Overflow)?;
+
+    Ok(rate)
+}
+
+pub fn get_stream_status_v2<'info>(
+    stream: &StreamV2,
+    clock: &Clock
+
+) -> Result<StreamStatus, StreamError> {
+
+    Ok(get_stream_status_at_v2(stream, clock.unix_timestamp as u64))
+}
+
+pub fn get_stream_vested_amount_v2<'info>(
+    stream: &StreamV2,
+    clock: &Clock
+
+) -> Result<u64, StreamError> {
+
+    get_vested_amount_at_v2(stream, clock.unix_timestamp as u64)
+}
+
+pub fn get_stream_unlocked_amount_v2(
+    stream: &StreamV2,
+    clock: &Clock
+
+) -> Result<u64, StreamError> {
+
+    get_unlocked_amount_at_v2(stream, clock.unix_timestamp as u64)
+}
 
-    //Check if RUNNING
-    if stream.rate_interval_in_seconds <= 0 || stream.rate_amount <= 0.0 {
-        return Err(StreamError::InvalidArgument.into());
+pub fn get_beneficiary_withdrawable_amount_v2<'info>(
+    stream: &StreamV2,
+    clock: &Clock
+
+) -> Result<u64, StreamError> {
+
+    get_withdrawable_amount_at_v2(stream, clock.unix_timestamp as u64)
+}
+
+pub fn get_treasury_pool_redeem_amount(
+    unallocated_balance: u64,
+    treasury_pool_amount: u64,
+    treasury_pool_supply: u64
+
+) -> Result<u64, StreamError> {
+
+    if treasury_pool_supply == 0 {
+        return Ok(0);
     }
-    let rate_amount_per_second = stream.rate_amount / (stream.rate_interval_in_seconds as f64);
-    let block_time_at_last_snap_or_resume = cmp::max(stream.stream_resumed_block_time, stream.escrow_vested_amount_snap_block_time);
-    let elapsed_time_since_last_snap_or_resume = (clock.unix_timestamp as u64)
-                                                .checked_sub(block_time_at_last_snap_or_resume)
-                                                .ok_or(StreamError::Overflow)?;
-    let vested_amount_since_last_snap_or_resume = rate_amount_per_second * elapsed_time_since_last_snap_or_resume as f64; 
-    let allocation_left_vested_amount = ((stream.escrow_vested_amount_snap * pow) as u64)
-                                        .checked_add((vested_amount_since_last_snap_or_resume * pow) as u64)
-                                        .ok_or(StreamError::Overflow)?;
-    let stream_allocation_left = (stream.allocation_left * pow) as u64;
-    let withdrawable = cmp::min(stream_allocation_left, allocation_left_vested_amount);
-    return Ok(withdrawable);
+
+    let redeem_amount = (unallocated_balance as u128)
+        .checked_mul(treasury_pool_amount as u128)
+        .ok_or(StreamError::Overflow)?
+        .checked_div(treasury_pool_supply as u128)
+        .ok_or(StreamError::Overflow)?;
+
+    Ok(redeem_amount as u64)
 }
 
 pub fn check_system_accounts<'info>(
@@ -0,0 +1,132 @@
+// Auto pause behaviour of the stream status and vesting calculations
+
+use money_streaming::{
+    solana_program::sysvar::clock::Clock,
+    state::{ StreamStatus, StreamV1, StreamV2 },
+    utils::{
+        get_stream_status,
+        get_stream_status_v2,
+        get_beneficiary_withdrawable_amount,
+        get_beneficiary_withdrawable_amount_v2
+    }
+};
+
+const START_TIME: u64 = 1_000_000;
+
+fn clock_at(unix_timestamp: u64) -> Clock {
+    Clock {
+        unix_timestamp: unix_timestamp as i64,
+        ..Clock::default()
+    }
+}
+
+fn running_stream_v2(auto_pause_in_seconds: u64) -> StreamV2 {
+    StreamV2 {
+        initialized: true,
+        rate_amount: 10,
+        rate_interval_in_seconds: 1,
+        allocation_assigned: 1_000_000,
+        allocation_left: 1_000_000,
+        start_utc: START_TIME * 1000,
+        escrow_vested_amount_snap_block_time: START_TIME,
+        stream_resumed_block_time: START_TIME,
+        auto_pause_in_seconds,
+        ..StreamV2::default()
+    }
+}
+
+fn running_stream_v1(auto_pause_in_seconds: u64) -> StreamV1 {
+    StreamV1 {
+        initialized: true,
+        rate_amount: 10.0,
+        rate_interval_in_seconds: 1,
+        allocation_assigned: 1_000.0,
+        allocation_left: 1_000.0,
+        start_utc: START_TIME * 1000,
+        escrow_vested_amount_snap_block_time: START_TIME,
+        stream_resumed_block_time: START_TIME,
+        auto_pause_in_seconds,
+        ..StreamV1::default()
+    }
+}
+
+#[test]
+fn stream_without_auto_pause_keeps_running() {
+    let stream = running_stream_v2(0);
+    let clock = clock_at(START_TIME + 10_000);
+
+    assert!(get_stream_status_v2(&stream, &clock).unwrap() == StreamStatus::Running);
+    assert_eq!(get_beneficiary_withdrawable_amount_v2(&stream, &clock).unwrap(), 100_000);
+}
+
+#[test]
+fn stream_runs_until_auto_pause_time() {
+    let stream = running_stream_v2(100);
+    let clock = clock_at(START_TIME + 99);
+
+    assert!(get_stream_status_v2(&stream, &clock).unwrap() == StreamStatus::Running);
+    assert_eq!(get_beneficiary_withdrawable_amount_v2(&stream, &clock).unwrap(), 990);
+}
+
+#[test]
+fn stream_is_paused_after_auto_pause_time() {
+    let stream = running_stream_v2(100);
+
+    for elapsed in [100u64, 101, 5_000].iter() {
+        let clock = clock_at(START_TIME + elapsed);
+        assert!(get_stream_status_v2(&stream, &clock).unwrap() == StreamStatus::Paused);
+        // Vesting stops at the auto pause time
+        assert_eq!(get_beneficiary_withdrawable_amount_v2(&stream, &clock).unwrap(), 1_000);
+    }
+}
+
+#[test]
+fn auto_pause_counts_from_last_resume() {
+    let mut stream = running_stream_v2(100);
+    // Manually paused after 50 seconds and resumed 1000 seconds later
+    stream.escrow_vested_amount_snap = 500;
+    stream.escrow_vested_amount_snap_block_time = START_TIME + 50;
+    stream.stream_resumed_block_time = START_TIME + 1_050;
+
+    let clock = clock_at(START_TIME + 1_149);
+    assert!(get_stream_status_v2(&stream, &clock).unwrap() == StreamStatus::Running);
+    assert_eq!(get_beneficiary_withdrawable_amount_v2(&stream, &clock).unwrap(), 1_490);
+
+    let clock = clock_at(START_TIME + 2_000);
+    assert!(get_stream_status_v2(&stream, &clock).unwrap() == StreamStatus::Paused);
+    assert_eq!(get_beneficiary_withdrawable_amount_v2(&stream, &clock).unwrap(), 1_500);
+}
+
+#[test]
+fn manual_pause_is_not_affected_by_auto_pause() {
+    let mut stream = running_stream_v2(100);
+    stream.escrow_vested_amount_snap = 300;
+    stream.escrow_vested_amount_snap_block_time = START_TIME + 30;
+
+    let clock = clock_at(START_TIME + 5_000);
+    assert!(get_stream_status_v2(&stream, &clock).unwrap() == StreamStatus::Paused);
+    assert_eq!(get_beneficiary_withdrawable_amount_v2(&stream, &clock).unwrap(), 300);
+}
+
+#[test]
+fn auto_paused_stream_does_not_exceed_allocation() {
+    let mut stream = running_stream_v2(100);
+    stream.allocation_left = 400;
+
+    let clock = clock_at(START_TIME + 5_000);
+    assert!(get_stream_status_v2(&stream, &clock).unwrap() == StreamStatus::Paused);
+    assert_eq!(get_beneficiary_withdrawable_amount_v2(&stream, &clock).unwrap(), 400);
+}
+
+#[test]
+fn stream_v1_is_paused_after_auto_pause_time() {
+    let stream = running_stream_v1(100);
+
+    let clock = clock_at(START_TIME + 50);
+    assert!(get_stream_status(&stream, &clock).unwrap() == StreamStatus::Running);
+    assert_eq!(get_beneficiary_withdrawable_amount(&stream, &clock, 6).unwrap(), 500_000_000);
+
+    let clock = clock_at(START_TIME + 5_000);
+    assert!(get_stream_status(&stream, &clock).unwrap() == StreamStatus::Paused);
+    assert_eq!(get_beneficiary_withdrawable_amount(&stream, &clock, 6).unwrap(), 1_000_000_000);
+}
@@ -0,0 +1,434 @@
+// Banks test validator harness shared by the integration tests
+#![allow(dead_code)]
+
+use std::sync::Once;
+use base64::{ Engine as _, engine::general_purpose::STANDARD };
+use solana_program_test::{ processor, BanksClientError, ProgramTest, ProgramTestContext };
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_stubs::{ set_syscall_stubs, SyscallStubs },
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{ Keypair, Signer },
+    transaction::Transaction
+};
+use spl_associated_token_account::get_associated_token_address;
+use money_streaming::{
+    instruction,
+    processor::Processor,
+    constants::*,
+    events::{ decode_events, MspEvent, PROGRAM_DATA_LOG_PREFIX },
+    state::{ Stream, Treasury },
+    utils::{ find_treasury_address, find_treasury_pool_mint_address }
+};
+
+pub const DECIMALS: u8 = 6;
+pub const TOKEN: u64 = 1_000_000;
+pub const SOL: u64 = 1_000_000_000;
+pub const TREASURY_SLOT: u64 = 1;
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub mint: Pubkey,
+    pub fee_treasury: Pubkey,
+    pub treasurer: Keypair,
+    pub beneficiary: Keypair
+}
+
+pub struct TestTreasury {
+    pub address: Pubkey,
+    pub pool_mint: Pubkey
+}
+
+// Names and labels are stored (and sent) as 32 bytes
+pub fn name(name: &str) -> String {
+    format!("{:<32}", name)
+}
+
+pub fn mint_account(mint_authority: Pubkey, supply: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(mint_authority),
+        supply,
+        decimals: DECIMALS,
+        is_initialized: true,
+        freeze_authority: COption::None
+    }.pack_into_slice(&mut data);
+
+    Account { lamports: SOL, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+}
+
+pub fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    }.pack_into_slice(&mut data);
+
+    Account { lamports: SOL, data, owner: spl_token::id(), executable: false, rent_epoch: 0 }
+}
+
+pub fn system_account(lamports: u64) -> Account {
+    Account { lamports, ..Account::default() }
+}
+
+pub fn program_account<T: Pack>(state: &T) -> Account {
+    let mut data = vec![0; T::LEN];
+    state.pack_into_slice(&mut data);
+
+    Account { lamports: SOL, data, owner: money_streaming::id(), executable: false, rent_epoch: 0 }
+}
+
+pub fn program_test() -> (ProgramTest, Pubkey, Keypair, Keypair) {
+    let mut program_test = ProgramTest::new(
+        "money_streaming", money_streaming::id(), processor!(Processor::process)
+    );
+
+    let mint = Pubkey::new_unique();
+    let treasurer = Keypair::new();
+    let beneficiary = Keypair::new();
+    let fee_treasury: Pubkey = FEE_TREASURY_ACCOUNT.parse().unwrap();
+
+    program_test.add_account(mint, mint_account(Pubkey::new_unique(), 1_000 * TOKEN));
+    program_test.add_account(treasurer.pubkey(), system_account(10 * SOL));
+    program_test.add_account(beneficiary.pubkey(), system_account(SOL));
+    program_test.add_account(fee_treasury, system_account(SOL));
+    program_test.add_account(
+        get_associated_token_address(&treasurer.pubkey(), &mint),
+        token_account(mint, treasurer.pubkey(), 1_000 * TOKEN)
+    );
+
+    (program_test, mint, treasurer, beneficiary)
+}
+
+// The native processor prints `sol_log_data` to stdout, this sends the events to the
+// transaction logs instead (as `Program log: Program data: ...`) and forwards everything else
+struct EventLogStubs(Box<dyn SyscallStubs>);
+
+struct DefaultStubs;
+
+impl SyscallStubs for DefaultStubs {}
+
+impl SyscallStubs for EventLogStubs {
+    fn sol_log(&self, message: &str) { self.0.sol_log(message) }
+    fn sol_log_compute_units(&self) { self.0.sol_log_compute_units() }
+    fn sol_remaining_compute_units(&self) -> u64 { self.0.sol_remaining_compute_units() }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]]
+    ) -> ProgramResult {
+        self.0.sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 { self.0.sol_get_clock_sysvar(var_addr) }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 { self.0.sol_get_epoch_schedule_sysvar(var_addr) }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 { self.0.sol_get_fees_sysvar(var_addr) }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 { self.0.sol_get_rent_sysvar(var_addr) }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 { self.0.sol_get_epoch_rewards_sysvar(var_addr) }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 { self.0.sol_get_last_restart_slot(var_addr) }
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) { self.0.sol_memcpy(dst, src, n) }
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) { self.0.sol_memmove(dst, src, n) }
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize, result: *mut i32) { self.0.sol_memcmp(s1, s2, n, result) }
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) { self.0.sol_memset(s, c, n) }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> { self.0.sol_get_return_data() }
+    fn sol_set_return_data(&self, data: &[u8]) { self.0.sol_set_return_data(data) }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 { self.0.sol_get_stack_height() }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<String> = fields.iter().map(|field| STANDARD.encode(field)).collect();
+        self.0.sol_log(&format!("{}{}", PROGRAM_DATA_LOG_PREFIX, fields.join(" ")));
+    }
+}
+
+pub async fn start(program_test: ProgramTest, mint: Pubkey, treasurer: Keypair, beneficiary: Keypair) -> TestEnv {
+    let context = program_test.start_with_context().await;
+    // The program test stubs are installed by the first start. Wrap them once, no test
+    // processes a transaction before every start got past this point
+    static EVENT_LOG_STUBS: Once = Once::new();
+    EVENT_LOG_STUBS.call_once(|| {
+        let stubs = set_syscall_stubs(Box::new(DefaultStubs));
+        set_syscall_stubs(Box::new(EventLogStubs(stubs)));
+    });
+
+    TestEnv {
+        context,
+        mint,
+        fee_treasury: FEE_TREASURY_ACCOUNT.parse().unwrap(),
+        treasurer,
+        beneficiary
+    }
+}
+
+pub async fn setup() -> TestEnv {
+    let (program_test, mint, treasurer, beneficiary) = program_test();
+    start(program_test, mint, treasurer, beneficiary).await
+}
+
+// Environment streaming the native mint (wrapped SOL)
+pub async fn native_setup() -> TestEnv {
+    let (mut program_test, _, treasurer, beneficiary) = program_test();
+    let mint = spl_token::native_mint::id();
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        decimals: spl_token::native_mint::DECIMALS,
+        is_initialized: true,
+        ..spl_token::state::Mint::default()
+    }.pack_into_slice(&mut data);
+
+    program_test.add_account(mint, Account { lamports: SOL, data, owner: spl_token::id(), executable: false, rent_epoch: 0 });
+    start(program_test, mint, treasurer, beneficiary).await
+}
+
+// Legacy (v0) treasury funded with `balance` and a legacy stream of 1 token per second
+// started at `start_time` with `deposits` allocated to it
+pub fn legacy_setup(balance: u64, deposits: f64, start_time: u64) -> (ProgramTest, Pubkey, Keypair, Keypair, TestTreasury, Pubkey) {
+    let (mut program_test, mint, treasurer, beneficiary) = program_test();
+    let (treasury, _) = find_treasury_address(&money_streaming::id(), &treasurer.pubkey(), TREASURY_SLOT);
+    let (pool_mint, _) = find_treasury_pool_mint_address(
+        &money_streaming::id(), &treasurer.pubkey(), &treasury, TREASURY_SLOT
+    );
+    let stream = Pubkey::new_unique();
+
+    program_test.add_account(treasury, program_account(&Treasury {
+        initialized: true,
+        treasury_block_height: TREASURY_SLOT,
+        treasury_mint_address: pool_mint,
+        treasury_base_address: treasurer.pubkey()
+    }));
+    program_test.add_account(pool_mint, mint_account(treasury, 0));
+    program_test.add_account(get_associated_token_address(&treasury, &mint), token_account(mint, treasury, balance));
+    program_test.add_account(stream, program_account(&Stream {
+        initialized: true,
+        stream_name: name("Legacy stream"),
+        treasurer_address: treasurer.pubkey(),
+        rate_amount: 1.0,
+        rate_interval_in_seconds: 1,
+        start_utc: start_time * 1000,
+        beneficiary_address: beneficiary.pubkey(),
+        beneficiary_associated_token: mint,
+        treasury_address: treasury,
+        total_deposits: deposits,
+        escrow_vested_amount_snap_block_time: start_time,
+        stream_resumed_block_time: start_time,
+        ..Stream::default()
+    }));
+
+    (program_test, mint, treasurer, beneficiary, TestTreasury { address: treasury, pool_mint }, stream)
+}
+
+impl TestEnv {
+
+    pub async fn process(&mut self, instruction: Instruction, signers: &[&Keypair]) -> Result<(), BanksClientError> {
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction], Some(&self.context.payer.pubkey()), &all_signers, blockhash
+        );
+
+        self.context.banks_client.process_transaction(transaction).await
+    }
+
+    // Processes the instruction, which must succeed, and returns the events it emitted
+    pub async fn process_events(&mut self, instruction: Instruction, signers: &[&Keypair]) -> Vec<MspEvent> {
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction], Some(&self.context.payer.pubkey()), &all_signers, blockhash
+        );
+        let result = self.context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+        result.result.unwrap();
+        let logs: Vec<String> = result.metadata.unwrap().log_messages.iter()
+            .map(|log| log.strip_prefix("Program log: ").unwrap_or(log).to_string())
+            .collect();
+
+        decode_events(&logs)
+    }
+
+    pub async fn now(&mut self) -> u64 {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp as u64
+    }
+
+    pub async fn warp(&mut self, seconds: u64) {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds as i64;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn account(&mut self, address: Pubkey) -> Option<Account> {
+        self.context.banks_client.get_account(address).await.unwrap()
+    }
+
+    pub async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.account(address).await.map_or(0, |account| account.lamports)
+    }
+
+    pub async fn token_balance(&mut self, owner: Pubkey) -> u64 {
+        let address = get_associated_token_address(&owner, &self.mint);
+        self.account(address).await.map_or(0, |account| {
+            spl_token::state::Account::unpack(&account.data).unwrap().amount
+        })
+    }
+
+    pub async fn unpack<T: Pack>(&mut self, address: Pubkey) -> T {
+        T::unpack_from_slice(&self.account(address).await.unwrap().data).unwrap()
+    }
+
+    // Appends `bytes` zeroed bytes to an account, as a realloc to a later layout would
+    pub async fn grow_account(&mut self, address: Pubkey, bytes: usize) {
+        let mut account = self.account(address).await.unwrap();
+        account.data.resize(account.data.len() + bytes, 0);
+        account.lamports += SOL;
+        self.context.set_account(&address, &account.into());
+    }
+
+    // Overwrites the state of an existing program account
+    pub async fn set_state<T: Pack>(&mut self, address: Pubkey, state: &T) {
+        let mut account = self.account(address).await.unwrap();
+        state.pack_into_slice(&mut account.data);
+        self.context.set_account(&address, &account.into());
+    }
+
+    pub async fn create_treasury(&mut self) -> TestTreasury {
+        let treasurer = self.treasurer.pubkey();
+        let (address, _) = find_treasury_address(&money_streaming::id(), &treasurer, TREASURY_SLOT);
+        let (pool_mint, _) = find_treasury_pool_mint_address(&money_streaming::id(), &treasurer, &address, TREASURY_SLOT);
+        let ix = instruction::create_treasury(
+            &money_streaming::id(), treasurer, self.fee_treasury, None,
+            TREASURY_SLOT, name("Treasury"), 0, false
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer]).await.unwrap();
+
+        TestTreasury { address, pool_mint }
+    }
+
+    pub async fn create_treasury_v2(&mut self) -> TestTreasury {
+        let treasurer = self.treasurer.pubkey();
+        let (address, _) = find_treasury_address(&money_streaming::id(), &treasurer, TREASURY_SLOT);
+        let (pool_mint, _) = find_treasury_pool_mint_address(&money_streaming::id(), &treasurer, &address, TREASURY_SLOT);
+        let ix = instruction::create_treasury_v2(
+            &money_streaming::id(), treasurer, self.fee_treasury, None,
+            TREASURY_SLOT, name("Treasury"), 0, false
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer]).await.unwrap();
+
+        TestTreasury { address, pool_mint }
+    }
+
+    pub async fn add_funds_v2(&mut self, treasury: &TestTreasury, amount: u64) {
+        let ix = instruction::add_funds_v2(
+            &money_streaming::id(), self.treasurer.pubkey(), self.mint, treasury.address,
+            treasury.pool_mint, self.fee_treasury, None, amount, 2, Pubkey::default(), false
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer]).await.unwrap();
+    }
+
+    pub async fn add_funds(&mut self, treasury: &TestTreasury, amount: f64, allocation_type: u8, stream: Pubkey) {
+        let ix = instruction::add_funds(
+            &money_streaming::id(), self.treasurer.pubkey(), self.mint, treasury.address,
+            treasury.pool_mint, self.fee_treasury, None, amount, allocation_type, stream
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer]).await.unwrap();
+    }
+
+    // A stream of 1 token per second starting now
+    pub async fn create_stream(&mut self, treasury: &TestTreasury, allocation: f64) -> Pubkey {
+        self.create_stream_with_rate(treasury, 1.0, 1, allocation).await
+    }
+
+    // A stream of `rate_amount` tokens every `rate_interval_in_seconds` starting now
+    pub async fn create_stream_with_rate(
+        &mut self,
+        treasury: &TestTreasury,
+        rate_amount: f64,
+        rate_interval_in_seconds: u64,
+        allocation: f64
+    ) -> Pubkey {
+        let stream = Keypair::new();
+        let start_utc = self.now().await * 1000;
+        let ix = instruction::create_stream(
+            &money_streaming::id(), self.treasurer.pubkey(), treasury.address, self.mint,
+            self.beneficiary.pubkey(), stream.pubkey(), self.fee_treasury, None,
+            name("Stream"), rate_amount, rate_interval_in_seconds, 0.0, allocation,
+            start_utc, start_utc, 0, 0.0, 0.0, 0
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer, &stream]).await.unwrap();
+
+        stream.pubkey()
+    }
+
+    pub async fn withdraw(&mut self, treasury: &TestTreasury, stream: Pubkey, amount: f64) -> Result<(), BanksClientError> {
+        let ix = instruction::withdraw(
+            &money_streaming::id(), self.beneficiary.pubkey(), self.mint, treasury.address,
+            stream, self.fee_treasury, None, amount
+        ).unwrap();
+
+        let beneficiary = self.beneficiary.insecure_clone();
+        self.process(ix, &[&beneficiary]).await
+    }
+
+    pub async fn pause_stream(&mut self, treasury: &TestTreasury, stream: Pubkey) -> Result<(), BanksClientError> {
+        let ix = instruction::pause_stream(
+            &money_streaming::id(), self.treasurer.pubkey(), self.mint, treasury.address, stream, self.fee_treasury
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer]).await
+    }
+
+    pub async fn resume_stream(&mut self, treasury: &TestTreasury, stream: Pubkey) -> Result<(), BanksClientError> {
+        let ix = instruction::resume_stream(
+            &money_streaming::id(), self.treasurer.pubkey(), self.mint, treasury.address, stream, self.fee_treasury
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer]).await
+    }
+
+    pub async fn close_stream(&mut self, treasury: &TestTreasury, stream: Pubkey, auto_close_treasury: bool) {
+        let ix = instruction::close_stream(
+            &money_streaming::id(), self.treasurer.pubkey(), self.treasurer.pubkey(),
+            self.beneficiary.pubkey(), self.mint, treasury.address, treasury.pool_mint,
+            stream, self.fee_treasury, None, auto_close_treasury, false
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer]).await.unwrap();
+    }
+
+    pub async fn close_treasury(&mut self, treasury: &TestTreasury) {
+        let ix = instruction::close_treasury(
+            &money_streaming::id(), self.treasurer.pubkey(), self.mint, treasury.address,
+            treasury.pool_mint, self.fee_treasury, None
+        ).unwrap();
+
+        let treasurer = self.treasurer.insecure_clone();
+        self.process(ix, &[&treasurer]).await.unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+// Decoding of the program events from the transaction logs, and the events each instruction emits
+
+mod common;
+
+use base64::{ Engine as _, engine::general_purpose::STANDARD };
+use borsh::BorshSerialize;
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::get_associated_token_address;
+use money_streaming::{
+    instruction,
+    constants::*,
+    solana_program::pubkey::Pubkey,
+    events::{ decode_events, MspEvent, MSP_EVENT_TAG, PROGRAM_DATA_LOG_PREFIX }
+};
+use common::*;
+
+fn event_log(event: &MspEvent) -> String {
+    format!(
+        "{}{} {}",
+        PROGRAM_DATA_LOG_PREFIX,
+        STANDARD.encode(MSP_EVENT_TAG),
+        STANDARD.encode(event.try_to_vec().unwrap())
+    )
+}
+
+#[test]
+fn decodes_events_in_order() {
+    let withdrawn = MspEvent::Withdrawn {
+        stream: Pubkey::new_unique(),
+        treasury: Pubkey::new_unique(),
+        beneficiary: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        amount: 1_000,
+        fee: 2
+    };
+    let closed = MspEvent::TreasuryClosed {
+        treasury: Pubkey::new_unique(),
+        treasurer: Pubkey::new_unique()
+    };
+    let logs = vec![
+        "Program H6wJxgkcc93yeUFnsZHgor3Q3pSWgGpEysfqKrwLtMko invoke [1]".to_string(),
+        "Program log: Instruction: Withdraw".to_string(),
+        event_log(&withdrawn),
+        event_log(&closed),
+        "Program H6wJxgkcc93yeUFnsZHgor3Q3pSWgGpEysfqKrwLtMko success".to_string()
+    ];
+
+    assert_eq!(decode_events(&logs), vec![withdrawn, closed]);
+}
+
+#[test]
+fn ignores_foreign_program_data() {
+    let data = STANDARD.encode([1u8, 2, 3]);
+    let logs = [
+        format!("{}{}", PROGRAM_DATA_LOG_PREFIX, data),
+        format!("{}{} {}", PROGRAM_DATA_LOG_PREFIX, STANDARD.encode(b"other"), data),
+        format!("{}{} {}", PROGRAM_DATA_LOG_PREFIX, STANDARD.encode(MSP_EVENT_TAG), data)
+    ];
+
+    assert!(decode_events(&logs).is_empty());
+}
+
+#[tokio::test]
+async fn legacy_paths_emit_events() {
+    let (program_test, mint, treasurer, beneficiary, treasury, stream) = legacy_setup(50 * TOKEN, 50.0, 0);
+    let mut env = start(program_test, mint, treasurer, beneficiary).await;
+    let program_id = money_streaming::id();
+    let (treasurer_signer, beneficiary_signer) = (env.treasurer.insecure_clone(), env.beneficiary.insecure_clone());
+    let treasurer = treasurer_signer.pubkey();
+    let beneficiary = beneficiary_signer.pubkey();
+    let fee_treasury = env.fee_treasury;
+
+    let ix = instruction::add_funds(
+        &program_id, treasurer, mint, treasury.address, treasury.pool_mint, fee_treasury, None, 10.0, 0, stream
+    ).unwrap();
+    assert_eq!(env.process_events(ix, &[&treasurer_signer]).await, vec![MspEvent::FundsAdded {
+        treasury: treasury.address,
+        stream,
+        contributor: treasurer,
+        mint,
+        amount: 10 * TOKEN,
+        allocation_type: 0
+    }]);
+
+    let ix = instruction::withdraw(&program_id, beneficiary, mint, treasury.address, stream, fee_treasury, None, 20.0).unwrap();
+    assert_eq!(env.process_events(ix, &[&beneficiary_signer]).await, vec![MspEvent::Withdrawn {
+        stream,
+        treasury: treasury.address,
+        beneficiary,
+        mint,
+        amount: 20 * TOKEN,
+        fee: 20 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR
+    }]);
+
+    let ix = instruction::close_stream(
+        &program_id, treasurer, treasurer, beneficiary, mint, treasury.address, treasury.pool_mint,
+        stream, fee_treasury, None, true, false
+    ).unwrap();
+    assert_eq!(env.process_events(ix, &[&treasurer_signer]).await, vec![
+        MspEvent::StreamClosed { stream, treasury: treasury.address, initializer: treasurer, treasury_closed: true },
+        MspEvent::TreasuryClosed { treasury: treasury.address, treasurer }
+    ]);
+
+    // A legacy treasury closed on its own
+    let (program_test, mint, treasurer_signer, beneficiary, treasury, stream) = legacy_setup(50 * TOKEN, 0.0, 0);
+    let mut env = start(program_test, mint, treasurer_signer.insecure_clone(), beneficiary).await;
+    let treasurer = treasurer_signer.pubkey();
+    env.close_stream(&treasury, stream, false).await;
+
+    let ix = instruction::close_treasury(
+        &program_id, treasurer, mint, treasury.address, treasury.pool_mint, fee_treasury, None
+    ).unwrap();
+    assert_eq!(env.process_events(ix, &[&treasurer_signer]).await, vec![
+        MspEvent::TreasuryClosed { treasury: treasury.address, treasurer }
+    ]);
+}
+
+#[tokio::test]
+async fn treasury_instructions_emit_events() {
+    let mut env = setup().await;
+    let program_id = money_streaming::id();
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+    let mint = env.mint;
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 40.0).await;
+
+    let ix = instruction::treasury_withdraw(&program_id, treasurer, mint, treasury.address, 10 * TOKEN).unwrap();
+    assert_eq!(env.process_events(ix, &[&treasurer_signer]).await, vec![MspEvent::TreasuryWithdrawn {
+        treasury: treasury.address,
+        treasurer,
+        destination: get_associated_token_address(&treasurer, &mint),
+        mint,
+        amount: 10 * TOKEN
+    }]);
+
+    let ix = instruction::allocate(&program_id, treasurer, mint, treasury.address, stream, 20 * TOKEN, 0).unwrap();
+    assert_eq!(env.process_events(ix, &[&treasurer_signer]).await, vec![MspEvent::FundsAllocated {
+        treasury: treasury.address,
+        stream,
+        treasurer,
+        mint,
+        amount: 20 * TOKEN,
+        allocation_type: 0
+    }]);
+
+    // 10 of the 100 pool tokens are a claim on a tenth of the 30 unallocated tokens
+    let ix = instruction::redeem(&program_id, treasurer, mint, treasury.address, treasury.pool_mint, 10 * TOKEN).unwrap();
+    assert_eq!(env.process_events(ix, &[&treasurer_signer]).await, vec![MspEvent::Redeemed {
+        treasury: treasury.address,
+        contributor: treasurer,
+        mint,
+        burned_amount: 10 * TOKEN,
+        amount: 3 * TOKEN
+    }]);
+}
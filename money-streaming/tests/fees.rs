@@ -0,0 +1,62 @@
+// How a fee tier maps onto the global fee schedule
+
+use money_streaming::{
+    solana_program::{ clock::Clock, pubkey::Pubkey },
+    constants::*,
+    state::{ FeeConfig, FeeTier }
+};
+
+const NOW: i64 = 1_000_000;
+
+fn fee_tier(expiry_utc: u64) -> FeeTier {
+    FeeTier {
+        initialized: true,
+        subject_address: Pubkey::new_unique(),
+        flat_fee: 1_000,
+        withdraw_percent_fee: 5,
+        expiry_utc,
+        bump: 0
+    }
+}
+
+fn clock() -> Clock {
+    Clock { unix_timestamp: NOW, ..Clock::default() }
+}
+
+#[test]
+fn tier_replaces_every_flat_and_percent_fee() {
+    let fee_config = FeeConfig::default();
+    let fees = fee_tier(0).apply(&fee_config, &clock());
+
+    // The flat fee covers every lamport fee
+    assert_eq!(fees.create_treasury_flat_fee, 1_000);
+    assert_eq!(fees.create_stream_flat_fee, 1_000);
+    assert_eq!(fees.add_funds_flat_fee, 1_000);
+    assert_eq!(fees.propose_update_flat_fee, 1_000);
+    assert_eq!(fees.close_stream_flat_fee, 1_000);
+    assert_eq!(fees.close_treasury_flat_fee, 1_000);
+    // The withdraw percent fee covers every token fee
+    assert_eq!(fees.withdraw_percent_fee, 5);
+    assert_eq!(fees.close_stream_percent_fee, 5);
+    assert_eq!(fees.close_treasury_percent_fee, 5);
+    // The recipient and the authority stay the global ones
+    assert_eq!(fees.fee_treasury_address, fee_config.fee_treasury_address);
+    assert_eq!(fees.authority, fee_config.authority);
+}
+
+#[test]
+fn expired_or_closed_tier_keeps_the_global_fees() {
+    let fee_config = FeeConfig::default();
+
+    let fees = fee_tier(NOW as u64 * 1000).apply(&fee_config, &clock());
+    assert_eq!(fees.create_stream_flat_fee, CREATE_STREAM_FLAT_FEE);
+    assert_eq!(fees.close_stream_percent_fee, CLOSE_STREAM_PERCENT_FEE);
+
+    let fees = FeeTier { initialized: false, ..fee_tier(0) }.apply(&fee_config, &clock());
+    assert_eq!(fees.close_treasury_flat_fee, CLOSE_TREASURY_FLAT_FEE);
+    assert_eq!(fees.withdraw_percent_fee, WITHDRAW_PERCENT_FEE);
+
+    // Tiers expiring later still apply
+    let fees = fee_tier(NOW as u64 * 1000 + 1).apply(&fee_config, &clock());
+    assert_eq!(fees.add_funds_flat_fee, 1_000);
+}
@@ -0,0 +1,209 @@
+// Accounting invariants of the V1 streams and treasuries over random sequences of instructions
+
+mod common;
+
+use proptest::prelude::*;
+use solana_program_test::tokio;
+use solana_sdk::{ pubkey::Pubkey, signature::Signer };
+use money_streaming::{
+    math::get_withdrawable_amount_at,
+    state::{ StreamV1, TreasuryV1 }
+};
+use common::*;
+
+const TREASURY_DEPOSIT: u64 = 300 * TOKEN;
+
+#[derive(Clone, Debug)]
+struct StreamParams {
+    rate_amount: u64,
+    rate_interval_in_seconds: u64,
+    allocation: u64
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Warp(u64),
+    AddFunds { stream: Option<usize>, amount: u64 },
+    Withdraw { stream: usize, percent: u64 },
+    Pause(usize),
+    Resume(usize),
+    Close(usize)
+}
+
+fn stream_params() -> impl Strategy<Value = StreamParams> {
+    (1..=5 * TOKEN, 1..=10u64, 1..=100 * TOKEN).prop_map(|(rate_amount, rate_interval_in_seconds, allocation)| {
+        StreamParams { rate_amount, rate_interval_in_seconds, allocation }
+    })
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (0..=30u64).prop_map(Op::Warp),
+        2 => (proptest::option::of(0..3usize), 1..=20 * TOKEN)
+            .prop_map(|(stream, amount)| Op::AddFunds { stream, amount }),
+        3 => (0..3usize, 0..=100u64).prop_map(|(stream, percent)| Op::Withdraw { stream, percent }),
+        2 => (0..3usize).prop_map(Op::Pause),
+        2 => (0..3usize).prop_map(Op::Resume),
+        1 => (0..3usize).prop_map(Op::Close)
+    ]
+}
+
+// The amounts kept as `f64` in the V1 state, in token base units
+fn units(amount: f64) -> u64 {
+    (amount * TOKEN as f64).round() as u64
+}
+
+fn tokens(amount: u64) -> f64 {
+    amount as f64 / TOKEN as f64
+}
+
+struct Snapshot {
+    withdrawable: u64,
+    stream_allocation_left: u64,
+    treasury_balance: u64,
+    treasury_allocation_left: u64,
+    treasury_amount: u64
+}
+
+async fn snapshot(env: &mut TestEnv, treasury: &TestTreasury, stream: Pubkey) -> Snapshot {
+    let now = env.now().await;
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+
+    Snapshot {
+        withdrawable: get_withdrawable_amount_at(&stream_state, now, DECIMALS.into()).unwrap(),
+        stream_allocation_left: units(stream_state.allocation_left),
+        treasury_balance: units(treasury_state.balance),
+        treasury_allocation_left: units(treasury_state.allocation_left),
+        treasury_amount: env.token_balance(treasury.address).await
+    }
+}
+
+async fn check_invariants(
+    env: &mut TestEnv,
+    treasury: &TestTreasury,
+    streams: &[Option<Pubkey>]
+
+) -> Result<(), TestCaseError> {
+
+    let now = env.now().await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    let treasury_amount = env.token_balance(treasury.address).await;
+
+    prop_assert_eq!(units(treasury_state.balance), treasury_amount, "treasury balance");
+
+    let mut streams_allocation_left = 0;
+
+    for stream in streams.iter().flatten() {
+        let stream_state: StreamV1 = env.unpack(*stream).await;
+        let withdrawable = get_withdrawable_amount_at(&stream_state, now, DECIMALS.into()).unwrap();
+
+        prop_assert!(
+            withdrawable <= units(stream_state.allocation_left),
+            "withdrawable {} exceeds the stream allocation left {}", withdrawable, stream_state.allocation_left
+        );
+
+        streams_allocation_left += units(stream_state.allocation_left);
+    }
+
+    prop_assert!(
+        streams_allocation_left <= units(treasury_state.allocation_left),
+        "streams allocation left {} exceeds the treasury allocation left {}",
+        streams_allocation_left, treasury_state.allocation_left
+    );
+
+    // Tokens only move between the treasurer, the treasury, the beneficiary and the fee treasury
+    let treasurer = env.treasurer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+    let fee_treasury = env.fee_treasury;
+    let total = env.token_balance(treasurer).await
+        + treasury_amount
+        + env.token_balance(beneficiary).await
+        + env.token_balance(fee_treasury).await;
+
+    prop_assert_eq!(total, 1_000 * TOKEN, "total token supply");
+
+    Ok(())
+}
+
+async fn run(params: Vec<StreamParams>, ops: Vec<Op>) -> Result<(), TestCaseError> {
+    let mut env = setup().await;
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, tokens(TREASURY_DEPOSIT), 2, Pubkey::default()).await;
+
+    let mut streams = vec![];
+
+    for stream in &params {
+        let address = env.create_stream_with_rate(
+            &treasury, tokens(stream.rate_amount), stream.rate_interval_in_seconds, tokens(stream.allocation)
+        ).await;
+
+        streams.push(Some(address));
+    }
+
+    check_invariants(&mut env, &treasury, &streams).await?;
+
+    for op in ops {
+        match op {
+            Op::Warp(seconds) => env.warp(seconds).await,
+            Op::AddFunds { stream, amount } => {
+                match stream.and_then(|index| streams.get(index).copied().flatten()) {
+                    Some(stream) => env.add_funds(&treasury, tokens(amount), 0, stream).await,
+                    None => env.add_funds(&treasury, tokens(amount), 2, Pubkey::default()).await
+                }
+            },
+            Op::Withdraw { stream, percent } => {
+                if let Some(stream) = streams.get(stream).copied().flatten() {
+                    let before = snapshot(&mut env, &treasury, stream).await;
+                    let amount = before.withdrawable * percent / 100;
+
+                    if amount > 0 {
+                        let result = env.withdraw(&treasury, stream, tokens(amount)).await;
+                        prop_assert!(result.is_ok(), "withdrawing {} of {} failed: {:?}", amount, before.withdrawable, result);
+                    }
+                }
+            },
+            Op::Pause(stream) | Op::Resume(stream) => {
+                if let Some(stream) = streams.get(stream).copied().flatten() {
+                    let before = snapshot(&mut env, &treasury, stream).await;
+                    // Either instruction may be rejected, e.g. pausing a paused stream
+                    let _ = match op {
+                        Op::Pause(_) => env.pause_stream(&treasury, stream).await,
+                        _ => env.resume_stream(&treasury, stream).await
+                    };
+                    let after = snapshot(&mut env, &treasury, stream).await;
+
+                    prop_assert_eq!(after.withdrawable, before.withdrawable, "withdrawable after {:?}", op);
+                    prop_assert_eq!(after.stream_allocation_left, before.stream_allocation_left);
+                    prop_assert_eq!(after.treasury_balance, before.treasury_balance);
+                    prop_assert_eq!(after.treasury_allocation_left, before.treasury_allocation_left);
+                    prop_assert_eq!(after.treasury_amount, before.treasury_amount);
+                }
+            },
+            Op::Close(stream) => {
+                if let Some(address) = streams.get(stream).copied().flatten() {
+                    env.close_stream(&treasury, address, false).await;
+                    streams[stream] = None;
+                }
+            }
+        }
+
+        check_invariants(&mut env, &treasury, &streams).await?;
+    }
+
+    Ok(())
+}
+
+proptest! {
+    // Every case boots a test validator, keep the number of cases low
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn stream_accounting_invariants(
+        params in proptest::collection::vec(stream_params(), 1..=3),
+        ops in proptest::collection::vec(op(), 1..20)
+    ) {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(run(params, ops))?;
+    }
+}
@@ -0,0 +1,190 @@
+// Off-chain projections with the clock-free stream math
+
+use money_streaming::{
+    solana_program::clock::Clock,
+    constants::RATE_PRECISION,
+    extensions::answer_update_update_stream_v2,
+    utils::TokenScale,
+    state::{ StreamStatus, StreamTerms, StreamV1, StreamV2, TreasuryV2, VestingMode },
+    math::{
+        get_stream_status_at,
+        get_withdrawable_amount_at,
+        get_stream_status_at_v2,
+        get_withdrawable_amount_at_v2,
+        get_unvested_amount_at_v2,
+        get_depletion_time,
+        get_treasury_depletion_time_v2,
+        get_stream_depletion_time_v2
+    }
+};
+
+const START_TIME: u64 = 1_000_000;
+
+fn running_stream(end_utc: u64) -> StreamV2 {
+    StreamV2 {
+        initialized: true,
+        rate_amount: 10,
+        rate_interval_in_seconds: 1,
+        allocation_assigned: 1_000,
+        allocation_left: 1_000,
+        start_utc: START_TIME * 1000,
+        escrow_vested_amount_snap_block_time: START_TIME,
+        stream_resumed_block_time: START_TIME,
+        end_utc,
+        ..StreamV2::default()
+    }
+}
+
+#[test]
+fn projects_vesting_at_future_times() {
+    let stream = running_stream(0);
+
+    assert!(get_stream_status_at_v2(&stream, START_TIME - 1) == StreamStatus::Scheduled);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME - 1).unwrap(), 0);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 30).unwrap(), 300);
+    assert_eq!(get_unvested_amount_at_v2(&stream, START_TIME + 30).unwrap(), 700);
+    // The vested amount never exceeds the allocation left
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 500).unwrap(), 1_000);
+    assert_eq!(get_unvested_amount_at_v2(&stream, START_TIME + 500).unwrap(), 0);
+}
+
+#[test]
+fn far_future_projections_do_not_overflow() {
+    let stream = running_stream(0);
+    assert!(get_stream_status_at_v2(&stream, u64::MAX) == StreamStatus::Running);
+
+    let ending_stream = running_stream((START_TIME + 50) * 1000);
+    assert!(get_stream_status_at_v2(&ending_stream, u64::MAX) == StreamStatus::Ended);
+
+    let stream = StreamV1 { start_utc: START_TIME * 1000, ..StreamV1::default() };
+    assert!(get_stream_status_at(&stream, u64::MAX) == StreamStatus::Running);
+}
+
+#[test]
+fn legacy_vesting_rounds_to_base_units() {
+    // 0.7 * 3 is 2.0999999999999996 as a float, truncating it would lose a base unit
+    let stream = StreamV1 {
+        initialized: true,
+        rate_amount: 0.7,
+        rate_interval_in_seconds: 1,
+        allocation_assigned: 100.0,
+        allocation_left: 100.0,
+        start_utc: START_TIME * 1000,
+        escrow_vested_amount_snap_block_time: START_TIME,
+        stream_resumed_block_time: START_TIME,
+        ..StreamV1::default()
+    };
+
+    assert_eq!(get_withdrawable_amount_at(&stream, START_TIME + 3, 6).unwrap(), 2_100_000);
+}
+
+#[test]
+fn token_scale_converts_in_base_units() {
+    let scale = TokenScale::new(6);
+
+    assert_eq!(scale.to_base_units(0.7 * 3.0), 2_100_000);
+    assert_eq!(scale.to_base_units(-1.0), 0);
+    assert_eq!(scale.from_base_units(2_100_000), 2.1);
+    assert_eq!(scale.to_rate(0.5), 500_000 * RATE_PRECISION);
+    // 0.1 + 0.2 is 0.30000000000000004 as floats, in base units it is exact
+    assert_eq!(scale.checked_add(0.1, 200_000).unwrap(), 0.3);
+    assert_eq!(scale.checked_sub(0.3, 100_000).unwrap(), 0.2);
+    assert!(scale.checked_sub(0.1, 200_000).is_err());
+    assert_eq!(scale.saturating_sub(0.1, 200_000), 0.0);
+}
+
+#[test]
+fn stream_depletes_unless_it_ends_first() {
+    let stream = running_stream(0);
+    assert_eq!(get_stream_depletion_time_v2(&stream, START_TIME + 30).unwrap(), Some(START_TIME + 100));
+
+    let ending_stream = running_stream((START_TIME + 50) * 1000);
+    assert_eq!(get_stream_depletion_time_v2(&ending_stream, START_TIME + 30).unwrap(), None);
+    assert!(get_stream_status_at_v2(&ending_stream, START_TIME + 50) == StreamStatus::Ended);
+}
+
+#[test]
+fn treasury_depletion_rounds_up() {
+    // 3 base units every 2 seconds
+    let rate = 3 * RATE_PRECISION / 2;
+    assert_eq!(get_depletion_time(10, rate, 100), Some(107));
+    assert_eq!(get_depletion_time(10, 0, 100), None);
+
+    let treasury = TreasuryV2 {
+        balance: 1_000,
+        depletion_rate: 10 * RATE_PRECISION,
+        ..TreasuryV2::default()
+    };
+    assert_eq!(get_treasury_depletion_time_v2(&treasury, START_TIME), Some(START_TIME + 100));
+}
+
+// A stepped stream unlocking 100 base units every 10 seconds on top of the cliff amount
+fn stepped_stream(cliff_vest_amount: u64, end_utc: u64) -> StreamV2 {
+    StreamV2 {
+        rate_amount: 100,
+        rate_interval_in_seconds: 10,
+        cliff_vest_amount,
+        escrow_vested_amount_snap: cliff_vest_amount,
+        vesting_mode: VestingMode::Stepped as u8,
+        ..running_stream(end_utc)
+    }
+}
+
+#[test]
+fn stepped_vesting_unlocks_whole_tranches() {
+    let stream = stepped_stream(0, 0);
+
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME).unwrap(), 0);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 9).unwrap(), 0);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 10).unwrap(), 100);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 25).unwrap(), 200);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 500).unwrap(), 1_000);
+}
+
+#[test]
+fn stepped_vesting_unlocks_the_cliff_first() {
+    let mut stream = stepped_stream(50, 0);
+
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME).unwrap(), 50);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 9).unwrap(), 50);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 10).unwrap(), 150);
+
+    // Withdraw everything unlocked, the next tranche still unlocks at the interval boundary
+    stream.allocation_left -= 150;
+    stream.escrow_vested_amount_snap = 0;
+    stream.stream_resumed_block_time = START_TIME + 10;
+
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 19).unwrap(), 0);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 20).unwrap(), 100);
+}
+
+#[test]
+fn stepped_vesting_ends_mid_interval() {
+    let stream = stepped_stream(0, (START_TIME + 25) * 1000);
+
+    // The partial tranche at the end date never unlocks
+    assert!(get_stream_status_at_v2(&stream, START_TIME + 30) == StreamStatus::Ended);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 30).unwrap(), 200);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 1_000).unwrap(), 200);
+}
+
+#[test]
+fn stepped_vesting_keeps_tranches_across_rate_changes() {
+    let mut stream = stepped_stream(0, 0);
+    let mut treasury = TreasuryV2::default();
+    let clock = Clock { unix_timestamp: (START_TIME + 25) as i64, ..Clock::default() };
+    let stream_terms = StreamTerms {
+        stream_name: stream.stream_name.clone(),
+        rate_amount: 300,
+        rate_interval_in_seconds: 10,
+        ..StreamTerms::default()
+    };
+
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 25).unwrap(), 200);
+    answer_update_update_stream_v2(&mut stream, &mut treasury, &stream_terms, &clock).unwrap();
+
+    // Raising the rate does not lock what already unlocked, the partial tranche carries over
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 25).unwrap(), 200);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 31).unwrap(), 200);
+    assert_eq!(get_withdrawable_amount_at_v2(&stream, START_TIME + 34).unwrap(), 500);
+}
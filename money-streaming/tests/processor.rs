@@ -0,0 +1,1487 @@
+// End to end instruction tests, running the program in the banks test validator
+
+mod common;
+
+use assert_matches::assert_matches;
+use solana_program_test::tokio;
+use solana_sdk::{ clock::Clock, program_pack::Pack, pubkey::Pubkey, signature::{ Keypair, Signer } };
+use spl_associated_token_account::get_associated_token_address;
+use money_streaming::{
+    instruction::{ self, BatchStreamTerms },
+    constants::*,
+    backwards_comp::get_beneficiary_withdrawable_amount_v0,
+    math::get_withdrawable_amount_at,
+    utils::{ find_fee_config_address, find_stream_terms_address, find_treasury_roles_address },
+    state::{ FeeConfig, Stream, StreamV1, StreamV2, TreasuryV1, TreasuryV2, TreasuryRole, TreasuryRoles, VestingMode }
+};
+use common::*;
+
+#[tokio::test]
+async fn treasury_and_stream_lifecycle() {
+    let mut env = setup().await;
+    let program_id = money_streaming::id();
+    let treasurer = env.treasurer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+    let fee_treasury = env.fee_treasury;
+
+    // Create treasury
+    let fee_lamports = env.lamports(fee_treasury).await;
+    let treasury = env.create_treasury().await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+
+    assert!(treasury_state.initialized);
+    assert_eq!(treasury_state.treasurer_address, treasurer);
+    assert_eq!(treasury_state.mint_address, treasury.pool_mint);
+    assert_eq!(env.lamports(fee_treasury).await, fee_lamports + CREATE_TREASURY_FLAT_FEE);
+
+    // Add unallocated funds
+    let fee_lamports = env.lamports(fee_treasury).await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+
+    assert_eq!(env.token_balance(treasury.address).await, 100 * TOKEN);
+    assert_eq!(env.token_balance(treasurer).await, 900 * TOKEN);
+    assert_eq!(env.lamports(fee_treasury).await, fee_lamports + ADD_FUNDS_FLAT_FEE);
+
+    let pool_token = get_associated_token_address(&treasurer, &treasury.pool_mint);
+    let pool_token_state: spl_token::state::Account = env.unpack(pool_token).await;
+    assert_eq!(pool_token_state.amount, 100 * TOKEN);
+
+    // Create stream
+    let fee_lamports = env.lamports(fee_treasury).await;
+    let stream = env.create_stream(&treasury, 50.0).await;
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+
+    assert_eq!(stream_state.beneficiary_address, beneficiary);
+    assert_eq!(stream_state.allocation_assigned, 50.0);
+    assert_eq!(treasury_state.streams_amount, 1);
+    assert_eq!(treasury_state.allocation_left, 50.0);
+    assert_eq!(treasury_state.depletion_rate, 1.0);
+    assert_eq!(env.lamports(fee_treasury).await, fee_lamports + CREATE_STREAM_FLAT_FEE);
+
+    // Withdraw part of the vested amount
+    env.warp(10).await;
+    assert_matches!(env.withdraw(&treasury, stream, 11.0).await, Err(_));
+    env.withdraw(&treasury, stream, 5.0).await.unwrap();
+
+    let fee = 5 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    assert_eq!(env.token_balance(beneficiary).await, 5 * TOKEN - fee);
+    assert_eq!(env.token_balance(fee_treasury).await, fee);
+    assert_eq!(env.token_balance(treasury.address).await, 95 * TOKEN);
+
+    // Pause a second later, the stream stops vesting and depleting the treasury
+    env.warp(1).await;
+    let ix = instruction::pause_stream(&program_id, treasurer, env.mint, treasury.address, stream, env.fee_treasury).unwrap();
+    let signer = env.treasurer.insecure_clone();
+    env.process(ix, &[&signer]).await.unwrap();
+
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(stream_state.escrow_vested_amount_snap, 6.0);
+    assert_eq!(treasury_state.depletion_rate, 0.0);
+    assert_eq!(treasury_state.estimated_depletion_utc, 0);
+
+    env.warp(100).await;
+    assert_matches!(env.withdraw(&treasury, stream, 7.0).await, Err(_));
+
+    // Resume
+    let ix = instruction::resume_stream(&program_id, treasurer, env.mint, treasury.address, stream, env.fee_treasury).unwrap();
+    env.process(ix, &[&signer]).await.unwrap();
+
+    let now = env.now().await;
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(stream_state.stream_resumed_block_time, now);
+    assert_eq!(treasury_state.depletion_rate, 1.0);
+    assert_eq!(treasury_state.estimated_depletion_utc, (now + 95) * 1000);
+    assert_eq!(stream_state.treasury_estimated_depletion_utc, treasury_state.estimated_depletion_utc);
+
+    // Refresh the balance after a direct deposit into the treasury token account
+    let ix = spl_token::instruction::transfer(
+        &spl_token::id(), &get_associated_token_address(&treasurer, &env.mint),
+        &get_associated_token_address(&treasury.address, &env.mint), &treasurer, &[], 10 * TOKEN
+    ).unwrap();
+    env.process(ix, &[&signer]).await.unwrap();
+
+    let ix = instruction::refresh_treasury_balance(&program_id, treasurer, env.mint, treasury.address).unwrap();
+    env.process(ix, &[&signer]).await.unwrap();
+
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(treasury_state.balance, 105.0);
+
+    // Close the stream, the beneficiary gets the vested amount minus the close fee
+    env.warp(10).await;
+    let beneficiary_balance = env.token_balance(beneficiary).await;
+    let fee_balance = env.token_balance(fee_treasury).await;
+    let fee_lamports = env.lamports(fee_treasury).await;
+    env.close_stream(&treasury, stream, false).await;
+
+    let vested = 16 * TOKEN;
+    let fee = vested * CLOSE_STREAM_PERCENT_FEE / BPS_DENOMINATOR;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+
+    assert_eq!(env.token_balance(beneficiary).await, beneficiary_balance + vested - fee);
+    assert_eq!(env.token_balance(fee_treasury).await, fee_balance + fee);
+    assert_eq!(env.lamports(fee_treasury).await, fee_lamports + CLOSE_STREAM_FLAT_FEE);
+    assert_eq!(env.account(stream).await, None);
+    assert_eq!(treasury_state.streams_amount, 0);
+    assert_eq!(treasury_state.depletion_rate, 0.0);
+
+    // Close the treasury, the treasurer gets the funds left minus the close treasury fee
+    let treasurer_balance = env.token_balance(treasurer).await;
+    let treasury_balance = env.token_balance(treasury.address).await;
+    let fee_balance = env.token_balance(fee_treasury).await;
+    let fee_lamports = env.lamports(fee_treasury).await;
+    env.close_treasury(&treasury).await;
+
+    let fee = treasury_balance * CLOSE_TREASURY_PERCENT_FEE / BPS_DENOMINATOR;
+    assert_eq!(env.token_balance(treasurer).await, treasurer_balance + treasury_balance - fee);
+    assert_eq!(env.token_balance(fee_treasury).await, fee_balance + fee);
+    assert_eq!(env.lamports(fee_treasury).await, fee_lamports + CLOSE_TREASURY_FLAT_FEE);
+    assert_eq!(env.account(treasury.address).await, None);
+}
+
+#[tokio::test]
+async fn fee_config_fee_treasury() {
+    let (mut program_test, mint, treasurer, beneficiary) = program_test();
+    let fee_treasury = Pubkey::new_unique();
+    let (fee_config, bump) = find_fee_config_address(&money_streaming::id());
+
+    program_test.add_account(fee_treasury, system_account(SOL));
+    program_test.add_account(fee_config, program_account(&FeeConfig {
+        initialized: true,
+        fee_treasury_address: fee_treasury,
+        close_treasury_flat_fee: 50_000,
+        close_treasury_percent_fee: 100,
+        bump,
+        ..FeeConfig::default()
+    }));
+
+    let mut env = start(program_test, mint, treasurer, beneficiary).await;
+    let program_id = money_streaming::id();
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+    let default_fee_treasury = env.fee_treasury;
+    env.fee_treasury = fee_treasury;
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 10.0).await;
+    env.warp(1).await;
+
+    // The default fee treasury is not the recipient set in the fee config anymore
+    let ix = instruction::pause_stream(&program_id, treasurer, mint, treasury.address, stream, default_fee_treasury).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+    env.pause_stream(&treasury, stream).await.unwrap();
+
+    let ix = instruction::resume_stream(&program_id, treasurer, mint, treasury.address, stream, default_fee_treasury).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+    env.resume_stream(&treasury, stream).await.unwrap();
+
+    env.warp(20).await;
+    env.close_stream(&treasury, stream, false).await;
+
+    let ix = instruction::close_treasury(
+        &program_id, treasurer, mint, treasury.address, treasury.pool_mint, default_fee_treasury, None
+    ).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+
+    // The close treasury fees come from the fee config too
+    let treasurer_balance = env.token_balance(treasurer).await;
+    let fee_balance = env.token_balance(fee_treasury).await;
+    let fee_lamports = env.lamports(fee_treasury).await;
+    env.close_treasury(&treasury).await;
+
+    let fee = 90 * TOKEN / 100;
+    assert_eq!(env.token_balance(treasurer).await, treasurer_balance + 90 * TOKEN - fee);
+    assert_eq!(env.token_balance(fee_treasury).await, fee_balance + fee);
+    assert_eq!(env.lamports(fee_treasury).await, fee_lamports + 50_000);
+    assert_eq!(env.token_balance(default_fee_treasury).await, 0);
+}
+
+#[tokio::test]
+async fn close_stream_auto_closes_treasury() {
+    let mut env = setup().await;
+    let treasurer = env.treasurer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 50.0).await;
+
+    env.warp(20).await;
+    let fee_lamports = env.lamports(env.fee_treasury).await;
+    env.close_stream(&treasury, stream, true).await;
+
+    let vested = 20 * TOKEN;
+    let fee = vested * CLOSE_STREAM_PERCENT_FEE / BPS_DENOMINATOR;
+    let close_treasury_fee = (100 * TOKEN - vested) * CLOSE_TREASURY_PERCENT_FEE / BPS_DENOMINATOR;
+
+    assert_eq!(env.token_balance(beneficiary).await, vested - fee);
+    assert_eq!(env.token_balance(treasurer).await, 1_000 * TOKEN - vested - close_treasury_fee);
+    assert_eq!(env.token_balance(env.fee_treasury).await, fee + close_treasury_fee);
+    assert_eq!(env.lamports(env.fee_treasury).await, fee_lamports + CLOSE_STREAM_FLAT_FEE + CLOSE_TREASURY_FLAT_FEE);
+    assert_eq!(env.account(stream).await, None);
+    assert_eq!(env.account(treasury.address).await, None);
+}
+
+#[tokio::test]
+async fn legacy_treasury_and_stream() {
+    let (program_test, mint, treasurer, beneficiary, treasury, stream) = legacy_setup(50 * TOKEN, 50.0, 0);
+    let mut env = start(program_test, mint, treasurer, beneficiary).await;
+    let treasurer = env.treasurer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+    let fee_treasury = env.fee_treasury;
+
+    // Add funds to the legacy stream
+    let fee_lamports = env.lamports(fee_treasury).await;
+    env.add_funds(&treasury, 10.0, 0, stream).await;
+
+    let stream_state: Stream = env.unpack(stream).await;
+    assert_eq!(stream_state.total_deposits, 60.0);
+    assert_eq!(env.token_balance(treasury.address).await, 60 * TOKEN);
+    assert_eq!(env.lamports(fee_treasury).await, fee_lamports + ADD_FUNDS_FLAT_FEE);
+
+    // The stream started long ago so it vested its whole allocation before the funds were added
+    assert_matches!(env.withdraw(&treasury, stream, 51.0).await, Err(_));
+    env.withdraw(&treasury, stream, 20.0).await.unwrap();
+
+    let fee = 20 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    let stream_state: Stream = env.unpack(stream).await;
+    assert_eq!(stream_state.total_withdrawals, 20.0);
+    assert_eq!(env.token_balance(beneficiary).await, 20 * TOKEN - fee);
+    assert_eq!(env.token_balance(fee_treasury).await, fee);
+
+    // Closing the stream pays the vested amount to the beneficiary and the funds
+    // added after the stream ran out, which did not vest yet, to the treasurer
+    let treasurer_balance = env.token_balance(treasurer).await;
+    env.close_stream(&treasury, stream, true).await;
+
+    let close_fee = 30 * TOKEN * CLOSE_STREAM_PERCENT_FEE / BPS_DENOMINATOR;
+    assert_eq!(env.token_balance(beneficiary).await, 50 * TOKEN - fee - close_fee);
+    assert_eq!(env.token_balance(treasurer).await, treasurer_balance + 10 * TOKEN);
+    assert_eq!(env.account(stream).await, None);
+    assert_eq!(env.account(treasury.address).await, None);
+}
+
+#[tokio::test]
+async fn legacy_treasury_close() {
+    let (program_test, mint, treasurer, beneficiary, treasury, stream) = legacy_setup(50 * TOKEN, 0.0, 0);
+    let mut env = start(program_test, mint, treasurer, beneficiary).await;
+    let treasurer = env.treasurer.pubkey();
+
+    // A stream without allocation does not keep the funds from the treasurer
+    env.close_stream(&treasury, stream, false).await;
+    assert_eq!(env.account(stream).await, None);
+    assert_eq!(env.token_balance(env.beneficiary.pubkey()).await, 0);
+
+    env.close_treasury(&treasury).await;
+
+    assert_eq!(env.token_balance(treasurer).await, 1_050 * TOKEN);
+    assert_eq!(env.account(treasury.address).await, None);
+}
+
+#[tokio::test]
+async fn treasury_withdraw() {
+    let mut env = setup().await;
+    let program_id = money_streaming::id();
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    env.create_stream(&treasury, 50.0).await;
+
+    // Only the 50 tokens not allocated to the stream can be withdrawn
+    let ix = instruction::treasury_withdraw(&program_id, treasurer, env.mint, treasury.address, 50 * TOKEN + 1).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+
+    let ix = instruction::treasury_withdraw(&program_id, treasurer, env.mint, treasury.address, 30 * TOKEN).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    let now = env.now().await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(treasurer).await, 930 * TOKEN);
+    assert_eq!(env.token_balance(treasury.address).await, 70 * TOKEN);
+    assert_eq!(treasury_state.balance, 70.0);
+    assert_eq!(treasury_state.allocation_assigned, 50.0);
+    assert_eq!(treasury_state.allocation_left, 50.0);
+    assert_eq!(treasury_state.estimated_depletion_utc, (now + 70) * 1000);
+}
+
+#[tokio::test]
+async fn v1_amounts_round_to_base_units() {
+    let mut env = setup().await;
+    let treasurer = env.treasurer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+
+    // 2.01 * 10^6 is 2009999.9999999998 as a float, truncating it would lose a base unit
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 2.01, 2, Pubkey::default()).await;
+
+    let pool_token_state: spl_token::state::Account = env.unpack(get_associated_token_address(&treasurer, &treasury.pool_mint)).await;
+    assert_eq!(env.token_balance(treasury.address).await, 2_010_000);
+    assert_eq!(pool_token_state.amount, 2_010_000);
+
+    let stream = env.create_stream(&treasury, 2.01).await;
+    env.warp(5).await;
+    env.withdraw(&treasury, stream, 2.01).await.unwrap();
+
+    // The whole allocation is paid out, no dust is left behind
+    let fee = 2_010_000 * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(beneficiary).await, 2_010_000 - fee);
+    assert_eq!(env.token_balance(treasury.address).await, 0);
+    assert_eq!(stream_state.allocation_left, 0.0);
+    assert_eq!(treasury_state.balance, 0.0);
+    assert_eq!(treasury_state.allocation_left, 0.0);
+}
+
+#[tokio::test]
+async fn v1_pause_and_resume_check_the_status() {
+    let mut env = setup().await;
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 50.0).await;
+    env.warp(1).await;
+
+    // A running stream can not be resumed, it would restart vesting from now
+    assert_matches!(env.resume_stream(&treasury, stream).await, Err(_));
+
+    env.warp(4).await;
+    env.pause_stream(&treasury, stream).await.unwrap();
+    let paused: StreamV1 = env.unpack(stream).await;
+    assert_eq!(paused.escrow_vested_amount_snap, 5.0);
+
+    // Pausing again would overwrite the vested snapshot
+    env.warp(10).await;
+    assert_matches!(env.pause_stream(&treasury, stream).await, Err(_));
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    assert_eq!(stream_state.escrow_vested_amount_snap, 5.0);
+    assert_eq!(stream_state.escrow_vested_amount_snap_block_time, paused.escrow_vested_amount_snap_block_time);
+
+    env.resume_stream(&treasury, stream).await.unwrap();
+    env.warp(1).await;
+    assert_matches!(env.resume_stream(&treasury, stream).await, Err(_));
+
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(treasury_state.depletion_rate, 1.0);
+}
+
+#[tokio::test]
+async fn v1_close_releases_the_unvested_allocation() {
+    let mut env = setup().await;
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+
+    // Nothing has vested, the whole allocation goes back to the treasury
+    let stream = env.create_stream(&treasury, 50.0).await;
+    env.close_stream(&treasury, stream, false).await;
+
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(treasury.address).await, 100 * TOKEN);
+    assert_eq!(treasury_state.balance, 100.0);
+    assert_eq!(treasury_state.allocation_left, 0.0);
+    assert_eq!(treasury_state.depletion_rate, 0.0);
+    assert_eq!(treasury_state.streams_amount, 0);
+
+    // Only the vested amount leaves the treasury
+    let stream = env.create_stream(&treasury, 50.0).await;
+    env.warp(10).await;
+    env.close_stream(&treasury, stream, false).await;
+
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(treasury.address).await, 90 * TOKEN);
+    assert_eq!(treasury_state.balance, 90.0);
+    assert_eq!(treasury_state.allocation_left, 0.0);
+    assert_eq!(treasury_state.streams_amount, 0);
+}
+
+#[tokio::test]
+async fn v1_close_fails_when_the_treasury_can_not_pay_the_vested_amount() {
+    let mut env = setup().await;
+    let treasurer = env.treasurer.pubkey();
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 50.0).await;
+    env.warp(10).await;
+
+    // The token account holds less than the vested amount, the close must not pay out a smaller amount
+    let treasury_token = get_associated_token_address(&treasury.address, &env.mint);
+    let mut treasury_token_state: spl_token::state::Account = env.unpack(treasury_token).await;
+    treasury_token_state.amount = 5 * TOKEN;
+    env.set_state(treasury_token, &treasury_token_state).await;
+
+    let ix = instruction::close_stream(
+        &money_streaming::id(), treasurer, treasurer, env.beneficiary.pubkey(), env.mint,
+        treasury.address, treasury.pool_mint, stream, env.fee_treasury, None, false, false
+    ).unwrap();
+    let signer = env.treasurer.insecure_clone();
+    assert_matches!(env.process(ix, &[&signer]).await, Err(_));
+
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    assert_eq!(stream_state.allocation_left, 50.0);
+    assert_eq!(env.token_balance(treasury.address).await, 5 * TOKEN);
+}
+
+#[tokio::test]
+async fn allocate() {
+    let mut env = setup().await;
+    let program_id = money_streaming::id();
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 40.0).await;
+
+    // The treasury has 60 tokens that are not allocated yet
+    let ix = instruction::allocate(&program_id, treasurer, env.mint, treasury.address, stream, 60 * TOKEN + 1, 0).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+
+    let ix = instruction::allocate(&program_id, treasurer, env.mint, treasury.address, stream, 20 * TOKEN, 1).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(stream_state.allocation_assigned, 60.0);
+    assert_eq!(stream_state.allocation_left, 60.0);
+    assert_eq!(stream_state.allocation_reserved, 20.0);
+    assert_eq!(treasury_state.allocation_assigned, 60.0);
+    assert_eq!(treasury_state.allocation_left, 60.0);
+    assert_eq!(treasury_state.allocation_reserved, 20.0);
+    // The funds were already in the treasury
+    assert_eq!(treasury_state.balance, 100.0);
+    assert_eq!(env.token_balance(treasury.address).await, 100 * TOKEN);
+}
+
+#[tokio::test]
+async fn redeem() {
+    let mut env = setup().await;
+    let program_id = money_streaming::id();
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    env.create_stream(&treasury, 40.0).await;
+
+    // The 100 pool tokens are a claim on the 60 tokens not allocated to the stream
+    let pool_token = get_associated_token_address(&treasurer, &treasury.pool_mint);
+    let pool_token_state: spl_token::state::Account = env.unpack(pool_token).await;
+    assert_eq!(pool_token_state.amount, 100 * TOKEN);
+
+    let ix = instruction::redeem(&program_id, treasurer, env.mint, treasury.address, treasury.pool_mint, 100 * TOKEN + 1).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+
+    // A share worth less than one base unit is refused
+    let ix = instruction::redeem(&program_id, treasurer, env.mint, treasury.address, treasury.pool_mint, 1).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+
+    // The redeemed amount rounds down, 3 pool units are worth 1.8 base units
+    let ix = instruction::redeem(&program_id, treasurer, env.mint, treasury.address, treasury.pool_mint, 3).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+    assert_eq!(env.token_balance(treasurer).await, 900 * TOKEN + 1);
+    assert_eq!(env.token_balance(treasury.address).await, 100 * TOKEN - 1);
+
+    let ix = instruction::redeem(&program_id, treasurer, env.mint, treasury.address, treasury.pool_mint, 50 * TOKEN).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    let pool_token_state: spl_token::state::Account = env.unpack(pool_token).await;
+    assert_eq!(pool_token_state.amount, 50 * TOKEN - 3);
+    assert_eq!(env.token_balance(treasurer).await, 930 * TOKEN + 1);
+    assert_eq!(env.token_balance(treasury.address).await, 70 * TOKEN - 1);
+    assert_eq!(treasury_state.allocation_left, 40.0);
+}
+
+#[tokio::test]
+async fn native_sol_stream() {
+    let mut env = native_setup().await;
+    let program_id = money_streaming::id();
+    let (mint, fee_treasury) = (env.mint, env.fee_treasury);
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let beneficiary_signer = env.beneficiary.insecure_clone();
+    let (treasurer, beneficiary) = (treasurer_signer.pubkey(), beneficiary_signer.pubkey());
+
+    // Lamports are wrapped into the treasury token account
+    let treasury = env.create_treasury_v2().await;
+    let treasurer_lamports = env.lamports(treasurer).await;
+    let ix = instruction::add_funds_v2(
+        &program_id, treasurer, mint, treasury.address, treasury.pool_mint,
+        fee_treasury, None, 5 * SOL, 2, Pubkey::default(), true
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(treasury.address).await, 5 * SOL);
+    assert_eq!(treasury_state.balance, 5 * SOL);
+    assert!(env.lamports(treasurer).await <= treasurer_lamports - 5 * SOL);
+
+    let stream = Keypair::new();
+    let now = env.now().await;
+    let ix = instruction::create_stream_v2(
+        &program_id, treasurer, treasury.address, mint, beneficiary, stream.pubkey(), fee_treasury, None,
+        name("Native"), SOL / 10, 1, 0, 2 * SOL, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer, &stream]).await.unwrap();
+    env.warp(5).await;
+
+    // The withdrawn amount arrives as SOL and the wrapped account is closed
+    let beneficiary_lamports = env.lamports(beneficiary).await;
+    let ix = instruction::withdraw_v2(
+        &program_id, beneficiary, mint, treasury.address, stream.pubkey(), fee_treasury, None, SOL / 5, true
+    ).unwrap();
+    env.process(ix, &[&beneficiary_signer]).await.unwrap();
+
+    let fee_treasury_token = env.lamports(get_associated_token_address(&fee_treasury, &mint)).await;
+    assert_eq!(env.account(get_associated_token_address(&beneficiary, &mint)).await, None);
+    assert_eq!(env.lamports(beneficiary).await, beneficiary_lamports + SOL / 5 - fee_treasury_token);
+
+    // Unwrapping closes an account of the beneficiary, so it needs their signature
+    let close = instruction::close_stream(
+        &program_id, treasurer, treasurer, beneficiary, mint, treasury.address,
+        treasury.pool_mint, stream.pubkey(), fee_treasury, None, false, true
+    ).unwrap();
+    let mut unsigned_close = close.clone();
+    unsigned_close.accounts.iter_mut().filter(|meta| meta.pubkey == beneficiary).for_each(|meta| meta.is_signer = false);
+    assert_matches!(env.process(unsigned_close, &[&treasurer_signer]).await, Err(_));
+    env.process(close, &[&treasurer_signer, &beneficiary_signer]).await.unwrap();
+
+    assert_eq!(env.account(stream.pubkey()).await, None);
+    assert_eq!(env.account(get_associated_token_address(&beneficiary, &mint)).await, None);
+}
+
+// Moves the start of a legacy stream to `seconds` ago
+async fn start_legacy_stream(env: &mut TestEnv, stream: Pubkey, seconds: u64) {
+    let start_time = env.now().await - seconds;
+    let mut stream_state: Stream = env.unpack(stream).await;
+    stream_state.start_utc = start_time * 1000;
+    stream_state.escrow_vested_amount_snap_block_time = start_time;
+    stream_state.stream_resumed_block_time = start_time;
+    env.set_state(stream, &stream_state).await;
+}
+
+#[tokio::test]
+async fn migrate_legacy_stream() {
+    let (program_test, mint, treasurer, beneficiary, treasury, stream) = legacy_setup(60 * TOKEN, 50.0, 0);
+    let mut env = start(program_test, mint, treasurer, beneficiary).await;
+    let program_id = money_streaming::id();
+    let treasurer = env.treasurer.pubkey();
+    let beneficiary_signer = env.beneficiary.insecure_clone();
+
+    start_legacy_stream(&mut env, stream, 20).await;
+    env.withdraw(&treasury, stream, 5.0).await.unwrap();
+
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    let legacy_state: Stream = env.unpack(stream).await;
+    let withdrawable = get_beneficiary_withdrawable_amount_v0(&legacy_state, &clock, DECIMALS as u64).unwrap();
+    assert_eq!(withdrawable, 15 * TOKEN);
+
+    // Only the treasurer or the beneficiary migrate the stream
+    let stranger = Keypair::new();
+    let ix = instruction::migrate_stream(&program_id, stranger.pubkey(), treasurer, mint, stream).unwrap();
+    assert_matches!(env.process(ix, &[&stranger]).await, Err(_));
+
+    let ix = instruction::migrate_stream(&program_id, beneficiary_signer.pubkey(), treasurer, mint, stream).unwrap();
+    env.process(ix, &[&beneficiary_signer]).await.unwrap();
+
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    assert_eq!(env.account(stream).await.unwrap().data.len(), StreamV1::LEN);
+    assert_eq!(stream_state.treasurer_address, treasurer);
+    assert_eq!(stream_state.beneficiary_address, beneficiary_signer.pubkey());
+    assert_eq!(stream_state.treasury_address, treasury.address);
+    assert_eq!(stream_state.allocation_assigned, 50.0);
+    assert_eq!(stream_state.allocation_left, 45.0);
+    assert_eq!(stream_state.allocation_reserved, 0.0);
+    assert_eq!(get_withdrawable_amount_at(&stream_state, clock.unix_timestamp as u64, DECIMALS as u64).unwrap(), withdrawable);
+    assert_eq!(get_withdrawable_amount_at(&stream_state, clock.unix_timestamp as u64 + 10, DECIMALS as u64).unwrap(), 25 * TOKEN);
+    assert_eq!(env.token_balance(treasury.address).await, 55 * TOKEN);
+
+    // The stream is not a legacy stream anymore
+    let ix = instruction::migrate_stream(&program_id, beneficiary_signer.pubkey(), treasurer, mint, stream).unwrap();
+    assert_matches!(env.process(ix, &[&beneficiary_signer]).await, Err(_));
+}
+
+#[tokio::test]
+async fn migrate_legacy_treasury() {
+    let (program_test, mint, treasurer, beneficiary, treasury, stream) = legacy_setup(60 * TOKEN, 50.0, 0);
+    let mut env = start(program_test, mint, treasurer, beneficiary).await;
+    let program_id = money_streaming::id();
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+
+    start_legacy_stream(&mut env, stream, 20).await;
+    env.withdraw(&treasury, stream, 5.0).await.unwrap();
+
+    // Every stream can only be counted once
+    let ix = instruction::migrate_treasury(&program_id, treasurer, treasurer, mint, treasury.address, &[stream, stream]).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+
+    let ix = instruction::migrate_treasury(&program_id, treasurer, treasurer, mint, treasury.address, &[stream]).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    let now = env.now().await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.account(treasury.address).await.unwrap().data.len(), TreasuryV1::LEN);
+    assert_eq!(treasury_state.treasurer_address, treasurer);
+    assert_eq!(treasury_state.mint_address, treasury.pool_mint);
+    assert_eq!(treasury_state.associated_token_address, mint);
+    assert_eq!(treasury_state.balance, 55.0);
+    assert_eq!(treasury_state.allocation_assigned, 50.0);
+    assert_eq!(treasury_state.allocation_left, 45.0);
+    assert_eq!(treasury_state.allocation_reserved, 0.0);
+    assert_eq!(treasury_state.streams_amount, 1);
+    assert_eq!(treasury_state.depletion_rate, 1.0);
+    assert_eq!(treasury_state.estimated_depletion_utc, (now + 55) * 1000);
+    assert_eq!(env.token_balance(treasury.address).await, 55 * TOKEN);
+
+    // After migrating the stream too, the beneficiary withdraws what vested under the legacy layouts
+    let ix = instruction::migrate_stream(&program_id, treasurer, treasurer, mint, stream).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    assert_matches!(env.withdraw(&treasury, stream, 15.1).await, Err(_));
+    env.withdraw(&treasury, stream, 15.0).await.unwrap();
+
+    let fee = 5 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR + 15 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(beneficiary).await, 20 * TOKEN - fee);
+    assert_eq!(env.token_balance(treasury.address).await, 40 * TOKEN);
+    assert_eq!(treasury_state.balance, 40.0);
+    assert_eq!(treasury_state.allocation_left, 30.0);
+}
+
+#[tokio::test]
+async fn legacy_pause_and_resume() {
+    let (program_test, mint, treasurer, beneficiary, treasury, stream) = legacy_setup(60 * TOKEN, 50.0, 0);
+    let mut env = start(program_test, mint, treasurer, beneficiary).await;
+    let program_id = money_streaming::id();
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+
+    start_legacy_stream(&mut env, stream, 20).await;
+
+    // Legacy streams have no pause state, they are paused and resumed once migrated
+    assert_matches!(env.pause_stream(&treasury, stream).await, Err(_));
+    assert_matches!(env.resume_stream(&treasury, stream).await, Err(_));
+    let legacy_state: Stream = env.unpack(stream).await;
+    assert_eq!(env.account(stream).await.unwrap().data.len(), Stream::LEN);
+    assert_eq!(legacy_state.total_withdrawals, 0.0);
+
+    let ix = instruction::migrate_treasury(&program_id, treasurer, treasurer, mint, treasury.address, &[stream]).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+    let ix = instruction::migrate_stream(&program_id, treasurer, treasurer, mint, stream).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    env.warp(1).await;
+    env.pause_stream(&treasury, stream).await.unwrap();
+
+    let paused: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(paused.escrow_vested_amount_snap, 21.0);
+    assert_eq!(treasury_state.depletion_rate, 0.0);
+
+    // Nothing vests while paused
+    env.warp(10).await;
+    assert_matches!(env.withdraw(&treasury, stream, 21.1).await, Err(_));
+
+    env.resume_stream(&treasury, stream).await.unwrap();
+    env.warp(4).await;
+    env.withdraw(&treasury, stream, 25.0).await.unwrap();
+
+    let fee = 25 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(env.beneficiary.pubkey()).await, 25 * TOKEN - fee);
+    assert_eq!(treasury_state.depletion_rate, 1.0);
+}
+
+#[tokio::test]
+async fn create_streams_batch() {
+    let mut env = setup().await;
+    let program_id = money_streaming::id();
+    let treasurer = env.treasurer.pubkey();
+    let fee_treasury = env.fee_treasury;
+
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 100 * TOKEN).await;
+
+    let now = env.now().await;
+    let streams: Vec<(Keypair, Pubkey)> = (0..3).map(|_| (Keypair::new(), Pubkey::new_unique())).collect();
+    let batch = |rate_amount: u64, allocation: u64| -> Vec<(Pubkey, Pubkey, BatchStreamTerms)> {
+        streams.iter().map(|(stream, beneficiary)| (stream.pubkey(), *beneficiary, BatchStreamTerms {
+            stream_name: name("Payroll"),
+            rate_amount,
+            rate_interval_in_seconds: 1,
+            allocation_reserved: allocation / 2,
+            allocation_assigned: allocation,
+            start_utc: now * 1000,
+            rate_cliff_in_seconds: 0,
+            cliff_vest_amount: 0,
+            cliff_vest_percent: 1_000,
+            auto_pause_in_seconds: 0,
+            end_utc: 0,
+            vesting_mode: VestingMode::Continuous as u8
+        })).collect()
+    };
+
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let mut signers = vec![&treasurer_signer];
+    signers.extend(streams.iter().map(|(stream, _)| stream));
+
+    // The allocations of the batch exceed the unallocated balance of the treasury
+    let ix = instruction::create_streams_batch(
+        &program_id, treasurer, treasury.address, env.mint, fee_treasury, None, &batch(TOKEN, 34 * TOKEN)
+    ).unwrap();
+    assert_matches!(env.process(ix, &signers).await, Err(_));
+
+    // A zero rate would never vest the allocation, in a batch or in a single stream
+    let ix = instruction::create_streams_batch(
+        &program_id, treasurer, treasury.address, env.mint, fee_treasury, None, &batch(0, 30 * TOKEN)
+    ).unwrap();
+    assert_matches!(env.process(ix, &signers).await, Err(_));
+
+    let (stream, beneficiary) = &streams[0];
+    let ix = instruction::create_stream_v2(
+        &program_id, treasurer, treasury.address, env.mint, *beneficiary, stream.pubkey(), fee_treasury, None,
+        name("Payroll"), 0, 1, 0, 30 * TOKEN, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer, stream]).await, Err(_));
+
+    let fee_lamports = env.lamports(fee_treasury).await;
+    let ix = instruction::create_streams_batch(
+        &program_id, treasurer, treasury.address, env.mint, fee_treasury, None, &batch(TOKEN, 30 * TOKEN)
+    ).unwrap();
+    env.process(ix, &signers).await.unwrap();
+
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+    assert_eq!(treasury_state.streams_amount, 3);
+    assert_eq!(treasury_state.allocation_assigned, 90 * TOKEN);
+    assert_eq!(treasury_state.allocation_left, 90 * TOKEN);
+    assert_eq!(treasury_state.allocation_reserved, 45 * TOKEN);
+    assert_eq!(treasury_state.depletion_rate, 3 * TOKEN as u128 * RATE_PRECISION);
+    assert_eq!(treasury_state.estimated_depletion_utc, (now + 34) * 1000);
+    assert_eq!(env.lamports(fee_treasury).await, fee_lamports + 3 * CREATE_STREAM_FLAT_FEE);
+
+    for (stream, beneficiary) in &streams {
+        let stream_state: StreamV2 = env.unpack(stream.pubkey()).await;
+
+        assert!(stream_state.initialized);
+        assert_eq!(stream_state.beneficiary_address, *beneficiary);
+        assert_eq!(stream_state.treasury_address, treasury.address);
+        assert_eq!(stream_state.allocation_left, 30 * TOKEN);
+        assert_eq!(stream_state.allocation_reserved, 15 * TOKEN);
+        assert_eq!(stream_state.cliff_vest_amount, 3 * TOKEN);
+        assert_eq!(stream_state.escrow_vested_amount_snap, 3 * TOKEN);
+        assert_eq!(stream_state.treasury_estimated_depletion_utc, treasury_state.estimated_depletion_utc);
+    }
+}
+
+#[tokio::test]
+async fn treasury_roles() {
+    let (mut program_test, mint, treasurer_signer, beneficiary) = program_test();
+    let creator = Keypair::new();
+    let pauser = Keypair::new();
+    program_test.add_account(creator.pubkey(), system_account(SOL));
+
+    let mut env = start(program_test, mint, treasurer_signer.insecure_clone(), beneficiary).await;
+    let program_id = money_streaming::id();
+    let treasurer = treasurer_signer.pubkey();
+    let fee_treasury = env.fee_treasury;
+
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 100 * TOKEN).await;
+
+    let create_stream = |stream: Pubkey, now: u64| instruction::with_treasury_roles(&program_id, instruction::create_stream_v2(
+        &program_id, creator.pubkey(), treasury.address, mint, Pubkey::new_unique(), stream,
+        fee_treasury, None, name("Delegated"), TOKEN, 1, 0, 10 * TOKEN,
+        now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap(), treasury.address);
+
+    // Not a member of the treasury yet
+    let stream = Keypair::new();
+    let now = env.now().await;
+    assert_matches!(env.process(create_stream(stream.pubkey(), now), &[&creator, &stream]).await, Err(_));
+
+    for (member, role) in [(creator.pubkey(), TreasuryRole::StreamCreator), (pauser.pubkey(), TreasuryRole::Pauser)] {
+        let ix = instruction::grant_treasury_roles(&program_id, treasurer, treasury.address, member, role as u8).unwrap();
+        env.process(ix, &[&treasurer_signer]).await.unwrap();
+    }
+
+    let (treasury_roles, _) = find_treasury_roles_address(&program_id, &treasury.address);
+    let roles_state: TreasuryRoles = env.unpack(treasury_roles).await;
+    assert_eq!(roles_state.treasury_address, treasury.address);
+    assert!(roles_state.has_role(&creator.pubkey(), TreasuryRole::StreamCreator));
+    assert!(!roles_state.has_role(&creator.pubkey(), TreasuryRole::Pauser));
+
+    // Members that are not admins cannot manage roles
+    let ix = instruction::grant_treasury_roles(
+        &program_id, creator.pubkey(), treasury.address, pauser.pubkey(), TreasuryRole::StreamCreator as u8
+    ).unwrap();
+    assert_matches!(env.process(ix, &[&creator]).await, Err(_));
+
+    // The stream creator creates streams on behalf of the treasurer
+    env.process(create_stream(stream.pubkey(), now), &[&creator, &stream]).await.unwrap();
+    let stream_state: StreamV2 = env.unpack(stream.pubkey()).await;
+    assert_eq!(stream_state.treasurer_address, treasurer);
+    assert_eq!(stream_state.allocation_left, 10 * TOKEN);
+
+    env.warp(10).await;
+
+    // Only the pauser can pause and resume it
+    let pause = |initializer: Pubkey| instruction::with_treasury_roles(&program_id, instruction::pause_stream(
+        &program_id, initializer, mint, treasury.address, stream.pubkey(), fee_treasury
+    ).unwrap(), treasury.address);
+    assert_matches!(env.process(pause(creator.pubkey()), &[&creator]).await, Err(_));
+    env.process(pause(pauser.pubkey()), &[&pauser]).await.unwrap();
+
+    let ix = instruction::with_treasury_roles(&program_id, instruction::resume_stream(
+        &program_id, pauser.pubkey(), mint, treasury.address, stream.pubkey(), fee_treasury
+    ).unwrap(), treasury.address);
+    env.process(ix, &[&pauser]).await.unwrap();
+
+    // Revoking the role takes the permission away
+    let ix = instruction::revoke_treasury_roles(
+        &program_id, treasurer, treasury.address, creator.pubkey(), TreasuryRole::StreamCreator as u8
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    let stream = Keypair::new();
+    assert_matches!(env.process(create_stream(stream.pubkey(), now), &[&creator, &stream]).await, Err(_));
+}
+
+#[tokio::test]
+async fn grown_accounts_keep_their_kind() {
+    let mut env = setup().await;
+    let program_id = money_streaming::id();
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 100 * TOKEN).await;
+
+    let stream = Keypair::new();
+    let now = env.now().await;
+    let ix = instruction::create_stream_v2(
+        &program_id, treasurer, treasury.address, env.mint, env.beneficiary.pubkey(), stream.pubkey(),
+        env.fee_treasury, None, name("Grown"), TOKEN, 1, 0, 50 * TOKEN, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer, &stream]).await.unwrap();
+
+    // Accounts reallocated to a bigger layout are still detected by their header
+    env.grow_account(treasury.address, 64).await;
+    env.grow_account(stream.pubkey(), 64).await;
+    env.warp(10).await;
+    env.pause_stream(&treasury, stream.pubkey()).await.unwrap();
+
+    let stream_state: StreamV2 = env.unpack(stream.pubkey()).await;
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+    assert_eq!(env.account(stream.pubkey()).await.unwrap().data.len(), StreamV2::LEN + 64);
+    assert_eq!(stream_state.escrow_vested_amount_snap, 10 * TOKEN);
+    assert_eq!(treasury_state.depletion_rate, 0);
+}
+
+// A keeper with lamports and an empty token account of the test mint
+async fn keeper_setup() -> (TestEnv, Keypair, Pubkey) {
+    let (mut program_test, mint, treasurer, beneficiary) = program_test();
+    let keeper = Keypair::new();
+    let keeper_token = get_associated_token_address(&keeper.pubkey(), &mint);
+    program_test.add_account(keeper.pubkey(), system_account(SOL));
+    program_test.add_account(keeper_token, token_account(mint, keeper.pubkey(), 0));
+
+    (start(program_test, mint, treasurer, beneficiary).await, keeper, keeper_token)
+}
+
+#[tokio::test]
+async fn push_vested() {
+    let (mut env, keeper, keeper_token) = keeper_setup().await;
+    let program_id = money_streaming::id();
+    let (mint, fee_treasury) = (env.mint, env.fee_treasury);
+    let (treasurer_signer, beneficiary_signer) = (env.treasurer.insecure_clone(), env.beneficiary.insecure_clone());
+    let treasurer = treasurer_signer.pubkey();
+    let beneficiary = beneficiary_signer.pubkey();
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 50.0).await;
+    env.warp(10).await;
+
+    let push = |stream: Pubkey, treasury: Pubkey| instruction::push_vested(
+        &program_id, keeper.pubkey(), keeper_token, beneficiary, mint, treasury, stream, fee_treasury, None
+    ).unwrap();
+
+    // The stream has not opted in
+    assert_matches!(env.process(push(stream, treasury.address), &[&keeper]).await, Err(_));
+
+    // Only the treasurer opts the stream in
+    let ix = instruction::set_push_vested(&program_id, beneficiary, treasury.address, stream, true, TOKEN).unwrap();
+    assert_matches!(env.process(ix, &[&beneficiary_signer]).await, Err(_));
+
+    let ix = instruction::set_push_vested(&program_id, treasurer, treasury.address, stream, true, TOKEN).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    // The keeper pushes the vested amount, the beneficiary never signs
+    env.process(push(stream, treasury.address), &[&keeper]).await.unwrap();
+
+    let fee = 10 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+
+    assert_eq!(env.token_balance(beneficiary).await, 10 * TOKEN - fee);
+    assert_eq!(env.token_balance(fee_treasury).await, fee);
+    assert_eq!(env.token_balance(keeper.pubkey()).await, TOKEN);
+    assert_eq!(env.token_balance(treasury.address).await, 89 * TOKEN);
+    assert_eq!(treasury_state.balance, 89.0);
+    assert_eq!(treasury_state.allocation_left, 40.0);
+    assert_eq!(stream_state.allocation_left, 40.0);
+    assert_eq!(stream_state.escrow_vested_amount_snap, 0.0);
+
+    // Nothing vested since the last push
+    assert_matches!(env.process(push(stream, treasury.address), &[&keeper]).await, Err(_));
+
+    // The beneficiary opts the stream out
+    env.warp(5).await;
+    let ix = instruction::set_push_vested(&program_id, beneficiary, treasury.address, stream, false, 0).unwrap();
+    env.process(ix, &[&beneficiary_signer]).await.unwrap();
+    assert_matches!(env.process(push(stream, treasury.address), &[&keeper]).await, Err(_));
+}
+
+#[tokio::test]
+async fn push_vested_v2() {
+    let (mut env, keeper, keeper_token) = keeper_setup().await;
+    let program_id = money_streaming::id();
+    let (mint, fee_treasury) = (env.mint, env.fee_treasury);
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+
+    let push = |stream: Pubkey, treasury: Pubkey| instruction::push_vested(
+        &program_id, keeper.pubkey(), keeper_token, beneficiary, mint, treasury, stream, fee_treasury, None
+    ).unwrap();
+
+    // The keeper tip is skipped when the unallocated balance can not cover it
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 51 * TOKEN).await;
+
+    let stream = Keypair::new();
+    let now = env.now().await;
+    let ix = instruction::create_stream_v2(
+        &program_id, treasurer, treasury.address, mint, beneficiary, stream.pubkey(), fee_treasury, None,
+        name("Pushed"), TOKEN, 1, 0, 50 * TOKEN, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer, &stream]).await.unwrap();
+
+    let ix = instruction::set_push_vested(&program_id, treasurer, treasury.address, stream.pubkey(), true, 2 * TOKEN).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    env.warp(20).await;
+    env.process(push(stream.pubkey(), treasury.address), &[&keeper]).await.unwrap();
+
+    let fee = 20 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+
+    assert_eq!(env.token_balance(beneficiary).await, 20 * TOKEN - fee);
+    assert_eq!(env.token_balance(fee_treasury).await, fee);
+    assert_eq!(env.token_balance(keeper.pubkey()).await, 0);
+    assert_eq!(env.token_balance(treasury.address).await, 31 * TOKEN);
+    assert_eq!(treasury_state.balance, 31 * TOKEN);
+    assert_eq!(treasury_state.allocation_left, 30 * TOKEN);
+}
+
+#[tokio::test]
+async fn push_vested_tip_once_per_interval() {
+    let (mut env, keeper, keeper_token) = keeper_setup().await;
+    let program_id = money_streaming::id();
+    let (mint, fee_treasury) = (env.mint, env.fee_treasury);
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+
+    let push = |stream: Pubkey, treasury: Pubkey| instruction::push_vested(
+        &program_id, keeper.pubkey(), keeper_token, beneficiary, mint, treasury, stream, fee_treasury, None
+    ).unwrap();
+
+    // 60 tokens every minute, vesting linearly
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 200 * TOKEN).await;
+
+    let stream = Keypair::new();
+    let now = env.now().await;
+    let ix = instruction::create_stream_v2(
+        &program_id, treasurer, treasury.address, mint, beneficiary, stream.pubkey(), fee_treasury, None,
+        name("Pushed"), 60 * TOKEN, 60, 0, 100 * TOKEN, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer, &stream]).await.unwrap();
+
+    let ix = instruction::set_push_vested(&program_id, treasurer, treasury.address, stream.pubkey(), true, TOKEN).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    // Two pushes within the rate interval pay the tip once
+    env.warp(10).await;
+    env.process(push(stream.pubkey(), treasury.address), &[&keeper]).await.unwrap();
+    env.warp(10).await;
+    env.process(push(stream.pubkey(), treasury.address), &[&keeper]).await.unwrap();
+
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(keeper.pubkey()).await, TOKEN);
+    assert_eq!(treasury_state.balance - treasury_state.allocation_left, 99 * TOKEN);
+
+    // The next tip is due a full interval after the last one
+    env.warp(50).await;
+    env.process(push(stream.pubkey(), treasury.address), &[&keeper]).await.unwrap();
+
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(keeper.pubkey()).await, 2 * TOKEN);
+    assert_eq!(treasury_state.balance - treasury_state.allocation_left, 98 * TOKEN);
+}
+
+#[tokio::test]
+async fn push_vested_tip_skips_dust_pushes() {
+    let (mut env, keeper, keeper_token) = keeper_setup().await;
+    let program_id = money_streaming::id();
+    let (mint, fee_treasury) = (env.mint, env.fee_treasury);
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+
+    let push = |stream: Pubkey, treasury: Pubkey| instruction::push_vested(
+        &program_id, keeper.pubkey(), keeper_token, beneficiary, mint, treasury, stream, fee_treasury, None
+    ).unwrap();
+
+    // A token every second, so a tip is due on every push of a keeper pushing every second
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 200 * TOKEN).await;
+
+    let stream = Keypair::new();
+    let now = env.now().await;
+    let ix = instruction::create_stream_v2(
+        &program_id, treasurer, treasury.address, mint, beneficiary, stream.pubkey(), fee_treasury, None,
+        name("Pushed"), TOKEN, 1, 0, 100 * TOKEN, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer, &stream]).await.unwrap();
+
+    let ix = instruction::set_push_vested(&program_id, treasurer, treasury.address, stream.pubkey(), true, 5 * TOKEN).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    // Pushing a token every interval earns nothing
+    for _ in 0..10 {
+        env.warp(1).await;
+        env.process(push(stream.pubkey(), treasury.address), &[&keeper]).await.unwrap();
+    }
+
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(keeper.pubkey()).await, 0);
+    assert_eq!(treasury_state.balance - treasury_state.allocation_left, 100 * TOKEN);
+
+    // A push of ten times the tip pays it
+    env.warp(50).await;
+    env.process(push(stream.pubkey(), treasury.address), &[&keeper]).await.unwrap();
+
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(keeper.pubkey()).await, 5 * TOKEN);
+    assert_eq!(treasury_state.balance - treasury_state.allocation_left, 95 * TOKEN);
+}
+
+#[tokio::test]
+async fn push_vested_tip_skips_dust_pushes_v1() {
+    let (mut env, keeper, keeper_token) = keeper_setup().await;
+    let program_id = money_streaming::id();
+    let (mint, fee_treasury) = (env.mint, env.fee_treasury);
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let treasurer = treasurer_signer.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+
+    let push = |stream: Pubkey, treasury: Pubkey| instruction::push_vested(
+        &program_id, keeper.pubkey(), keeper_token, beneficiary, mint, treasury, stream, fee_treasury, None
+    ).unwrap();
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 200.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream_with_rate(&treasury, 1.0, 1, 100.0).await;
+
+    let ix = instruction::set_push_vested(&program_id, treasurer, treasury.address, stream, true, 5 * TOKEN).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    // Pushing a token every interval earns nothing
+    for _ in 0..10 {
+        env.warp(1).await;
+        env.process(push(stream, treasury.address), &[&keeper]).await.unwrap();
+    }
+
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(keeper.pubkey()).await, 0);
+    assert_eq!(treasury_state.balance - treasury_state.allocation_left, 100.0);
+
+    // A push of ten times the tip pays it
+    env.warp(50).await;
+    env.process(push(stream, treasury.address), &[&keeper]).await.unwrap();
+
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+    assert_eq!(env.token_balance(keeper.pubkey()).await, 5 * TOKEN);
+    assert_eq!(treasury_state.balance - treasury_state.allocation_left, 95.0);
+}
+
+// Environment with a second beneficiary to transfer the streams to
+async fn transfer_stream_setup() -> (TestEnv, Keypair) {
+    let (mut program_test, mint, treasurer, beneficiary) = program_test();
+    let new_beneficiary = Keypair::new();
+    program_test.add_account(new_beneficiary.pubkey(), system_account(SOL));
+
+    (start(program_test, mint, treasurer, beneficiary).await, new_beneficiary)
+}
+
+#[tokio::test]
+async fn transfer_stream_settles_the_vested_amount() {
+    let (mut env, new_beneficiary) = transfer_stream_setup().await;
+    let program_id = money_streaming::id();
+    let (mint, fee_treasury) = (env.mint, env.fee_treasury);
+    let beneficiary_signer = env.beneficiary.insecure_clone();
+    let beneficiary = beneficiary_signer.pubkey();
+
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 50.0).await;
+    env.warp(10).await;
+
+    // Only the current beneficiary transfers the stream
+    let ix = instruction::transfer_stream(
+        &program_id, new_beneficiary.pubkey(), mint, treasury.address, stream, fee_treasury, None, beneficiary, true
+    ).unwrap();
+    assert_matches!(env.process(ix, &[&new_beneficiary]).await, Err(_));
+
+    // The vested amount goes to the current beneficiary, minus the withdraw fee
+    let ix = instruction::transfer_stream(
+        &program_id, beneficiary, mint, treasury.address, stream, fee_treasury, None, new_beneficiary.pubkey(), true
+    ).unwrap();
+    env.process(ix, &[&beneficiary_signer]).await.unwrap();
+
+    let fee = 10 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    let stream_state: StreamV1 = env.unpack(stream).await;
+    let treasury_state: TreasuryV1 = env.unpack(treasury.address).await;
+
+    assert_eq!(stream_state.beneficiary_address, new_beneficiary.pubkey());
+    assert_eq!(stream_state.allocation_left, 40.0);
+    assert_eq!(stream_state.escrow_vested_amount_snap, 0.0);
+    assert_eq!(treasury_state.balance, 90.0);
+    assert_eq!(treasury_state.allocation_left, 40.0);
+    assert_eq!(env.token_balance(beneficiary).await, 10 * TOKEN - fee);
+    assert_eq!(env.token_balance(new_beneficiary.pubkey()).await, 0);
+    assert_eq!(env.token_balance(fee_treasury).await, fee);
+
+    // From now on the stream vests to the new beneficiary only
+    env.warp(5).await;
+    assert_matches!(env.withdraw(&treasury, stream, 5.0).await, Err(_));
+
+    let ix = instruction::withdraw(
+        &program_id, new_beneficiary.pubkey(), mint, treasury.address, stream, fee_treasury, None, 5.0
+    ).unwrap();
+    env.process(ix, &[&new_beneficiary]).await.unwrap();
+
+    let new_fee = 5 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    assert_eq!(env.token_balance(beneficiary).await, 10 * TOKEN - fee);
+    assert_eq!(env.token_balance(new_beneficiary.pubkey()).await, 5 * TOKEN - new_fee);
+}
+
+#[tokio::test]
+async fn transfer_stream_without_settling() {
+    let (mut env, new_beneficiary) = transfer_stream_setup().await;
+    let program_id = money_streaming::id();
+    let (mint, fee_treasury) = (env.mint, env.fee_treasury);
+    let treasurer_signer = env.treasurer.insecure_clone();
+    let beneficiary_signer = env.beneficiary.insecure_clone();
+    let beneficiary = beneficiary_signer.pubkey();
+
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 100 * TOKEN).await;
+
+    let stream = Keypair::new();
+    let now = env.now().await;
+    let ix = instruction::create_stream_v2(
+        &program_id, treasurer_signer.pubkey(), treasury.address, mint, beneficiary, stream.pubkey(), fee_treasury, None,
+        name("Transferred"), TOKEN, 1, 0, 50 * TOKEN, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer, &stream]).await.unwrap();
+    let stream = stream.pubkey();
+    env.warp(10).await;
+
+    // The vested amount stays in the stream and moves with it
+    let ix = instruction::transfer_stream(
+        &program_id, beneficiary, mint, treasury.address, stream, fee_treasury, None, new_beneficiary.pubkey(), false
+    ).unwrap();
+    env.process(ix, &[&beneficiary_signer]).await.unwrap();
+
+    let stream_state: StreamV2 = env.unpack(stream).await;
+    assert_eq!(stream_state.beneficiary_address, new_beneficiary.pubkey());
+    assert_eq!(stream_state.allocation_left, 50 * TOKEN);
+    assert_eq!(env.token_balance(beneficiary).await, 0);
+    assert_eq!(env.token_balance(new_beneficiary.pubkey()).await, 0);
+
+    let ix = instruction::withdraw_v2(
+        &program_id, beneficiary, mint, treasury.address, stream, fee_treasury, None, TOKEN, false
+    ).unwrap();
+    assert_matches!(env.process(ix, &[&beneficiary_signer]).await, Err(_));
+
+    // Transferring it back settles the vested amount to the new beneficiary
+    env.warp(5).await;
+    let ix = instruction::transfer_stream(
+        &program_id, new_beneficiary.pubkey(), mint, treasury.address, stream, fee_treasury, None, beneficiary, true
+    ).unwrap();
+    env.process(ix, &[&new_beneficiary]).await.unwrap();
+
+    let fee = 15 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    let stream_state: StreamV2 = env.unpack(stream).await;
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+
+    assert_eq!(stream_state.beneficiary_address, beneficiary);
+    assert_eq!(stream_state.allocation_left, 35 * TOKEN);
+    assert_eq!(treasury_state.balance, 85 * TOKEN);
+    assert_eq!(treasury_state.allocation_left, 35 * TOKEN);
+    assert_eq!(env.token_balance(beneficiary).await, 0);
+    assert_eq!(env.token_balance(new_beneficiary.pubkey()).await, 15 * TOKEN - fee);
+    assert_eq!(env.token_balance(fee_treasury).await, fee);
+}
+
+#[tokio::test]
+async fn transfer_treasury() {
+    let (mut program_test, mint, treasurer_signer, beneficiary) = program_test();
+    let new_treasurer = Keypair::new();
+    program_test.add_account(new_treasurer.pubkey(), system_account(10 * SOL));
+    program_test.add_account(
+        get_associated_token_address(&new_treasurer.pubkey(), &mint),
+        token_account(mint, new_treasurer.pubkey(), 0)
+    );
+
+    let mut env = start(program_test, mint, treasurer_signer.insecure_clone(), beneficiary).await;
+    let program_id = money_streaming::id();
+    let treasurer = treasurer_signer.pubkey();
+    let (beneficiary, fee_treasury) = (env.beneficiary.pubkey(), env.fee_treasury);
+
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 100 * TOKEN).await;
+
+    let create_stream = |signer: Pubkey, stream: Pubkey, now: u64| instruction::create_stream_v2(
+        &program_id, signer, treasury.address, mint, beneficiary, stream, fee_treasury, None,
+        name("Stream"), TOKEN, 1, 0, 10 * TOKEN, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+
+    let mut streams = vec![];
+
+    for _ in 0..2 {
+        let stream = Keypair::new();
+        let now = env.now().await;
+        env.process(create_stream(treasurer, stream.pubkey(), now), &[&treasurer_signer, &stream]).await.unwrap();
+        streams.push(stream.pubkey());
+    }
+
+    env.warp(10).await;
+
+    // Only the treasurer transfers the treasury
+    let ix = instruction::transfer_treasury(&program_id, new_treasurer.pubkey(), treasury.address, &[], treasurer).unwrap();
+    assert_matches!(env.process(ix, &[&new_treasurer]).await, Err(_));
+
+    // Not every stream has to be passed, the passed ones get their treasurer copy refreshed
+    let ix = instruction::transfer_treasury(
+        &program_id, treasurer, treasury.address, &streams[..1], new_treasurer.pubkey()
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    let treasury_state: TreasuryV2 = env.unpack(treasury.address).await;
+    let refreshed: StreamV2 = env.unpack(streams[0]).await;
+    let stale: StreamV2 = env.unpack(streams[1]).await;
+    assert_eq!(treasury_state.treasurer_address, new_treasurer.pubkey());
+    assert_eq!(refreshed.treasurer_address, new_treasurer.pubkey());
+    assert_eq!(stale.treasurer_address, treasurer);
+
+    // The previous treasurer lost the control of the treasury and of every stream
+    let stream = Keypair::new();
+    let now = env.now().await;
+    assert_matches!(env.process(create_stream(treasurer, stream.pubkey(), now), &[&treasurer_signer, &stream]).await, Err(_));
+
+    let pause = |initializer: Pubkey, stream: Pubkey| instruction::pause_stream(
+        &program_id, initializer, mint, treasury.address, stream, fee_treasury
+    ).unwrap();
+    assert_matches!(env.process(pause(treasurer, streams[1]), &[&treasurer_signer]).await, Err(_));
+
+    let close_treasury = |treasurer: Pubkey| instruction::close_treasury(
+        &program_id, treasurer, mint, treasury.address, treasury.pool_mint, fee_treasury, None
+    ).unwrap();
+    assert_matches!(env.process(close_treasury(treasurer), &[&treasurer_signer]).await, Err(_));
+
+    // The new treasurer controls all of them, refreshed or not
+    env.process(create_stream(new_treasurer.pubkey(), stream.pubkey(), now), &[&new_treasurer, &stream]).await.unwrap();
+    streams.push(stream.pubkey());
+    env.process(pause(new_treasurer.pubkey(), streams[1]), &[&new_treasurer]).await.unwrap();
+
+    for stream in streams.iter() {
+        let close = |initializer: Pubkey| instruction::close_stream(
+            &program_id, initializer, initializer, beneficiary, mint, treasury.address,
+            treasury.pool_mint, *stream, fee_treasury, None, false, false
+        ).unwrap();
+        assert_matches!(env.process(close(treasurer), &[&treasurer_signer]).await, Err(_));
+        env.process(close(new_treasurer.pubkey()), &[&new_treasurer]).await.unwrap();
+    }
+
+    let treasury_balance = env.token_balance(treasury.address).await;
+    let treasurer_balance = env.token_balance(treasurer).await;
+    env.process(close_treasury(new_treasurer.pubkey()), &[&new_treasurer]).await.unwrap();
+
+    assert!(env.account(treasury.address).await.is_none());
+    let fee = treasury_balance * CLOSE_TREASURY_PERCENT_FEE / BPS_DENOMINATOR;
+    assert_eq!(env.token_balance(new_treasurer.pubkey()).await, treasury_balance - fee);
+    assert_eq!(env.token_balance(treasurer).await, treasurer_balance);
+}
+
+#[tokio::test]
+async fn transfer_treasury_drops_the_roles() {
+    let (mut program_test, mint, treasurer_signer, beneficiary) = program_test();
+    let new_treasurer = Keypair::new();
+    let creator = Keypair::new();
+    let pauser = Keypair::new();
+    program_test.add_account(new_treasurer.pubkey(), system_account(SOL));
+    program_test.add_account(creator.pubkey(), system_account(SOL));
+
+    let mut env = start(program_test, mint, treasurer_signer.insecure_clone(), beneficiary).await;
+    let program_id = money_streaming::id();
+    let treasurer = treasurer_signer.pubkey();
+    let fee_treasury = env.fee_treasury;
+
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 100 * TOKEN).await;
+    let (treasury_roles, _) = find_treasury_roles_address(&program_id, &treasury.address);
+
+    for (member, role) in [(creator.pubkey(), TreasuryRole::StreamCreator), (pauser.pubkey(), TreasuryRole::Pauser)] {
+        let ix = instruction::grant_treasury_roles(&program_id, treasurer, treasury.address, member, role as u8).unwrap();
+        env.process(ix, &[&treasurer_signer]).await.unwrap();
+    }
+
+    let create_stream = |stream: Pubkey, now: u64| instruction::with_treasury_roles(&program_id, instruction::create_stream_v2(
+        &program_id, creator.pubkey(), treasury.address, mint, Pubkey::new_unique(), stream,
+        fee_treasury, None, name("Delegated"), TOKEN, 1, 0, 10 * TOKEN,
+        now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap(), treasury.address);
+
+    let stream = Keypair::new();
+    let now = env.now().await;
+    env.process(create_stream(stream.pubkey(), now), &[&creator, &stream]).await.unwrap();
+    let stream = stream.pubkey();
+    env.warp(10).await;
+
+    // The roles account is closed and its rent goes back to the previous treasurer
+    let treasurer_lamports = env.lamports(treasurer).await;
+    let roles_lamports = env.lamports(treasury_roles).await;
+
+    let ix = instruction::transfer_treasury(&program_id, treasurer, treasury.address, &[], new_treasurer.pubkey()).unwrap();
+    env.process(ix, &[&treasurer_signer]).await.unwrap();
+
+    assert!(env.account(treasury_roles).await.is_none());
+    assert_eq!(env.lamports(treasurer).await, treasurer_lamports + roles_lamports);
+
+    // The delegates of the previous treasurer lost their roles
+    let new_stream = Keypair::new();
+    let now = env.now().await;
+    assert_matches!(env.process(create_stream(new_stream.pubkey(), now), &[&creator, &new_stream]).await, Err(_));
+
+    let pause = |initializer: Pubkey| instruction::with_treasury_roles(&program_id, instruction::pause_stream(
+        &program_id, initializer, mint, treasury.address, stream, fee_treasury
+    ).unwrap(), treasury.address);
+    assert_matches!(env.process(pause(pauser.pubkey()), &[&pauser]).await, Err(_));
+
+    // Until the new treasurer grants them again
+    let ix = instruction::grant_treasury_roles(
+        &program_id, new_treasurer.pubkey(), treasury.address, pauser.pubkey(), TreasuryRole::Pauser as u8
+    ).unwrap();
+    env.process(ix, &[&new_treasurer]).await.unwrap();
+    env.process(pause(pauser.pubkey()), &[&pauser]).await.unwrap();
+}
+
+#[tokio::test]
+async fn propose_and_answer_update() {
+    let (mut program_test, mint, treasurer_signer, beneficiary_signer) = program_test();
+    let stranger = Keypair::new();
+    program_test.add_account(stranger.pubkey(), system_account(SOL));
+
+    let mut env = start(program_test, mint, treasurer_signer.insecure_clone(), beneficiary_signer.insecure_clone()).await;
+    let program_id = money_streaming::id();
+    let (treasurer, beneficiary) = (treasurer_signer.pubkey(), beneficiary_signer.pubkey());
+    let fee_treasury = env.fee_treasury;
+
+    let treasury = env.create_treasury_v2().await;
+    env.add_funds_v2(&treasury, 100 * TOKEN).await;
+
+    let stream = Keypair::new();
+    let now = env.now().await;
+    let ix = instruction::create_stream_v2(
+        &program_id, treasurer, treasury.address, mint, beneficiary, stream.pubkey(), fee_treasury, None,
+        name("Salary"), TOKEN, 1, 0, 50 * TOKEN, now * 1000, now * 1000, 0, 0, 0, 0
+    ).unwrap();
+    env.process(ix, &[&treasurer_signer, &stream]).await.unwrap();
+    let stream = stream.pubkey();
+    let (stream_terms, _) = find_stream_terms_address(&program_id, &stream);
+    env.warp(10).await;
+
+    let propose = |proposer: Pubkey, stream_name: &str, rate_amount: u64| instruction::propose_update(
+        &program_id, proposer, treasury.address, stream, fee_treasury, None, name(stream_name), rate_amount, 1
+    ).unwrap();
+    let answer = |answerer: Pubkey, proposer: Pubkey, approve: bool| instruction::answer_update(
+        &program_id, answerer, proposer, treasury.address, stream, approve
+    ).unwrap();
+
+    // Only the treasurer or the beneficiary propose new terms
+    assert_matches!(env.process(propose(stranger.pubkey(), "Raise", 2 * TOKEN), &[&stranger]).await, Err(_));
+
+    let fee_treasury_lamports = env.lamports(fee_treasury).await;
+    env.process(propose(treasurer, "Raise", 2 * TOKEN), &[&treasurer_signer]).await.unwrap();
+    assert_eq!(env.lamports(fee_treasury).await, fee_treasury_lamports + PROPOSE_UPDATE_FLAT_FEE);
+    assert!(env.account(stream_terms).await.is_some());
+
+    // One proposal at a time, answered by the counterparty only
+    assert_matches!(env.process(propose(beneficiary, "Raise", 3 * TOKEN), &[&beneficiary_signer]).await, Err(_));
+    assert_matches!(env.process(answer(treasurer, treasurer, true), &[&treasurer_signer]).await, Err(_));
+    assert_matches!(env.process(answer(stranger.pubkey(), treasurer, true), &[&stranger]).await, Err(_));
+
+    // Accepting snapshots the vested amount and applies the new rate from now on
+    env.process(answer(beneficiary, treasurer, true), &[&beneficiary_signer]).await.unwrap();
+
+    let stream_state: StreamV2 = env.unpack(stream).await;
+    assert!(env.account(stream_terms).await.is_none());
+    assert_eq!(stream_state.stream_name, name("Raise"));
+    assert_eq!(stream_state.rate_amount, 2 * TOKEN);
+    assert_eq!(stream_state.escrow_vested_amount_snap, 10 * TOKEN);
+
+    env.warp(5).await;
+    let withdraw = |amount: u64| instruction::withdraw_v2(
+        &program_id, beneficiary, mint, treasury.address, stream, fee_treasury, None, amount, false
+    ).unwrap();
+    assert_matches!(env.process(withdraw(20 * TOKEN + 1), &[&beneficiary_signer]).await, Err(_));
+    env.process(withdraw(20 * TOKEN), &[&beneficiary_signer]).await.unwrap();
+
+    let fee = 20 * TOKEN * WITHDRAW_PERCENT_FEE / BPS_DENOMINATOR;
+    assert_eq!(env.token_balance(beneficiary).await, 20 * TOKEN - fee);
+
+    // Rejecting leaves the terms as they are and gives the rent back to the proposer
+    env.process(propose(beneficiary, "Raise again", 3 * TOKEN), &[&beneficiary_signer]).await.unwrap();
+    let beneficiary_lamports = env.lamports(beneficiary).await;
+    let stream_terms_lamports = env.lamports(stream_terms).await;
+
+    env.process(answer(treasurer, beneficiary, false), &[&treasurer_signer]).await.unwrap();
+
+    let stream_state: StreamV2 = env.unpack(stream).await;
+    assert!(env.account(stream_terms).await.is_none());
+    assert_eq!(env.lamports(beneficiary).await, beneficiary_lamports + stream_terms_lamports);
+    assert_eq!(stream_state.stream_name, name("Raise"));
+    assert_eq!(stream_state.rate_amount, 2 * TOKEN);
+
+    // V1 streams keep their terms
+    let mut env = setup().await;
+    let treasury = env.create_treasury().await;
+    env.add_funds(&treasury, 100.0, 2, Pubkey::default()).await;
+    let stream = env.create_stream(&treasury, 50.0).await;
+
+    let treasurer_signer = env.treasurer.insecure_clone();
+
+    let ix = instruction::propose_update(
+        &program_id, treasurer_signer.pubkey(), treasury.address, stream, fee_treasury, None, name("Raise"), 2 * TOKEN, 1
+    ).unwrap();
+    assert_matches!(env.process(ix, &[&treasurer_signer]).await, Err(_));
+}